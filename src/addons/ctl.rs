@@ -0,0 +1,182 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    Database, DurationStyle, get_readable_duration,
+    ipc::{self, IpcCommand, IpcStatus, NotRunning},
+};
+
+const DEFAULT_FORMAT: &str = "{artist} - {title}";
+
+/// Commands sent to a running instance of NoctaVox
+#[derive(Subcommand, Debug)]
+pub(super) enum Ctl {
+    /// Toggle between playing and paused
+    PlayPause,
+
+    /// Resume playback, or play a song from the library immediately
+    Play { path: Option<PathBuf> },
+
+    /// Pause playback
+    Pause,
+
+    /// Stop playback and clear the queue
+    Stop,
+
+    /// Play the next song in the queue
+    Next,
+
+    /// Play the previous song in history
+    Prev,
+
+    /// Seek to a position in seconds, or relative to the current position
+    /// with a sign (e.g. `+10`, `-5`)
+    Seek {
+        #[arg(allow_hyphen_values = true)]
+        position: String,
+    },
+
+    /// Add songs from the library to the queue
+    Queue {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Print the current song.
+    /// Placeholders: {title} {artist} {album} {elapsed} {duration} {status}
+    NowPlaying {
+        #[arg(long, short, default_value = DEFAULT_FORMAT)]
+        format: String,
+    },
+}
+
+pub(super) fn run(ctl: Ctl) -> Result<()> {
+    let command = match ctl {
+        Ctl::PlayPause => IpcCommand::Toggle,
+        Ctl::Play { path } => IpcCommand::Play {
+            id: None,
+            path: path.map(absolute_path).transpose()?,
+        },
+        Ctl::Pause => IpcCommand::Pause,
+        Ctl::Stop => IpcCommand::Stop,
+        Ctl::Next => IpcCommand::Next,
+        Ctl::Prev => IpcCommand::Prev,
+        Ctl::Seek { position } => parse_seek(&position)?,
+        Ctl::Queue { paths } => {
+            for path in paths {
+                ipc::send(&IpcCommand::Enqueue {
+                    id: None,
+                    path: Some(absolute_path(path)?),
+                })?;
+            }
+            return Ok(());
+        }
+        Ctl::NowPlaying { format } => return now_playing(&format),
+    };
+
+    ipc::send(&command)?;
+    Ok(())
+}
+
+fn parse_seek(s: &str) -> Result<IpcCommand> {
+    let invalid = || anyhow!("Invalid seek position `{s}`");
+
+    let cmd = match s.as_bytes().first() {
+        Some(b'+') => IpcCommand::SeekForward {
+            secs: s[1..].parse().map_err(|_| invalid())?,
+        },
+        Some(b'-') => IpcCommand::SeekBack {
+            secs: s[1..].parse().map_err(|_| invalid())?,
+        },
+        _ => IpcCommand::Seek {
+            position: s.parse().map_err(|_| invalid())?,
+        },
+    };
+
+    Ok(cmd)
+}
+
+/// The running instance may not share our working directory
+fn absolute_path(path: PathBuf) -> Result<String> {
+    let path = std::path::absolute(path)?;
+    Ok(path
+        .canonicalize()
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string())
+}
+
+fn now_playing(format: &str) -> Result<()> {
+    let line = match ipc::send(&IpcCommand::State) {
+        Ok(response) => {
+            let Some(state) = response.state else {
+                return Ok(());
+            };
+            let Some(song) = state.now_playing else {
+                return Ok(());
+            };
+
+            render_format(
+                format,
+                &song.title,
+                &song.artist,
+                &song.album,
+                state.elapsed,
+                song.duration,
+                state.status,
+            )
+        }
+        // Fallback onto the database when no instance is running
+        Err(e) if e.is::<NotRunning>() => {
+            let Some(view) = Database::open()?.get_now_playing_view()? else {
+                return Ok(());
+            };
+
+            render_format(
+                format,
+                &view.title,
+                &view.artist,
+                &view.album,
+                view.elapsed,
+                view.duration,
+                IpcStatus::Stopped,
+            )
+        }
+        Err(e) => return Err(e),
+    };
+
+    println!("{line}");
+    Ok(())
+}
+
+fn render_format(
+    format: &str,
+    title: &str,
+    artist: &str,
+    album: &str,
+    elapsed: f32,
+    duration: f32,
+    status: IpcStatus,
+) -> String {
+    let readable = |secs: f32| {
+        get_readable_duration(
+            Duration::from_secs_f32(secs.max(0.0)),
+            DurationStyle::Compact,
+        )
+    };
+
+    let status = match status {
+        IpcStatus::Playing => "playing",
+        IpcStatus::Paused => "paused",
+        IpcStatus::Stopped => "stopped",
+    };
+
+    format
+        .replace("{title}", title)
+        .replace("{artist}", artist)
+        .replace("{album}", album)
+        .replace("{elapsed}", &readable(elapsed))
+        .replace("{duration}", &readable(duration))
+        .replace("{status}", status)
+}
@@ -1,7 +1,10 @@
-use crate::{ADDON_DIR, ADDON_TRANSPOSE, reset_noctavox};
+mod ctl;
+
+use crate::{reset_noctavox, transpose};
 use anyhow::Result;
 use clap::{ArgGroup, Parser};
-use std::{path::PathBuf, process::Command};
+use ctl::Ctl;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -13,15 +16,26 @@ use std::{path::PathBuf, process::Command};
       ArgGroup::new("mode")
           .args(["import_playlist", "export_playlist", "list", "reset"]),
   ))]
-
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Import a playlist from a csv or m3u file
-    #[arg(long, short)]
-    import_playlist: bool,
+    #[command(subcommand)]
+    command: Option<Ctl>,
+
+    /// Import a playlist from an m3u, m3u8, pls, xspf, csv, or json file
+    #[arg(long, short, value_name = "FILE")]
+    import_playlist: Option<PathBuf>,
 
-    /// Export a playlist to m3u, csv, or json format
-    #[arg(long, short)]
-    export_playlist: bool,
+    /// Name of the imported playlist [default: name stored in the file, or the file name]
+    #[arg(long, requires = "import_playlist")]
+    name: Option<String>,
+
+    /// Export a playlist to an m3u, m3u8, pls, xspf, csv, or json file
+    #[arg(long, short, value_name = "PLAYLIST")]
+    export_playlist: Option<String>,
+
+    /// Destination of the exported playlist, the format is chosen by extension [default: ./<PLAYLIST>.m3u8]
+    #[arg(long, short, requires = "export_playlist")]
+    output: Option<PathBuf>,
 
     /// List playlists in the library
     #[arg(long)]
@@ -35,80 +49,46 @@ struct Cli {
 pub fn parse_args() {
     let cli = Cli::parse();
 
-    if cli.import_playlist {
-        let _ = run_addon(ADDON_TRANSPOSE, &["--import"]);
-    } else if cli.export_playlist {
-        let _ = run_addon(ADDON_TRANSPOSE, &["--export"]);
+    let result = if let Some(command) = cli.command {
+        ctl::run(command)
+    } else if let Some(file) = cli.import_playlist {
+        import_playlist(&file, cli.name)
+    } else if let Some(name) = cli.export_playlist {
+        export_playlist(&name, cli.output)
     } else if cli.list {
-        let _ = run_addon(ADDON_TRANSPOSE, &["--list"]);
+        list_playlists()
     } else if cli.reset {
-        let _ = reset_noctavox();
+        reset_noctavox()
     } else {
         return;
     };
-}
 
-fn addon_path(name: &str) -> PathBuf {
-    if let Ok(entries) = std::fs::read_dir(&*ADDON_DIR) {
-        let mut matches: Vec<PathBuf> = entries
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.file_name()
-                    .to_str()
-                    .map(|s| s.starts_with(name))
-                    .unwrap_or(false)
-            })
-            .map(|e| e.path())
-            .collect();
-
-        match matches.len() {
-            1 => return matches.remove(0),
-            n if n > 1 => {
-                eprintln!(
-                    "Multiple matches for addon `{name}` in {}:",
-                    ADDON_DIR.display()
-                );
-                for m in &matches {
-                    eprintln!("  {}", m.display());
-                }
-                eprintln!("Keep only one, or rename the correct one to `{name}`.");
-                std::process::exit(1);
-            }
-            _ => {}
-        }
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
     }
+}
 
-    let exact = ADDON_DIR.join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
-    exact
+fn import_playlist(file: &std::path::Path, name: Option<String>) -> Result<()> {
+    let report = transpose::import_playlist(file, name)?;
+    println!("{report}");
+    Ok(())
 }
 
-fn run_addon(name: &str, params: &[&str]) -> Result<i32> {
-    let bin = addon_path(name);
-    if !bin.exists() {
-        eprintln!(
-            "Addon `{name}` not found.\nExpected addon in: {}/\n\nDownload `{name}` at https://github.com/Jaxx497/NoctaVox-Plugins/releases/latest/\n",
-            ADDON_DIR.display()
-        );
-        std::process::exit(1)
-    }
+fn export_playlist(name: &str, output: Option<PathBuf>) -> Result<()> {
+    let path = transpose::export_playlist(name, output)?;
+    println!("Exported \"{name}\" to {}", path.display());
+    Ok(())
+}
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mode = std::fs::metadata(&bin)?.permissions().mode();
-        if mode & 0o111 == 0 {
-            eprintln!("addon at {} is not executable (chmod +x it)", bin.display());
-            std::process::exit(1);
-        }
+fn list_playlists() -> Result<()> {
+    let playlists = transpose::list_playlists()?;
+    if playlists.is_empty() {
+        println!("No playlists found");
     }
 
-    let status = Command::new(&bin)
-        .args(params)
-        .status()
-        .unwrap_or_else(|e| {
-            eprintln!("Error: {e}");
-            std::process::exit(1)
-        });
-
-    Ok(status.code().unwrap_or(1))
+    for (name, len) in playlists {
+        println!("{name} ({len} songs)");
+    }
+    Ok(())
 }
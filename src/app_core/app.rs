@@ -2,14 +2,16 @@ use crate::{
     Library, USER_CONFIG, UserConfig,
     app_core::{NoctaVox, key_loop},
     config::{TIMING, Timing},
-    key_handler::KeyBuffer,
+    key_handler::{KEYMAP, KeyBuffer, Keymap},
     overwrite_line,
+    playback::{PlayMode, ValidatedSong},
     player::{PlayerHandle, VoxioTrack},
+    scrobbler::ScrobblerHandle,
     tui,
     ui_state::{Mode, PopupType, SettingsMode, UiState},
     user_config,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use std::sync::Arc;
 
 impl NoctaVox {
@@ -40,18 +42,49 @@ impl NoctaVox {
             .map_err(|e| eprintln!("OS media controls unavailable: {e}"))
             .ok();
 
+        let mut ipc_err = None;
+        let ipc = match user_config().ipc {
+            true => crate::ipc::IpcHandle::new()
+                .map_err(|e| ipc_err = Some(anyhow!("IPC socket unavailable: {e}")))
+                .ok(),
+            false => None,
+        };
+
+        let mut mpd_err = None;
+        let mpd = match user_config().mpd {
+            true => crate::mpd::MpdHandle::new(&user_config().mpd_address)
+                .map_err(|e| mpd_err = Some(anyhow!("MPD server unavailable: {e}")))
+                .ok(),
+            false => None,
+        };
+
+        let mut scrobble_err = None;
+        let scrobbler = user_config().scrobble.clone().and_then(|cfg| {
+            ScrobblerHandle::spawn(cfg)
+                .map_err(|e| scrobble_err = Some(e))
+                .ok()
+        });
+
         let mut nv = NoctaVox {
             library: lib,
             player,
             ui: UiState::new(lib_clone, metrics),
             library_refresh_rec: None,
+            tag_edit_rec: None,
+            gain_scan: None,
+            waveform_scan: None,
+            library_watcher: None,
             key_buffer: KeyBuffer::new(),
             media_controls,
+            ipc,
+            mpd,
+            scrobbler,
             tick_sync: 0,
             restored_song_id: None,
+            saved_queue: (Vec::new(), PlayMode::Off),
         };
 
-        if let Some(e) = config_err {
+        if let Some(e) = config_err.or(ipc_err).or(mpd_err).or(scrobble_err) {
             nv.ui.set_error(e);
         }
 
@@ -61,8 +94,12 @@ impl NoctaVox {
     pub fn run(&mut self) {
         match ratatui::run(|t| -> anyhow::Result<()> {
             self.preload_lib();
+            self.restore_queue();
             self.restore_ui();
             let _ = self.restore_last_played();
+            self.resolve_replay_gain();
+            self.precompute_waveforms();
+            self.watch_library();
 
             if self.library.roots.is_empty() {
                 self.ui
@@ -74,9 +111,12 @@ impl NoctaVox {
             loop {
                 self.select_shortcut(&key_rx);
                 t.draw(|f| tui::render(f, &mut self.ui))?;
+                tui::draw_graphics(t, &mut self.ui)?;
 
                 if self.ui.get_mode() == Mode::QUIT {
                     self.ui.update_now_playing_elapsed();
+                    self.persist_queue();
+                    self.cancel_waveform_scan();
                     self.player.stop()?;
                     if let Some(mc) = self.media_controls.take() {
                         std::thread::spawn(move || drop(mc));
@@ -97,8 +137,17 @@ impl NoctaVox {
     fn load_config() -> Option<anyhow::Error> {
         match UserConfig::load() {
             Ok(cfg) => {
+                let (keymap, errors) = Keymap::from_config(&cfg.keys);
+                let _ = KEYMAP.set(keymap);
                 let _ = USER_CONFIG.set(cfg);
-                None
+
+                match errors.is_empty() {
+                    true => None,
+                    false => Some(anyhow::anyhow!(
+                        "Invalid keybindings in config.toml:\n{}",
+                        errors.join("\n")
+                    )),
+                }
             }
             Err(e) => {
                 let _ = USER_CONFIG.set(UserConfig::default());
@@ -124,6 +173,52 @@ impl NoctaVox {
 
     fn restore_ui(&mut self) {
         let _ = self.ui.restore_state();
+
+        let _ = self.player.set_volume(self.player.volume());
+        let _ = self.player.set_muted(self.player.is_muted());
+        let _ = self.player.set_equalizer(self.ui.get_eq_gains());
+        self.sync_media_volume();
+    }
+
+    /// Queue songs left over from the last session. Songs which are no longer
+    /// in the library or on disk are reported by their path
+    fn restore_queue(&mut self) {
+        let (entries, mode) = match self.ui.load_queue() {
+            Ok(saved) => saved,
+            Err(e) => return self.ui.set_error(e),
+        };
+
+        let mut songs = vec![];
+        let mut auto_len = 0;
+        let mut missing = vec![];
+        for entry in &entries {
+            let restored = self
+                .library
+                .get_song_by_id(entry.id)
+                .and_then(|song| ValidatedSong::new(song).ok());
+
+            match restored {
+                Some(song) => {
+                    auto_len = match entry.auto_added {
+                        true => auto_len + 1,
+                        false => 0,
+                    };
+                    songs.push((song, entry.unshuffled_pos));
+                }
+                None => missing.push(entry.path.as_str()),
+            }
+        }
+        self.ui.playback.restore_queue(songs, auto_len, mode);
+
+        if !missing.is_empty() {
+            self.ui.set_error(anyhow!(
+                "Could not restore {} queued song(s):\n{}",
+                missing.len(),
+                missing.join("\n")
+            ));
+        }
+
+        self.saved_queue = (entries, mode);
     }
 
     fn restore_last_played(&mut self) -> Result<()> {
@@ -135,6 +230,7 @@ impl NoctaVox {
                 self.player.play(song)?;
 
                 self.player.seek_to(elapsed_secs)?;
+                self.force_sync()?;
 
                 if !user_config().auto_resume {
                     self.player.pause()?;
@@ -0,0 +1,121 @@
+use anyhow::{Result, anyhow, bail};
+use std::sync::Arc;
+
+use crate::{
+    app_core::NoctaVox,
+    calculate_signature, expand_tilde,
+    ipc::{IpcCommand, IpcEvent, IpcRequest, IpcResponse, IpcSong, IpcState, IpcStatus},
+    library::{SimpleSong, SongInfo},
+    playback::ValidatedSong,
+};
+
+impl NoctaVox {
+    pub(super) fn handle_ipc_request(&mut self, request: IpcRequest) {
+        let response = self
+            .run_ipc_command(&request.command)
+            .unwrap_or_else(IpcResponse::error);
+
+        request.reply(response);
+    }
+
+    fn run_ipc_command(&mut self, command: &IpcCommand) -> Result<IpcResponse> {
+        match command {
+            IpcCommand::Play { id, path } => match self.find_ipc_song(*id, path.as_deref())? {
+                Some(song) => self.play_ipc_song(&song)?,
+                None => self.player.resume()?,
+            },
+            IpcCommand::Pause => self.player.pause()?,
+            IpcCommand::Toggle => self.player.toggle_playback()?,
+            IpcCommand::Stop => self.stop()?,
+            IpcCommand::Next => self.play_next()?,
+            IpcCommand::Prev => self.play_prev()?,
+            IpcCommand::Seek { position } => self.player.seek_to(*position)?,
+            IpcCommand::SeekForward { secs } => self.player.seek_forward(*secs)?,
+            IpcCommand::SeekBack { secs } => self.player.seek_back(*secs)?,
+            IpcCommand::Enqueue { id, path } => {
+                let song = self
+                    .find_ipc_song(*id, path.as_deref())?
+                    .ok_or_else(|| anyhow!("enqueue requires an id or path"))?;
+
+                match self.player.is_stopped() {
+                    true => {
+                        let validated = ValidatedSong::new(&song)?;
+                        self.play_song(&validated)?;
+                    }
+                    false => self.queue_song(&song)?,
+                }
+                self.ui.set_legal_songs();
+            }
+            IpcCommand::State => return Ok(IpcResponse::with_state(self.ipc_state())),
+            // Subscriptions are taken over by the connection thread and never
+            // forwarded to the app, but answer rather than panic if one is
+            IpcCommand::Subscribe => {
+                return Ok(IpcResponse::error(
+                    "Subscriptions are handled by the connection",
+                ));
+            }
+        }
+
+        Ok(IpcResponse::ok())
+    }
+
+    pub(super) fn play_ipc_song(&mut self, song: &Arc<SimpleSong>) -> Result<()> {
+        let validated = ValidatedSong::new(song)?;
+
+        if let Some(current) = self.ui.playback.get_now_playing() {
+            self.ui.insert_history_entry(current.get_id());
+            self.ui.playback.push_history(&Arc::clone(current));
+        }
+
+        self.play_song(&validated)?;
+        self.force_sync()
+    }
+
+    fn find_ipc_song(
+        &self,
+        id: Option<u64>,
+        path: Option<&str>,
+    ) -> Result<Option<Arc<SimpleSong>>> {
+        let id = match (id, path) {
+            (Some(id), _) => id,
+            (None, Some(path)) => {
+                let path = expand_tilde(path)?;
+                let path = path.canonicalize().unwrap_or(path);
+                calculate_signature(&path)?
+            }
+            (None, None) => return Ok(None),
+        };
+
+        match self.library.get_song_by_id(id) {
+            Some(song) => Ok(Some(Arc::clone(song))),
+            None => bail!("Song not found in library"),
+        }
+    }
+
+    fn ipc_state(&mut self) -> IpcState {
+        IpcState {
+            status: IpcStatus::from(&self.player.get_playback_state()),
+            elapsed: self.player.elapsed().as_secs_f32(),
+            repeat: self.ui.playback.get_play_mode().is_repeat(),
+            play_mode: self.ui.playback.get_play_mode().key().to_string(),
+            now_playing: self
+                .ui
+                .playback
+                .get_now_playing()
+                .map(|s| IpcSong::from(s.as_ref())),
+            queue: self
+                .ui
+                .playback
+                .get_queue()
+                .iter()
+                .map(|s| IpcSong::from(s.as_ref()))
+                .collect(),
+        }
+    }
+
+    pub(super) fn broadcast_ipc(&self, event: IpcEvent) {
+        if let Some(ipc) = self.ipc.as_ref() {
+            ipc.broadcast(&event);
+        }
+    }
+}
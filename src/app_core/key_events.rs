@@ -15,6 +15,10 @@ impl NoctaVox {
             Action::SeekBack(s)     => self.player.seek_back(s)?,
             Action::PlayNext        => self.play_next()?,
             Action::PlayPrev        => self.play_prev()?,
+            Action::VolumeUp(v)     => self.adjust_volume(v as i16)?,
+            Action::VolumeDown(v)   => self.adjust_volume(-(v as i16))?,
+            Action::SetVolume(v)    => self.set_volume(v)?,
+            Action::ToggleMute      => self.toggle_mute()?,
 
             // UI 
             Action::Scroll(s)       => self.ui.scroll(s),
@@ -26,6 +30,8 @@ impl NoctaVox {
             Action::SortColumnsNext => self.ui.next_song_column(),
             Action::SortColumnsPrev => self.ui.prev_song_column(),
             Action::ToggleAlbumSort(next)   => self.ui.toggle_album_sort(next),
+            Action::OpenCategory    => self.ui.open_category(),
+            Action::CloseCategory   => self.ui.close_category(),
 
             // Search Related
             Action::UpdateSearch(k) => self.ui.process_search(k),
@@ -41,9 +47,24 @@ impl NoctaVox {
             Action::RenamePlaylist  => self.ui.rename_playlist_popup(),
             Action::RenamePlaylistConfirm => self.ui.rename_playlist()?,
 
+            Action::CreateSmartPlaylist => self.ui.create_smart_playlist_popup(),
+            Action::CreateSmartPlaylistConfirm => self.ui.create_smart_playlist()?,
+
+            Action::EditPlaylistRules => self.ui.edit_playlist_rules_popup(),
+            Action::EditPlaylistRulesConfirm => self.ui.edit_playlist_rules()?,
+
             Action::DeletePlaylist  => self.ui.delete_playlist_popup(),
             Action::DeletePlaylistConfirm => self.ui.delete_playlist()?,
 
+            // Tags
+            Action::EditTags        => self.ui.edit_tags_popup()?,
+            Action::EditTagsConfirm => self.edit_tags()?,
+            Action::TagEditorSelectField(d) => self.ui.tag_editor_select_field(d),
+
+            // Ratings
+            Action::Rate(stars)     => self.rate_songs(stars)?,
+            Action::ToggleFavorite  => self.ui.toggle_favorite()?,
+
             // Queue
             Action::QueueSong       => self.queue_handler(None)?,
             Action::QueueMany{sel_type, shuffle} => self.queue_selection(sel_type, shuffle)?,
@@ -51,7 +72,8 @@ impl NoctaVox {
             Action::AddToPlaylist   => self.ui.add_to_playlist_popup(),
             Action::AddToPlaylistConfirm => self.ui.add_to_playlist()?,
 
-            Action::ToggleRepeat => self.toggle_repeat()?,
+            Action::CyclePlayMode => self.cycle_play_mode()?,
+            Action::TogglePlayMode(mode) => self.toggle_play_mode(mode)?,
 
             Action::ShuffleElements => self.shuffle_queue(),
 
@@ -63,6 +85,7 @@ impl NoctaVox {
             Action::ShiftPosition(direction) => self.shift_position(direction)?,
             Action::IncrementWFSmoothness(direction) => self.ui.increment_wf_smoothness(direction),
             Action::IncrementSidebarSize(x) => self.ui.adjust_sidebar_size(x),
+            Action::ShiftLyricsOffset(ms)   => self.ui.shift_lyrics_offset(ms),
 
             Action::NextProgressDisplay     => self.ui.next_progress_display(),
             Action::SetProgressDisplay(p)   => self.ui.set_progress_display(p),
@@ -74,9 +97,20 @@ impl NoctaVox {
             Action::ThemeManager    => self.ui.open_theme_manager(),
             Action::CycleTheme(dir) => self.ui.cycle_theme(dir),
 
+            Action::Equalizer       => self.ui.open_equalizer(),
+            Action::EqSelectBand(d) => self.ui.eq_select_band(d),
+            Action::EqAdjustGain(g) => self.update_equalizer(|ui| ui.eq_adjust_gain(g))?,
+            Action::EqResetBand     => self.update_equalizer(|ui| ui.eq_reset_band())?,
+            Action::EqCyclePreset(d) => self.update_equalizer(|ui| ui.eq_cycle_preset(d))?,
+            Action::ToggleEqualizer => self.update_equalizer(|ui| ui.toggle_equalizer())?,
+            Action::ToggleEqOverlay => self.ui.toggle_eq_overlay(),
+
             // Ops
 
             Action::ShowStats       => self.ui.show_stats_popup()?,
+            Action::StatsPage(d)    => self.ui.shift_stats_page(d),
+            Action::CycleStatsPeriod => self.ui.cycle_stats_period(),
+            Action::ExportStats     => self.ui.export_stats_page()?,
             Action::PopupInput(key) => self.ui.process_popup_input(&key),
             Action::ClosePopup      => self.ui.close_popup(),
             Action::SoftReset       => self.ui.soft_reset(),
@@ -1,9 +1,26 @@
 use crate::{
-    Library,
-    app_core::{LibraryRefreshProgress, NoctaVox},
+    Database, Library,
+    app_core::{
+        LibraryRefreshProgress, NoctaVox, TagEditResult, WaveformScan, WaveformScanProgress,
+    },
+    config::ReplayGainMode,
+    library::{LibraryWatcher, SongInfo, TagEdit, resolve_replay_gain, supports_rating},
+    mpd::Subsystem,
+    playback::ValidatedSong,
+    player::VoxioTrack,
+    ui_state::precompute_waveforms,
+    user_config,
+};
+use anyhow::{Result, anyhow, bail};
+use crossbeam::channel::Receiver;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
 };
-use anyhow::{Result, anyhow};
-use std::{sync::Arc, thread};
 
 impl NoctaVox {
     pub(crate) fn update_library(&mut self) -> Result<()> {
@@ -11,6 +28,9 @@ impl NoctaVox {
             return Ok(());
         }
 
+        // Songs may be removed by the refresh, the scan restarts once it completes
+        self.cancel_waveform_scan();
+
         let (tx, rx) = crossbeam::channel::bounded(1);
         self.library_refresh_rec = Some(rx);
 
@@ -18,6 +38,10 @@ impl NoctaVox {
 
         thread::spawn(move || {
             let _ = tx.send(LibraryRefreshProgress::Scanning { progress: 1 });
+
+            // Songs whose waveform couldn't be decoded get another try
+            let _ = Database::open().and_then(|mut db| db.clear_failed_waveforms());
+
             let mut updated_lib = match Library::init() {
                 Ok(l) => l,
                 Err(e) => {
@@ -40,6 +64,296 @@ impl NoctaVox {
         Ok(())
     }
 
+    /// Fill in gain for newly scanned songs in the background
+    pub(super) fn resolve_replay_gain(&mut self) {
+        if user_config().replaygain == ReplayGainMode::Off || self.gain_scan.is_some() {
+            return;
+        }
+
+        let analyze = user_config().replaygain_analysis;
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        self.gain_scan = Some(rx);
+
+        // Songs carry their gain, so the library is rebuilt once any changed
+        thread::spawn(move || {
+            let rebuild = || -> Result<Option<Library>> {
+                if resolve_replay_gain(analyze)? == 0 {
+                    return Ok(None);
+                }
+
+                let mut library = Library::init()?;
+                library.collect_songs()?;
+                library.build_albums()?;
+
+                // Queued songs hold the entries they were added with, relinking
+                // them in place picks up the new gain
+                library.relinked = library.songs.keys().map(|id| (*id, *id)).collect();
+                Ok(Some(library))
+            };
+
+            if let Some(update) = rebuild().transpose() {
+                let _ = tx.send(update);
+            }
+        });
+    }
+
+    /// Fill the waveform cache for the whole library in the background
+    pub(super) fn precompute_waveforms(&mut self) {
+        if !user_config().precompute_waveforms
+            || self.waveform_scan.is_some()
+            || self.library_refresh_rec.is_some()
+        {
+            return;
+        }
+
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = Arc::clone(&cancel);
+
+        thread::spawn(move || {
+            let _ = match precompute_waveforms(&tx, worker_cancel) {
+                Ok(_) => tx.send(WaveformScanProgress::Complete),
+                Err(e) => tx.send(WaveformScanProgress::Error(e.to_string())),
+            };
+        });
+
+        self.waveform_scan = Some(WaveformScan { rx, cancel });
+    }
+
+    pub(super) fn cancel_waveform_scan(&mut self) {
+        if let Some(scan) = self.waveform_scan.take() {
+            scan.cancel.store(true, Ordering::Relaxed);
+            if !self.ui.is_library_refreshing() {
+                self.ui.set_library_refresh_progress(None);
+                self.ui.set_library_refresh_detail(None);
+            }
+        }
+    }
+
+    pub(super) fn waveform_scan_reciever(&self) -> Option<&Receiver<WaveformScanProgress>> {
+        self.waveform_scan.as_ref().map(|scan| &scan.rx)
+    }
+
+    pub(super) fn handle_waveform_progress(&mut self, progress: WaveformScanProgress) {
+        match progress {
+            WaveformScanProgress::Processing {
+                progress,
+                current,
+                total,
+            } => {
+                self.ui.set_library_refresh_progress(Some(progress));
+                self.ui.set_library_refresh_detail(Some(format!(
+                    "Generating waveforms {}/{}",
+                    current, total
+                )));
+            }
+            WaveformScanProgress::Complete => {
+                self.ui.set_library_refresh_progress(None);
+                self.ui.set_library_refresh_detail(None);
+                self.waveform_scan = None;
+            }
+            WaveformScanProgress::Error(e) => {
+                self.ui.set_error(anyhow!(e));
+                self.ui.set_library_refresh_progress(None);
+                self.ui.set_library_refresh_detail(None);
+                self.waveform_scan = None;
+            }
+        }
+    }
+
+    /// Start watching the library roots, restarting the watcher if the roots
+    /// have changed since it was spawned
+    pub(super) fn watch_library(&mut self) {
+        if !user_config().watch_library || self.library.roots.is_empty() {
+            self.library_watcher = None;
+            return;
+        }
+
+        if self
+            .library_watcher
+            .as_ref()
+            .is_none_or(|w| *w.roots() != self.library.roots)
+        {
+            self.library_watcher = Some(LibraryWatcher::spawn(self.library.roots.clone()));
+        }
+    }
+
+    pub(super) fn library_watcher_reciever(&self) -> Option<&Receiver<Result<Library>>> {
+        self.library_watcher.as_ref().map(|w| w.updates())
+    }
+
+    pub(super) fn handle_library_update(&mut self, update: Result<Library>) {
+        match update {
+            // A running refresh will pick up the change on its own
+            Ok(_) if self.library_refresh_rec.is_some() => {}
+            Ok(new_library) => {
+                self.cancel_waveform_scan();
+                self.swap_library(new_library);
+            }
+            Err(e) => self.ui.set_error(e),
+        }
+    }
+
+    /// Write the tag editor's changes to disk in the background
+    pub(crate) fn edit_tags(&mut self) -> Result<()> {
+        if self.tag_edit_rec.is_some() {
+            bail!("Tags are still being written!");
+        }
+
+        let (ids, edit) = self.ui.get_tag_edit();
+        edit.validate()?;
+
+        self.ui.close_popup();
+        if edit.is_empty() {
+            return Ok(());
+        }
+
+        self.spawn_tag_edit(ids, edit);
+        Ok(())
+    }
+
+    /// Rate the selected songs, writing the stars to their rating tags when
+    /// those are enabled
+    pub(crate) fn rate_songs(&mut self, stars: Option<u8>) -> Result<()> {
+        let write_tags = user_config().rating_tags;
+        if write_tags && self.tag_edit_rec.is_some() {
+            bail!("Tags are still being written!");
+        }
+
+        let (songs, stars) = self.ui.rate_songs(stars)?;
+        let ids = songs
+            .iter()
+            .filter(|s| supports_rating(s.filetype))
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+
+        if write_tags && !ids.is_empty() {
+            let mut edit = TagEdit::default();
+            edit.set_rating(stars);
+            self.spawn_tag_edit(ids, edit);
+        }
+
+        Ok(())
+    }
+
+    fn spawn_tag_edit(&mut self, ids: Vec<u64>, edit: TagEdit) {
+        self.ui.clear_multi_select();
+        self.ui.set_library_refresh_progress(Some(0));
+        self.ui
+            .set_library_refresh_detail(Some(format!("Writing tags to {} songs...", ids.len())));
+
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        self.tag_edit_rec = Some(rx);
+
+        thread::spawn(move || {
+            let result = (|| {
+                let mut library = Library::init()?;
+                let mut failed = vec![];
+
+                // Each row is updated right after its file is written, well
+                // before the library watcher would pick up the change
+                for id in ids {
+                    if let Err(e) = library.retag_song(id, &edit) {
+                        failed.push(e.to_string());
+                    }
+                }
+
+                library.collect_songs()?;
+                library.build_albums()?;
+
+                Ok(TagEditResult { failed, library })
+            })();
+
+            let _ = tx.send(result);
+        });
+    }
+
+    pub(super) fn handle_tag_edit(&mut self, result: Result<TagEditResult>) {
+        self.tag_edit_rec = None;
+        if self.library_refresh_rec.is_none() {
+            self.ui.set_library_refresh_progress(None);
+            self.ui.set_library_refresh_detail(None);
+        }
+
+        let TagEditResult { failed, library } = match result {
+            Ok(result) => result,
+            Err(e) => return self.ui.set_error(e),
+        };
+
+        self.cancel_waveform_scan();
+        self.swap_library(library);
+
+        if !failed.is_empty() {
+            self.ui.set_error(anyhow!(
+                "Could not write tags to {} song(s):\n{}",
+                failed.len(),
+                failed.join("\n")
+            ));
+        }
+    }
+
+    fn swap_library(&mut self, mut new_library: Library) {
+        let cached = self.ui.display_state.album_pos.selected();
+        let cached_offset = self.ui.display_state.album_pos.offset();
+        let updated_len = new_library.albums.len();
+        let relinked = std::mem::take(&mut new_library.relinked);
+
+        self.library = Arc::new(new_library);
+        if let Err(e) = self.ui.sync_library(Arc::clone(&self.library)) {
+            self.ui.set_error(e);
+        }
+
+        if updated_len > 0 {
+            self.ui
+                .display_state
+                .album_pos
+                .select(match cached < Some(updated_len) {
+                    true => cached,
+                    false => Some(updated_len / 2),
+                });
+            *self.ui.display_state.album_pos.offset_mut() = cached_offset;
+        }
+
+        if !relinked.is_empty() {
+            self.relink_songs(&relinked);
+        }
+
+        if let Some(mpd) = self.mpd.as_mut() {
+            mpd.invalidate_index();
+            mpd.notify(&[Subsystem::Database]);
+        }
+
+        self.ui.set_legal_songs();
+        self.resolve_replay_gain();
+        self.precompute_waveforms();
+        self.watch_library();
+    }
+
+    /// Follow songs which were given a new id into the session
+    fn relink_songs(&mut self, relinked: &HashMap<u64, u64>) {
+        let delta = self
+            .ui
+            .playback
+            .remap_songs(relinked, self.library.get_songs_map());
+        self.sync_player(&delta);
+
+        let now_playing = self.ui.get_now_playing().cloned();
+        if let Some(song) = now_playing
+            && relinked.values().any(|id| *id == song.get_id())
+        {
+            self.update_media_metadata(&song);
+
+            // The player repeats the track it was handed, under its old id
+            if self.ui.playback.repeats_song()
+                && let Ok(validated) = ValidatedSong::new(&song)
+            {
+                let _ = self
+                    .player
+                    .set_next(Some(VoxioTrack::from(validated.as_ref())));
+            }
+        }
+    }
+
     pub(super) fn handle_library_progress(&mut self, progress: LibraryRefreshProgress) {
         match progress {
             LibraryRefreshProgress::Scanning { progress } => {
@@ -67,30 +381,10 @@ impl NoctaVox {
                     .set_library_refresh_detail(Some("Rebuilding library...".to_string()));
             }
             LibraryRefreshProgress::Complete(new_library) => {
-                let cached = self.ui.display_state.album_pos.selected();
-                let cached_offset = self.ui.display_state.album_pos.offset();
-                let updated_len = new_library.albums.len();
-
-                self.library = Arc::new(new_library);
-                if let Err(e) = self.ui.sync_library(Arc::clone(&self.library)) {
-                    self.ui.set_error(e);
-                }
-
-                if updated_len > 0 {
-                    self.ui
-                        .display_state
-                        .album_pos
-                        .select(match cached < Some(updated_len) {
-                            true => cached,
-                            false => Some(updated_len / 2),
-                        });
-                    *self.ui.display_state.album_pos.offset_mut() = cached_offset;
-                }
-
-                self.ui.set_legal_songs();
                 self.ui.set_library_refresh_progress(None);
                 self.ui.set_library_refresh_detail(None);
                 self.library_refresh_rec = None;
+                self.swap_library(new_library);
             }
             LibraryRefreshProgress::Error(e) => {
                 self.ui.set_error(anyhow!(e));
@@ -1,13 +1,23 @@
 use crate::{
-    Library, key_handler::KeyBuffer, media_controls::MediaControlsHandle, player::PlayerHandle,
+    Library,
+    ipc::IpcHandle,
+    key_handler::KeyBuffer,
+    library::LibraryWatcher,
+    media_controls::MediaControlsHandle,
+    mpd::MpdHandle,
+    playback::{PlayMode, SavedEntry},
+    player::PlayerHandle,
+    scrobbler::ScrobblerHandle,
     ui_state::UiState,
 };
 use crossbeam::channel::Receiver;
-use std::sync::Arc;
+use std::sync::{Arc, atomic::AtomicBool};
 
 mod app;
+mod ipc;
 mod key_events;
 mod library;
+mod mpd;
 mod playback;
 mod player;
 mod select;
@@ -20,9 +30,18 @@ pub struct NoctaVox {
     player: PlayerHandle,
     key_buffer: KeyBuffer,
     library_refresh_rec: Option<Receiver<LibraryRefreshProgress>>,
+    tag_edit_rec: Option<Receiver<anyhow::Result<TagEditResult>>>,
+    gain_scan: Option<Receiver<anyhow::Result<Library>>>,
+    waveform_scan: Option<WaveformScan>,
+    library_watcher: Option<LibraryWatcher>,
     media_controls: Option<MediaControlsHandle>,
+    ipc: Option<IpcHandle>,
+    mpd: Option<MpdHandle>,
+    scrobbler: Option<ScrobblerHandle>,
     tick_sync: u32,
     restored_song_id: Option<u64>,
+    /// The queue and play mode as they were last written to the database
+    saved_queue: (Vec<SavedEntry>, PlayMode),
 }
 
 pub enum LibraryRefreshProgress {
@@ -43,3 +62,27 @@ pub enum LibraryRefreshProgress {
     Complete(crate::Library),
     Error(String),
 }
+
+pub enum WaveformScanProgress {
+    Processing {
+        progress: u8,
+        current: usize,
+        total: usize,
+    },
+    Complete,
+    Error(String),
+}
+
+/// Outcome of writing edited tags in the background
+pub struct TagEditResult {
+    /// Songs that could not be updated, along with the reason
+    failed: Vec<String>,
+    library: Library,
+}
+
+/// Background waveform generation, dropping the receiver stops any further
+/// progress from being reported
+pub(crate) struct WaveformScan {
+    rx: Receiver<WaveformScanProgress>,
+    cancel: Arc<AtomicBool>,
+}
@@ -0,0 +1,974 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{
+    Database,
+    app_core::NoctaVox,
+    library::{Playlist, SimpleSong},
+    mpd::{
+        Ack, AckCode, Filter, MpdRequest, MpdResult, MpdSnapshot, Subsystem, Tag, UriIndex, field,
+    },
+    playback::PlayMode,
+    player::PlaybackState,
+};
+
+/// Genres of each song, by id
+type Genres = HashMap<u64, Vec<String>>;
+
+#[rustfmt::skip]
+const COMMANDS: &[&str] = &[
+    "add", "addid", "clear", "clearerror", "close", "command_list_begin",
+    "command_list_end", "command_list_ok_begin", "commands", "consume", "count",
+    "currentsong", "delete", "deleteid", "find", "findadd", "getvol", "idle",
+    "list", "listall", "listallinfo", "listplaylist", "listplaylistinfo",
+    "listplaylists", "load", "lsinfo", "move", "next", "noidle", "notcommands",
+    "outputs", "password", "pause", "ping", "play", "playid", "playlistadd",
+    "playlistclear", "playlistdelete", "playlistid", "playlistinfo", "plchanges",
+    "plchangesposid", "previous", "random", "rename", "repeat", "rescan", "rm",
+    "save", "search", "searchadd", "seek", "seekcur", "seekid", "setvol",
+    "shuffle", "single", "stats", "status", "stop", "tagtypes", "update",
+    "volume",
+];
+
+/// Where a position in the MPD playlist points. The playlist is the playing
+/// song, if there is one, followed by the queue
+enum Slot {
+    Current,
+    Queued(usize),
+}
+
+impl NoctaVox {
+    pub(super) fn handle_mpd_request(&mut self, request: MpdRequest) {
+        let mut response = String::new();
+
+        for (i, args) in request.commands.iter().enumerate() {
+            let Some((command, args)) = args.split_first() else {
+                continue;
+            };
+
+            // Output of a failed command is discarded, along with the rest of the list
+            let mut out = String::new();
+            match self.run_mpd_command(&command.to_lowercase(), args, &mut out) {
+                Ok(()) => {
+                    response.push_str(&out);
+                    if request.list_ok {
+                        response.push_str("list_OK\n");
+                    }
+                }
+                Err(ack) => {
+                    response.push_str(&ack.to_line(i, command));
+                    request.reply(response);
+                    self.sync_mpd();
+                    return;
+                }
+            }
+        }
+
+        response.push_str("OK\n");
+        request.reply(response);
+        self.sync_mpd();
+    }
+
+    fn run_mpd_command(&mut self, command: &str, args: &[String], out: &mut String) -> MpdResult {
+        match command {
+            "ping" | "clearerror" | "password" | "notcommands" => (),
+            "commands" => COMMANDS.iter().for_each(|c| field(out, "command", c)),
+            "tagtypes" if args.is_empty() => {
+                Tag::SUPPORTED.iter().for_each(|t| field(out, "tagtype", t))
+            }
+            // Enabling and disabling tags is accepted, all tags are always sent
+            "tagtypes" => (),
+            "outputs" => {
+                field(out, "outputid", 0);
+                field(out, "outputname", "NoctaVox");
+                field(out, "plugin", "cpal");
+                field(out, "outputenabled", 1);
+            }
+            "status" => self.mpd_status(out)?,
+            "stats" => self.mpd_stats(out),
+            "currentsong" => {
+                let index = self.mpd_index()?;
+                let ids = self.mpd_ids()?;
+                if let Some(song) = self.ui.playback.get_now_playing() {
+                    write_queued(out, song, 0, ids[0], &index, &self.library.genres);
+                }
+            }
+
+            // PLAYBACK
+            "play" => match args.first() {
+                Some(_) => self.mpd_play(arg(args, 0)?)?,
+                None => self.mpd_resume()?,
+            },
+            "playid" => match args.first() {
+                Some(_) => {
+                    let pos = self.mpd_id_to_pos(arg(args, 0)?)?;
+                    self.mpd_play(pos)?
+                }
+                None => self.mpd_resume()?,
+            },
+            "pause" => match args.first().map(String::as_str) {
+                Some("1") => self.player.pause()?,
+                Some("0") => self.player.resume()?,
+                Some(_) => return Err(Ack::arg("Boolean (0/1) expected")),
+                None => self.player.toggle_playback()?,
+            },
+            "stop" => self.stop()?,
+            "next" => self.play_next()?,
+            "previous" => self.play_prev()?,
+            "seek" => self.mpd_seek(arg(args, 0)?, arg(args, 1)?)?,
+            "seekid" => {
+                let pos = self.mpd_id_to_pos(arg(args, 0)?)?;
+                self.mpd_seek(pos, arg(args, 1)?)?
+            }
+            "seekcur" => {
+                let time = args.first().ok_or_else(|| Ack::arg("Missing argument"))?;
+                let offset = parse::<f32>(time.trim_start_matches('+'))?;
+                let secs = match time.starts_with(['+', '-']) {
+                    true => self.player.elapsed().as_secs_f32() + offset,
+                    false => offset,
+                };
+                self.player.seek_to(secs.max(0.0))?;
+                self.notify_mpd(&[Subsystem::Player]);
+            }
+            "setvol" => {
+                self.player.set_muted(false)?;
+                self.set_volume(arg::<u8>(args, 0)?.min(100))?;
+            }
+            "volume" => self.adjust_volume(arg(args, 0)?)?,
+            "getvol" => field(out, "volume", self.player.volume()),
+            "repeat" | "random" | "single" => {
+                let mode = self.ui.playback.get_play_mode();
+                let enabled = bool_arg(args)?;
+
+                let mode = match command {
+                    "repeat" if enabled && !mode.is_repeat() => PlayMode::RepeatAll,
+                    "repeat" if !enabled && mode.is_repeat() => PlayMode::Off,
+                    "random" if enabled && !mode.is_shuffle() => PlayMode::Shuffle,
+                    "random" if !enabled && mode.is_shuffle() => PlayMode::Off,
+                    "single" if enabled => PlayMode::RepeatOne,
+                    "single" if mode == PlayMode::RepeatOne => PlayMode::RepeatAll,
+                    _ => mode,
+                };
+                self.set_play_mode(mode)?;
+            }
+            "consume" => {
+                if bool_arg(args)? {
+                    return Err(Ack::arg(format!("{command} mode is not supported")));
+                }
+            }
+
+            // QUEUE
+            "playlistinfo" => {
+                let index = self.mpd_index()?;
+                let (playlist, ids) = (self.mpd_playlist(), self.mpd_ids()?);
+                let range = match args.first() {
+                    Some(range) => parse_range(range, playlist.len())?,
+                    None => 0..playlist.len(),
+                };
+                for pos in range {
+                    write_queued(
+                        out,
+                        &playlist[pos],
+                        pos,
+                        ids[pos],
+                        &index,
+                        &self.library.genres,
+                    );
+                }
+            }
+            "playlistid" => {
+                let index = self.mpd_index()?;
+                let (playlist, ids) = (self.mpd_playlist(), self.mpd_ids()?);
+                let range = match args.first() {
+                    Some(_) => {
+                        let pos = self.mpd_id_to_pos(arg(args, 0)?)?;
+                        pos..pos + 1
+                    }
+                    None => 0..playlist.len(),
+                };
+                for pos in range {
+                    write_queued(
+                        out,
+                        &playlist[pos],
+                        pos,
+                        ids[pos],
+                        &index,
+                        &self.library.genres,
+                    );
+                }
+            }
+            // Changes aren't tracked per version, anything older gets everything
+            "plchanges" | "plchangesposid" => {
+                let version = arg::<u32>(args, 0)?;
+                if self
+                    .mpd
+                    .as_ref()
+                    .is_some_and(|m| version < m.playlist_version)
+                {
+                    let index = self.mpd_index()?;
+                    let ids = self.mpd_ids()?;
+                    for (pos, song) in self.mpd_playlist().iter().enumerate() {
+                        match command == "plchanges" {
+                            true => {
+                                write_queued(out, song, pos, ids[pos], &index, &self.library.genres)
+                            }
+                            false => {
+                                field(out, "cpos", pos);
+                                field(out, "Id", ids[pos]);
+                            }
+                        }
+                    }
+                }
+            }
+            "add" => {
+                let uri = args.first().ok_or_else(|| Ack::arg("Missing argument"))?;
+                let songs = self.mpd_songs_within(uri)?;
+                self.mpd_enqueue(songs, None)?;
+            }
+            "addid" => {
+                let uri = args.first().ok_or_else(|| Ack::arg("Missing argument"))?;
+                let index = self.mpd_index()?;
+                let song = index
+                    .id(uri)
+                    .and_then(|id| self.library.get_song_by_id(id))
+                    .cloned()
+                    .ok_or_else(|| Ack::no_exist("No such song"))?;
+
+                let to = args.get(1).map(|_| arg::<usize>(args, 1)).transpose()?;
+                let added = self.mpd_enqueue(vec![song], to)?;
+                field(out, "Id", added[0]);
+            }
+            "delete" => {
+                let len = self.mpd_playlist().len();
+                let range = parse_range(args.first().map_or("", String::as_str), len)?;
+                self.mpd_delete(range)?;
+            }
+            "deleteid" => {
+                let pos = self.mpd_id_to_pos(arg(args, 0)?)?;
+                self.mpd_delete(pos..pos + 1)?;
+            }
+            "clear" => self.stop()?,
+            "move" => {
+                let len = self.mpd_playlist().len();
+                let range = parse_range(args.first().map_or("", String::as_str), len)?;
+                let to = arg::<usize>(args, 1)?;
+                match range.len() {
+                    1 => self.mpd_move(range.start, to)?,
+                    _ => return Err(Ack::arg("Only single songs can be moved")),
+                }
+            }
+            "shuffle" => self.shuffle_queue(),
+
+            // DATABASE
+            "list" => self.mpd_list(args, out)?,
+            "find" | "search" => {
+                let (songs, index) = self.mpd_search(args, command == "find")?;
+                for song in songs {
+                    write_song(out, &song, &index, &self.library.genres);
+                }
+            }
+            "findadd" | "searchadd" => {
+                let (songs, _) = self.mpd_search(args, command == "findadd")?;
+                self.mpd_enqueue(songs, None)?;
+            }
+            "count" => {
+                let (songs, _) = self.mpd_search(args, true)?;
+                field(out, "songs", songs.len());
+                field(
+                    out,
+                    "playtime",
+                    songs.iter().map(|s| s.duration.as_secs()).sum::<u64>(),
+                );
+            }
+            "lsinfo" => self.mpd_lsinfo(args.first().map_or("", String::as_str), out)?,
+            "listall" => {
+                let index = self.mpd_index()?;
+                let dir = args.first().map_or("", String::as_str);
+                for (_, uri) in songs_within(&index, dir)? {
+                    field(out, "file", uri);
+                }
+            }
+            "listallinfo" => {
+                let index = self.mpd_index()?;
+                let dir = args.first().map_or("", String::as_str);
+                for (id, _) in songs_within(&index, dir)? {
+                    if let Some(song) = self.library.get_song_by_id(id) {
+                        write_song(out, song, &index, &self.library.genres);
+                    }
+                }
+            }
+            "update" | "rescan" => {
+                self.update_library()?;
+                field(out, "updating_db", 1);
+            }
+
+            // STORED PLAYLISTS
+            "listplaylists" => {
+                for playlist in &self.ui.playlists {
+                    field(out, "playlist", &playlist.name);
+                }
+            }
+            "listplaylist" | "listplaylistinfo" => {
+                let index = self.mpd_index()?;
+                let playlist = self.stored_playlist(args)?;
+                for entry in &playlist.tracklist {
+                    match command == "listplaylist" {
+                        true => field(out, "file", index.uri(entry.song.id).unwrap_or_default()),
+                        false => write_song(out, &entry.song, &index, &self.library.genres),
+                    }
+                }
+            }
+            "load" => {
+                let playlist = self.stored_playlist(args)?;
+                let songs = playlist
+                    .tracklist
+                    .iter()
+                    .map(|e| Arc::clone(&e.song))
+                    .collect::<Vec<_>>();
+                let range = match args.get(1) {
+                    Some(range) => parse_range(range, songs.len())?,
+                    None => 0..songs.len(),
+                };
+                self.mpd_enqueue(songs[range].to_vec(), None)?;
+            }
+            "playlistadd" => {
+                let uri = args.get(1).ok_or_else(|| Ack::arg("Missing argument"))?;
+                let songs = self.mpd_songs_within(uri)?;
+                let ids = songs.iter().map(|s| s.id).collect::<Vec<_>>();
+
+                if self.stored_playlist(args).is_err() {
+                    let name = args[0].clone();
+                    self.edit_stored_playlists(|db| db.create_playlist(&name))?;
+                }
+                let playlist_id = self.editable_playlist(args)?.id;
+                self.edit_stored_playlists(|db| db.add_to_playlist_multi(ids, playlist_id))?;
+            }
+            "playlistdelete" => {
+                let pos = arg::<usize>(args, 1)?;
+                let playlist = self.editable_playlist(args)?;
+                let entry = playlist
+                    .tracklist
+                    .get(pos)
+                    .map(|e| e.id)
+                    .ok_or_else(|| Ack::arg("Bad song index"))?;
+                self.edit_stored_playlists(|db| db.remove_from_playlist(&[entry]))?;
+            }
+            "playlistclear" => {
+                let playlist = self.editable_playlist(args)?;
+                let entries = playlist.tracklist.iter().map(|e| e.id).collect::<Vec<_>>();
+                self.edit_stored_playlists(|db| db.remove_from_playlist(&entries))?;
+            }
+            "rm" => {
+                let playlist_id = self.stored_playlist(args)?.id;
+                self.edit_stored_playlists(|db| db.delete_playlist(playlist_id))?;
+            }
+            "rename" => {
+                let name = args.get(1).ok_or_else(|| Ack::arg("Missing argument"))?;
+                if self.ui.playlists.iter().any(|p| &p.name == name) {
+                    return Err(Ack::new(AckCode::Exist, "Playlist already exists"));
+                }
+                let playlist_id = self.stored_playlist(args)?.id;
+                self.edit_stored_playlists(|db| db.rename_playlist(name, playlist_id))?;
+            }
+            "save" => {
+                let name = args.first().ok_or_else(|| Ack::arg("Missing argument"))?;
+                if self.ui.playlists.iter().any(|p| &p.name == name) {
+                    return Err(Ack::new(AckCode::Exist, "Playlist already exists"));
+                }
+                let ids = self.mpd_playlist().iter().map(|s| s.id).collect::<Vec<_>>();
+                self.edit_stored_playlists(|db| db.create_playlist(name))?;
+                let playlist_id = self.stored_playlist(args)?.id;
+                self.edit_stored_playlists(|db| db.add_to_playlist_multi(ids, playlist_id))?;
+            }
+
+            _ => {
+                return Err(Ack::new(
+                    AckCode::Unknown,
+                    format!("unknown command \"{command}\""),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compares the player against what clients were last told, waking those
+    /// waiting on whatever changed
+    pub(super) fn sync_mpd(&mut self) {
+        if self.mpd.is_none() {
+            return;
+        }
+
+        let snapshot = MpdSnapshot {
+            playlist: self.mpd_ids().unwrap_or_default(),
+            volume: self.player.volume(),
+            muted: self.player.is_muted(),
+            mode: self.ui.playback.get_play_mode(),
+        };
+
+        let Some(mpd) = self.mpd.as_mut() else {
+            return;
+        };
+
+        let mut changed = Vec::new();
+        if snapshot.playlist != mpd.snapshot.playlist {
+            mpd.playlist_version = mpd.playlist_version.wrapping_add(1);
+            changed.push(Subsystem::Playlist);
+        }
+        if (snapshot.volume, snapshot.muted) != (mpd.snapshot.volume, mpd.snapshot.muted) {
+            changed.push(Subsystem::Mixer);
+        }
+        if snapshot.mode != mpd.snapshot.mode {
+            changed.push(Subsystem::Options);
+        }
+
+        mpd.snapshot = snapshot;
+        if !changed.is_empty() {
+            mpd.notify(&changed);
+        }
+    }
+
+    pub(super) fn notify_mpd(&self, changed: &[Subsystem]) {
+        if let Some(mpd) = self.mpd.as_ref() {
+            mpd.notify(changed);
+        }
+    }
+
+    fn mpd_index(&mut self) -> MpdResult<Arc<UriIndex>> {
+        let roots = &self.library.roots;
+        let mpd = self
+            .mpd
+            .as_mut()
+            .ok_or_else(|| Ack::new(AckCode::System, "MPD server is not running"))?;
+
+        Ok(mpd.index(|| {
+            let paths = Database::open()
+                .and_then(|mut db| db.get_song_paths())
+                .unwrap_or_default();
+            UriIndex::new(roots, paths)
+        }))
+    }
+
+    fn mpd_playlist(&mut self) -> Vec<Arc<SimpleSong>> {
+        let now_playing = self.ui.playback.get_now_playing().cloned();
+        now_playing
+            .into_iter()
+            .chain(self.ui.playback.get_queue())
+            .collect()
+    }
+
+    /// Song ids of the playlist, by position
+    fn mpd_ids(&mut self) -> MpdResult<Vec<u32>> {
+        let current = self.ui.playback.get_now_playing().map(|s| s.id);
+        let queued = self.ui.playback.queued_entries();
+        let mpd = self
+            .mpd
+            .as_mut()
+            .ok_or_else(|| Ack::new(AckCode::System, "MPD server is not running"))?;
+
+        Ok(mpd.song_ids(current, queued))
+    }
+
+    fn mpd_id_to_pos(&mut self, id: u32) -> MpdResult<usize> {
+        self.mpd_ids()?
+            .iter()
+            .position(|i| *i == id)
+            .ok_or_else(|| Ack::no_exist("No such song"))
+    }
+
+    fn mpd_slot(&self, pos: usize) -> MpdResult<Slot> {
+        let offset = self.ui.playback.get_now_playing().is_some() as usize;
+
+        match pos {
+            0 if offset == 1 => Ok(Slot::Current),
+            p if p - offset < self.ui.playback.queue_len() => Ok(Slot::Queued(p - offset)),
+            _ => Err(Ack::arg("Bad song index")),
+        }
+    }
+
+    fn mpd_status(&mut self, out: &mut String) -> MpdResult {
+        let playlist = self.mpd_ids()?;
+        let (version, volume) = (
+            self.mpd.as_ref().map_or(0, |m| m.playlist_version),
+            match self.player.is_muted() {
+                true => 0,
+                false => self.player.volume(),
+            },
+        );
+
+        field(out, "volume", volume);
+        let mode = self.ui.playback.get_play_mode();
+        field(out, "repeat", mode.is_repeat() as u8);
+        field(out, "random", mode.is_shuffle() as u8);
+        field(out, "single", (mode == PlayMode::RepeatOne) as u8);
+        field(out, "consume", 0);
+        field(out, "playlist", version);
+        field(out, "playlistlength", playlist.len());
+        field(
+            out,
+            "state",
+            match self.player.get_playback_state() {
+                PlaybackState::Playing => "play",
+                PlaybackState::Paused => "pause",
+                PlaybackState::Stopped => "stop",
+            },
+        );
+
+        if let Some(song) = self.ui.playback.get_now_playing() {
+            let elapsed = self.player.elapsed().as_secs_f32();
+            let duration = song.duration.as_secs_f32();
+
+            field(out, "song", 0);
+            field(out, "songid", playlist[0]);
+            field(
+                out,
+                "time",
+                format!("{}:{}", elapsed as u32, duration as u32),
+            );
+            field(out, "elapsed", format!("{elapsed:.3}"));
+            field(out, "duration", format!("{duration:.3}"));
+
+            if playlist.len() > 1 {
+                field(out, "nextsong", 1);
+                field(out, "nextsongid", playlist[1]);
+            }
+        }
+        Ok(())
+    }
+
+    fn mpd_stats(&self, out: &mut String) {
+        let songs = self.library.get_songs_map();
+        let artists = songs.values().map(|s| &s.artist).collect::<HashSet<_>>();
+
+        field(out, "artists", artists.len());
+        field(out, "albums", self.library.albums.len());
+        field(out, "songs", songs.len());
+        field(
+            out,
+            "db_playtime",
+            songs.values().map(|s| s.duration.as_secs()).sum::<u64>(),
+        );
+    }
+
+    /// Resume playback, or start on the queue when nothing is playing
+    fn mpd_resume(&mut self) -> MpdResult {
+        match self.player.is_stopped() {
+            true if !self.ui.playback.queue_is_empty() => self.play_next()?,
+            true => (),
+            false => self.player.resume()?,
+        }
+        Ok(())
+    }
+
+    /// Queued songs are taken out of the queue to be played, as when picked
+    /// from the queue view
+    fn mpd_play(&mut self, pos: usize) -> MpdResult {
+        match self.mpd_slot(pos)? {
+            Slot::Current => {
+                self.player.seek_to(0.0)?;
+                self.player.resume()?;
+            }
+            Slot::Queued(idx) => {
+                let (delta, song) = self.ui.playback.remove_from_queue(idx);
+                self.sync_player(&delta);
+                if let Some(song) = song {
+                    self.play_ipc_song(&song.meta)?;
+                }
+                self.ui.set_legal_songs();
+            }
+        }
+        Ok(())
+    }
+
+    fn mpd_seek(&mut self, pos: usize, secs: f32) -> MpdResult {
+        match self.mpd_slot(pos)? {
+            Slot::Current => self.player.seek_to(secs.max(0.0))?,
+            Slot::Queued(_) => return Err(Ack::arg("Only the current song can be seeked")),
+        }
+        self.notify_mpd(&[Subsystem::Player]);
+        Ok(())
+    }
+
+    /// Queue songs, moving the first to `to` when given, and start on the
+    /// queue when nothing is playing. Returns the ids of the added songs
+    fn mpd_enqueue(
+        &mut self,
+        songs: Vec<Arc<SimpleSong>>,
+        to: Option<usize>,
+    ) -> MpdResult<Vec<u32>> {
+        if songs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let before = self.mpd_ids()?;
+        let delta = self.ui.playback.enqueue_multi(&songs)?;
+        self.sync_player(&delta);
+        self.ui.set_legal_songs();
+
+        // Taken while the songs are still queued, so the first keeps its id
+        // once it starts
+        let added = self
+            .mpd_ids()?
+            .into_iter()
+            .filter(|id| !before.contains(id))
+            .collect::<Vec<_>>();
+
+        if let (Some(to), Some(id)) = (to, added.first()) {
+            let from = self.mpd_id_to_pos(*id)?;
+            self.mpd_move(from, to)?;
+        }
+
+        if self.player.is_stopped() {
+            self.play_next()?;
+        }
+        Ok(added)
+    }
+
+    fn mpd_delete(&mut self, range: Range<usize>) -> MpdResult {
+        let slots = range
+            .map(|pos| self.mpd_slot(pos))
+            .collect::<MpdResult<Vec<_>>>()?;
+
+        // Later songs go first so that earlier indices stay put
+        for slot in slots.iter().rev() {
+            if let Slot::Queued(idx) = slot {
+                let (delta, _) = self.ui.playback.remove_from_queue(*idx);
+                self.sync_player(&delta);
+            }
+        }
+
+        if matches!(slots.first(), Some(Slot::Current)) {
+            self.play_next()?;
+        }
+
+        self.ui.set_legal_songs();
+        Ok(())
+    }
+
+    fn mpd_move(&mut self, from: usize, to: usize) -> MpdResult {
+        let (Slot::Queued(from), Slot::Queued(to)) = (self.mpd_slot(from)?, self.mpd_slot(to)?)
+        else {
+            return Err(Ack::arg("The current song can't be moved"));
+        };
+
+        if let Some(delta) = self.ui.playback.move_queued(from, to) {
+            self.sync_player(&delta);
+        }
+        self.ui.set_legal_songs();
+        Ok(())
+    }
+
+    /// The song at `uri`, or every song beneath it when it's a directory
+    fn mpd_songs_within(&mut self, uri: &str) -> MpdResult<Vec<Arc<SimpleSong>>> {
+        let index = self.mpd_index()?;
+
+        Ok(songs_within(&index, uri)?
+            .into_iter()
+            .filter_map(|(id, _)| self.library.get_song_by_id(id).cloned())
+            .collect())
+    }
+
+    fn mpd_search(
+        &mut self,
+        args: &[String],
+        exact: bool,
+    ) -> MpdResult<(Vec<Arc<SimpleSong>>, Arc<UriIndex>)> {
+        let (filter, mut rest) = Filter::parse_args(args, exact)?;
+        let filter = filter.ok_or_else(|| Ack::arg("Missing filter"))?;
+        let index = self.mpd_index()?;
+
+        let mut songs = self
+            .library
+            .get_songs_map()
+            .values()
+            .filter(|s| {
+                let get = |tag| tag_values(s, tag, &index, &self.library.genres);
+                filter.matches(&get, !exact)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        songs.sort_by_key(|s| index.uri(s.id));
+
+        while let [option, value, tail @ ..] = rest {
+            match option.to_lowercase().as_str() {
+                "sort" => {
+                    let (descending, name) = match value.strip_prefix('-') {
+                        Some(name) => (true, name),
+                        None => (false, value.as_str()),
+                    };
+                    let tag = Tag::parse(name)?;
+                    songs.sort_by_cached_key(|s| sort_key(s, tag, &index, &self.library.genres));
+                    if descending {
+                        songs.reverse();
+                    }
+                }
+                // Unlike positions, windows may run past the end of the results
+                "window" => {
+                    let (start, end) = value.split_once(':').unwrap_or((value, ""));
+                    let end = match end {
+                        "" => songs.len(),
+                        end => parse::<usize>(end)?.min(songs.len()),
+                    };
+                    songs = songs
+                        .get(parse(start)?..end)
+                        .map(<[_]>::to_vec)
+                        .unwrap_or_default();
+                }
+                _ => return Err(Ack::arg(format!("Unknown option: {option}"))),
+            }
+            rest = tail;
+        }
+
+        match rest.is_empty() {
+            true => Ok((songs, index)),
+            false => Err(Ack::arg("Incorrect number of arguments")),
+        }
+    }
+
+    /// Unique values of a tag, optionally grouped by others, e.g.
+    /// `list album group albumartist`
+    fn mpd_list(&mut self, args: &[String], out: &mut String) -> MpdResult {
+        let tag = Tag::parse(args.first().ok_or_else(|| Ack::arg("Missing argument"))?)?;
+
+        // The legacy `list album ARTIST` form
+        let legacy;
+        let mut rest = &args[1..];
+        if tag == Tag::Album && rest.len() == 1 && !rest[0].starts_with('(') {
+            legacy = ["artist".to_string(), rest[0].clone()];
+            rest = &legacy;
+        }
+
+        let (filter, mut rest) = Filter::parse_args(rest, true)?;
+        let mut groups = Vec::new();
+        while let [option, name, tail @ ..] = rest {
+            match option.to_lowercase() == "group" {
+                true => groups.push(Tag::parse(name)?),
+                false => return Err(Ack::arg(format!("Unknown option: {option}"))),
+            }
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(Ack::arg("Incorrect number of arguments"));
+        }
+
+        let index = self.mpd_index()?;
+        let genres = &self.library.genres;
+        let mut rows = BTreeSet::new();
+
+        for song in self.library.get_songs_map().values() {
+            let get = |tag| tag_values(song, tag, &index, genres);
+            if filter.as_ref().is_some_and(|f| !f.matches(&get, false)) {
+                continue;
+            }
+
+            // Songs with several genres are listed under each of them
+            let mut grouped = vec![vec![]];
+            for group in &groups {
+                let mut values = get(*group);
+                if values.is_empty() {
+                    values.push(String::new());
+                }
+                grouped = grouped
+                    .into_iter()
+                    .flat_map(|row: Vec<String>| {
+                        values.iter().map(move |v| {
+                            let mut row = row.clone();
+                            row.push(v.clone());
+                            row
+                        })
+                    })
+                    .collect();
+            }
+
+            for value in get(tag) {
+                rows.extend(grouped.iter().map(|row| (row.clone(), value.clone())));
+            }
+        }
+
+        let mut previous: Option<&Vec<String>> = None;
+        for (grouped, value) in &rows {
+            for (i, group) in groups.iter().enumerate() {
+                if previous.is_none_or(|p| p[..=i] != grouped[..=i]) {
+                    field(out, group.name(), &grouped[i]);
+                }
+            }
+            previous = Some(grouped);
+            field(out, tag.name(), value);
+        }
+
+        Ok(())
+    }
+
+    fn mpd_lsinfo(&mut self, uri: &str, out: &mut String) -> MpdResult {
+        let index = self.mpd_index()?;
+
+        if let Some(song) = index.id(uri).and_then(|id| self.library.get_song_by_id(id)) {
+            write_song(out, song, &index, &self.library.genres);
+            return Ok(());
+        }
+
+        let (dirs, songs) = index.list_dir(uri);
+        let root = uri.trim_matches('/').is_empty();
+        if !root && dirs.is_empty() && songs.is_empty() {
+            return Err(Ack::no_exist("No such directory"));
+        }
+
+        for dir in dirs {
+            field(out, "directory", dir);
+        }
+        for (id, _) in songs {
+            if let Some(song) = self.library.get_song_by_id(id) {
+                write_song(out, song, &index, &self.library.genres);
+            }
+        }
+        if root {
+            for playlist in &self.ui.playlists {
+                field(out, "playlist", &playlist.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stored_playlist(&self, args: &[String]) -> MpdResult<&Playlist> {
+        let name = args.first().ok_or_else(|| Ack::arg("Missing argument"))?;
+
+        self.ui
+            .playlists
+            .iter()
+            .find(|p| &p.name == name)
+            .ok_or_else(|| Ack::no_exist("No such playlist"))
+    }
+
+    /// A playlist which can be edited, smart playlists are generated from rules
+    fn editable_playlist(&self, args: &[String]) -> MpdResult<&Playlist> {
+        let playlist = self.stored_playlist(args)?;
+        match playlist.is_smart() {
+            true => Err(Ack::new(
+                AckCode::Permission,
+                "Smart playlists are read-only",
+            )),
+            false => Ok(playlist),
+        }
+    }
+
+    fn edit_stored_playlists(
+        &mut self,
+        edit: impl FnOnce(&mut Database) -> anyhow::Result<()>,
+    ) -> MpdResult {
+        let mut db = Database::open()?;
+        edit(&mut db)?;
+
+        self.ui.get_playlists()?;
+        self.ui.set_legal_songs();
+        self.notify_mpd(&[Subsystem::StoredPlaylist]);
+        Ok(())
+    }
+}
+
+fn write_song(out: &mut String, song: &SimpleSong, index: &UriIndex, genres: &Genres) {
+    field(out, "file", index.uri(song.id).unwrap_or_default());
+    for tag in Tag::SUPPORTED {
+        for value in tag_values(song, tag, index, genres) {
+            field(out, tag.name(), value);
+        }
+    }
+    field(out, "Time", song.duration.as_secs());
+    field(
+        out,
+        "duration",
+        format!("{:.3}", song.duration.as_secs_f32()),
+    );
+}
+
+fn write_queued(
+    out: &mut String,
+    song: &SimpleSong,
+    pos: usize,
+    id: u32,
+    index: &UriIndex,
+    genres: &Genres,
+) {
+    write_song(out, song, index, genres);
+    field(out, "Pos", pos);
+    field(out, "Id", id);
+}
+
+/// A song's values for a tag, empty when it has none
+fn tag_values(song: &SimpleSong, tag: Tag, index: &UriIndex, genres: &Genres) -> Vec<String> {
+    let number = |n: Option<u32>| n.map(|n| n.to_string());
+
+    let value = match tag {
+        Tag::Artist => Some(song.artist.to_string()),
+        Tag::AlbumArtist => Some(song.album_artist.to_string()),
+        Tag::Album => Some(song.album.to_string()),
+        Tag::Title => Some(song.title.clone()),
+        Tag::Track => number(song.track_no),
+        Tag::Disc => number(song.disc_no),
+        Tag::Date => number(song.year),
+        Tag::File => index.uri(song.id).map(str::to_string),
+        Tag::Genre => return genres.get(&song.id).cloned().unwrap_or_default(),
+        Tag::Any => None,
+    };
+
+    value.into_iter().filter(|v| !v.is_empty()).collect()
+}
+
+/// Numbers are padded so that they sort numerically
+fn sort_key(song: &SimpleSong, tag: Tag, index: &UriIndex, genres: &Genres) -> String {
+    let value = tag_values(song, tag, index, genres).join(";");
+    match tag {
+        Tag::Track | Tag::Disc | Tag::Date => format!("{value:0>10}"),
+        _ => value.to_lowercase(),
+    }
+}
+
+fn songs_within<'a>(index: &'a UriIndex, dir: &str) -> MpdResult<Vec<(u64, &'a str)>> {
+    let songs = index.songs_within(dir);
+    match songs.is_empty() && !dir.trim_matches('/').is_empty() {
+        true => Err(Ack::no_exist("No such directory")),
+        false => Ok(songs),
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> MpdResult<T> {
+    value
+        .parse()
+        .map_err(|_| Ack::arg(format!("Invalid argument: {value}")))
+}
+
+fn arg<T: FromStr>(args: &[String], i: usize) -> MpdResult<T> {
+    parse(args.get(i).ok_or_else(|| Ack::arg("Missing argument"))?)
+}
+
+fn bool_arg(args: &[String]) -> MpdResult<bool> {
+    match args.first().map(String::as_str) {
+        Some("1") => Ok(true),
+        Some("0") => Ok(false),
+        _ => Err(Ack::arg("Boolean (0/1) expected")),
+    }
+}
+
+/// `POS` or `START:END`, where a missing end runs to the end of the list
+fn parse_range(value: &str, len: usize) -> MpdResult<Range<usize>> {
+    let range = match value.split_once(':') {
+        Some((start, "")) => parse(start)?..len,
+        Some((start, end)) => parse(start)?..parse(end)?,
+        None => {
+            let pos = parse::<usize>(value)?;
+            let end = pos
+                .checked_add(1)
+                .ok_or_else(|| Ack::arg("Bad song index"))?;
+            pos..end
+        }
+    };
+
+    match range.start < range.end && range.end <= len {
+        true => Ok(range),
+        false => Err(Ack::arg("Bad song index")),
+    }
+}
@@ -1,14 +1,16 @@
 use crate::{
     app_core::NoctaVox,
+    config::RadioStrategy,
     key_handler::{Director, Incrementor, SelectionType},
-    library::{SimpleSong, SongDatabase, SongInfo},
-    playback::{QueueDelta, ValidatedSong},
+    library::{SimpleSong, SongInfo},
+    playback::{PlayMode, QueueDelta, ValidatedSong},
     player::VoxioTrack,
     ui_state::{LibraryView, Mode},
+    user_config,
 };
 use anyhow::Result;
 use rand::seq::SliceRandom;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 impl NoctaVox {
     pub fn advance_to_next_gapless(&mut self) -> Option<Arc<ValidatedSong>> {
@@ -173,36 +175,80 @@ impl NoctaVox {
         Ok(())
     }
 
-    pub fn toggle_repeat(&mut self) -> Result<()> {
-        match self.ui.playback.repeat_is_enabled() {
-            true => self.disable_repeat(),
-            false => self.enable_repeat(),
+    pub fn cycle_play_mode(&mut self) -> Result<()> {
+        self.set_play_mode(self.ui.playback.get_play_mode().next())
+    }
+
+    /// Switch between `mode` and regular playback
+    pub fn toggle_play_mode(&mut self, mode: PlayMode) -> Result<()> {
+        match self.ui.playback.get_play_mode() == mode {
+            true => self.set_play_mode(PlayMode::Off),
+            false => self.set_play_mode(mode),
         }
     }
 
-    pub fn disable_repeat(&mut self) -> Result<()> {
-        self.ui.playback.set_repeat(false);
-        self.force_sync()
+    pub fn set_play_mode(&mut self, mode: PlayMode) -> Result<()> {
+        self.ui.playback.set_play_mode(mode);
+        if self.ui.get_mode() == Mode::Queue {
+            self.ui.set_legal_songs();
+        }
+
+        match self.ui.playback.get_now_playing() {
+            Some(np) if mode == PlayMode::RepeatOne => {
+                let _ = self
+                    .player
+                    .set_next(Some(VoxioTrack::try_from(np.as_ref())?));
+                Ok(())
+            }
+            _ => self.force_sync(),
+        }
     }
 
-    pub fn enable_repeat(&mut self) -> Result<()> {
-        self.ui.playback.set_repeat(true);
-        if let Some(np) = self.ui.playback.get_now_playing() {
-            let _ = self
-                .player
-                .set_next(Some(VoxioTrack::new(np.get_id(), np.get_path()?)));
+    /// Top up the queue with songs picked by the radio once it runs dry
+    pub(super) fn fill_radio(&mut self) {
+        let strategy = user_config().radio;
+        if strategy == RadioStrategy::Off || !self.ui.playback.wants_radio() {
+            return;
         }
 
-        Ok(())
+        let stats = match strategy {
+            RadioStrategy::LeastRecent | RadioStrategy::Weighted => {
+                self.ui.get_song_stats().unwrap_or_default()
+            }
+            _ => HashMap::new(),
+        };
+
+        let delta = self
+            .ui
+            .playback
+            .add_radio_songs(strategy, &self.library.songs, &stats);
+        self.sync_player(&delta);
+
+        if self.ui.get_mode() == Mode::Queue {
+            self.ui.set_legal_songs();
+        }
+    }
+
+    /// Write the queue to the database if it changed since it was last saved
+    pub(super) fn persist_queue(&mut self) {
+        let current = (
+            self.ui.playback.queue_entries(),
+            self.ui.playback.get_play_mode(),
+        );
+
+        if current != self.saved_queue {
+            self.ui.save_queue(current.0.clone(), current.1);
+            self.saved_queue = current;
+        }
     }
 
     /// Ensure that player's up_next value is always synced
     pub fn sync_player(&self, delta: &QueueDelta) {
-        if self.ui.playback.repeat_is_enabled() {
+        if self.ui.playback.repeats_song() {
             return;
         }
         if let QueueDelta::HeadChanged { curr, .. } = delta {
-            let next = curr.as_ref().map(|s| VoxioTrack::new(s.id(), s.path()));
+            let next = curr.as_ref().map(|s| VoxioTrack::from(s.as_ref()));
             let _ = self.player.set_next(next);
         }
     }
@@ -3,11 +3,13 @@ use std::{sync::Arc, time::Duration};
 
 use crate::{
     app_core::NoctaVox,
+    ipc::{IpcEvent, IpcSong},
     key_handler::SelectionType,
     library::{SimpleSong, SongDatabase, SongInfo},
+    mpd::Subsystem,
     playback::ValidatedSong,
     player::{PlaybackState, PlayerEvent, VoxioTrack},
-    ui_state::{LibraryView, Mode},
+    ui_state::{CoverLoad, LibraryView, Mode, UiState},
 };
 
 impl NoctaVox {
@@ -42,6 +44,7 @@ impl NoctaVox {
     }
 
     pub(crate) fn play_next(&mut self) -> Result<()> {
+        self.fill_radio();
         let (delta, next, current) = self.ui.playback.advance();
 
         match next {
@@ -77,9 +80,76 @@ impl NoctaVox {
 
     pub fn stop(&mut self) -> Result<()> {
         self.ui.playback.clear_queue();
+
+        // Ended here so the stop event neither tops up the radio nor repeats
+        // the song
+        if let Some(song) = self.ui.playback.end_current() {
+            self.ui.insert_history_entry(song.get_id());
+        }
         self.player.stop()
     }
 
+    pub(crate) fn set_volume(&mut self, volume: u8) -> Result<()> {
+        self.player.set_volume(volume)?;
+        self.sync_media_volume();
+        Ok(())
+    }
+
+    pub(crate) fn adjust_volume(&mut self, delta: i16) -> Result<()> {
+        self.player.adjust_volume(delta)?;
+        self.sync_media_volume();
+        Ok(())
+    }
+
+    pub(crate) fn toggle_mute(&mut self) -> Result<()> {
+        self.player.toggle_mute()?;
+        self.sync_media_volume();
+        Ok(())
+    }
+
+    /// Equalizer settings live in the UI, the player only receives the gains
+    pub(crate) fn update_equalizer(&mut self, update: impl FnOnce(&mut UiState)) -> Result<()> {
+        update(&mut self.ui);
+        self.player.set_equalizer(self.ui.get_eq_gains())
+    }
+
+    pub(super) fn sync_media_volume(&mut self) {
+        let volume = match self.player.is_muted() {
+            true => 0.0,
+            false => self.player.volume() as f64 / 100.0,
+        };
+
+        if let Some(mc) = self.media_controls.as_mut() {
+            mc.set_volume(volume);
+        }
+    }
+
+    /// The cover is requested alongside, the metadata is sent again once it
+    /// has been extracted
+    pub(super) fn update_media_metadata(&mut self, song: &Arc<SimpleSong>) {
+        let cover = self.ui.get_cover(song, true);
+
+        if let Some(mc) = self.media_controls.as_mut() {
+            mc.update_metadata(
+                song.get_title(),
+                song.get_artist(),
+                song.get_album(),
+                song.get_duration(),
+                cover.as_ref().map(|c| c.path.as_path()),
+            );
+        }
+    }
+
+    pub(super) fn handle_cover_result(&mut self, key: u64, load: CoverLoad) {
+        let loaded = matches!(load, CoverLoad::Loaded(_));
+        self.ui.handle_cover_result(key, load);
+
+        let now_playing = self.ui.playback.get_now_playing().cloned();
+        if let Some(song) = now_playing.filter(|s| loaded && s.cover_key() == key) {
+            self.update_media_metadata(&song);
+        }
+    }
+
     pub fn remove_song(&mut self) -> Result<()> {
         match self.ui.get_mode() {
             Mode::Queue => match self.ui.multi_select_empty() {
@@ -121,7 +191,7 @@ impl NoctaVox {
         match event {
             PlayerEvent::TrackStarted((prev_song, was_gapless)) => {
                 let last_played_id = prev_song.id();
-                let is_repeat = self.ui.playback.repeat_is_enabled();
+                let is_repeat = self.ui.playback.repeats_song();
 
                 if was_gapless && !is_repeat {
                     self.advance_to_next_gapless();
@@ -129,6 +199,7 @@ impl NoctaVox {
 
                 let song = self.library.get_song_by_id(last_played_id).cloned();
                 self.ui.set_now_playing(song);
+                self.fill_radio();
 
                 if is_repeat {
                     self.player.set_next(Some(prev_song.clone()))?;
@@ -136,22 +207,28 @@ impl NoctaVox {
 
                 let is_restore = self.restored_song_id.take() == Some(last_played_id);
                 if let Some(song) = self.library.get_song_by_id(last_played_id).cloned() {
+                    self.broadcast_ipc(IpcEvent::TrackStarted {
+                        song: IpcSong::from(song.as_ref()),
+                    });
+                    self.notify_mpd(&[Subsystem::Player]);
+                    self.sync_mpd();
+
                     if !is_restore {
                         song.update_play_count()?;
                     }
 
+                    if let Some(scrobbler) = self.scrobbler.as_mut() {
+                        scrobbler.track_started(&song);
+                    }
+
                     // Update if not on repeat and not gapless
                     if !(is_repeat && was_gapless) {
                         self.ui.clear_waveform();
                         self.ui.request_waveform(&song);
+                        self.ui.request_lyrics(&song);
 
+                        self.update_media_metadata(&song);
                         if let Some(mc) = self.media_controls.as_mut() {
-                            mc.update_metadata(
-                                song.get_title(),
-                                song.get_artist(),
-                                song.get_album(),
-                                song.get_duration(),
-                            );
                             mc.set_playing(Duration::ZERO);
                         }
                     }
@@ -160,6 +237,7 @@ impl NoctaVox {
                 Ok(())
             }
             PlayerEvent::PlaybackStopped => {
+                self.fill_radio();
                 let (delta, next, current) = self.ui.playback.advance();
 
                 if let Some(np) = current {
@@ -175,6 +253,11 @@ impl NoctaVox {
                 if let Some(mc) = self.media_controls.as_mut() {
                     mc.set_stopped();
                 }
+                if let Some(scrobbler) = self.scrobbler.as_mut() {
+                    scrobbler.stopped();
+                }
+                self.broadcast_ipc(IpcEvent::Stopped);
+                self.notify_mpd(&[Subsystem::Player]);
 
                 if self.ui.get_mode() == Mode::Fullscreen {
                     self.ui.revert_fullscreen();
@@ -182,15 +265,22 @@ impl NoctaVox {
 
                 self.ui.set_now_playing(None);
                 self.ui.clear_waveform();
+                self.ui.clear_lyrics();
                 self.ui.set_legal_songs();
 
                 Ok(())
             }
             PlayerEvent::Error(e) => {
+                self.broadcast_ipc(IpcEvent::Error { message: e.clone() });
                 self.ui.set_error(anyhow!(e));
                 Ok(())
             }
             PlayerEvent::StateChanged(state) => {
+                self.broadcast_ipc(IpcEvent::StateChanged {
+                    status: (&state).into(),
+                });
+                self.notify_mpd(&[Subsystem::Player]);
+
                 if let Some(mc) = self.media_controls.as_mut() {
                     let elapsed = self.player.elapsed();
                     match state {
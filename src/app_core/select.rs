@@ -22,6 +22,32 @@ impl NoctaVox {
                 }
             }
 
+            recv(self.tag_edit_rec.as_ref().unwrap_or(&never())) -> result => {
+                if let Ok(result) = result {
+                    self.handle_tag_edit(result)
+                }
+            }
+
+            recv(self.library_watcher_reciever().unwrap_or(&never())) -> update => {
+                match update {
+                    Ok(update) => self.handle_library_update(update),
+                    Err(_) => self.library_watcher = None,
+                }
+            }
+
+            recv(self.gain_scan.as_ref().unwrap_or(&never())) -> update => {
+                match update {
+                    Ok(update) => self.handle_library_update(update),
+                    Err(_) => self.gain_scan = None,
+                }
+            }
+
+            recv(self.waveform_scan_reciever().unwrap_or(&never())) -> progress => {
+                if let Ok(progress) = progress {
+                    self.handle_waveform_progress(progress)
+                }
+            }
+
             recv(&self.ui.wf_reciever().unwrap_or(&never())) -> result => {
                 if let Ok(res) = result {
                     let now_playing = &self.ui.playback.get_now_playing().cloned();
@@ -29,6 +55,18 @@ impl NoctaVox {
                 }
             }
 
+            recv(&self.ui.lyrics_reciever().unwrap_or(&never())) -> result => {
+                if let Ok(res) = result {
+                    self.ui.handle_lyrics_result(res);
+                }
+            }
+
+            recv(self.ui.cover_reciever()) -> result => {
+                if let Ok((key, load)) = result {
+                    self.handle_cover_result(key, load);
+                }
+            }
+
             recv(self.media_controls.as_ref().map(|m| m.event_rx()).unwrap_or(&never())) -> event => {
                 if let Ok(event) = event {
                     if let Err(e) = self.handle_media_control_event(event) {
@@ -37,6 +75,18 @@ impl NoctaVox {
                 }
             }
 
+            recv(self.ipc.as_ref().map(|i| i.request_rx()).unwrap_or(&never())) -> request => {
+                if let Ok(request) = request {
+                    self.handle_ipc_request(request);
+                }
+            }
+
+            recv(self.mpd.as_ref().map(|m| m.request_rx()).unwrap_or(&never())) -> request => {
+                if let Ok(request) = request {
+                    self.handle_mpd_request(request);
+                }
+            }
+
             recv(key_rx) -> key => {
                 if let Ok(key) = key {
                     if let Some(action) = key_handler::handle_key_event(key, &mut self.ui, &mut self.key_buffer) {
@@ -69,6 +119,10 @@ impl NoctaVox {
             MediaControlEvent::SeekBy(SeekDirection::Backward, dur) => {
                 self.player.seek_back(dur.as_secs())?
             }
+            MediaControlEvent::SetVolume(v) => {
+                self.player.set_muted(false)?;
+                self.set_volume((v.clamp(0.0, 1.0) * 100.0).round() as u8)?
+            }
             _ => {}
         }
         Ok(())
@@ -85,15 +139,28 @@ impl NoctaVox {
             self.ui.update_now_playing_elapsed();
         }
 
+        if self.tick_sync.is_multiple_of(timing().db_tick) {
+            self.fill_radio();
+            self.persist_queue();
+        }
+
         if self.tick_sync % timing().media_tick == 0 {
+            let elapsed = self.player.elapsed();
+            let playing = !self.player.is_paused() && !self.player.is_stopped();
+
             if let Some(ref mut mc) = self.media_controls {
-                let elapsed = self.player.elapsed();
                 if self.player.is_paused() {
                     mc.set_paused(elapsed);
-                } else if !self.player.is_stopped() {
+                } else if playing {
                     mc.set_playing(elapsed);
                 }
             }
+
+            if let Some(scrobbler) = self.scrobbler.as_mut().filter(|_| playing) {
+                scrobbler.update(elapsed);
+            }
+
+            self.sync_mpd();
         }
     }
 }
@@ -2,4 +2,7 @@ mod timing;
 mod user_config;
 
 pub use timing::{TIMING, Timing, timing};
-pub use user_config::UserConfig;
+pub use user_config::{
+    AlbumArtMode, CrossfadeCurve, RadioStrategy, ReplayGainMode, ScrobbleConfig, ScrobbleService,
+    UserConfig,
+};
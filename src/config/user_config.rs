@@ -1,6 +1,6 @@
 use anyhow::Context;
 use serde::Deserialize;
-use std::fs;
+use std::{collections::HashMap, fs};
 
 use crate::CONFIG_DIR;
 
@@ -19,6 +19,10 @@ pub struct UserConfig {
     )]
     pub history_capacity: usize,
 
+    /// Days of play history kept for the stats, 0 keeps all of it
+    #[serde(default = "defaults::history_retention_days")]
+    pub history_retention_days: u32,
+
     #[serde(default = "defaults::update_on_start")]
     pub update_on_start: bool,
 
@@ -27,6 +31,134 @@ pub struct UserConfig {
 
     #[serde(default = "defaults::broadcast")]
     pub broadcast: bool,
+
+    #[serde(default = "defaults::ipc")]
+    pub ipc: bool,
+
+    /// Serve the MPD protocol, so that MPD clients can control playback
+    #[serde(default = "defaults::mpd")]
+    pub mpd: bool,
+
+    #[serde(default = "defaults::mpd_address")]
+    pub mpd_address: String,
+
+    #[serde(default)]
+    pub replaygain: ReplayGainMode,
+
+    #[serde(default = "defaults::replaygain_preamp")]
+    pub replaygain_preamp: f32,
+
+    #[serde(default = "defaults::replaygain_prevent_clipping")]
+    pub replaygain_prevent_clipping: bool,
+
+    /// Measure the loudness of songs without ReplayGain tags in the background
+    #[serde(default = "defaults::replaygain_analysis")]
+    pub replaygain_analysis: bool,
+
+    /// Pick up changes to the library roots as they happen
+    #[serde(default = "defaults::watch_library")]
+    pub watch_library: bool,
+
+    /// Generate waveforms for the whole library in the background
+    #[serde(default = "defaults::precompute_waveforms")]
+    pub precompute_waveforms: bool,
+
+    /// Preload the next song so that it follows without a gap
+    #[serde(default = "defaults::gapless")]
+    pub gapless: bool,
+
+    /// Seconds the end of a song overlaps the start of the next, 0 disables
+    #[serde(
+        default = "defaults::crossfade",
+        deserialize_with = "deserialize_crossfade"
+    )]
+    pub crossfade: f32,
+
+    #[serde(default)]
+    pub crossfade_curve: CrossfadeCurve,
+
+    /// Keep playing songs picked from the library once the queue runs dry
+    #[serde(default)]
+    pub radio: RadioStrategy,
+
+    /// How album art is drawn, `auto` picks a protocol based on the terminal
+    #[serde(default)]
+    pub album_art: AlbumArtMode,
+
+    /// Read star ratings from POPM and FMPS_RATING tags, and write them back
+    #[serde(default = "defaults::rating_tags")]
+    pub rating_tags: bool,
+
+    /// Submit played songs to a ListenBrainz or Last.fm compatible service
+    #[serde(default)]
+    pub scrobble: Option<ScrobbleConfig>,
+
+    /// Raw `[keys.<context>]` tables, parsed into a `Keymap` on startup
+    #[serde(default)]
+    pub keys: HashMap<String, HashMap<String, String>>,
+}
+
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossfadeCurve {
+    Linear,
+    #[default]
+    EqualPower,
+}
+
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RadioStrategy {
+    #[default]
+    Off,
+    /// Songs by the album artist of the one playing
+    AlbumArtist,
+    /// Songs released within a few years of the one playing
+    Year,
+    /// Songs which haven't been played the longest, unplayed songs first
+    LeastRecent,
+    /// Songs picked at random, favoring those played most often
+    Weighted,
+}
+
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AlbumArtMode {
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    Iterm,
+    Halfblocks,
+    Off,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ScrobbleConfig {
+    pub service: ScrobbleService,
+    /// Overrides the service's API root, e.g. for a self-hosted instance
+    pub url: Option<String>,
+    /// ListenBrainz user token, or a Last.fm session key
+    pub token: String,
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrobbleService {
+    ListenBrainz,
+    LastFm,
 }
 
 mod defaults {
@@ -38,6 +170,10 @@ mod defaults {
         64
     }
 
+    pub fn history_retention_days() -> u32 {
+        730
+    }
+
     pub fn update_on_start() -> bool {
         true
     }
@@ -49,12 +185,63 @@ mod defaults {
     pub fn broadcast() -> bool {
         false
     }
+
+    pub fn ipc() -> bool {
+        true
+    }
+
+    pub fn mpd() -> bool {
+        false
+    }
+
+    pub fn mpd_address() -> String {
+        "127.0.0.1:6600".to_string()
+    }
+
+    pub fn replaygain_preamp() -> f32 {
+        0.0
+    }
+
+    pub fn replaygain_prevent_clipping() -> bool {
+        true
+    }
+
+    pub fn replaygain_analysis() -> bool {
+        false
+    }
+
+    pub fn watch_library() -> bool {
+        true
+    }
+
+    pub fn precompute_waveforms() -> bool {
+        false
+    }
+
+    pub fn gapless() -> bool {
+        true
+    }
+
+    pub fn crossfade() -> f32 {
+        0.0
+    }
+
+    pub fn rating_tags() -> bool {
+        false
+    }
 }
 
 fn deserialize_framerate<'de, D: serde::Deserializer<'de>>(d: D) -> Result<u16, D::Error> {
     u16::deserialize(d).map(|v| v.clamp(20, 360))
 }
 
+fn deserialize_crossfade<'de, D: serde::Deserializer<'de>>(d: D) -> Result<f32, D::Error> {
+    f32::deserialize(d).map(|v| match v.is_finite() {
+        true => v.clamp(0.0, 12.0),
+        false => 0.0,
+    })
+}
+
 fn deserialize_history<'de, D: serde::Deserializer<'de>>(d: D) -> Result<usize, D::Error> {
     usize::deserialize(d).map(|v| v.max(1024))
 }
@@ -64,9 +251,27 @@ impl Default for UserConfig {
         Self {
             framerate: defaults::framerate(),
             history_capacity: defaults::history(),
+            history_retention_days: defaults::history_retention_days(),
             update_on_start: defaults::update_on_start(),
             auto_resume: defaults::auto_resume(),
             broadcast: defaults::broadcast(),
+            ipc: defaults::ipc(),
+            mpd: defaults::mpd(),
+            mpd_address: defaults::mpd_address(),
+            replaygain: ReplayGainMode::default(),
+            replaygain_preamp: defaults::replaygain_preamp(),
+            replaygain_prevent_clipping: defaults::replaygain_prevent_clipping(),
+            replaygain_analysis: defaults::replaygain_analysis(),
+            watch_library: defaults::watch_library(),
+            precompute_waveforms: defaults::precompute_waveforms(),
+            gapless: defaults::gapless(),
+            crossfade: defaults::crossfade(),
+            crossfade_curve: CrossfadeCurve::default(),
+            radio: RadioStrategy::default(),
+            album_art: AlbumArtMode::default(),
+            rating_tags: defaults::rating_tags(),
+            scrobble: None,
+            keys: HashMap::new(),
         }
     }
 }
@@ -1,11 +1,11 @@
 use crate::{
     DB_PATH, SongMap,
-    database::schema::CREATE_SCHEMA,
-    library::{LongSong, SimpleSong, SongInfo},
+    database::schema::{CREATE_SCHEMA, MIGRATIONS},
+    library::{GainState, LongSong, ReplayGain, SimpleSong, SongInfo},
     ui_state::LibraryStats,
     user_config,
 };
-use anyhow::Result;
+use anyhow::{Result, bail};
 use queries::*;
 use rusqlite::{Connection, OptionalExtension, params};
 use std::{
@@ -19,8 +19,13 @@ use std::{
 
 mod playlists;
 mod queries;
+mod queue;
+mod ratings;
+mod relink;
 mod schema;
+mod scrobbles;
 mod snapshot;
+mod stats;
 mod worker;
 
 pub(crate) const DB_BOUND: usize = 100;
@@ -33,6 +38,15 @@ pub struct Database {
     album_map: HashMap<i64, Arc<String>>,
 }
 
+/// The last song played, as stored by the most recent instance
+pub struct NowPlayingView {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
 impl Database {
     pub fn open() -> Result<Self> {
         match Self::_open() {
@@ -74,7 +88,38 @@ impl Database {
 
     fn create_tables(&mut self) -> Result<()> {
         let tx = self.conn.transaction()?;
+
+        let tables = tx
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(Result::ok)
+            .collect::<HashSet<String>>();
+
         tx.execute_batch(&CREATE_SCHEMA)?;
+
+        // Songs scanned before genres were stored have theirs read on the
+        // next library update
+        if tables.contains("songs") && !tables.contains("genres") {
+            tx.execute(SET_GENRE_BACKFILL, [])?;
+        }
+
+        let columns = tx
+            .prepare(
+                "SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p
+                 WHERE m.type = 'table'",
+            )?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(Result::ok)
+            .collect::<HashSet<(String, String)>>();
+
+        for (table, column, migration) in MIGRATIONS {
+            if !columns.contains(&(table.to_string(), column.to_string())) {
+                tx.execute(migration, [])?;
+            }
+        }
+
         tx.commit()?;
 
         Ok(())
@@ -124,8 +169,15 @@ impl Database {
                     &song.channels,
                     &song.bitrate,
                     &song.sample_rate,
-                    &song.filetype
+                    &song.filetype,
+                    &song.replay_gain.track_gain,
+                    &song.replay_gain.track_peak,
+                    &song.replay_gain.album_gain,
+                    &song.replay_gain.album_peak,
+                    song.replay_gain.state() as u8,
                 ])?;
+                set_genres(&tx, song.id, &song.genres)?;
+                import_rating(&tx, song)?;
             }
         }
 
@@ -174,6 +226,12 @@ impl Database {
                     disc_no: row.get("disc_no")?,
                     duration: Duration::from_secs_f32(row.get("duration")?),
                     filetype: row.get("format")?,
+                    gain: ReplayGain {
+                        track_gain: row.get("track_gain")?,
+                        track_peak: row.get("track_peak")?,
+                        album_gain: row.get("album_gain")?,
+                        album_peak: row.get("album_peak")?,
+                    },
                 };
 
                 Ok((hash, Arc::new(song)))
@@ -184,6 +242,37 @@ impl Database {
         Ok(songs)
     }
 
+    pub(crate) fn get_genres(&mut self) -> Result<HashMap<u64, Vec<String>>> {
+        let mut genres: HashMap<u64, Vec<String>> = HashMap::new();
+        let mut stmt = self.conn.prepare(GET_GENRES)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((convert_from_bytes(row.get(0)?), row.get::<_, String>(1)?))
+        })?;
+
+        for (id, name) in rows.filter_map(Result::ok) {
+            genres.entry(id).or_default().push(name);
+        }
+
+        Ok(genres)
+    }
+
+    pub(crate) fn genre_backfill_pending(&self) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(GENRE_BACKFILL_PENDING, [], |r| r.get(0))?)
+    }
+
+    /// Store the genres of songs which were scanned before genres were kept
+    pub(crate) fn backfill_genres(&mut self, genres: &[(u64, Vec<String>)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for (id, names) in genres {
+            set_genres(&tx, *id, names)?;
+        }
+        tx.execute(CLEAR_GENRE_BACKFILL, [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
     pub(crate) fn delete_songs(&mut self, to_delete: &[u64]) -> Result<()> {
         let tx = self.conn.transaction()?;
         {
@@ -196,6 +285,89 @@ impl Database {
         Ok(())
     }
 
+    /// Ids of the songs at `paths`, or beneath them
+    pub(crate) fn get_ids_by_path(&mut self, paths: &[PathBuf]) -> Result<HashSet<u64>> {
+        let mut stmt = self.conn.prepare(GET_IDS_BY_PATH)?;
+        let mut ids = HashSet::new();
+        for path in paths {
+            let path = path.to_string_lossy();
+            let dir = format!("{}{}", path, std::path::MAIN_SEPARATOR);
+            let rows = stmt.query_map(params![path, dir], |row| {
+                Ok(convert_from_bytes(row.get("id")?))
+            })?;
+            ids.extend(rows.filter_map(Result::ok));
+        }
+        Ok(ids)
+    }
+
+    /// Removes songs at the given paths, or anywhere beneath them
+    pub(crate) fn delete_songs_by_path(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(DELETE_SONGS_BY_PATH)?;
+            for path in paths {
+                let path = path.to_string_lossy();
+                let dir = format!("{}{}", path, std::path::MAIN_SEPARATOR);
+                stmt.execute(params![path, dir])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the re-read metadata of a song whose tags were edited. The row is
+    /// updated in place so playlists, history and play counts follow it to
+    /// its new signature
+    pub(crate) fn update_song_tags(&mut self, old_id: u64, song: &LongSong) -> Result<()> {
+        self.insert_artists(&HashSet::from([
+            song.artist.as_str(),
+            song.album_artist.as_str(),
+        ]))?;
+        self.insert_albums(&HashSet::from([(
+            song.album_artist.as_str(),
+            song.album.as_str(),
+        )]))?;
+
+        let artist_map = self.get_artist_map_name_to_id()?;
+        let album_map = self.get_album_map_name_to_id()?;
+
+        let artist_id = artist_map.get(song.artist.as_str());
+        let album_id = artist_map
+            .get(song.album_artist.as_str())
+            .and_then(|aid| album_map.get(&(song.album.to_string(), *aid)));
+
+        let (Some(artist_id), Some(album_id)) = (artist_id, album_id) else {
+            bail!("Could not resolve the artist or album of {}", song.title);
+        };
+
+        let (old_id, new_id) = (old_id.to_le_bytes(), song.id.to_le_bytes());
+
+        let tx = self.conn.transaction()?;
+        // Songs are referenced without ON UPDATE CASCADE
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        tx.execute(
+            UPDATE_SONG_TAGS,
+            params![
+                new_id,
+                &song.title,
+                &song.year,
+                artist_id,
+                album_id,
+                &song.track_no,
+                &song.disc_no,
+                old_id,
+            ],
+        )?;
+        for query in REASSIGN_SONG_ID {
+            tx.execute(query, params![new_id, old_id])?;
+        }
+        tx.execute(DELETE_GENRES, [old_id])?;
+        set_genres(&tx, song.id, &song.genres)?;
+        import_rating(&tx, song)?;
+        tx.commit()?;
+        Ok(())
+    }
+
     pub(crate) fn update_play_count(&mut self, id: u64) -> Result<()> {
         let id = id.to_le_bytes();
         self.conn.execute(UPDATE_PLAY_COUNT, params![id])?;
@@ -210,6 +382,61 @@ impl Database {
         Ok(output)
     }
 
+    /// Returns (id, path, album_id) of every song in the given state,
+    /// grouped by album
+    pub(crate) fn get_songs_by_gain_state(
+        &mut self,
+        state: GainState,
+    ) -> Result<Vec<(u64, String, i64)>> {
+        let songs = self
+            .conn
+            .prepare(GET_SONGS_BY_GAIN_STATE)?
+            .query_map([state as u8], |row| {
+                Ok((
+                    convert_from_bytes(row.get("id")?),
+                    row.get("path")?,
+                    row.get("album_id")?,
+                ))
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(songs)
+    }
+
+    pub(crate) fn set_replay_gain(
+        &mut self,
+        id: u64,
+        gain: &ReplayGain,
+        state: GainState,
+    ) -> Result<()> {
+        self.conn.execute(
+            SET_REPLAY_GAIN,
+            params![
+                id.to_le_bytes(),
+                gain.track_gain,
+                gain.track_peak,
+                gain.album_gain,
+                gain.album_peak,
+                state as u8,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn get_song_paths(&mut self) -> Result<HashMap<u64, String>> {
+        let map = self
+            .conn
+            .prepare(GET_ALL_PATHS)?
+            .query_map([], |row| {
+                Ok((convert_from_bytes(row.get("id")?), row.get("path")?))
+            })?
+            .filter_map(Result::ok)
+            .collect::<HashMap<u64, String>>();
+
+        Ok(map)
+    }
+
     pub(crate) fn get_hashes(&mut self) -> Result<HashSet<u64>> {
         let map = self
             .conn
@@ -349,12 +576,33 @@ impl Database {
         Ok(())
     }
 
+    /// Returns (id, path) of every song without a cached waveform
+    pub(crate) fn get_songs_without_waveform(&mut self) -> Result<Vec<(u64, String)>> {
+        let songs = self
+            .conn
+            .prepare(GET_SONGS_WITHOUT_WAVEFORM)?
+            .query_map([], |row| {
+                Ok((convert_from_bytes(row.get("id")?), row.get("path")?))
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(songs)
+    }
+
+    /// Forget which songs couldn't be decoded, so their waveforms are tried again
+    pub(crate) fn clear_failed_waveforms(&mut self) -> Result<()> {
+        self.conn.execute(DELETE_FAILED_WAVEFORMS, [])?;
+        Ok(())
+    }
+
     // ============
     //   HISTORY
     // ============
 
     pub fn insert_to_history(&mut self, song_id: u64) -> Result<()> {
         let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let retention = user_config().history_retention_days as i64;
 
         let tx = self.conn.transaction()?;
 
@@ -364,10 +612,9 @@ impl Database {
                 params![song_id.to_le_bytes(), timestamp],
             )?;
 
-            tx.execute(
-                HISTORY_CLEANUP,
-                params![user_config().history_capacity as u16],
-            )?;
+            if retention > 0 {
+                tx.execute(HISTORY_CLEANUP, params![timestamp - retention * 86400])?;
+            }
         }
 
         tx.commit()?;
@@ -496,6 +743,21 @@ impl Database {
         })?)
     }
 
+    pub fn get_now_playing_view(&self) -> Result<Option<NowPlayingView>> {
+        Ok(self
+            .conn
+            .query_row(GET_NOW_PLAYING_VIEW, [], |row| {
+                Ok(NowPlayingView {
+                    title: row.get(0)?,
+                    artist: row.get(1)?,
+                    album: row.get(2)?,
+                    duration: row.get(3)?,
+                    elapsed: row.get(4)?,
+                })
+            })
+            .optional()?)
+    }
+
     pub fn set_now_playing(&self, id: u64) -> Result<()> {
         let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
 
@@ -519,6 +781,28 @@ impl Database {
     }
 }
 
+/// Replace the genres stored for a song
+fn set_genres(conn: &Connection, id: u64, genres: &[String]) -> Result<()> {
+    let id = id.to_le_bytes();
+    conn.execute(DELETE_GENRES, [id])?;
+
+    let mut stmt = conn.prepare_cached(INSERT_GENRE)?;
+    for genre in genres {
+        stmt.execute(params![id, genre])?;
+    }
+    Ok(())
+}
+
+/// Take on the stars from a song's rating tag, if those are to be read
+fn import_rating(conn: &Connection, song: &LongSong) -> Result<()> {
+    if let Some(stars) = song.rating
+        && user_config().rating_tags
+    {
+        conn.execute(SET_STARS, params![song.id.to_le_bytes(), stars])?;
+    }
+    Ok(())
+}
+
 #[inline]
 fn convert_from_bytes(raw_bytes: Vec<u8>) -> u64 {
     match raw_bytes.try_into() {
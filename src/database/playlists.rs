@@ -1,9 +1,15 @@
-use crate::{Database, database::queries::*};
+use crate::{
+    Database,
+    database::{convert_from_bytes, queries::*},
+    library::{Rating, SongStats},
+};
 use anyhow::Result;
 use indexmap::IndexMap;
 use rusqlite::params;
+use std::collections::HashMap;
 
-type PlaylistMap = IndexMap<(i64, String), Vec<(i64, u64)>>;
+/// Keyed by (id, name, smart playlist rules)
+pub(crate) type PlaylistMap = IndexMap<(i64, String, Option<String>), Vec<(i64, u64)>>;
 
 impl Database {
     pub fn create_playlist(&mut self, name: &str) -> Result<()> {
@@ -12,6 +18,43 @@ impl Database {
         Ok(())
     }
 
+    pub fn create_smart_playlist(&mut self, name: &str, rules: &str) -> Result<()> {
+        self.conn
+            .execute(CREATE_SMART_PLAYLIST, params![name, rules])?;
+
+        Ok(())
+    }
+
+    pub fn set_playlist_rules(&mut self, id: i64, rules: &str) -> Result<()> {
+        self.conn.execute(SET_PLAYLIST_RULES, params![rules, id])?;
+
+        Ok(())
+    }
+
+    /// Play counts, scan dates, last plays and ratings used to evaluate smart
+    /// playlists
+    pub fn get_song_stats(&mut self) -> Result<HashMap<u64, SongStats>> {
+        let stats = self
+            .conn
+            .prepare(GET_SONG_STATS)?
+            .query_map([], |row| {
+                let stats = SongStats {
+                    plays: row.get("plays")?,
+                    added: row.get("added_at")?,
+                    last_played: row.get("last_played")?,
+                    rating: Rating {
+                        stars: row.get("stars")?,
+                        favorite: row.get("favorite")?,
+                    },
+                };
+                Ok((convert_from_bytes(row.get("id")?), stats))
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(stats)
+    }
+
     pub fn delete_playlist(&mut self, id: i64) -> Result<()> {
         self.conn.execute(DELETE_PLAYLIST, params![id])?;
 
@@ -109,6 +152,7 @@ impl Database {
         let mut rows = stmt.query_map([], |r| {
             let ps_id: Option<i64> = r.get("id")?;
             let name: String = r.get("name")?;
+            let rules: Option<String> = r.get("rules")?;
             let playlist_id: i64 = r.get("playlist_id")?;
 
             let song_id: Option<u64> = match r.get::<_, Option<Vec<u8>>>("song_id")? {
@@ -125,12 +169,14 @@ impl Database {
                 None => None,
             };
 
-            Ok((playlist_id, song_id, ps_id, name))
+            Ok((playlist_id, song_id, ps_id, name, rules))
         })?;
 
         let playlist_map: PlaylistMap = rows.try_fold(PlaylistMap::new(), |mut map, row| {
-            let (playlist_id, song_id_opt, ps_id_opt, name) = row?;
-            let entry = map.entry((playlist_id, name)).or_insert_with(Vec::new);
+            let (playlist_id, song_id_opt, ps_id_opt, name, rules) = row?;
+            let entry = map
+                .entry((playlist_id, name, rules))
+                .or_insert_with(Vec::new);
             if let (Some(song_id), Some(ps_id)) = (song_id_opt, ps_id_opt) {
                 entry.push((ps_id, song_id));
             }
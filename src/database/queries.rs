@@ -8,6 +8,12 @@ pub const INSERT_WAVEFORM: &str = "
     VALUES (?1, ?2)
 ";
 
+pub const GET_SONGS_WITHOUT_WAVEFORM: &str = "
+    SELECT s.id, s.path FROM songs s
+    LEFT JOIN waveforms w ON w.song_id = s.id
+    WHERE w.song_id IS NULL
+";
+
 pub const GET_ALL_SONGS: &str = "
     SELECT
         s.id,
@@ -20,6 +26,10 @@ pub const GET_ALL_SONGS: &str = "
         s.artist_id,
         s.album_id,
         s.format,
+        s.track_gain,
+        s.track_peak,
+        s.album_gain,
+        s.album_peak,
         a.title as album,
         a.artist_id as album_artist
     from songs s
@@ -44,8 +54,15 @@ pub const INSERT_SONG: &str = "
         channels,
         bit_rate,
         sample_rate, 
-        format
-    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13
+        format,
+        track_gain,
+        track_peak,
+        album_gain,
+        album_peak,
+        gain_state,
+        added_at
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+        COALESCE((SELECT added_at FROM songs WHERE path = ?4), strftime('%s', 'now'))
 )";
 
 pub const INSERT_ARTIST: &str = "
@@ -61,11 +78,51 @@ pub const INSERT_ALBUM: &str = "
 ) VALUES (?1, ?2)
 ";
 
+pub const INSERT_GENRE: &str = "
+    INSERT OR IGNORE INTO genres (song_id, name) VALUES (?1, ?2)
+";
+
+pub const DELETE_GENRES: &str = "
+    DELETE FROM genres WHERE song_id = ?
+";
+
+pub const GET_GENRES: &str = "
+    SELECT song_id, name FROM genres
+";
+
+pub const GENRE_BACKFILL_PENDING: &str = "
+    SELECT EXISTS(SELECT 1 FROM scan_cache WHERE key = 'genre_backfill')
+";
+
+pub const SET_GENRE_BACKFILL: &str = "
+    INSERT OR REPLACE INTO scan_cache (key, value) VALUES ('genre_backfill', 1)
+";
+
+pub const CLEAR_GENRE_BACKFILL: &str = "
+    DELETE FROM scan_cache WHERE key = 'genre_backfill'
+";
+
 pub const GET_PATH: &str = "
     SELECT path FROM songs
     WHERE id = ?
 ";
 
+pub const GET_SONGS_BY_GAIN_STATE: &str = "
+    SELECT id, path, album_id FROM songs
+    WHERE gain_state = ?
+    ORDER BY album_id
+";
+
+pub const SET_REPLAY_GAIN: &str = "
+    UPDATE songs
+    SET track_gain = ?2, track_peak = ?3, album_gain = ?4, album_peak = ?5, gain_state = ?6
+    WHERE id = ?1
+";
+
+pub const GET_ALL_PATHS: &str = "
+    SELECT id, path FROM songs
+";
+
 pub const GET_ARTIST_MAP: &str = "
     SELECT id, name FROM artists
 ";
@@ -101,6 +158,83 @@ pub const DELETE_SONGS: &str = "
     DELETE FROM songs WHERE id = ?
 ";
 
+pub const DELETE_SONGS_BY_PATH: &str = "
+    DELETE FROM songs
+    WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2
+";
+
+pub const GET_IDS_BY_PATH: &str = "
+    SELECT id FROM songs
+    WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2
+";
+
+pub const UPDATE_SONG_TAGS: &str = "
+    UPDATE songs SET
+        id = ?1,
+        title = ?2,
+        year = ?3,
+        artist_id = ?4,
+        album_id = ?5,
+        track_no = ?6,
+        disc_no = ?7
+    WHERE id = ?8
+";
+
+pub const DELETE_FAILED_WAVEFORM: &str = "
+    DELETE FROM waveforms WHERE song_id = ? AND length(waveform) = 0
+";
+
+pub const DELETE_FAILED_WAVEFORMS: &str = "
+    DELETE FROM waveforms WHERE length(waveform) = 0
+";
+
+pub const REASSIGN_SONG_ID: [&str; 7] = [
+    "UPDATE history SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE plays SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE ratings SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE waveforms SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE playlist_songs SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE now_playing SET song_id = ?1 WHERE song_id = ?2",
+    "UPDATE queue SET song_id = ?1 WHERE song_id = ?2",
+];
+
+pub const GET_SONG_IDENTITY: &str = "
+    SELECT
+        s.id,
+        s.path,
+        s.title,
+        ar.name AS artist,
+        al.title AS album,
+        s.duration
+    FROM songs s
+    LEFT JOIN artists ar ON ar.id = s.artist_id
+    LEFT JOIN albums al ON al.id = s.album_id
+    WHERE s.id = ?
+";
+
+pub const RELINK_SONG: &str = "
+    UPDATE songs SET
+        id = ?1,
+        title = ?2,
+        year = ?3,
+        path = ?4,
+        artist_id = ?5,
+        album_id = ?6,
+        track_no = ?7,
+        disc_no = ?8,
+        duration = ?9,
+        channels = ?10,
+        bit_rate = ?11,
+        sample_rate = ?12,
+        format = ?13,
+        track_gain = COALESCE(?14, track_gain),
+        track_peak = COALESCE(?15, track_peak),
+        album_gain = COALESCE(?16, album_gain),
+        album_peak = COALESCE(?17, album_peak),
+        gain_state = MAX(?18, gain_state)
+    WHERE id = ?19
+";
+
 pub const LOAD_HISTORY: &str = "
     SELECT song_id FROM history
     ORDER BY timestamp DESC
@@ -111,13 +245,12 @@ pub const INSERT_INTO_HISTORY: &str = "
     INSERT INTO history (song_id, timestamp) VALUES (?, ?)";
 
 pub const HISTORY_CLEANUP: &str = "
-    DELETE FROM history WHERE id NOT IN 
-        (SELECT id FROM history ORDER BY timestamp DESC LIMIT ?)
+    DELETE FROM history WHERE timestamp < ?
 ";
 
 pub const HISTORY_DELETE_LATEST: &str = "
     DELETE FROM history WHERE id = 
-    (SELECT id FROM history ORDER BY timestamp LIMIT 1)
+    (SELECT id FROM history ORDER BY timestamp DESC, id DESC LIMIT 1)
 ";
 
 pub const UPDATE_PLAY_COUNT: &str = "
@@ -144,6 +277,17 @@ pub const CREATE_NEW_PLAYLIST: &str = "
         VALUES (?, strftime('%s', 'now'))
 ";
 
+pub const CREATE_SMART_PLAYLIST: &str = "
+    INSERT OR IGNORE INTO playlists (name, rules, updated_at)
+        VALUES (?, ?, strftime('%s', 'now'))
+";
+
+pub const SET_PLAYLIST_RULES: &str = "
+    UPDATE playlists
+        SET rules = ?, updated_at = strftime('%s', 'now')
+        WHERE id = ?
+";
+
 pub const UPDATE_PLAYLIST: &str = "
     UPDATE playlists
         SET updated_at = strftime('%s', 'now')
@@ -192,7 +336,8 @@ pub const PLAYLIST_BUILDER: &str = "
         ps.id,
         ps.song_id, 
         p.id as playlist_id, 
-        p.name 
+        p.name,
+        p.rules
     FROM playlists p
     LEFT JOIN playlist_songs ps 
         ON p.id = ps.playlist_id
@@ -216,6 +361,23 @@ pub const RENAME_PLAYLIST: &str = "
     UPDATE playlists SET name = ? WHERE id = ?
 ";
 
+pub const GET_SONG_STATS: &str = "
+    SELECT
+        s.id,
+        s.added_at,
+        COALESCE(p.count, 0) AS plays,
+        h.last_played,
+        COALESCE(r.stars, 0) AS stars,
+        COALESCE(r.favorite, 0) AS favorite
+    FROM songs s
+    LEFT JOIN plays p ON p.song_id = s.id
+    LEFT JOIN ratings r ON r.song_id = s.id
+    LEFT JOIN (
+        SELECT song_id, MAX(timestamp) AS last_played
+        FROM history GROUP BY song_id
+    ) h ON h.song_id = s.id
+";
+
 pub const GET_STATS: &str = "
     SELECT
         (SELECT COUNT(*) FROM songs)                  AS total_tracks,
@@ -241,6 +403,65 @@ pub const GET_TOP_SONGS: &str = "
     LIMIT ?
 ";
 
+pub const GET_PLAYS_SINCE: &str = "
+    SELECT song_id, COUNT(*) AS plays
+    FROM history
+    WHERE timestamp >= ?
+    GROUP BY song_id
+";
+
+pub const GET_DAILY_LISTENING: &str = "
+    SELECT
+        CAST(strftime('%s', date(h.timestamp, 'unixepoch', 'localtime')) AS INTEGER)
+            / 86400                                       AS day,
+        date(h.timestamp, 'unixepoch', 'localtime')       AS date,
+        COUNT(*)                                          AS plays,
+        SUM(s.duration)                                   AS seconds
+    FROM history h
+    INNER JOIN songs s ON s.id = h.song_id
+    GROUP BY day
+    ORDER BY day
+";
+
+pub const GET_TODAY: &str = "
+    SELECT CAST(strftime('%s', date('now', 'localtime')) AS INTEGER) / 86400
+";
+
+pub const GET_MISSING_BITRATES: &str = "
+    SELECT id, path, duration FROM songs WHERE bit_rate IS NULL
+";
+
+pub const SET_BITRATE: &str = "
+    UPDATE songs SET bit_rate = ? WHERE id = ?
+";
+
+pub const GET_FORMATS: &str = "
+    SELECT format, bit_rate, duration FROM songs
+";
+
+pub const GET_RATINGS: &str = "
+    SELECT song_id, stars, favorite FROM ratings
+    WHERE stars > 0 OR favorite = 1
+";
+
+pub const SET_STARS: &str = "
+    INSERT INTO ratings (song_id, stars) VALUES (?1, ?2)
+    ON CONFLICT(song_id) DO UPDATE SET stars = excluded.stars
+";
+
+pub const SET_FAVORITE: &str = "
+    INSERT INTO ratings (song_id, favorite) VALUES (?1, ?2)
+    ON CONFLICT(song_id) DO UPDATE SET favorite = excluded.favorite
+";
+
+pub const RATINGS_IMPORTED: &str = "
+    SELECT EXISTS(SELECT 1 FROM scan_cache WHERE key = 'rating_import')
+";
+
+pub const SET_RATINGS_IMPORTED: &str = "
+    INSERT OR REPLACE INTO scan_cache (key, value) VALUES ('rating_import', 1)
+";
+
 pub const GET_LAST_SCAN: &str = "
     SELECT value FROM scan_cache WHERE key = 'last_scan'
 ";
@@ -267,6 +488,52 @@ pub const GET_NOW_PLAYING: &str = "
     FROM now_playing WHERE id = 1
 ";
 
+pub const GET_NOW_PLAYING_VIEW: &str = "
+    SELECT 
+        title, 
+        COALESCE(artist, ''), 
+        COALESCE(album, ''), 
+        duration_secs, 
+        position_secs
+    FROM now_playing_v1
+";
+
 pub const CLEAR_NOW_PLAYING: &str = "
     DELETE FROM now_playing
 ";
+
+pub const CLEAR_QUEUE: &str = "
+    DELETE FROM queue
+";
+
+pub const INSERT_QUEUE_ENTRY: &str = "
+    INSERT INTO queue (position, song_id, path, unshuffled_pos, auto_added)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+";
+
+pub const GET_QUEUE: &str = "
+    SELECT song_id, path, unshuffled_pos, auto_added
+    FROM queue
+    ORDER BY position
+";
+
+pub const GET_PLAY_MODE: &str = "
+    SELECT value FROM session_state
+    WHERE key = 'play_mode'
+";
+
+pub const INSERT_SCROBBLE: &str = "
+    INSERT INTO scrobbles (title, artist, album, album_artist, track_no, duration, timestamp)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+";
+
+pub const GET_PENDING_SCROBBLES: &str = "
+    SELECT id, title, artist, album, album_artist, track_no, duration, timestamp
+    FROM scrobbles
+    ORDER BY timestamp
+    LIMIT ?1
+";
+
+pub const DELETE_SCROBBLE: &str = "
+    DELETE FROM scrobbles WHERE id = ?1
+";
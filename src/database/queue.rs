@@ -0,0 +1,57 @@
+use super::convert_from_bytes;
+use crate::{
+    Database,
+    database::queries::*,
+    playback::{PlayMode, SavedEntry},
+};
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+
+impl Database {
+    /// Replace the stored queue with `entries`, in the order they're played
+    pub(crate) fn save_queue(&mut self, entries: &[SavedEntry], mode: PlayMode) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            tx.execute(CLEAR_QUEUE, [])?;
+            let mut stmt = tx.prepare(INSERT_QUEUE_ENTRY)?;
+            for (position, entry) in entries.iter().enumerate() {
+                stmt.execute(params![
+                    position as i64,
+                    entry.id.to_le_bytes(),
+                    entry.path,
+                    entry.unshuffled_pos.map(|p| p as i64),
+                    entry.auto_added,
+                ])?;
+            }
+            tx.execute(SET_SESSION_STATE, params!["play_mode", mode.key()])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The queue as it was last saved, along with the play mode
+    pub(crate) fn load_queue(&mut self) -> Result<(Vec<SavedEntry>, PlayMode)> {
+        let entries = self
+            .conn
+            .prepare(GET_QUEUE)?
+            .query_map([], |row| {
+                Ok(SavedEntry {
+                    id: convert_from_bytes(row.get("song_id")?),
+                    path: row.get("path")?,
+                    unshuffled_pos: row
+                        .get::<_, Option<i64>>("unshuffled_pos")?
+                        .map(|p| p as usize),
+                    auto_added: row.get("auto_added")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mode = self
+            .conn
+            .query_row(GET_PLAY_MODE, [], |row| row.get::<_, String>(0))
+            .optional()?
+            .map_or(PlayMode::Off, |key| PlayMode::from_key(&key));
+
+        Ok((entries, mode))
+    }
+}
@@ -0,0 +1,70 @@
+use super::convert_from_bytes;
+use crate::{Database, database::queries::*, library::Rating};
+use anyhow::Result;
+use rusqlite::params;
+use std::collections::HashMap;
+
+impl Database {
+    /// Every song which has been rated or marked as a favorite
+    pub(crate) fn get_ratings(&mut self) -> Result<HashMap<u64, Rating>> {
+        let ratings = self
+            .conn
+            .prepare(GET_RATINGS)?
+            .query_map([], |row| {
+                let rating = Rating {
+                    stars: row.get("stars")?,
+                    favorite: row.get("favorite")?,
+                };
+                Ok((convert_from_bytes(row.get("song_id")?), rating))
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(ratings)
+    }
+
+    pub(crate) fn ratings_imported(&self) -> Result<bool> {
+        Ok(self.conn.query_row(RATINGS_IMPORTED, [], |r| r.get(0))?)
+    }
+
+    /// Store the stars read from the rating tags of the whole library
+    pub(crate) fn import_ratings(&mut self, ratings: &[(u64, u8)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(SET_STARS)?;
+            for (id, stars) in ratings {
+                stmt.execute(params![id.to_le_bytes(), stars])?;
+            }
+        }
+        tx.execute(SET_RATINGS_IMPORTED, [])?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub(crate) fn set_stars(&mut self, ids: &[u64], stars: u8) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(SET_STARS)?;
+            for id in ids {
+                stmt.execute(params![id.to_le_bytes(), stars])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub(crate) fn set_favorite(&mut self, ids: &[u64], favorite: bool) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(SET_FAVORITE)?;
+            for id in ids {
+                stmt.execute(params![id.to_le_bytes(), favorite])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    Database,
+    database::{import_rating, queries::*, set_genres},
+    library::{LongSong, SongInfo},
+};
+use anyhow::Result;
+use rusqlite::params;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// Leeway when matching the duration of a moved song against its old entry
+const DURATION_TOLERANCE: f32 = 1.0;
+
+impl Database {
+    /// Carry stale songs over to the entries of files which were moved,
+    /// renamed or modified, so that their plays, history, waveforms and
+    /// playlist entries survive the rescan.
+    ///
+    /// A stale song is matched by path when its file was modified in place,
+    /// otherwise by title, artist, album and duration once its file is gone.
+    /// Matched songs are taken out of `songs`, returning their old and new ids
+    pub(crate) fn relink_songs(
+        &mut self,
+        stale: &HashSet<u64>,
+        songs: &mut Vec<LongSong>,
+    ) -> Result<HashMap<u64, u64>> {
+        let mut relinked = HashMap::new();
+        if stale.is_empty() || songs.is_empty() {
+            return Ok(relinked);
+        }
+
+        let new_paths = songs
+            .iter()
+            .filter_map(|s| s.path.to_str())
+            .collect::<HashSet<_>>();
+
+        let mut by_path = HashMap::new();
+        let mut by_tags: HashMap<(String, String, String), Vec<(u64, f32)>> = HashMap::new();
+        {
+            let mut stmt = self.conn.prepare(GET_SONG_IDENTITY)?;
+            let rows = stale.iter().filter_map(|id| {
+                stmt.query_row([id.to_le_bytes()], |row| {
+                    Ok((
+                        *id,
+                        row.get::<_, String>("path")?,
+                        row.get::<_, String>("title")?,
+                        row.get::<_, Option<String>>("artist")?.unwrap_or_default(),
+                        row.get::<_, Option<String>>("album")?.unwrap_or_default(),
+                        row.get::<_, Option<f32>>("duration")?.unwrap_or_default(),
+                    ))
+                })
+                .ok()
+            });
+
+            for (id, path, title, artist, album, duration) in rows {
+                match new_paths.contains(path.as_str()) {
+                    true => {
+                        by_path.insert(path, id);
+                    }
+                    false if !Path::new(&path).exists() => by_tags
+                        .entry((title, artist, album))
+                        .or_default()
+                        .push((id, duration)),
+                    false => {}
+                }
+            }
+        }
+
+        let mut matches = Vec::new();
+        for song in songs.iter() {
+            let by_path = song.path.to_str().and_then(|p| by_path.remove(p));
+            let id = by_path.or_else(|| {
+                let key = (
+                    song.title.clone(),
+                    song.get_artist().to_string(),
+                    song.get_album().to_string(),
+                );
+                let candidates = by_tags.get_mut(&key)?;
+                let duration = song.duration.as_secs_f32();
+                let idx = candidates
+                    .iter()
+                    .position(|(_, d)| (d - duration).abs() < DURATION_TOLERANCE)?;
+
+                Some(candidates.swap_remove(idx).0)
+            });
+
+            if let Some(id) = id {
+                matches.push((id, song));
+            }
+        }
+
+        if matches.is_empty() {
+            return Ok(relinked);
+        }
+
+        let artist_map = self.get_artist_map_name_to_id()?;
+        let album_map = self.get_album_map_name_to_id()?;
+
+        let tx = self.conn.transaction()?;
+        // Songs are referenced without ON UPDATE CASCADE
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        {
+            let mut relink = tx.prepare(RELINK_SONG)?;
+            for (old_id, song) in matches {
+                let artist_id = artist_map.get(song.get_artist());
+                let album_id = artist_map
+                    .get(song.album_artist.as_str())
+                    .and_then(|aid| album_map.get(&(song.get_album().to_string(), *aid)));
+
+                let (Some(artist_id), Some(album_id)) = (artist_id, album_id) else {
+                    continue;
+                };
+
+                let (old, new) = (old_id.to_le_bytes(), song.id.to_le_bytes());
+                relink.execute(params![
+                    new,
+                    &song.title,
+                    &song.year,
+                    &song.path.to_str(),
+                    artist_id,
+                    album_id,
+                    &song.track_no,
+                    &song.disc_no,
+                    &song.duration.as_secs_f32(),
+                    &song.channels,
+                    &song.bitrate,
+                    &song.sample_rate,
+                    &song.filetype,
+                    &song.replay_gain.track_gain,
+                    &song.replay_gain.track_peak,
+                    &song.replay_gain.album_gain,
+                    &song.replay_gain.album_peak,
+                    song.replay_gain.state() as u8,
+                    old,
+                ])?;
+
+                // The new file may well decode where the old one did not
+                tx.execute(DELETE_FAILED_WAVEFORM, [old])?;
+                for query in REASSIGN_SONG_ID {
+                    tx.execute(query, params![new, old])?;
+                }
+                tx.execute(DELETE_GENRES, [old])?;
+                set_genres(&tx, song.id, &song.genres)?;
+                import_rating(&tx, song)?;
+                relinked.insert(old_id, song.id);
+            }
+        }
+        tx.commit()?;
+
+        let moved = relinked.values().collect::<HashSet<_>>();
+        songs.retain(|s| !moved.contains(&s.id));
+
+        Ok(relinked)
+    }
+}
@@ -18,6 +18,12 @@ pub const CREATE_SCHEMA: &str = r"
         bit_rate INTEGER,
         sample_rate INTEGER,
         format INTEGER,
+        track_gain REAL,
+        track_peak REAL,
+        album_gain REAL,
+        album_peak REAL,
+        gain_state INTEGER NOT NULL DEFAULT 0,
+        added_at INTEGER,
         FOREIGN KEY(artist_id) REFERENCES artists(id),
         FOREIGN KEY(album_id) REFERENCES albums(id)
     );
@@ -35,6 +41,13 @@ pub const CREATE_SCHEMA: &str = r"
         UNIQUE (title, artist_id)
     );
 
+    CREATE TABLE IF NOT EXISTS genres(
+        song_id BLOB NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY(song_id, name),
+        FOREIGN KEY(song_id) REFERENCES songs(id) ON DELETE CASCADE
+    );
+
     CREATE TABLE IF NOT EXISTS waveforms(
         song_id BLOB PRIMARY KEY,
         waveform BLOB,
@@ -48,12 +61,21 @@ pub const CREATE_SCHEMA: &str = r"
         FOREIGN KEY(song_id) REFERENCES songs(id) ON DELETE CASCADE
     );
 
+    CREATE INDEX IF NOT EXISTS history_timestamp ON history(timestamp);
+
     CREATE TABLE IF NOT EXISTS plays(
         song_id BLOB PRIMARY KEY,
         count INTEGER,
         FOREIGN KEY(song_id) REFERENCES songs(id) ON DELETE CASCADE
     );
 
+    CREATE TABLE IF NOT EXISTS ratings(
+        song_id BLOB PRIMARY KEY,
+        stars INTEGER NOT NULL DEFAULT 0,
+        favorite INTEGER NOT NULL DEFAULT 0,
+        FOREIGN KEY(song_id) REFERENCES songs(id) ON DELETE CASCADE
+    );
+
     CREATE TABLE IF NOT EXISTS session_state(
         key TEXT PRIMARY KEY,
         value TEXT NOT NULL
@@ -62,7 +84,8 @@ pub const CREATE_SCHEMA: &str = r"
     CREATE TABLE IF NOT EXISTS playlists(
         id INTEGER PRIMARY KEY,
         name TEXT UNIQUE NOT NULL,
-        updated_at INTEGER NOT NULL
+        updated_at INTEGER NOT NULL,
+        rules TEXT
     );
 
     CREATE TABLE IF NOT EXISTS playlist_songs(
@@ -75,11 +98,30 @@ pub const CREATE_SCHEMA: &str = r"
         UNIQUE(playlist_id, position)
     );
 
+    CREATE TABLE IF NOT EXISTS queue(
+        position INTEGER PRIMARY KEY,
+        song_id BLOB NOT NULL,
+        path TEXT NOT NULL,
+        unshuffled_pos INTEGER,
+        auto_added INTEGER NOT NULL DEFAULT 0
+    );
+
     CREATE TABLE IF NOT EXISTS scan_cache(
         key TEXT PRIMARY KEY,
         value BLOB NOT NULL
     );
 
+    CREATE TABLE IF NOT EXISTS scrobbles(
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        artist TEXT NOT NULL,
+        album TEXT,
+        album_artist TEXT,
+        track_no INTEGER,
+        duration REAL,
+        timestamp INTEGER NOT NULL
+    );
+
     CREATE TABLE IF NOT EXISTS now_playing(
         id INTEGER PRIMARY KEY CHECK(id = 1),
         song_id BLOB NOT NULL,
@@ -106,3 +148,18 @@ pub const CREATE_SCHEMA: &str = r"
     LEFT JOIN albums  al ON al.id = s.album_id
     WHERE np.id = 1;
 ";
+
+/// Columns added after the initial release as (table, column, statement),
+/// appended to existing databases on open
+#[rustfmt::skip]
+pub const MIGRATIONS: [(&str, &str, &str); 9] = [
+    ("songs",     "track_gain",     "ALTER TABLE songs ADD COLUMN track_gain REAL"),
+    ("songs",     "track_peak",     "ALTER TABLE songs ADD COLUMN track_peak REAL"),
+    ("songs",     "album_gain",     "ALTER TABLE songs ADD COLUMN album_gain REAL"),
+    ("songs",     "album_peak",     "ALTER TABLE songs ADD COLUMN album_peak REAL"),
+    ("songs",     "gain_state",     "ALTER TABLE songs ADD COLUMN gain_state INTEGER NOT NULL DEFAULT 0"),
+    ("songs",     "added_at",       "ALTER TABLE songs ADD COLUMN added_at INTEGER"),
+    ("playlists", "rules",          "ALTER TABLE playlists ADD COLUMN rules TEXT"),
+    ("queue",     "unshuffled_pos", "ALTER TABLE queue ADD COLUMN unshuffled_pos INTEGER"),
+    ("queue",     "auto_added",     "ALTER TABLE queue ADD COLUMN auto_added INTEGER NOT NULL DEFAULT 0"),
+];
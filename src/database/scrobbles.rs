@@ -0,0 +1,83 @@
+use crate::{Database, database::queries::*, scrobbler::Scrobble};
+use anyhow::Result;
+use rusqlite::{Row, params};
+use std::time::Duration;
+
+impl Database {
+    pub(crate) fn queue_scrobble(&mut self, scrobble: &Scrobble) -> Result<()> {
+        self.conn.execute(
+            INSERT_SCROBBLE,
+            params![
+                &scrobble.title,
+                &scrobble.artist,
+                &scrobble.album,
+                &scrobble.album_artist,
+                &scrobble.track_no,
+                scrobble.duration.as_secs_f32(),
+                scrobble.timestamp as i64,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Oldest scrobbles which have yet to be submitted, along with their row
+    /// id. Rows which can't be read are deleted, as they would otherwise stay
+    /// at the head of the queue
+    pub(crate) fn get_pending_scrobbles(&mut self, limit: usize) -> Result<Vec<(i64, Scrobble)>> {
+        let rows = {
+            let mut stmt = self.conn.prepare(GET_PENDING_SCROBBLES)?;
+            stmt.query_map([limit as i64], |row| {
+                Ok((row.get("id")?, read_scrobble(row)))
+            })?
+            .collect::<rusqlite::Result<Vec<(i64, Result<Scrobble>)>>>()?
+        };
+
+        let mut scrobbles = Vec::new();
+        let mut unreadable = Vec::new();
+        for (id, scrobble) in rows {
+            match scrobble {
+                Ok(scrobble) => scrobbles.push((id, scrobble)),
+                Err(_) => unreadable.push(id),
+            }
+        }
+
+        if unreadable.is_empty() {
+            return Ok(scrobbles);
+        }
+
+        self.delete_scrobbles(&unreadable)?;
+        match scrobbles.is_empty() {
+            true => self.get_pending_scrobbles(limit),
+            false => Ok(scrobbles),
+        }
+    }
+
+    pub(crate) fn delete_scrobbles(&mut self, ids: &[i64]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(DELETE_SCROBBLE)?;
+            for id in ids {
+                stmt.execute([id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn read_scrobble(row: &Row) -> Result<Scrobble> {
+    Ok(Scrobble {
+        title: row.get("title")?,
+        artist: row.get("artist")?,
+        album: row.get::<_, Option<String>>("album")?.unwrap_or_default(),
+        album_artist: row
+            .get::<_, Option<String>>("album_artist")?
+            .unwrap_or_default(),
+        track_no: row.get("track_no")?,
+        duration: Duration::try_from_secs_f32(
+            row.get::<_, Option<f32>>("duration")?.unwrap_or_default(),
+        )?,
+        timestamp: row.get::<_, i64>("timestamp")?.try_into()?,
+    })
+}
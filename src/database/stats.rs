@@ -0,0 +1,101 @@
+use super::convert_from_bytes;
+use crate::{
+    Database,
+    database::queries::*,
+    library::{FileType, average_bitrate},
+    ui_state::ListeningDay,
+};
+use anyhow::Result;
+use rusqlite::params;
+use std::{fs, time::Duration};
+
+impl Database {
+    /// Number of history entries for each song at or after `since`
+    pub(crate) fn get_plays_since(&mut self, since: i64) -> Result<Vec<(u64, u32)>> {
+        let plays = self
+            .conn
+            .prepare(GET_PLAYS_SINCE)?
+            .query_map([since], |row| {
+                Ok((convert_from_bytes(row.get("song_id")?), row.get("plays")?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(plays)
+    }
+
+    /// Today's day index along with every day which has history entries,
+    /// both counted in local days since the unix epoch
+    pub(crate) fn get_daily_listening(&mut self) -> Result<(i64, Vec<ListeningDay>)> {
+        let today = self.conn.query_one(GET_TODAY, [], |row| row.get(0))?;
+
+        let days = self
+            .conn
+            .prepare(GET_DAILY_LISTENING)?
+            .query_map([], |row| {
+                Ok(ListeningDay {
+                    day: row.get("day")?,
+                    date: row.get("date")?,
+                    plays: row.get("plays")?,
+                    seconds: row.get::<_, Option<f64>>("seconds")?.unwrap_or(0.0),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((today, days))
+    }
+
+    /// Format, average bitrate (kbps) and duration of every song
+    pub(crate) fn get_formats(&mut self) -> Result<Vec<(FileType, Option<u32>, f64)>> {
+        let formats = self
+            .conn
+            .prepare(GET_FORMATS)?
+            .query_map([], |row| {
+                Ok((
+                    row.get("format")?,
+                    row.get("bit_rate")?,
+                    row.get::<_, Option<f64>>("duration")?.unwrap_or(0.0),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(formats)
+    }
+
+    /// Songs scanned before bitrates were stored have theirs derived from the
+    /// file size, rather than being read again
+    pub(crate) fn backfill_bitrates(&mut self) -> Result<()> {
+        let missing = self
+            .conn
+            .prepare(GET_MISSING_BITRATES)?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Vec<u8>>("id")?,
+                    row.get::<_, String>("path")?,
+                    row.get::<_, Option<f64>>("duration")?.unwrap_or(0.0),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(SET_BITRATE)?;
+            for (id, path, duration) in missing {
+                let Ok(meta) = fs::metadata(&path) else {
+                    continue;
+                };
+
+                let duration = Duration::from_secs_f64(duration.max(0.0));
+                if let Some(kbps) = average_bitrate(meta.len(), duration) {
+                    stmt.execute(params![kbps, id])?;
+                }
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
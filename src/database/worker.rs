@@ -1,13 +1,13 @@
 use crate::{
     SongMap,
-    database::{DB_BOUND, Database},
-    library::SimpleSong,
-    ui_state::{LibraryStats, UiSnapshot},
+    database::{DB_BOUND, Database, playlists::PlaylistMap},
+    library::{FileType, Rating, SimpleSong, SongStats},
+    playback::{PlayMode, SavedEntry},
+    ui_state::{LibraryStats, ListeningDay, UiSnapshot},
 };
 use anyhow::{Result, anyhow};
-use indexmap::IndexMap;
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
     thread,
 };
@@ -124,10 +124,22 @@ impl DbWorker {
         self.execute_sync(move |db| db.get_hashes())
     }
 
-    pub fn build_playlists(&mut self) -> Result<IndexMap<(i64, String), Vec<(i64, u64)>>> {
+    pub fn build_playlists(&mut self) -> Result<PlaylistMap> {
         self.execute_sync(move |db| db.build_playlists())
     }
 
+    pub fn create_smart_playlist(&self, name: String, rules: String) -> Result<()> {
+        self.execute_sync(move |db| db.create_smart_playlist(&name, &rules))
+    }
+
+    pub fn set_playlist_rules(&self, id: i64, rules: String) -> Result<()> {
+        self.execute_sync(move |db| db.set_playlist_rules(id, &rules))
+    }
+
+    pub fn get_song_stats(&self) -> Result<HashMap<u64, SongStats>> {
+        self.execute_sync(move |db| db.get_song_stats())
+    }
+
     pub fn save_ui_snapshot(&self, snapshot: UiSnapshot) -> Result<()> {
         self.execute_sync(move |db| db.save_ui_snapshot(snapshot))
     }
@@ -180,6 +192,30 @@ impl DbWorker {
         self.execute_sync(move |db| db.get_most_played(count))
     }
 
+    pub fn get_plays_since(&self, since: i64) -> Result<Vec<(u64, u32)>> {
+        self.execute_sync(move |db| db.get_plays_since(since))
+    }
+
+    pub fn get_daily_listening(&self) -> Result<(i64, Vec<ListeningDay>)> {
+        self.execute_sync(move |db| db.get_daily_listening())
+    }
+
+    pub fn get_formats(&self) -> Result<Vec<(FileType, Option<u32>, f64)>> {
+        self.execute_sync(move |db| db.get_formats())
+    }
+
+    pub fn get_ratings(&self) -> Result<HashMap<u64, Rating>> {
+        self.execute_sync(move |db| db.get_ratings())
+    }
+
+    pub fn set_stars(&self, ids: Vec<u64>, stars: u8) -> Result<()> {
+        self.execute_sync(move |db| db.set_stars(&ids, stars))
+    }
+
+    pub fn set_favorite(&self, ids: Vec<u64>, favorite: bool) -> Result<()> {
+        self.execute_sync(move |db| db.set_favorite(&ids, favorite))
+    }
+
     pub fn get_last_played(&self) -> Result<(u64, f32)> {
         self.execute_sync(move |db| db.get_last_played())
     }
@@ -201,6 +237,16 @@ impl DbWorker {
             let _ = db.clear_now_playing();
         });
     }
+
+    pub fn save_queue(&self, entries: Vec<SavedEntry>, mode: PlayMode) {
+        self.execute(move |db| {
+            let _ = db.save_queue(&entries, mode);
+        });
+    }
+
+    pub fn load_queue(&self) -> Result<(Vec<SavedEntry>, PlayMode)> {
+        self.execute_sync(move |db| db.load_queue())
+    }
 }
 
 impl Drop for DbWorker {
@@ -0,0 +1,27 @@
+use anyhow::{Result, bail};
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    time::Duration,
+};
+
+use crate::ipc::{IpcCommand, IpcResponse, NotRunning, to_line};
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(super) fn send(path: &Path, command: &IpcCommand) -> Result<IpcResponse> {
+    let mut stream = UnixStream::connect(path).map_err(|_| NotRunning)?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+
+    writeln!(stream, "{}", to_line(command))?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+
+    let response = serde_json::from_str::<IpcResponse>(&line)?;
+    match response.ok {
+        true => Ok(response),
+        false => bail!(response.error.unwrap_or_default()),
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(unix)]
+mod client;
+mod protocol;
+#[cfg(unix)]
+mod server;
+
+pub use protocol::{IpcCommand, IpcEvent, IpcResponse, IpcSong, IpcState, IpcStatus};
+
+use crossbeam::channel::{Receiver, Sender, bounded};
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::SOCKET_PATH;
+
+type Subscribers = Arc<Mutex<Vec<Sender<String>>>>;
+
+pub struct IpcRequest {
+    pub command: IpcCommand,
+    reply: Sender<String>,
+}
+
+impl IpcRequest {
+    pub fn reply(&self, response: IpcResponse) {
+        let _ = self.reply.send(to_line(&response));
+    }
+}
+
+pub struct IpcHandle {
+    request_rx: Receiver<IpcRequest>,
+    subscribers: Subscribers,
+}
+
+impl IpcHandle {
+    pub fn new() -> anyhow::Result<Self> {
+        let (request_tx, request_rx) = bounded::<IpcRequest>(32);
+        let subscribers = Subscribers::default();
+
+        listen(request_tx, Arc::clone(&subscribers))?;
+
+        Ok(Self {
+            request_rx,
+            subscribers,
+        })
+    }
+
+    pub fn request_rx(&self) -> &Receiver<IpcRequest> {
+        &self.request_rx
+    }
+
+    /// Sends an event to all subscribers, dropping any that have disconnected
+    pub fn broadcast(&self, event: &IpcEvent) {
+        let line = to_line(event);
+        if let Ok(mut subs) = self.subscribers.lock() {
+            subs.retain(|tx| tx.send(line.clone()).is_ok());
+        }
+    }
+}
+
+impl Drop for IpcHandle {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&*SOCKET_PATH);
+    }
+}
+
+#[cfg(unix)]
+fn listen(request_tx: Sender<IpcRequest>, subscribers: Subscribers) -> anyhow::Result<()> {
+    server::listen(&SOCKET_PATH, request_tx, subscribers)
+}
+
+#[cfg(not(unix))]
+fn listen(_: Sender<IpcRequest>, _: Subscribers) -> anyhow::Result<()> {
+    anyhow::bail!("IPC socket is only supported on unix platforms")
+}
+
+/// Returned by `send` when there is no instance listening on the socket
+#[derive(Debug)]
+pub struct NotRunning;
+
+impl fmt::Display for NotRunning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NoctaVox is not currently running")
+    }
+}
+
+impl std::error::Error for NotRunning {}
+
+/// Sends a single command to a running instance and waits for its reply
+#[cfg(unix)]
+pub fn send(command: &IpcCommand) -> anyhow::Result<IpcResponse> {
+    client::send(&SOCKET_PATH, command)
+}
+
+#[cfg(not(unix))]
+pub fn send(_: &IpcCommand) -> anyhow::Result<IpcResponse> {
+    anyhow::bail!("IPC socket is only supported on unix platforms")
+}
+
+pub(crate) fn to_line<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
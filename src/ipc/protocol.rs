@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{library::SongInfo, player::PlaybackState};
+
+/// Line-delimited JSON commands accepted on the socket, e.g.
+/// `{"cmd": "enqueue", "path": "~/Music/song.flac"}`
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+pub enum IpcCommand {
+    Play {
+        id: Option<u64>,
+        path: Option<String>,
+    },
+    Pause,
+    Toggle,
+    Stop,
+    Next,
+    Prev,
+    Seek {
+        position: f32,
+    },
+    SeekForward {
+        secs: u64,
+    },
+    SeekBack {
+        secs: u64,
+    },
+    Enqueue {
+        id: Option<u64>,
+        path: Option<String>,
+    },
+    State,
+    Subscribe,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct IpcResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<IpcState>,
+}
+
+impl IpcResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn error(e: impl ToString) -> Self {
+        Self {
+            ok: false,
+            error: Some(e.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_state(state: IpcState) -> Self {
+        Self {
+            ok: true,
+            state: Some(state),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IpcState {
+    pub status: IpcStatus,
+    pub elapsed: f32,
+    pub repeat: bool,
+    /// `off`, `repeat_one`, `repeat_all`, `shuffle` or `smart_shuffle`
+    pub play_mode: String,
+    pub now_playing: Option<IpcSong>,
+    pub queue: Vec<IpcSong>,
+}
+
+/// Pushed to every subscribed connection as playback changes
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum IpcEvent {
+    TrackStarted { song: IpcSong },
+    StateChanged { status: IpcStatus },
+    Stopped,
+    Error { message: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+impl From<&PlaybackState> for IpcStatus {
+    fn from(state: &PlaybackState) -> Self {
+        match state {
+            PlaybackState::Playing => Self::Playing,
+            PlaybackState::Paused => Self::Paused,
+            PlaybackState::Stopped => Self::Stopped,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IpcSong {
+    pub id: u64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: f32,
+}
+
+impl<S: SongInfo> From<&S> for IpcSong {
+    fn from(song: &S) -> Self {
+        Self {
+            id: song.get_id(),
+            title: song.get_title().to_string(),
+            artist: song.get_artist().to_string(),
+            album: song.get_album().to_string(),
+            duration: song.get_duration_f32(),
+        }
+    }
+}
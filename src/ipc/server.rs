@@ -0,0 +1,88 @@
+use anyhow::{Result, bail};
+use crossbeam::channel::{Sender, unbounded};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::Arc,
+    thread,
+};
+
+use crate::ipc::{IpcCommand, IpcRequest, IpcResponse, Subscribers, to_line};
+
+pub(super) fn listen(
+    path: &Path,
+    request_tx: Sender<IpcRequest>,
+    subscribers: Subscribers,
+) -> Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("Another instance is listening on {}", path.display());
+        }
+        // Stale socket left behind by a crash
+        fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle_client(stream, request_tx.clone(), Arc::clone(&subscribers));
+        }
+    });
+
+    Ok(())
+}
+
+/// Each client gets a reader thread for commands and a writer thread which
+/// serializes both replies and subscribed events back onto the socket.
+fn handle_client(stream: UnixStream, request_tx: Sender<IpcRequest>, subscribers: Subscribers) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    let (reply_tx, reply_rx) = unbounded::<String>();
+
+    thread::spawn(move || {
+        for line in reply_rx {
+            if writeln!(writer, "{line}").is_err() {
+                break;
+            }
+        }
+    });
+
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<IpcCommand>(&line) {
+                Ok(IpcCommand::Subscribe) => {
+                    if let Ok(mut subs) = subscribers.lock() {
+                        subs.push(reply_tx.clone());
+                    }
+                    let _ = reply_tx.send(to_line(&IpcResponse::ok()));
+                }
+                Ok(command) => {
+                    let request = IpcRequest {
+                        command,
+                        reply: reply_tx.clone(),
+                    };
+                    if request_tx.send(request).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = reply_tx.send(to_line(&IpcResponse::error(e)));
+                }
+            }
+        }
+    });
+}
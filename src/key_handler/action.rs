@@ -1,6 +1,7 @@
 use crate::{
     config::timing,
     key_handler::{key_buffer::KeyBuffer, *},
+    library::MAX_STARS,
     ui_state::{
         LibraryView, Mode, Pane, PlaylistAction, PopupType, ProgressDisplay, SettingsMode, UiState,
     },
@@ -24,6 +25,10 @@ pub fn handle_key_event(key_event: KeyEvent, state: &mut UiState, buffer: &mut K
 
     let buffer_count = buffer.take_count();
 
+    if let Some(action) = keymap().lookup(&state.get_input_context(), &key_event, state, buffer_count) {
+        return action;
+    }
+
     if let Some(action) = global_commands(&key_event, &state, buffer_count) {
         return Some(action);
     }
@@ -34,6 +39,7 @@ pub fn handle_key_event(key_event: KeyEvent, state: &mut UiState, buffer: &mut K
         InputContext::TrackList(_)  => handle_tracklist(&key_event, &state, buffer_count),
         InputContext::AlbumView     => handle_album_browser(&key_event),
         InputContext::PlaylistView  => handle_playlist_browswer(&key_event),
+        InputContext::CategoryView  => handle_category_browser(&key_event, state),
         InputContext::Search        => handle_search_pane(&key_event, &state),
 
         _ => None,
@@ -44,6 +50,7 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
     let in_search = state.get_pane() == Pane::Search;
     let fullscreen = matches!(state.get_mode(), Mode::Fullscreen);
     let popup_active = state.popup.is_open();
+    let raw_count = buf_count;
 
     if buf_count == 0 {
         buf_count = 1
@@ -64,11 +71,12 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
 
             (S, Char('C')) => Some(Action::ThemeManager),
             (X, F(6)) => Some(Action::ThemeRefresh),
+            (S, Char('E')) => Some(Action::Equalizer),
 
             (C, Char('t')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Playlists))),
             (C, Char('q')) => Some(Action::ChangeMode(Mode::Queue)),
             (C, Char('z')) => Some(Action::ChangeMode(Mode::Power)),
-            (C, Char('r')) => Some(Action::ToggleRepeat),
+            (C, Char('r')) => Some(Action::CyclePlayMode),
 
             (_, Char('`') | Char('~')) => Some(Action::ViewSettings),
             (X, Char(' ')) => Some(Action::TogglePlayback),
@@ -80,6 +88,11 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
             (X, Char('p')) => Some(Action::SeekBack(SEEK_SMALL)),
             (S, Char('P')) => Some(Action::SeekBack(SEEK_LARGE)),
 
+            (_, Char('+')) => Some(Action::VolumeUp(VOLUME_STEP)),
+            (X, Char('-')) => Some(Action::VolumeDown(VOLUME_STEP)),
+            (_, Char('%')) if raw_count > 0 => Some(Action::SetVolume(raw_count.min(100) as u8)),
+            (S, Char('M')) => Some(Action::ToggleMute),
+
             // NAVIGATION
             (X, Char('/')) => Some(Action::ChangeMode(Mode::Search)),
             (X, Char('=')) => Some(Action::GoToNowPlaying),
@@ -90,6 +103,9 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
             (C, Char('1')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Albums))),
             (C, Char('2')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Playlists))),
             (C, Char('3')) => Some(Action::ChangeMode(Mode::Queue)),
+            (C, Char('4')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Artists))),
+            (C, Char('5')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Genres))),
+            (C, Char('6')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Years))),
             (C, Char('0')) => Some(Action::ChangeMode(Mode::Power)),
 
             // SCROLLING
@@ -107,6 +123,9 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
             (_, Char('{')) => Some(Action::IncrementWFSmoothness(Incrementor::Down)),
             (_, Char('}')) => Some(Action::IncrementWFSmoothness(Incrementor::Up)),
 
+            (_, Char('(')) => Some(Action::ShiftLyricsOffset(LYRICS_OFFSET_STEP)),
+            (_, Char(')')) => Some(Action::ShiftLyricsOffset(-LYRICS_OFFSET_STEP)),
+
             (_, Char('<')) => Some(Action::CycleTheme(Incrementor::Up)),
             (_, Char('>')) => Some(Action::CycleTheme(Incrementor::Down)),
 
@@ -117,6 +136,7 @@ fn global_commands(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Opt
             (S, Char('O')) => Some(Action::SetProgressDisplay(ProgressDisplay::Oscilloscope)),
             (S, Char('S')) => Some(Action::SetProgressDisplay(ProgressDisplay::Spectrum)),
             (S, Char('B')) => Some(Action::SetProgressDisplay(ProgressDisplay::ProgressBar)),
+            (S, Char('L')) => Some(Action::SetProgressDisplay(ProgressDisplay::Lyrics)),
             (C, Char('u')) | (X, F(5)) => Some(Action::UpdateLibrary),
 
             _ => None,
@@ -131,7 +151,11 @@ fn handle_tracklist(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Op
 
         (X, Char('a')) => Some(Action::AddToPlaylist),
         (C, Char('a')) => Some(Action::GoToAlbum),
+        (X, Char('e')) => Some(Action::EditTags),
         (X, Char('q')) => Some(Action::QueueSong),
+        (X, Char('r')) => Some(Action::Rate(stars_from_count(buf_count))),
+        (S, Char('R')) => Some(Action::Rate(Some(0))),
+        (_, Char('*')) => Some(Action::ToggleFavorite),
         (X, Char('v')) => Some(Action::MultiSelect(buf_count)),
         (C, Char('v')) => Some(Action::ClearMultiSelect),
         (X, Char('g')) => {
@@ -183,6 +207,14 @@ fn handle_tracklist(key: &KeyEvent, state: &UiState, mut buf_count: usize) -> Op
     }
 }
 
+/// A count typed before `r` gives that many stars, otherwise it adds one
+pub(super) fn stars_from_count(count: usize) -> Option<u8> {
+    match count {
+        0 => None,
+        n => Some(n.min(MAX_STARS as usize) as u8),
+    }
+}
+
 fn handle_album_browser(key: &KeyEvent) -> Option<Action> {
     match (key.modifiers, key.code) {
         (X, Char('q')) => Some(Action::QueueMany {
@@ -196,6 +228,7 @@ fn handle_album_browser(key: &KeyEvent) -> Option<Action> {
             sel_type: SelectionType::Legal,
             shuffle: true,
         }),
+        (X, Char('e')) => Some(Action::EditTags),
 
         // Change album sorting algorithm
         (X, Char('g')) => Some(Action::Scroll(Director::Top)),
@@ -221,6 +254,8 @@ fn handle_playlist_browswer(key: &KeyEvent) -> Option<Action> {
 
         (X, Char('g')) => Some(Action::Scroll(Director::Top)),
         (X, Char('c')) => Some(Action::CreatePlaylist),
+        (S, Char('A')) => Some(Action::CreateSmartPlaylist),
+        (X, Char('e')) => Some(Action::EditPlaylistRules),
         (C, Char('d')) => Some(Action::DeletePlaylist),
         (X, Char('s')) => Some(Action::QueueMany {
             sel_type: SelectionType::Legal,
@@ -230,6 +265,39 @@ fn handle_playlist_browswer(key: &KeyEvent) -> Option<Action> {
     }
 }
 
+fn handle_category_browser(key: &KeyEvent, state: &UiState) -> Option<Action> {
+    let view = state.display_state.sidebar_view;
+    let open = state.category_is_open();
+
+    match (key.modifiers, key.code) {
+        (C, Char('a')) => Some(Action::ChangeMode(Mode::Library(LibraryView::Albums))),
+        (X, Char('q')) => Some(Action::QueueMany {
+            sel_type: SelectionType::Legal,
+            shuffle: false,
+        }),
+        (X, Char('s')) => Some(Action::QueueMany {
+            sel_type: SelectionType::Legal,
+            shuffle: true,
+        }),
+        (X, Char('e')) => Some(Action::EditTags),
+
+        (X, Enter) | (X, Right) | (X, Char('l')) => match open {
+            true => Some(Action::ChangePane(Pane::TrackList)),
+            false => Some(Action::OpenCategory),
+        },
+        (X, Tab) => Some(Action::ChangePane(Pane::TrackList)),
+        (X, Left) | (X, Char('h')) if open => Some(Action::CloseCategory),
+
+        (X, Char('g')) => Some(Action::Scroll(Director::Top)),
+        (C, Left) | (C, Char('h')) => Some(Action::ChangeMode(Mode::Library(view.prev_category()))),
+        (C, Right) | (C, Char('l')) => {
+            Some(Action::ChangeMode(Mode::Library(view.next_category())))
+        }
+
+        _ => None,
+    }
+}
+
 fn handle_search_pane(key: &KeyEvent, state: &UiState) -> Option<Action> {
     match (key.modifiers, key.code) {
         (X, Esc) => Some(Action::ChangeMode(Mode::Library(
@@ -258,16 +326,24 @@ fn handle_fullscreen(key: &KeyEvent) -> Option<Action> {
         (X, Char('p')) => Action::SeekBack(SEEK_SMALL),
         (S, Char('P')) => Action::SeekBack(SEEK_LARGE),
 
+        (_, Char('+')) => Action::VolumeUp(VOLUME_STEP),
+        (X, Char('-')) => Action::VolumeDown(VOLUME_STEP),
+        (S, Char('M')) => Action::ToggleMute,
+
         (X, Char('w')) | (S, Char('W')) => Action::SetProgressDisplay(ProgressDisplay::Waveform),
         (X, Char('o')) | (S, Char('O')) => {
             Action::SetProgressDisplay(ProgressDisplay::Oscilloscope)
         }
         (X, Char('s')) | (S, Char('S')) => Action::SetProgressDisplay(ProgressDisplay::Spectrum),
         (X, Char('b')) | (S, Char('B')) => Action::SetProgressDisplay(ProgressDisplay::ProgressBar),
+        (X, Char('l')) | (S, Char('L')) => Action::SetProgressDisplay(ProgressDisplay::Lyrics),
 
         (_, Char('{')) => Action::IncrementWFSmoothness(Incrementor::Down),
         (_, Char('}')) => Action::IncrementWFSmoothness(Incrementor::Up),
 
+        (_, Char('(')) => Action::ShiftLyricsOffset(LYRICS_OFFSET_STEP),
+        (_, Char(')')) => Action::ShiftLyricsOffset(-LYRICS_OFFSET_STEP),
+
         _ => Action::RevertFullscreen,
     };
 
@@ -279,6 +355,9 @@ fn handle_popup(key: &KeyEvent, popup: &PopupType) -> Option<Action> {
         PopupType::Settings(s) => root_manager(key, s),
         PopupType::Playlist(p) => handle_playlist(key, p),
         PopupType::ThemeManager => handle_themeing(key),
+        PopupType::Equalizer => handle_equalizer(key),
+        PopupType::TagEditor => handle_tag_editor(key),
+        PopupType::Stats => handle_stats(key),
         _ => Some(Action::ClosePopup),
     }
 }
@@ -339,6 +418,14 @@ fn handle_playlist(key: &KeyEvent, variant: &PlaylistAction) -> Option<Action> {
             Enter => Some(Action::RenamePlaylistConfirm),
             _ => Some(Action::PopupInput(*key)),
         },
+        CreateSmart | CreateSmartRules(_) => match key.code {
+            Enter => Some(Action::CreateSmartPlaylistConfirm),
+            _ => Some(Action::PopupInput(*key)),
+        },
+        EditRules => match key.code {
+            Enter => Some(Action::EditPlaylistRulesConfirm),
+            _ => Some(Action::PopupInput(*key)),
+        },
     }
 }
 
@@ -350,6 +437,44 @@ fn handle_themeing(key: &KeyEvent) -> Option<Action> {
     }
 }
 
+#[rustfmt::skip]
+fn handle_equalizer(key: &KeyEvent) -> Option<Action> {
+    match key.code {
+        Left | Char('h')    => Some(Action::EqSelectBand(Incrementor::Up)),
+        Right | Char('l')   => Some(Action::EqSelectBand(Incrementor::Down)),
+        Up | Char('k')      => Some(Action::EqAdjustGain(EQ_GAIN_STEP)),
+        Down | Char('j')    => Some(Action::EqAdjustGain(-EQ_GAIN_STEP)),
+        Char('0')           => Some(Action::EqResetBand),
+        Char('p')           => Some(Action::EqCyclePreset(Incrementor::Down)),
+        Char('P')           => Some(Action::EqCyclePreset(Incrementor::Up)),
+        Char('e')           => Some(Action::ToggleEqualizer),
+        Char('o')           => Some(Action::ToggleEqOverlay),
+        Esc | Enter         => Some(Action::ClosePopup),
+        _ => None,
+    }
+}
+
+#[rustfmt::skip]
+fn handle_stats(key: &KeyEvent) -> Option<Action> {
+    match key.code {
+        Tab | Right | Char('l')     => Some(Action::StatsPage(Incrementor::Down)),
+        BackTab | Left | Char('h')  => Some(Action::StatsPage(Incrementor::Up)),
+        Char('p')                   => Some(Action::CycleStatsPeriod),
+        Char('x')                   => Some(Action::ExportStats),
+        _ => Some(Action::ClosePopup),
+    }
+}
+
+fn handle_tag_editor(key: &KeyEvent) -> Option<Action> {
+    match key.code {
+        Esc => Some(Action::ClosePopup),
+        Enter => Some(Action::EditTagsConfirm),
+        Up | BackTab => Some(Action::TagEditorSelectField(Incrementor::Up)),
+        Down | Tab => Some(Action::TagEditorSelectField(Incrementor::Down)),
+        _ => Some(Action::PopupInput(*key)),
+    }
+}
+
 pub fn next_event() -> Result<Option<Event>> {
     match event::poll(timing().refresh_rate)? {
         true => Ok(Some(event::read()?)),
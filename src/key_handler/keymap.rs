@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use anyhow::{Result, anyhow, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    key_handler::{
+        Action, Director, Incrementor, InputContext, LYRICS_OFFSET_STEP, SCROLL_MID, SCROLL_XTRA,
+        SEEK_LARGE, SEEK_SMALL, SIDEBAR_INCREMENT, SelectionType, VOLUME_STEP,
+        action::stars_from_count,
+    },
+    playback::PlayMode,
+    ui_state::{LibraryView, Mode, Pane, ProgressDisplay, UiState},
+};
+
+pub static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+type Chord = (KeyModifiers, KeyCode);
+
+/// User defined bindings from the `[keys]` table of config.toml. Anything not
+/// bound here falls through to the default keymaps in `action.rs`.
+#[derive(Default)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, HashMap<Chord, KeyCommand>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum KeyContext {
+    Global,
+    TrackList,
+    Albums,
+    Playlists,
+    Categories,
+    Search,
+    Fullscreen,
+}
+
+impl KeyContext {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "global" => Some(Self::Global),
+            "tracklist" => Some(Self::TrackList),
+            "albums" => Some(Self::Albums),
+            "playlists" => Some(Self::Playlists),
+            "categories" => Some(Self::Categories),
+            "search" => Some(Self::Search),
+            "fullscreen" => Some(Self::Fullscreen),
+            _ => None,
+        }
+    }
+
+    /// Tables searched for a given input context, most specific first
+    fn lookup_order(ctx: &InputContext) -> &'static [KeyContext] {
+        match ctx {
+            InputContext::TrackList(_) | InputContext::Queue => &[Self::TrackList, Self::Global],
+            InputContext::AlbumView => &[Self::Albums, Self::Global],
+            InputContext::PlaylistView => &[Self::Playlists, Self::Global],
+            InputContext::CategoryView => &[Self::Categories, Self::Global],
+            InputContext::Search => &[Self::Search],
+            InputContext::Fullscreen => &[Self::Fullscreen],
+            InputContext::Popup(_) => &[],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyCommand {
+    Unbound,
+    Quit,
+    TogglePlayback,
+    Stop,
+    PlayNext,
+    PlayPrev,
+    SeekForward,
+    SeekForwardLarge,
+    SeekBack,
+    SeekBackLarge,
+    VolumeUp,
+    VolumeDown,
+    SetVolume,
+    ToggleMute,
+    CyclePlayMode,
+    ToggleRepeat,
+    ToggleRepeatAll,
+    ToggleShuffle,
+    ToggleSmartShuffle,
+    SoftReset,
+    ClearKeyBuffer,
+    ThemeManager,
+    ThemeRefresh,
+    ThemePrev,
+    ThemeNext,
+    ViewSettings,
+    ViewAlbums,
+    ViewPlaylists,
+    ViewArtists,
+    ViewGenres,
+    ViewYears,
+    ViewQueue,
+    ViewPower,
+    Search,
+    Fullscreen,
+    RevertFullscreen,
+    SwapLayout,
+    ShowStats,
+    GoToNowPlaying,
+    ScrollUp,
+    ScrollDown,
+    ScrollUpMid,
+    ScrollDownMid,
+    ScrollUpFar,
+    ScrollDownFar,
+    ScrollTop,
+    ScrollBottom,
+    SidebarShrink,
+    SidebarGrow,
+    SmoothnessDown,
+    SmoothnessUp,
+    NextDisplay,
+    Waveform,
+    Oscilloscope,
+    Spectrum,
+    ProgressBar,
+    Lyrics,
+    LyricsEarlier,
+    LyricsLater,
+    Equalizer,
+    ToggleEqualizer,
+    UpdateLibrary,
+    Play,
+    QueueSong,
+    QueueAll,
+    QueueShuffled,
+    RemoveSong,
+    ShuffleQueue,
+    ShiftUp,
+    ShiftDown,
+    MultiSelect,
+    MultiSelectAll,
+    ClearMultiSelect,
+    GoToTrack,
+    GoToAlbum,
+    AddToPlaylist,
+    CreatePlaylist,
+    CreateSmartPlaylist,
+    EditPlaylistRules,
+    RenamePlaylist,
+    DeletePlaylist,
+    EditTags,
+    Rate,
+    ClearRating,
+    ToggleFavorite,
+    SortNext,
+    SortPrev,
+    AlbumSortNext,
+    AlbumSortPrev,
+    OpenCategory,
+    CloseCategory,
+    FocusTracklist,
+    FocusSidebar,
+    SendSearch,
+}
+
+impl KeyCommand {
+    #[rustfmt::skip]
+    fn from_str(s: &str) -> Option<Self> {
+        use KeyCommand::*;
+        let cmd = match s {
+            "unbound"               => Unbound,
+            "quit"                  => Quit,
+            "toggle_playback"       => TogglePlayback,
+            "stop"                  => Stop,
+            "play_next"             => PlayNext,
+            "play_prev"             => PlayPrev,
+            "seek_forward"          => SeekForward,
+            "seek_forward_large"    => SeekForwardLarge,
+            "seek_back"             => SeekBack,
+            "seek_back_large"       => SeekBackLarge,
+            "volume_up"             => VolumeUp,
+            "volume_down"           => VolumeDown,
+            "set_volume"            => SetVolume,
+            "toggle_mute"           => ToggleMute,
+            "cycle_play_mode"       => CyclePlayMode,
+            "toggle_repeat"         => ToggleRepeat,
+            "toggle_repeat_all"     => ToggleRepeatAll,
+            "toggle_shuffle"        => ToggleShuffle,
+            "toggle_smart_shuffle"  => ToggleSmartShuffle,
+            "soft_reset"            => SoftReset,
+            "clear_key_buffer"      => ClearKeyBuffer,
+            "theme_manager"         => ThemeManager,
+            "theme_refresh"         => ThemeRefresh,
+            "theme_prev"            => ThemePrev,
+            "theme_next"            => ThemeNext,
+            "view_settings"         => ViewSettings,
+            "view_albums"           => ViewAlbums,
+            "view_playlists"        => ViewPlaylists,
+            "view_artists"          => ViewArtists,
+            "view_genres"           => ViewGenres,
+            "view_years"            => ViewYears,
+            "view_queue"            => ViewQueue,
+            "view_power"            => ViewPower,
+            "search"                => Search,
+            "fullscreen"            => Fullscreen,
+            "revert_fullscreen"     => RevertFullscreen,
+            "swap_layout"           => SwapLayout,
+            "show_stats"            => ShowStats,
+            "go_to_now_playing"     => GoToNowPlaying,
+            "scroll_up"             => ScrollUp,
+            "scroll_down"           => ScrollDown,
+            "scroll_up_mid"         => ScrollUpMid,
+            "scroll_down_mid"       => ScrollDownMid,
+            "scroll_up_far"         => ScrollUpFar,
+            "scroll_down_far"       => ScrollDownFar,
+            "scroll_top"            => ScrollTop,
+            "scroll_bottom"         => ScrollBottom,
+            "sidebar_shrink"        => SidebarShrink,
+            "sidebar_grow"          => SidebarGrow,
+            "smoothness_down"       => SmoothnessDown,
+            "smoothness_up"         => SmoothnessUp,
+            "next_display"          => NextDisplay,
+            "waveform"              => Waveform,
+            "oscilloscope"          => Oscilloscope,
+            "spectrum"              => Spectrum,
+            "progress_bar"          => ProgressBar,
+            "lyrics"                => Lyrics,
+            "lyrics_earlier"        => LyricsEarlier,
+            "lyrics_later"          => LyricsLater,
+            "equalizer"             => Equalizer,
+            "equalizer_toggle"      => ToggleEqualizer,
+            "update_library"        => UpdateLibrary,
+            "play"                  => Play,
+            "queue_song"            => QueueSong,
+            "queue_all"             => QueueAll,
+            "queue_shuffled"        => QueueShuffled,
+            "remove_song"           => RemoveSong,
+            "shuffle_queue"         => ShuffleQueue,
+            "shift_up"              => ShiftUp,
+            "shift_down"            => ShiftDown,
+            "multi_select"          => MultiSelect,
+            "multi_select_all"      => MultiSelectAll,
+            "clear_multi_select"    => ClearMultiSelect,
+            "go_to_track"           => GoToTrack,
+            "go_to_album"           => GoToAlbum,
+            "add_to_playlist"       => AddToPlaylist,
+            "create_playlist"       => CreatePlaylist,
+            "create_smart_playlist" => CreateSmartPlaylist,
+            "edit_playlist_rules"   => EditPlaylistRules,
+            "rename_playlist"       => RenamePlaylist,
+            "delete_playlist"       => DeletePlaylist,
+            "edit_tags"             => EditTags,
+            "rate"                  => Rate,
+            "clear_rating"          => ClearRating,
+            "toggle_favorite"       => ToggleFavorite,
+            "sort_next"             => SortNext,
+            "sort_prev"             => SortPrev,
+            "album_sort_next"       => AlbumSortNext,
+            "album_sort_prev"       => AlbumSortPrev,
+            "open_category"         => OpenCategory,
+            "close_category"        => CloseCategory,
+            "focus_tracklist"       => FocusTracklist,
+            "focus_sidebar"         => FocusSidebar,
+            "send_search"           => SendSearch,
+            _ => return None,
+        };
+        Some(cmd)
+    }
+
+    /// Resolve into an `Action`. `count` is the raw key buffer count (0 when
+    /// nothing was typed).
+    #[rustfmt::skip]
+    fn to_action(self, state: &UiState, count: usize) -> Option<Action> {
+        use KeyCommand::*;
+        let at_least_one = count.max(1);
+
+        let action = match self {
+            Unbound             => return None,
+            Quit                => Action::QUIT,
+            TogglePlayback      => Action::TogglePlayback,
+            Stop                => Action::Stop,
+            PlayNext            => Action::PlayNext,
+            PlayPrev            => Action::PlayPrev,
+            SeekForward         => Action::SeekForward(SEEK_SMALL),
+            SeekForwardLarge    => Action::SeekForward(SEEK_LARGE),
+            SeekBack            => Action::SeekBack(SEEK_SMALL),
+            SeekBackLarge       => Action::SeekBack(SEEK_LARGE),
+            VolumeUp            => Action::VolumeUp(VOLUME_STEP),
+            VolumeDown          => Action::VolumeDown(VOLUME_STEP),
+            SetVolume if count == 0 => return None,
+            SetVolume           => Action::SetVolume(count.min(100) as u8),
+            ToggleMute          => Action::ToggleMute,
+            CyclePlayMode       => Action::CyclePlayMode,
+            ToggleRepeat        => Action::TogglePlayMode(PlayMode::RepeatOne),
+            ToggleRepeatAll     => Action::TogglePlayMode(PlayMode::RepeatAll),
+            ToggleShuffle       => Action::TogglePlayMode(PlayMode::Shuffle),
+            ToggleSmartShuffle  => Action::TogglePlayMode(PlayMode::SmartShuffle),
+            SoftReset           => Action::SoftReset,
+            ClearKeyBuffer      => Action::ClearKeyBuffer,
+            ThemeManager        => Action::ThemeManager,
+            ThemeRefresh        => Action::ThemeRefresh,
+            ThemePrev           => Action::CycleTheme(Incrementor::Up),
+            ThemeNext           => Action::CycleTheme(Incrementor::Down),
+            ViewSettings        => Action::ViewSettings,
+            ViewAlbums          => Action::ChangeMode(Mode::Library(LibraryView::Albums)),
+            ViewPlaylists       => Action::ChangeMode(Mode::Library(LibraryView::Playlists)),
+            ViewArtists         => Action::ChangeMode(Mode::Library(LibraryView::Artists)),
+            ViewGenres          => Action::ChangeMode(Mode::Library(LibraryView::Genres)),
+            ViewYears           => Action::ChangeMode(Mode::Library(LibraryView::Years)),
+            ViewQueue           => Action::ChangeMode(Mode::Queue),
+            ViewPower           => Action::ChangeMode(Mode::Power),
+            Search              => Action::ChangeMode(Mode::Search),
+            Fullscreen          => Action::ChangeMode(Mode::Fullscreen),
+            RevertFullscreen    => Action::RevertFullscreen,
+            SwapLayout          => Action::SwapLayout,
+            ShowStats           => Action::ShowStats,
+            GoToNowPlaying      => Action::GoToNowPlaying,
+            ScrollUp            => Action::Scroll(Director::Up(at_least_one)),
+            ScrollDown          => Action::Scroll(Director::Down(at_least_one)),
+            ScrollUpMid         => Action::Scroll(Director::Up(SCROLL_MID)),
+            ScrollDownMid       => Action::Scroll(Director::Down(SCROLL_MID)),
+            ScrollUpFar         => Action::Scroll(Director::Up(SCROLL_XTRA)),
+            ScrollDownFar       => Action::Scroll(Director::Down(SCROLL_XTRA)),
+            ScrollTop           => Action::Scroll(Director::Top),
+            ScrollBottom        => Action::Scroll(Director::Bottom),
+            SidebarShrink       => Action::IncrementSidebarSize(-SIDEBAR_INCREMENT),
+            SidebarGrow         => Action::IncrementSidebarSize(SIDEBAR_INCREMENT),
+            SmoothnessDown      => Action::IncrementWFSmoothness(Incrementor::Down),
+            SmoothnessUp        => Action::IncrementWFSmoothness(Incrementor::Up),
+            NextDisplay         => Action::NextProgressDisplay,
+            Waveform            => Action::SetProgressDisplay(ProgressDisplay::Waveform),
+            Oscilloscope        => Action::SetProgressDisplay(ProgressDisplay::Oscilloscope),
+            Spectrum            => Action::SetProgressDisplay(ProgressDisplay::Spectrum),
+            ProgressBar         => Action::SetProgressDisplay(ProgressDisplay::ProgressBar),
+            Lyrics              => Action::SetProgressDisplay(ProgressDisplay::Lyrics),
+            LyricsEarlier       => Action::ShiftLyricsOffset(LYRICS_OFFSET_STEP),
+            LyricsLater         => Action::ShiftLyricsOffset(-LYRICS_OFFSET_STEP),
+            Equalizer           => Action::Equalizer,
+            ToggleEqualizer     => Action::ToggleEqualizer,
+            UpdateLibrary       => Action::UpdateLibrary,
+            Play                => Action::Play(count),
+            QueueSong           => Action::QueueSong,
+            QueueAll            => Action::QueueMany { sel_type: SelectionType::Legal, shuffle: false },
+            QueueShuffled       => Action::QueueMany { sel_type: SelectionType::Legal, shuffle: true },
+            RemoveSong          => Action::RemoveSong,
+            ShuffleQueue        => Action::ShuffleElements,
+            ShiftUp             => Action::ShiftPosition(Incrementor::Up),
+            ShiftDown           => Action::ShiftPosition(Incrementor::Down),
+            MultiSelect         => Action::MultiSelect(count),
+            MultiSelectAll      => Action::MultiSelectAll,
+            ClearMultiSelect    => Action::ClearMultiSelect,
+            GoToTrack           => Action::GoToTrack(at_least_one),
+            GoToAlbum           => Action::GoToAlbum,
+            AddToPlaylist       => Action::AddToPlaylist,
+            CreatePlaylist      => Action::CreatePlaylist,
+            CreateSmartPlaylist => Action::CreateSmartPlaylist,
+            EditPlaylistRules   => Action::EditPlaylistRules,
+            RenamePlaylist      => Action::RenamePlaylist,
+            DeletePlaylist      => Action::DeletePlaylist,
+            EditTags            => Action::EditTags,
+            Rate                => Action::Rate(stars_from_count(count)),
+            ClearRating         => Action::Rate(Some(0)),
+            ToggleFavorite      => Action::ToggleFavorite,
+            SortNext            => Action::SortColumnsNext,
+            SortPrev            => Action::SortColumnsPrev,
+            AlbumSortNext       => Action::ToggleAlbumSort(true),
+            AlbumSortPrev       => Action::ToggleAlbumSort(false),
+            OpenCategory        => Action::OpenCategory,
+            CloseCategory       => Action::CloseCategory,
+            FocusTracklist      => Action::ChangePane(Pane::TrackList),
+            FocusSidebar        => Action::ChangeMode(Mode::Library(state.display_state.sidebar_view)),
+            SendSearch          => Action::SendSearch,
+        };
+
+        Some(action)
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the raw `[keys]` table. Invalid entries are
+    /// skipped (leaving the default binding in place) and reported back.
+    pub fn from_config(raw: &HashMap<String, HashMap<String, String>>) -> (Self, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut errors = vec![];
+
+        let mut contexts = raw.iter().collect::<Vec<_>>();
+        contexts.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (ctx_name, table) in contexts {
+            let Some(ctx) = KeyContext::from_str(ctx_name) else {
+                errors.push(format!("Unknown key context [keys.{ctx_name}]"));
+                continue;
+            };
+
+            let mut entries = table.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            let mut bound: HashMap<Chord, (&str, KeyCommand)> = HashMap::new();
+            let mut conflicts = vec![];
+
+            for (chord_str, cmd_str) in entries {
+                let chord = match parse_chord(chord_str) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        errors.push(format!("[keys.{ctx_name}] {e}"));
+                        continue;
+                    }
+                };
+
+                let Some(cmd) = KeyCommand::from_str(cmd_str) else {
+                    errors.push(format!(
+                        "[keys.{ctx_name}] Unknown action \"{cmd_str}\" for \"{chord_str}\""
+                    ));
+                    continue;
+                };
+
+                match bound.get(&chord) {
+                    Some((prev, prev_cmd)) if *prev_cmd != cmd => {
+                        errors.push(format!(
+                            "[keys.{ctx_name}] \"{prev}\" and \"{chord_str}\" conflict"
+                        ));
+                        conflicts.push(chord);
+                    }
+                    Some(_) => (),
+                    None => {
+                        bound.insert(chord, (chord_str, cmd));
+                    }
+                }
+            }
+
+            for chord in conflicts {
+                bound.remove(&chord);
+            }
+
+            keymap.bindings.insert(
+                ctx,
+                bound.into_iter().map(|(ch, (_, cmd))| (ch, cmd)).collect(),
+            );
+        }
+
+        (keymap, errors)
+    }
+
+    /// Returns `Some` if the key is user bound in the current context. The
+    /// inner value is `None` when the key has been explicitly unbound.
+    pub fn lookup(
+        &self,
+        ctx: &InputContext,
+        key: &KeyEvent,
+        state: &UiState,
+        count: usize,
+    ) -> Option<Option<Action>> {
+        let chord = normalize(key.modifiers, key.code);
+
+        KeyContext::lookup_order(ctx)
+            .iter()
+            .filter_map(|c| self.bindings.get(c))
+            .find_map(|table| table.get(&chord))
+            .map(|cmd| cmd.to_action(state, count))
+    }
+}
+
+pub fn keymap() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+/// Parses chords such as `ctrl+n`, `N`, `shift+tab`, `space` or `F5`
+fn parse_chord(s: &str) -> Result<Chord> {
+    let (mod_str, key_str) = match s {
+        "+" => ("", "+"),
+        _ if s.ends_with("++") => (&s[..s.len() - 2], "+"),
+        _ => s.rsplit_once('+').unwrap_or(("", s)),
+    };
+
+    let mut mods = KeyModifiers::NONE;
+    for m in mod_str.split('+').filter(|m| !m.is_empty()) {
+        mods |= match m.trim().to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "shift" => KeyModifiers::SHIFT,
+            "alt" => KeyModifiers::ALT,
+            _ => bail!("Unknown modifier \"{m}\" in \"{s}\""),
+        }
+    }
+
+    let mut chars = key_str.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key_str.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            f if f.starts_with('f') => match f[1..].parse::<u8>() {
+                Ok(n @ 1..=12) => KeyCode::F(n),
+                _ => bail!("Unknown key \"{key_str}\" in \"{s}\""),
+            },
+            _ => bail!("Unknown key \"{key_str}\" in \"{s}\""),
+        },
+    };
+
+    let chord = normalize(mods, code);
+    if matches!(chord, (KeyModifiers::NONE, KeyCode::Char('0'..='9'))) {
+        return Err(anyhow!("\"{s}\" is reserved for count prefixes"));
+    }
+
+    Ok(chord)
+}
+
+/// Terminals disagree on whether shift is reported alongside symbols and
+/// uppercase letters, so both sides of a lookup are folded into one form.
+fn normalize(mut mods: KeyModifiers, code: KeyCode) -> Chord {
+    let code = match code {
+        KeyCode::Char(c) if c.is_alphabetic() => {
+            if c.is_uppercase() {
+                mods |= KeyModifiers::SHIFT;
+            }
+            match mods.contains(KeyModifiers::SHIFT) {
+                true => KeyCode::Char(c.to_uppercase().next().unwrap_or(c)),
+                false => KeyCode::Char(c),
+            }
+        }
+        KeyCode::Char(c) => {
+            mods.remove(KeyModifiers::SHIFT);
+            KeyCode::Char(c)
+        }
+        KeyCode::Tab if mods.contains(KeyModifiers::SHIFT) => {
+            mods.remove(KeyModifiers::SHIFT);
+            KeyCode::BackTab
+        }
+        KeyCode::BackTab => {
+            mods.remove(KeyModifiers::SHIFT);
+            KeyCode::BackTab
+        }
+        other => other,
+    };
+
+    (mods, code)
+}
@@ -1,5 +1,6 @@
 mod action;
 mod key_buffer;
+mod keymap;
 
 use std::cell::RefCell;
 use std::collections::HashSet;
@@ -10,9 +11,11 @@ use std::time::Instant;
 pub use action::handle_key_event;
 pub use action::next_event;
 pub use key_buffer::KeyBuffer;
+pub use keymap::{KEYMAP, Keymap, keymap};
 use ratatui::crossterm::event::KeyEvent;
 use ratatui::crossterm::event::KeyModifiers;
 
+use crate::playback::PlayMode;
 use crate::ui_state::Mode;
 use crate::ui_state::Pane;
 use crate::ui_state::PopupType;
@@ -29,6 +32,9 @@ const SEEK_LARGE: u64 = 30;
 const SCROLL_MID: usize = 5;
 const SCROLL_XTRA: usize = 20;
 const SIDEBAR_INCREMENT: isize = 1;
+const VOLUME_STEP: u8 = 5;
+const LYRICS_OFFSET_STEP: i64 = 250;
+const EQ_GAIN_STEP: i8 = 1;
 
 #[derive(PartialEq, Eq)]
 pub enum Action {
@@ -40,6 +46,10 @@ pub enum Action {
     PlayPrev,
     SeekForward(u64),
     SeekBack(u64),
+    VolumeUp(u8),
+    VolumeDown(u8),
+    SetVolume(u8),
+    ToggleMute,
 
     // Queue & Playlist Actions
     QueueSong,
@@ -49,7 +59,8 @@ pub enum Action {
     },
     RemoveSong,
 
-    ToggleRepeat,
+    CyclePlayMode,
+    TogglePlayMode(PlayMode),
 
     AddToPlaylist,
     AddToPlaylistConfirm,
@@ -64,6 +75,8 @@ pub enum Action {
     SortColumnsNext,
     SortColumnsPrev,
     ToggleAlbumSort(bool),
+    OpenCategory,
+    CloseCategory,
     ChangeMode(Mode),
     ChangePane(Pane),
     GoToTrack(usize),
@@ -86,6 +99,21 @@ pub enum Action {
     RenamePlaylist,
     RenamePlaylistConfirm,
 
+    CreateSmartPlaylist,
+    CreateSmartPlaylistConfirm,
+
+    EditPlaylistRules,
+    EditPlaylistRulesConfirm,
+
+    // Tags
+    EditTags,
+    EditTagsConfirm,
+    TagEditorSelectField(Incrementor),
+
+    // Ratings
+    Rate(Option<u8>),
+    ToggleFavorite,
+
     ShiftPosition(Incrementor),
     ShuffleElements,
 
@@ -96,8 +124,17 @@ pub enum Action {
     ThemeManager,
     ThemeRefresh,
 
+    Equalizer,
+    EqSelectBand(Incrementor),
+    EqAdjustGain(i8),
+    EqResetBand,
+    EqCyclePreset(Incrementor),
+    ToggleEqualizer,
+    ToggleEqOverlay,
+
     IncrementWFSmoothness(Incrementor),
     IncrementSidebarSize(isize),
+    ShiftLyricsOffset(i64),
 
     SetProgressDisplay(ProgressDisplay),
     NextProgressDisplay,
@@ -108,6 +145,9 @@ pub enum Action {
     PopupScrollDown,
     PopupInput(KeyEvent),
     ShowStats,
+    StatsPage(Incrementor),
+    CycleStatsPeriod,
+    ExportStats,
 
     ClosePopup,
 
@@ -125,6 +165,7 @@ pub enum Action {
 pub enum InputContext {
     AlbumView,
     PlaylistView,
+    CategoryView,
     TrackList(Mode),
     Fullscreen,
     Search,
@@ -23,11 +23,15 @@ pub mod addons;
 pub mod app_core;
 pub mod config;
 pub mod database;
+pub mod ipc;
 pub mod key_handler;
 pub mod library;
 pub mod media_controls;
+pub mod mpd;
 pub mod playback;
 pub mod player;
+pub mod scrobbler;
+pub mod transpose;
 pub mod tui;
 pub mod ui_state;
 
@@ -63,10 +67,12 @@ static CONFIG_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
 });
 
 pub static THEME_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("themes"));
-pub static ADDON_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("addons"));
+pub static COVER_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("covers"));
+pub static EQ_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("equalizer"));
+pub static STATS_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("stats"));
 pub static DB_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("noctavox.db"));
+pub static SOCKET_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("noctavox.sock"));
 
-pub const ADDON_TRANSPOSE: &str = "nv-transpose";
 pub const TAP_BUFFER_CAPACITY: usize = 2048;
 
 pub fn reset_noctavox() -> anyhow::Result<()> {
@@ -0,0 +1,61 @@
+//! Second order IIR filter shared by the loudness meter and the equalizer
+
+use std::f64::consts::PI;
+
+/// Normalized coefficients (`a0 == 1`) along with the filter's state, run as
+/// transposed direct form II
+#[derive(Clone, Copy)]
+pub(crate) struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    /// Peaking filter from the RBJ audio EQ cookbook
+    pub fn peaking(freq: f64, gain_db: f64, q: f64, sample_rate: u32) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / sample_rate as f64;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        let a0 = 1.0 + alpha / a;
+        Biquad::new(
+            [
+                (1.0 + alpha * a) / a0,
+                (-2.0 * cos) / a0,
+                (1.0 - alpha * a) / a0,
+            ],
+            [(-2.0 * cos) / a0, (1.0 - alpha / a) / a0],
+        )
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+
+    pub fn reset(&mut self) {
+        self.z = [0.0; 2];
+    }
+
+    /// Linear gain of the filter at `freq`
+    pub fn magnitude(&self, freq: f64, sample_rate: u32) -> f64 {
+        let w = 2.0 * PI * freq / sample_rate as f64;
+        let (cos1, sin1) = (w.cos(), w.sin());
+        let (cos2, sin2) = ((2.0 * w).cos(), (2.0 * w).sin());
+
+        let num_re = self.b[0] + self.b[1] * cos1 + self.b[2] * cos2;
+        let num_im = self.b[1] * sin1 + self.b[2] * sin2;
+        let den_re = 1.0 + self.a[0] * cos1 + self.a[1] * cos2;
+        let den_im = self.a[0] * sin1 + self.a[1] * sin2;
+
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+}
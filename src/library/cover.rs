@@ -0,0 +1,115 @@
+//! Album artwork, read from embedded pictures (ID3 APIC, FLAC PICTURE, MP4
+//! covr) or sidecar images and cached in the config directory
+
+use anyhow::Result;
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+use symphonia::{
+    core::{
+        formats::probe::Hint,
+        io::MediaSourceStream,
+        meta::{StandardVisualKey, Visual},
+    },
+    default::get_probe,
+};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::COVER_DIR;
+
+/// Sidecar file names, in order of preference
+const SIDECARS: [&str; 4] = ["cover", "folder", "front", "album"];
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG";
+
+/// Covers are shared by every song of an album
+pub fn cover_key(album_artist: &str, album: &str) -> u64 {
+    xxh3_64(format!("{album_artist}\0{album}").as_bytes())
+}
+
+/// Path of an already cached cover, if any
+pub fn cached_cover(key: u64) -> Option<PathBuf> {
+    ["jpg", "png"]
+        .iter()
+        .map(|ext| COVER_DIR.join(format!("{key:016x}.{ext}")))
+        .find(|path| path.exists())
+}
+
+/// Find the cover of the album `song_path` belongs to, extracting it into the
+/// cache on first use. Embedded art takes priority over sidecar images
+pub fn find_cover(key: u64, song_path: &Path) -> Result<Option<PathBuf>> {
+    if let Some(cached) = cached_cover(key) {
+        return Ok(Some(cached));
+    }
+
+    let data = match embedded_cover(song_path)? {
+        Some(data) => data,
+        None => match sidecar_cover(song_path) {
+            Some(sidecar) => fs::read(sidecar)?,
+            None => return Ok(None),
+        },
+    };
+
+    let ext = match data.starts_with(PNG_SIGNATURE) {
+        true => "png",
+        false => "jpg",
+    };
+
+    fs::create_dir_all(&*COVER_DIR)?;
+    let path = COVER_DIR.join(format!("{key:016x}.{ext}"));
+    fs::write(&path, data)?;
+
+    Ok(Some(path))
+}
+
+fn embedded_cover(path: &Path) -> Result<Option<Vec<u8>>> {
+    let src = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let mut probed = get_probe().probe(&hint, mss, Default::default(), Default::default())?;
+    let mut metadata = probed.metadata();
+    let mut visuals: Vec<Visual> = vec![];
+
+    loop {
+        if let Some(md) = metadata.current() {
+            visuals.extend(md.media.visuals.iter().cloned());
+        }
+        if metadata.is_latest() {
+            break;
+        }
+        metadata.pop();
+    }
+
+    let front = visuals
+        .iter()
+        .position(|v| v.usage == Some(StandardVisualKey::FrontCover))
+        .unwrap_or(0);
+
+    Ok((!visuals.is_empty()).then(|| visuals.swap_remove(front).data.into_vec()))
+}
+
+fn sidecar_cover(song_path: &Path) -> Option<PathBuf> {
+    let dir = song_path.parent()?;
+
+    let images = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?.to_lowercase();
+            let ext = path.extension()?.to_str()?.to_lowercase();
+            let rank = SIDECARS.iter().position(|s| *s == stem)?;
+            IMAGE_EXTENSIONS
+                .contains(&ext.as_str())
+                .then_some((rank, path))
+        });
+
+    images.min_by_key(|(rank, _)| *rank).map(|(_, path)| path)
+}
@@ -0,0 +1,245 @@
+use anyhow::{Result, anyhow};
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use symphonia::{
+    core::{
+        audio::{Channels, Position},
+        codecs::{
+            audio::{
+                AudioCodecParameters, AudioDecoder, AudioDecoderOptions, CODEC_ID_NULL_AUDIO,
+                well_known::CODEC_ID_OPUS,
+            },
+            registry::CodecRegistry,
+        },
+        errors::Error as SymphoniaError,
+        formats::{FormatReader, SeekMode, SeekTo, probe::Hint},
+        io::MediaSourceStream,
+        units::{Time, TimeBase},
+    },
+    default::{get_probe, register_enabled_codecs},
+};
+use symphonia_adapter_libopus::OpusDecoder;
+
+/// Packets decoded while looking for the layout of streams which don't
+/// declare it up front
+const MAX_PROBE_PACKETS: usize = 10;
+
+static CODECS: LazyLock<CodecRegistry> = LazyLock::new(|| {
+    let mut registry = CodecRegistry::new();
+    register_enabled_codecs(&mut registry);
+    registry.register_audio_decoder::<OpusDecoder>();
+    registry
+});
+
+/// Decodes a file into interleaved f32 samples, one packet at a time
+pub(crate) struct PcmDecoder {
+    path: PathBuf,
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn AudioDecoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    n_frames: Option<u64>,
+    buf: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: usize,
+}
+
+impl PcmDecoder {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut format = open_format(path)?;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| {
+                t.codec_params
+                    .as_ref()
+                    .and_then(|cp| cp.audio())
+                    .is_some_and(|a| a.codec != CODEC_ID_NULL_AUDIO)
+            })
+            .ok_or_else(|| anyhow!("No audio tracks!"))?;
+
+        let track_id = track.id;
+        let time_base = track.time_base;
+        let n_frames = track.num_frames;
+
+        let params = track
+            .codec_params
+            .as_ref()
+            .and_then(|cp| cp.audio())
+            .ok_or_else(|| anyhow!("No audio codec parameters"))?;
+
+        let declared = (
+            params.sample_rate,
+            params.channels.as_ref().map(|c| c.count()),
+        );
+        let mut decoder = make_decoder(params)?;
+
+        let (sample_rate, channels) = match declared {
+            (Some(rate), Some(channels)) => (rate, channels),
+            _ => probe_layout(&mut format, &mut decoder, track_id)
+                .ok_or_else(|| anyhow!("Unknown sample rate or channel layout"))?,
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            decoder,
+            track_id,
+            time_base,
+            n_frames,
+            buf: Vec::new(),
+            sample_rate,
+            channels,
+        })
+    }
+
+    /// Returns `None` once the end of the stream is reached
+    pub fn next_chunk(&mut self) -> Result<Option<&[f32]>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(Some(p)) => p,
+                Ok(None) => return Ok(None),
+                Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(None);
+                }
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(SymphoniaError::ResetRequired) => {
+                    self.decoder.reset();
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if packet.track_id != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(d) => d,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(None);
+                }
+                Err(SymphoniaError::ResetRequired) => {
+                    self.decoder.reset();
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if decoded.frames() == 0 {
+                continue;
+            }
+
+            decoded.copy_to_vec_interleaved::<f32>(&mut self.buf);
+            return Ok(Some(&self.buf));
+        }
+    }
+
+    /// Playable length in seconds, if the container declares it
+    pub fn duration(&self) -> Option<f64> {
+        self.n_frames
+            .map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Seek close to `secs`, returning the position actually landed on
+    pub fn seek(&mut self, secs: f64) -> Result<f64> {
+        let time = Time::try_from_secs_f64(secs).ok_or_else(|| anyhow!("Invalid seek time"))?;
+        let to = || SeekTo::Time {
+            time,
+            track_id: Some(self.track_id),
+        };
+
+        let seeked = match self.format.seek(SeekMode::Coarse, to()) {
+            Ok(seeked) => seeked,
+            // Containers without an index can't seek backwards, so start over
+            // from the top and seek forwards instead
+            Err(_) => {
+                self.format = open_format(&self.path)?;
+                self.format.seek(SeekMode::Coarse, to())?
+            }
+        };
+
+        self.decoder.reset();
+
+        let actual = match self.time_base {
+            Some(tb) => tb.calc_time(seeked.actual_ts).map(|t| t.as_secs_f64()),
+            None => Some(seeked.actual_ts.get() as f64 / self.sample_rate as f64),
+        };
+
+        Ok(actual.unwrap_or(secs))
+    }
+}
+
+fn open_format(path: &Path) -> Result<Box<dyn FormatReader>> {
+    let src = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    Ok(get_probe().probe(&hint, mss, Default::default(), Default::default())?)
+}
+
+fn make_decoder(params: &AudioCodecParameters) -> Result<Box<dyn AudioDecoder>> {
+    let options = AudioDecoderOptions::default();
+
+    // Opus streams in some containers leave out the channel layout
+    if params.codec == CODEC_ID_OPUS && params.channels.is_none() {
+        let mut params = params.clone();
+        params.channels = Some(Channels::Positioned(
+            Position::FRONT_LEFT | Position::FRONT_RIGHT,
+        ));
+        return Ok(CODECS.make_audio_decoder(&params, &options)?);
+    }
+
+    Ok(CODECS.make_audio_decoder(params, &options)?)
+}
+
+/// Decode the first few packets to find out the sample rate and channel count,
+/// then rewind to the start
+fn probe_layout(
+    format: &mut Box<dyn FormatReader>,
+    decoder: &mut Box<dyn AudioDecoder>,
+    track_id: u32,
+) -> Option<(u32, usize)> {
+    let mut layout = None;
+
+    for _ in 0..MAX_PROBE_PACKETS {
+        let Ok(Some(packet)) = format.next_packet() else {
+            break;
+        };
+
+        if packet.track_id != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = decoded.spec();
+                layout = Some((spec.rate(), spec.channels().count()));
+                break;
+            }
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(_) => break,
+        }
+    }
+
+    let _ = format.seek(
+        SeekMode::Accurate,
+        SeekTo::Time {
+            time: Time::ZERO,
+            track_id: Some(track_id),
+        },
+    );
+    decoder.reset();
+
+    layout
+}
@@ -1,5 +1,6 @@
 use super::SimpleSong;
-use std::sync::Arc;
+use crate::library::cover_key;
+use std::{path::PathBuf, sync::Arc};
 
 #[derive(Default, Clone)]
 pub struct Album {
@@ -8,6 +9,8 @@ pub struct Album {
     pub artist: Arc<String>,
     pub year: Option<u32>,
     pub tracklist: Arc<[Arc<SimpleSong>]>,
+    /// Cached artwork, if it has been extracted before
+    pub cover: Option<PathBuf>,
 }
 
 impl Album {
@@ -19,9 +22,14 @@ impl Album {
             artist,
             year: None,
             tracklist: Arc::new([]),
+            cover: None,
         }
     }
 
+    pub fn cover_key(&self) -> u64 {
+        cover_key(&self.artist, &self.title)
+    }
+
     pub fn get_tracklist(&self) -> Vec<Arc<SimpleSong>> {
         self.tracklist.to_vec()
     }
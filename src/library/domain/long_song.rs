@@ -1,4 +1,8 @@
-use super::{FileType, SongInfo};
+use super::{
+    FileType, Lyrics, ReplayGain, SongInfo,
+    rating::{parse_fmps, parse_popm},
+    replay_gain::{parse_gain, parse_peak, parse_r128},
+};
 use crate::{
     DurationStyle, calculate_signature, database::Database, get_readable_duration,
     normalize_metadata_str as nms,
@@ -9,7 +13,7 @@ use symphonia::{
     core::{
         formats::{TrackType, probe::Hint},
         io::MediaSourceStream,
-        meta::StandardTag,
+        meta::{RawValue, StandardTag},
         units::{Duration as SymphoniaDuration, TimeBase},
     },
     default::get_probe,
@@ -34,11 +38,16 @@ pub struct LongSong {
     pub(crate) album: Arc<String>,
     pub(crate) track_no: Option<u32>,
     pub(crate) disc_no: Option<u32>,
+    pub(crate) genres: Vec<String>,
     pub(crate) duration: Duration,
     pub(crate) channels: Option<u8>,
     pub(crate) bitrate: Option<u32>,
     pub(crate) sample_rate: Option<u32>,
     pub(crate) filetype: FileType,
+    pub(crate) replay_gain: ReplayGain,
+    pub(crate) lyrics: Option<Lyrics>,
+    /// Stars read from a POPM or FMPS_RATING tag
+    pub(crate) rating: Option<u8>,
     pub(crate) path: PathBuf,
 }
 
@@ -52,6 +61,7 @@ impl LongSong {
 
     pub fn build_song_symphonia(path: PathBuf) -> Result<LongSong> {
         let src = File::open(&path)?;
+        let file_size = src.metadata()?.len();
 
         let mss = MediaSourceStream::new(Box::new(src), Default::default());
         let mut hint = Hint::new();
@@ -109,6 +119,7 @@ impl LongSong {
         song_info.sample_rate = sample_rate;
 
         song_info.duration = duration;
+        song_info.bitrate = average_bitrate(file_size, duration);
 
         let mut release_year = None;
         let mut recording_year = None;
@@ -116,10 +127,39 @@ impl LongSong {
         let mut artist: Option<(u8, Arc<String>)> = None;
         let mut alb_art: Option<(u8, Arc<String>)> = None;
 
+        let mut gain = ReplayGain::default();
+        let mut r128_track = None;
+        let mut r128_album = None;
+
+        let mut synced_lyrics = None;
+        let mut lyrics = None;
+        let mut rating = None;
+
         let mut metadata = probed.metadata();
         loop {
             if let Some(md) = metadata.current() {
                 for tag in &md.media.tags {
+                    // Opus files carry R128 gains which symphonia does not map
+                    match tag.raw.key.to_ascii_uppercase().as_str() {
+                        "R128_TRACK_GAIN" => r128_track = parse_r128(&tag.raw.value.to_string()),
+                        "R128_ALBUM_GAIN" => r128_album = parse_r128(&tag.raw.value.to_string()),
+                        "SYLT" => {
+                            if let RawValue::Binary(data) = &tag.raw.value {
+                                synced_lyrics = synced_lyrics.or_else(|| Lyrics::from_sylt(data));
+                            }
+                        }
+                        "LYRICS" | "UNSYNCEDLYRICS" | "\u{a9}LYR" => {
+                            lyrics = lyrics.or_else(|| Lyrics::parse(&tag.raw.value.to_string()));
+                        }
+                        "POPM" => {
+                            rating = rating.or_else(|| parse_popm(&tag.raw.value.to_string()));
+                        }
+                        "FMPS_RATING" => {
+                            rating = rating.or_else(|| parse_fmps(&tag.raw.value.to_string()));
+                        }
+                        _ => {}
+                    }
+
                     if let Some(std_tag) = &tag.std {
                         match std_tag {
                             StandardTag::TrackTitle(t) => song_info.title = nms(t),
@@ -136,6 +176,15 @@ impl LongSong {
                             StandardTag::AlbumArtist(aa) => alb_art = best(alb_art, 0, aa),
                             StandardTag::SortAlbumArtist(s) => alb_art = best(alb_art, 1, s),
 
+                            StandardTag::Genre(g) => {
+                                // Multiple genres are often packed into one value
+                                for genre in g.split([';', '\0']).map(nms) {
+                                    if !genre.is_empty() && !song_info.genres.contains(&genre) {
+                                        song_info.genres.push(genre);
+                                    }
+                                }
+                            }
+
                             StandardTag::TrackNumber(t) => song_info.track_no = Some(*t as u32),
                             StandardTag::DiscNumber(d) => song_info.disc_no = Some(*d as u32),
 
@@ -148,6 +197,13 @@ impl LongSong {
                                 recording_year =
                                     recording_year.or_else(|| d.get(..4)?.parse().ok());
                             }
+
+                            StandardTag::ReplayGainTrackGain(g) => gain.track_gain = parse_gain(g),
+                            StandardTag::ReplayGainTrackPeak(p) => gain.track_peak = parse_peak(p),
+                            StandardTag::ReplayGainAlbumGain(g) => gain.album_gain = parse_gain(g),
+                            StandardTag::ReplayGainAlbumPeak(p) => gain.album_peak = parse_peak(p),
+
+                            StandardTag::Lyrics(l) => lyrics = lyrics.or_else(|| Lyrics::parse(l)),
                             _ => {}
                         }
                     }
@@ -165,6 +221,14 @@ impl LongSong {
 
         song_info.year = release_year.or(recording_year);
 
+        gain.track_gain = gain.track_gain.or(r128_track);
+        gain.album_gain = gain.album_gain.or(r128_album);
+        song_info.replay_gain = gain;
+
+        // Synchronized frames take priority over plain (possibly LRC) text
+        song_info.lyrics = synced_lyrics.or(lyrics);
+        song_info.rating = rating;
+
         match artist {
             Some((_, a)) => song_info.artist = Arc::new(nms(&a)),
             None => song_info.artist = Arc::clone(&NO_ARTIST),
@@ -218,6 +282,13 @@ fn get_duration(dur: SymphoniaDuration, tb: TimeBase) -> Duration {
     Duration::from_secs_f64(secs)
 }
 
+/// Average bitrate in kbps, derived from the file size as symphonia does not
+/// report one for most codecs
+pub(crate) fn average_bitrate(file_size: u64, duration: Duration) -> Option<u32> {
+    let secs = duration.as_secs_f64();
+    (secs > 0.0).then(|| (file_size as f64 * 8.0 / secs / 1000.0).round() as u32)
+}
+
 fn best<C: Clone>(current: Option<(u8, C)>, priority: u8, value: &C) -> Option<(u8, C)> {
     match current {
         Some((p, _)) if p < priority => current,
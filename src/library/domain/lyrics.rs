@@ -0,0 +1,250 @@
+use super::LongSong;
+use anyhow::Result;
+use std::{fs, path::Path, time::Duration};
+
+/// ID3 SYLT frames store timestamps as either MPEG frames or milliseconds
+const SYLT_MILLISECONDS: u8 = 2;
+
+#[derive(Debug)]
+pub struct LyricLine {
+    /// `None` for unsynchronized lyrics
+    pub time: Option<Duration>,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Lyrics {
+    lines: Vec<LyricLine>,
+    /// Offset from the `[offset:]` tag in milliseconds, positive values show
+    /// lines sooner
+    offset: i64,
+}
+
+impl Lyrics {
+    /// Parses LRC formatted lyrics, falling back onto plain text when no line
+    /// carries a timestamp
+    pub fn parse(source: &str) -> Option<Self> {
+        let mut lines = vec![];
+        let mut offset = 0;
+        let mut synced = false;
+
+        for raw in source.lines() {
+            let mut rest = raw.trim();
+            let mut times = vec![];
+            let mut tagged = false;
+
+            while let Some(tag) = rest.strip_prefix('[')
+                && let Some((tag, after)) = tag.split_once(']')
+            {
+                if let Some(time) = parse_timestamp(tag) {
+                    times.push(time);
+                } else if let Some(value) = tag.strip_prefix("offset:") {
+                    offset = value.trim().parse().unwrap_or(0);
+                } else if !is_metadata_tag(tag) {
+                    break;
+                }
+                tagged = true;
+                rest = after.trim_start();
+            }
+
+            let text = strip_word_timestamps(rest);
+
+            match times.is_empty() {
+                true if tagged && text.is_empty() => (),
+                true => lines.push(LyricLine { time: None, text }),
+                false => {
+                    synced = true;
+                    lines.extend(times.into_iter().map(|time| LyricLine {
+                        time: Some(time),
+                        text: text.clone(),
+                    }))
+                }
+            }
+        }
+
+        match synced {
+            true => {
+                lines.retain(|line| line.time.is_some());
+                lines.sort_by_key(|line| line.time);
+            }
+            false => trim_blank_lines(&mut lines),
+        }
+
+        (!lines.is_empty()).then_some(Lyrics { lines, offset })
+    }
+
+    /// Lyrics for the song at `path`, an `.lrc` file next to it takes
+    /// priority over lyrics embedded in the tags
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let sidecar = path.with_extension("lrc");
+        if sidecar.is_file() {
+            let bytes = fs::read(&sidecar)?;
+            let text = String::from_utf8_lossy(&bytes);
+            return Ok(Lyrics::parse(text.trim_start_matches('\u{feff}')));
+        }
+
+        Ok(LongSong::build_song_symphonia(path.to_path_buf())?.lyrics)
+    }
+
+    /// Decodes the binary ID3v2 `SYLT` frame. Only millisecond timestamps
+    /// are supported
+    pub fn from_sylt(data: &[u8]) -> Option<Self> {
+        let (&encoding, data) = data.split_first()?;
+        let data = data.get(3..)?; // Language code
+        let (&format, data) = data.split_first()?;
+        let data = data.get(1..)?; // Content type
+
+        if format != SYLT_MILLISECONDS {
+            return None;
+        }
+
+        let (_, mut data) = read_sylt_text(data, encoding)?;
+        let mut entries = vec![];
+
+        while !data.is_empty() {
+            let (text, rest) = read_sylt_text(data, encoding)?;
+            let time = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?);
+            entries.push((Duration::from_millis(time as u64), text));
+            data = &rest[4..];
+        }
+
+        // Some taggers store syllables rather than whole lines, in which case
+        // new lines are marked by a leading line break
+        let by_syllable = entries
+            .iter()
+            .any(|(_, text)| text.starts_with(['\n', '\r']));
+
+        let mut lines: Vec<LyricLine> = vec![];
+        for (time, text) in entries {
+            match lines.last_mut() {
+                Some(line) if by_syllable && !text.starts_with(['\n', '\r']) => {
+                    line.text.push_str(&text)
+                }
+                _ => lines.push(LyricLine {
+                    time: Some(time),
+                    text: text.trim().to_string(),
+                }),
+            }
+        }
+
+        lines
+            .iter_mut()
+            .for_each(|l| l.text = l.text.trim().to_string());
+        lines.sort_by_key(|line| line.time);
+
+        (!lines.is_empty()).then_some(Lyrics { lines, offset: 0 })
+    }
+
+    pub fn lines(&self) -> &[LyricLine] {
+        &self.lines
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.lines.first().is_some_and(|l| l.time.is_some())
+    }
+
+    /// Index of the line being sung at `elapsed`. `adjust` is added on top of
+    /// the offset from the file, both in milliseconds
+    pub fn current_line(&self, elapsed: Duration, adjust: i64) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+
+        let position = elapsed.as_millis() as i64 + self.offset + adjust;
+        let position = Duration::from_millis(position.max(0) as u64);
+
+        self.lines
+            .partition_point(|line| line.time.is_some_and(|t| t <= position))
+            .checked_sub(1)
+    }
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss:xx`
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (mins, secs) = tag.split_once(':')?;
+    let mins = mins.trim().parse::<u64>().ok()?;
+
+    let secs = secs.trim().replacen(':', ".", 1);
+    let secs = secs
+        .parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s >= 0.0)?;
+
+    Some(Duration::from_secs(mins * 60) + Duration::from_secs_f64(secs))
+}
+
+fn is_metadata_tag(tag: &str) -> bool {
+    tag.split_once(':')
+        .is_some_and(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// Enhanced LRC marks individual words with `<mm:ss.xx>`, these are dropped
+fn strip_word_timestamps(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+
+        out.push_str(&rest[..start]);
+        if parse_timestamp(&rest[start + 1..start + end]).is_none() {
+            out.push_str(&rest[start..=start + end]);
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+fn trim_blank_lines(lines: &mut Vec<LyricLine>) {
+    while lines.last().is_some_and(|l| l.text.is_empty()) {
+        lines.pop();
+    }
+
+    let leading = lines.iter().take_while(|l| l.text.is_empty()).count();
+    lines.drain(..leading);
+}
+
+/// Reads a terminated string in the given ID3 text encoding, returning the
+/// remaining bytes after the terminator
+fn read_sylt_text(data: &[u8], encoding: u8) -> Option<(String, &[u8])> {
+    match encoding {
+        // UTF-16 with a BOM, or big endian without one
+        1 | 2 => {
+            let end = data
+                .chunks_exact(2)
+                .position(|c| c == [0, 0])
+                .map(|i| i * 2)?;
+            let units = data[..end].chunks_exact(2);
+
+            let big_endian = !matches!(units.clone().next(), Some([0xFF, 0xFE]));
+
+            let units = units
+                .map(|c| match big_endian {
+                    true => u16::from_be_bytes([c[0], c[1]]),
+                    false => u16::from_le_bytes([c[0], c[1]]),
+                })
+                .filter(|u| *u != 0xFEFF);
+
+            let text = char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect();
+
+            Some((text, &data[end + 2..]))
+        }
+        _ => {
+            let end = data.iter().position(|b| *b == 0)?;
+            let bytes = &data[..end];
+
+            let text = match encoding {
+                3 => String::from_utf8_lossy(bytes).into_owned(),
+                _ => bytes.iter().map(|&b| b as char).collect(),
+            };
+
+            Some((text, &data[end + 1..]))
+        }
+    }
+}
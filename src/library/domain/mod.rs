@@ -1,14 +1,28 @@
 mod album;
 mod filetype;
 mod long_song;
+mod lyrics;
 mod playlist;
+mod rating;
+mod replay_gain;
 mod simple_song;
+mod smart_playlist;
+mod song_field;
 
 pub use album::Album;
 pub use filetype::{FileType, LEGAL_EXTENSION};
 pub use long_song::LongSong;
+pub(crate) use long_song::average_bitrate;
+pub use lyrics::{LyricLine, Lyrics};
 pub use playlist::{Playlist, PlaylistSong};
+pub use rating::{MAX_STARS, Rating};
+pub(crate) use rating::{fmps_value, popm_byte};
+pub use replay_gain::{GainState, REFERENCE_LUFS, ReplayGain, db_to_linear};
 pub use simple_song::SimpleSong;
+pub use smart_playlist::{SmartRules, SongStats};
+pub(crate) use song_field::{
+    SongField, fold_text, parse_days, parse_duration, parse_flag, parse_format,
+};
 
 use crate::DurationStyle;
 
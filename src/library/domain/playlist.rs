@@ -1,12 +1,14 @@
 use crate::{DurationStyle, get_readable_duration, library::SongInfo};
 
-use super::SimpleSong;
+use super::{SimpleSong, SmartRules};
 use std::{sync::Arc, time::Duration};
 
 pub struct Playlist {
     pub id: i64,
     pub name: String,
     pub tracklist: Vec<PlaylistSong>,
+    /// Smart playlists are generated from rules rather than stored songs
+    pub rules: Option<SmartRules>,
     length: Duration,
 }
 
@@ -18,10 +20,27 @@ impl Playlist {
             id,
             name,
             tracklist,
+            rules: None,
             length,
         }
     }
 
+    pub fn smart(id: i64, name: String, rules: SmartRules, songs: Vec<Arc<SimpleSong>>) -> Self {
+        let tracklist = songs
+            .into_iter()
+            .map(|song| PlaylistSong { id: 0, song })
+            .collect();
+
+        Playlist {
+            rules: Some(rules),
+            ..Self::new(id, name, tracklist)
+        }
+    }
+
+    pub fn is_smart(&self) -> bool {
+        self.rules.is_some()
+    }
+
     pub fn get_tracklist(&self) -> Vec<Arc<SimpleSong>> {
         self.tracklist
             .iter()
@@ -0,0 +1,55 @@
+/// Highest number of stars a song can be given
+pub const MAX_STARS: u8 = 5;
+
+/// A user's 0-5 star rating of a song and whether it's a favorite
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    pub stars: u8,
+    pub favorite: bool,
+}
+
+impl Rating {
+    pub fn is_empty(&self) -> bool {
+        self.stars == 0 && !self.favorite
+    }
+}
+
+/// Maps the 0-255 rating of an ID3 POPM frame onto stars, using the same
+/// ranges as Windows Media Player and most taggers. 0 means unrated
+pub fn parse_popm(s: &str) -> Option<u8> {
+    let stars = match s.trim().parse::<u8>().ok()? {
+        0 => return None,
+        1..=31 => 1,
+        32..=95 => 2,
+        96..=159 => 3,
+        160..=223 => 4,
+        _ => 5,
+    };
+    Some(stars)
+}
+
+pub fn popm_byte(stars: u8) -> u8 {
+    match stars.min(MAX_STARS) {
+        0 => 0,
+        1 => 1,
+        2 => 64,
+        3 => 128,
+        4 => 196,
+        _ => 255,
+    }
+}
+
+/// FMPS ratings are stored as a fraction between 0.0 and 1.0
+pub fn parse_fmps(s: &str) -> Option<u8> {
+    let value = s.trim().parse::<f32>().ok()?;
+    if !(0.0..=1.0).contains(&value) {
+        return None;
+    }
+
+    let stars = (value * MAX_STARS as f32).round() as u8;
+    (stars > 0).then_some(stars)
+}
+
+pub fn fmps_value(stars: u8) -> String {
+    format!("{:.1}", stars.min(MAX_STARS) as f32 / MAX_STARS as f32)
+}
@@ -0,0 +1,100 @@
+use crate::{config::ReplayGainMode, user_config};
+
+/// Reference loudness of ReplayGain 2.0, R128 tags are relative to -23 LUFS
+pub const REFERENCE_LUFS: f32 = -18.0;
+const R128_REFERENCE_LUFS: f32 = -23.0;
+
+/// Gains are stored in dB, peaks as linear sample amplitude
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+/// Tracks whether a song's loudness still needs to be looked at by the
+/// background pass
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GainState {
+    /// Scanned before loudness was tracked
+    #[default]
+    Unchecked = 0,
+    /// Tags were read but held no gain information
+    Untagged = 1,
+    /// Gain was read from tags or measured (or could not be measured)
+    Resolved = 2,
+}
+
+impl ReplayGain {
+    pub fn is_empty(&self) -> bool {
+        self.track_gain.is_none() && self.album_gain.is_none()
+    }
+
+    pub fn state(&self) -> GainState {
+        match self.is_empty() {
+            true => GainState::Untagged,
+            false => GainState::Resolved,
+        }
+    }
+
+    /// Linear gain factor to apply during playback based on the user config.
+    /// Songs without any gain information are played back untouched.
+    pub fn factor(&self) -> f32 {
+        let config = user_config();
+
+        let (gain, peak) = match config.replaygain {
+            ReplayGainMode::Off => return 1.0,
+            ReplayGainMode::Track => (
+                self.track_gain.or(self.album_gain),
+                self.track_peak.or(self.album_peak),
+            ),
+            ReplayGainMode::Album => (
+                self.album_gain.or(self.track_gain),
+                self.album_peak.or(self.track_peak),
+            ),
+        };
+
+        let Some(gain) = gain else {
+            return 1.0;
+        };
+
+        let mut gain = gain + config.replaygain_preamp;
+
+        let peak = peak.filter(|p| config.replaygain_prevent_clipping && *p > 0.0);
+        if let Some(peak) = peak {
+            gain = gain.min(-20.0 * peak.log10());
+        }
+
+        db_to_linear(gain)
+    }
+}
+
+pub fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Parses values such as `-6.54 dB` or `+1.2`
+pub fn parse_gain(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let s = match s.len() >= 2 && s[s.len() - 2..].eq_ignore_ascii_case("db") {
+        true => s[..s.len() - 2].trim_end(),
+        false => s,
+    };
+
+    s.parse::<f32>().ok().filter(|g| g.is_finite())
+}
+
+pub fn parse_peak(s: &str) -> Option<f32> {
+    s.trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|p| p.is_finite() && *p >= 0.0)
+}
+
+/// R128 gains are Q7.8 fixed point integers relative to -23 LUFS
+pub fn parse_r128(s: &str) -> Option<f32> {
+    let q = s.trim().parse::<i16>().ok()?;
+    Some(q as f32 / 256.0 + (REFERENCE_LUFS - R128_REFERENCE_LUFS))
+}
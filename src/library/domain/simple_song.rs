@@ -1,9 +1,9 @@
-use super::{FileType, SongInfo};
-use crate::{Database, DurationStyle, get_readable_duration};
+use super::{FileType, ReplayGain, SongInfo};
+use crate::{Database, DurationStyle, get_readable_duration, library::cover_key};
 use anyhow::Result;
 use std::{sync::Arc, time::Duration};
 
-#[derive(Default, Hash, Eq, PartialEq)]
+#[derive(Default, PartialEq)]
 pub struct SimpleSong {
     pub(crate) id: u64,
     pub(crate) title: String,
@@ -16,6 +16,7 @@ pub struct SimpleSong {
     pub(crate) disc_no: Option<u32>,
     pub(crate) duration: Duration,
     pub(crate) filetype: FileType,
+    pub(crate) gain: ReplayGain,
 }
 
 /// DATABASE RELATED METHODS
@@ -46,6 +47,13 @@ impl super::SongDatabase for SimpleSong {
     }
 }
 
+impl SimpleSong {
+    /// Key of the album artwork shared by every song on the album
+    pub fn cover_key(&self) -> u64 {
+        cover_key(&self.album_artist, &self.album)
+    }
+}
+
 /// Generic getter methods
 impl SongInfo for SimpleSong {
     fn get_id(&self) -> u64 {
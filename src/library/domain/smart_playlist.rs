@@ -0,0 +1,340 @@
+use super::{
+    FileType, Rating, SimpleSong,
+    song_field::{
+        SongField as Field, fold_text, parse_days, parse_duration, parse_flag, parse_format,
+    },
+};
+use crate::SongMap;
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Per-song statistics from the `plays`, `history` and `ratings` tables
+#[derive(Default, Clone, Copy)]
+pub struct SongStats {
+    pub plays: u32,
+    /// Unix timestamp of when the song was first scanned
+    pub added: Option<i64>,
+    /// Unix timestamp of the latest play still held in history
+    pub last_played: Option<i64>,
+    pub rating: Rating,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+enum Value {
+    Text(String),
+    Number(f64),
+    Format(FileType),
+}
+
+struct Rule {
+    field: Field,
+    cmp: Cmp,
+    value: Value,
+}
+
+/// The rules behind a smart playlist, e.g. `artist = X and year >= 2000`.
+///
+/// Conditions joined by `and` bind tighter than `or`. Values containing
+/// spaces or keywords can be wrapped in double quotes.
+pub struct SmartRules {
+    source: String,
+    groups: Vec<Vec<Rule>>,
+}
+
+impl SmartRules {
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = source.trim();
+        let tokens = tokenize(source)?;
+
+        let mut groups = vec![vec![]];
+        let mut clause = vec![];
+
+        for token in tokens {
+            let joiner = match &token {
+                Token::Word(w) if w.eq_ignore_ascii_case("and") => Some(false),
+                Token::Word(w) if w.eq_ignore_ascii_case("or") => Some(true),
+                _ => None,
+            };
+
+            match joiner {
+                Some(or) => {
+                    if clause.is_empty() {
+                        bail!("Expected a rule before 'and'/'or'");
+                    }
+                    let group = groups.last_mut().expect("groups is never empty");
+                    group.push(parse_rule(&std::mem::take(&mut clause))?);
+                    if or {
+                        groups.push(vec![]);
+                    }
+                }
+                None => clause.push(token),
+            }
+        }
+
+        if clause.is_empty() {
+            bail!("Expected a rule");
+        }
+        let group = groups.last_mut().expect("groups is never empty");
+        group.push(parse_rule(&clause)?);
+
+        Ok(SmartRules {
+            source: source.to_string(),
+            groups,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Every song in the map matching the rules, in library order
+    pub fn evaluate(
+        &self,
+        songs: &SongMap,
+        stats: &HashMap<u64, SongStats>,
+    ) -> Vec<Arc<SimpleSong>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+
+        songs
+            .values()
+            .filter(|song| {
+                let stats = stats.get(&song.id).copied().unwrap_or_default();
+                self.groups
+                    .iter()
+                    .any(|group| group.iter().all(|rule| rule.matches(song, &stats, now)))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl Rule {
+    fn matches(&self, song: &SimpleSong, stats: &SongStats, now: i64) -> bool {
+        match &self.value {
+            Value::Text(value) => {
+                let Some(text) = self.field.text(song).map(fold_text) else {
+                    return false;
+                };
+
+                match self.cmp {
+                    Cmp::Eq => text == *value,
+                    Cmp::Ne => text != *value,
+                    Cmp::Contains => text.contains(value.as_str()),
+                    _ => false,
+                }
+            }
+            Value::Format(format) => match self.cmp {
+                Cmp::Eq => song.filetype == *format,
+                Cmp::Ne => song.filetype != *format,
+                _ => false,
+            },
+            Value::Number(value) => self
+                .field
+                .number(song, stats, now)
+                .is_some_and(|actual| compare(actual, *value, self.cmp)),
+        }
+    }
+}
+
+fn compare(actual: f64, value: f64, cmp: Cmp) -> bool {
+    match cmp {
+        Cmp::Eq => (actual - value).abs() < f64::EPSILON,
+        Cmp::Ne => (actual - value).abs() >= f64::EPSILON,
+        Cmp::Lt => actual < value,
+        Cmp::Le => actual <= value,
+        Cmp::Gt => actual > value,
+        Cmp::Ge => actual >= value,
+        Cmp::Contains => false,
+    }
+}
+
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Cmp),
+}
+
+impl Token {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Token::Word(s) | Token::Quoted(s) => Some(s),
+            Token::Op(_) => None,
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let quoted = chars.by_ref().take_while(|c| *c != '"').collect::<String>();
+                tokens.push(Token::Quoted(quoted));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', _) => Cmp::Eq,
+                    ('!', true) => Cmp::Ne,
+                    ('<', false) => Cmp::Lt,
+                    ('<', true) => Cmp::Le,
+                    ('>', false) => Cmp::Gt,
+                    ('>', true) => Cmp::Ge,
+                    ('~', false) => Cmp::Contains,
+                    _ => bail!("Unknown operator '{c}'"),
+                };
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"\"=!<>~".contains(*c))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_rule(tokens: &[Token]) -> Result<Rule> {
+    let Some(op_idx) = tokens.iter().position(|t| matches!(t, Token::Op(_))) else {
+        return parse_phrase(tokens);
+    };
+
+    let Token::Op(cmp) = tokens[op_idx] else {
+        unreachable!()
+    };
+
+    let field = join_words(&tokens[..op_idx])?;
+    let value = join_words(&tokens[op_idx + 1..])?;
+
+    if field.is_empty() {
+        bail!("Missing field before operator");
+    }
+    if value.is_empty() {
+        bail!("Missing value for '{field}'");
+    }
+
+    build_rule(parse_field(&field)?, cmp, &value)
+}
+
+/// Plain english rules such as `never played` or `added in last 30 days`
+fn parse_phrase(tokens: &[Token]) -> Result<Rule> {
+    let phrase = join_words(tokens)?.to_lowercase();
+    let words = phrase
+        .split_whitespace()
+        .filter(|w| *w != "the")
+        .collect::<Vec<_>>();
+
+    match words.as_slice() {
+        ["never", "played"] | ["unplayed"] => Ok(Rule {
+            field: Field::Plays,
+            cmp: Cmp::Eq,
+            value: Value::Number(0.0),
+        }),
+        ["favorite" | "favorites" | "loved"] => Ok(Rule {
+            field: Field::Favorite,
+            cmp: Cmp::Eq,
+            value: Value::Number(1.0),
+        }),
+        [field @ ("added" | "played"), "in", "last", amount @ ..] if !amount.is_empty() => {
+            build_rule(parse_field(field)?, Cmp::Le, &amount.join(" "))
+        }
+        _ => bail!("Could not understand rule '{phrase}'"),
+    }
+}
+
+fn join_words(tokens: &[Token]) -> Result<String> {
+    let words = tokens
+        .iter()
+        .map(|t| t.text().ok_or_else(|| anyhow!("Unexpected operator")))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(words.join(" "))
+}
+
+fn parse_field(name: &str) -> Result<Field> {
+    Field::parse(name).ok_or_else(|| anyhow!("Unknown field '{}'", name.to_lowercase()))
+}
+
+fn build_rule(field: Field, cmp: Cmp, value: &str) -> Result<Rule> {
+    let value = match field {
+        Field::Title | Field::Artist | Field::Album | Field::AlbumArtist => {
+            if !matches!(cmp, Cmp::Eq | Cmp::Ne | Cmp::Contains) {
+                bail!("Text fields only support '=', '!=' and '~'");
+            }
+            Value::Text(fold_text(value))
+        }
+        Field::Format => {
+            if !matches!(cmp, Cmp::Eq | Cmp::Ne) {
+                bail!("Format only supports '=' and '!='");
+            }
+            match parse_format(value) {
+                Some(format) => Value::Format(format),
+                None => bail!("Unknown format '{value}'"),
+            }
+        }
+        Field::Added | Field::Played => {
+            if matches!(cmp, Cmp::Eq | Cmp::Ne | Cmp::Contains) {
+                bail!("'added' and 'played' only support '<', '<=', '>' and '>='");
+            }
+            match parse_days(value) {
+                Some(days) => Value::Number(days),
+                None => bail!("Expected a number of days, found '{value}'"),
+            }
+        }
+        Field::Favorite => {
+            if !matches!(cmp, Cmp::Eq | Cmp::Ne) {
+                bail!("Favorite only supports '=' and '!='");
+            }
+            match parse_flag(value) {
+                Some(flag) => Value::Number(flag as u8 as f64),
+                None => bail!("Expected yes or no, found '{value}'"),
+            }
+        }
+        Field::Duration => match parse_duration(value) {
+            Some(secs) => Value::Number(secs),
+            None => bail!("Expected a duration, found '{value}'"),
+        },
+        Field::Year | Field::Track | Field::Disc | Field::Plays | Field::Rating => {
+            if cmp == Cmp::Contains {
+                bail!("Numeric fields do not support '~'");
+            }
+            Value::Number(parse_number(value)?)
+        }
+    };
+
+    Ok(Rule { field, cmp, value })
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .map_err(|_| anyhow!("Expected a number, found '{value}'"))
+}
@@ -0,0 +1,154 @@
+use super::{FileType, SimpleSong, SongStats};
+use crate::strip_diacritics;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// A song attribute which search filters and smart playlist rules can test,
+/// so that both understand the same field names and values
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SongField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Year,
+    Track,
+    Disc,
+    Duration,
+    Plays,
+    Format,
+    Added,
+    Played,
+    Rating,
+    Favorite,
+}
+
+impl SongField {
+    pub fn parse(name: &str) -> Option<Self> {
+        let field = match name.to_lowercase().as_str() {
+            "title" | "t" => SongField::Title,
+            "artist" | "ar" => SongField::Artist,
+            "album" | "al" => SongField::Album,
+            "album artist" | "albumartist" | "album_artist" | "aa" => SongField::AlbumArtist,
+            "year" | "y" => SongField::Year,
+            "track" => SongField::Track,
+            "disc" => SongField::Disc,
+            "duration" | "dur" | "length" => SongField::Duration,
+            "plays" | "play count" | "playcount" | "play_count" => SongField::Plays,
+            "format" | "filetype" | "ext" => SongField::Format,
+            "added" => SongField::Added,
+            "played" | "last played" => SongField::Played,
+            "rating" | "stars" => SongField::Rating,
+            "favorite" | "fav" | "loved" => SongField::Favorite,
+            _ => return None,
+        };
+
+        Some(field)
+    }
+
+    /// Whether the field needs play counts or history from the database
+    pub fn uses_stats(self) -> bool {
+        matches!(
+            self,
+            SongField::Plays | SongField::Added | SongField::Played
+        )
+    }
+
+    pub fn text(self, song: &SimpleSong) -> Option<&str> {
+        match self {
+            SongField::Title => Some(&song.title),
+            SongField::Artist => Some(&song.artist),
+            SongField::Album => Some(&song.album),
+            SongField::AlbumArtist => Some(&song.album_artist),
+            _ => None,
+        }
+    }
+
+    /// The value of a numeric field. `added` and `played` are in days before
+    /// `now`, favorites are `1` or `0`
+    pub fn number(self, song: &SimpleSong, stats: &SongStats, now: i64) -> Option<f64> {
+        let days_since = |ts: Option<i64>| ts.map(|ts| (now - ts) as f64 / SECS_PER_DAY);
+
+        match self {
+            SongField::Year => song.year.map(f64::from),
+            SongField::Track => song.track_no.map(f64::from),
+            SongField::Disc => song.disc_no.map(f64::from),
+            SongField::Duration => Some(song.duration.as_secs_f64()),
+            SongField::Plays => Some(stats.plays as f64),
+            SongField::Added => days_since(stats.added),
+            SongField::Played => days_since(stats.last_played),
+            SongField::Rating => Some(stats.rating.stars as f64),
+            SongField::Favorite => Some(stats.rating.favorite as u8 as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Text is compared ignoring case and diacritics
+pub(crate) fn fold_text(text: &str) -> String {
+    strip_diacritics(text)
+}
+
+pub(crate) fn parse_format(value: &str) -> Option<FileType> {
+    match FileType::from(value.to_lowercase().as_str()) {
+        FileType::ERR => None,
+        format => Some(format),
+    }
+}
+
+pub(crate) fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" | "1" => Some(true),
+        "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Seconds, `m:ss`, or units such as `5m`, `90s` and `1h30m`
+pub(crate) fn parse_duration(value: &str) -> Option<f64> {
+    if let Some((mins, secs)) = value.split_once(':') {
+        return Some(mins.parse::<f64>().ok()? * 60.0 + secs.parse::<f64>().ok()?);
+    }
+
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(secs);
+    }
+
+    let mut total = 0.0;
+    let mut amount = String::new();
+    for c in value.to_lowercase().chars() {
+        let unit = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            c if c.is_ascii_digit() || c == '.' => {
+                amount.push(c);
+                continue;
+            }
+            _ => return None,
+        };
+        total += amount.parse::<f64>().ok()? * unit;
+        amount.clear();
+    }
+
+    amount.is_empty().then_some(total)
+}
+
+/// Days, or `30d`, `30 days`, `2w`, `3m` and `1y`
+pub(crate) fn parse_days(value: &str) -> Option<f64> {
+    let value = value.to_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+
+    let multiplier = match unit.trim() {
+        "" | "d" | "day" | "days" => 1.0,
+        "w" | "week" | "weeks" => 7.0,
+        "m" | "month" | "months" => 30.0,
+        "y" | "year" | "years" => 365.0,
+        _ => return None,
+    };
+
+    Some(amount.parse::<f64>().ok()? * multiplier)
+}
@@ -5,7 +5,8 @@ use crate::{
     calculate_signature,
     database::Database,
     expand_tilde,
-    library::{Album, LongSong, SimpleSong, SongInfo},
+    library::{Album, LongSong, SimpleSong, SongInfo, TagEdit, cached_cover, write_tags},
+    user_config,
 };
 
 use anyhow::{Result, anyhow};
@@ -13,7 +14,7 @@ use crossbeam::channel::Sender;
 use indexmap::IndexMap;
 use rayon::prelude::*;
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     path::{Path, PathBuf},
     sync::{
         Arc,
@@ -28,6 +29,10 @@ pub struct Library {
     pub roots: HashSet<PathBuf>,
     pub songs: SongMap,
     pub albums: IndexMap<i64, Album>,
+    pub genres: HashMap<u64, Vec<String>>,
+    /// Songs carried over to a new id while building this library, keyed by
+    /// their old id
+    pub relinked: HashMap<u64, u64>,
 }
 
 const SCANNING_FINISHED: u8 = 25;
@@ -42,6 +47,8 @@ impl Library {
             roots: HashSet::new(),
             songs: SongMap::default(),
             albums: IndexMap::new(),
+            genres: HashMap::new(),
+            relinked: HashMap::new(),
         })
     }
 
@@ -87,10 +94,16 @@ impl Library {
         }
 
         if !self.any_root_modified()? {
+            self.backfill_genres()?;
+            self.db.backfill_bitrates()?;
+            self.import_ratings()?;
             self.collect_songs()?;
             self.build_albums()?;
         } else {
             self.update_db_by_root()?;
+            self.backfill_genres()?;
+            self.db.backfill_bitrates()?;
+            self.import_ratings()?;
             self.collect_songs()?;
             self.build_albums()?;
 
@@ -112,12 +125,13 @@ impl Library {
             new_files.extend(new);
         }
 
-        let removed_ids = existing_hashes.into_iter().collect::<Vec<u64>>();
+        let removed_ids = existing_hashes.iter().copied().collect::<Vec<u64>>();
         let new_file_count = new_files.len();
 
         // WARNING: Flip these two if statements in the event that INSERT OR REPLACE fails us
         if !new_files.is_empty() {
-            Self::insert_new_songs(&mut self.db, new_files)?;
+            let relinked = Self::insert_new_songs(&mut self.db, new_files, &existing_hashes)?;
+            self.relinked.extend(relinked);
         }
 
         if !removed_ids.is_empty() {
@@ -127,6 +141,83 @@ impl Library {
         Ok((new_file_count, removed_ids.len()))
     }
 
+    /// Update the database for a set of changed paths, without walking the
+    /// roots. Directories are scanned as a whole, paths which no longer exist
+    /// have their songs (or any song beneath them) removed.
+    ///
+    /// Returns false when none of the paths resulted in a change
+    pub fn apply_changes(&mut self, paths: HashSet<PathBuf>) -> Result<bool> {
+        let mut existing_hashes = self.db.get_hashes()?;
+        let mut candidates = Vec::new();
+        let mut removed = Vec::new();
+
+        for path in paths {
+            if path.is_dir() {
+                candidates.extend(Self::collect_valid_files(&path).collect::<Vec<_>>());
+            } else if !path.exists() {
+                removed.push(path);
+            } else if Self::is_valid_file(&path)
+                && let Ok(canon) = path.canonicalize()
+            {
+                candidates.push(canon);
+            }
+        }
+
+        // Modified files share a path with their stale entry, which gets
+        // replaced on insert
+        let new_files = Self::filter_files(candidates, &mut existing_hashes);
+        let changed = !new_files.is_empty() || !removed.is_empty();
+
+        if !new_files.is_empty() {
+            // Only songs which were removed or rewritten can be carried over
+            let paths = removed
+                .iter()
+                .chain(&new_files)
+                .cloned()
+                .collect::<Vec<_>>();
+            let stale = self.db.get_ids_by_path(&paths)?;
+
+            let relinked = Self::insert_new_songs(&mut self.db, new_files, &stale)?;
+            self.relinked.extend(relinked);
+        }
+
+        if !removed.is_empty() {
+            self.db.delete_songs_by_path(&removed)?;
+        }
+
+        if changed {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            self.db.set_last_scan(timestamp)?;
+        }
+
+        Ok(changed)
+    }
+
+    /// Write `edit` into a song's file and update its row to match, returning
+    /// the song's new id
+    pub fn retag_song(&mut self, id: u64, edit: &TagEdit) -> Result<u64> {
+        let path = PathBuf::from(self.db.get_song_path(id)?);
+        write_tags(&path, edit)?;
+
+        let song = LongSong::build_song_symphonia(path)?;
+        self.db.update_song_tags(id, &song)?;
+        self.relinked.insert(id, song.id);
+
+        Ok(song.id)
+    }
+
+    fn is_valid_file(path: &Path) -> bool {
+        let legal = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| LEGAL_EXTENSION.contains(ext.to_lowercase().as_str()));
+
+        legal
+            && path
+                .parent()
+                .is_some_and(|dir| !dir.join(".nomedia").exists())
+    }
+
     /// Collect valid files from a root directory
     ///
     /// Function collects valid files with vetted extensions
@@ -191,8 +282,12 @@ impl Library {
             .collect::<Vec<LongSong>>()
     }
 
-    fn insert_new_songs(db: &mut Database, new_files: Vec<PathBuf>) -> Result<()> {
-        let songs = Self::process_songs(new_files);
+    fn insert_new_songs(
+        db: &mut Database,
+        new_files: Vec<PathBuf>,
+        stale: &HashSet<u64>,
+    ) -> Result<HashMap<u64, u64>> {
+        let mut songs = Self::process_songs(new_files);
 
         let mut artist_cache = HashSet::new();
         let mut aa_binding = HashSet::new();
@@ -208,16 +303,59 @@ impl Library {
         // ORDER IS IMPORTANT HERE
         db.insert_artists(&artist_cache)?;
         db.insert_albums(&aa_binding)?;
+
+        // Moved and modified songs keep their rows, the rest are inserted
+        let relinked = db.relink_songs(stale, &mut songs)?;
         db.insert_songs(&songs)?;
 
-        Ok(())
+        Ok(relinked)
     }
 
     pub fn collect_songs(&mut self) -> Result<()> {
         self.songs = self.db.get_all_songs()?;
+        self.genres = self.db.get_genres()?;
         Ok(())
     }
 
+    /// Re-read the tags of songs scanned before genres were stored
+    fn backfill_genres(&mut self) -> Result<()> {
+        if !self.db.genre_backfill_pending()? {
+            return Ok(());
+        }
+
+        let genres = self
+            .db
+            .get_song_paths()?
+            .into_par_iter()
+            .filter_map(|(id, path)| {
+                let song = LongSong::build_song_symphonia(PathBuf::from(path)).ok()?;
+                Some((id, song.genres))
+            })
+            .collect::<Vec<_>>();
+
+        self.db.backfill_genres(&genres)
+    }
+
+    /// Read the rating tags of the whole library the first time they're
+    /// enabled, later scans only read those of new or changed files
+    fn import_ratings(&mut self) -> Result<()> {
+        if !user_config().rating_tags || self.db.ratings_imported()? {
+            return Ok(());
+        }
+
+        let ratings = self
+            .db
+            .get_song_paths()?
+            .into_par_iter()
+            .filter_map(|(id, path)| {
+                let song = LongSong::build_song_symphonia(PathBuf::from(path)).ok()?;
+                Some((id, song.rating?))
+            })
+            .collect::<Vec<_>>();
+
+        self.db.import_ratings(&ratings)
+    }
+
     pub fn get_songs_map(&self) -> &SongMap {
         &self.songs
     }
@@ -261,6 +399,10 @@ impl Library {
 
         self.albums.retain(|_id, album| !album.tracklist.is_empty());
 
+        for album in self.albums.values_mut() {
+            album.cover = cached_cover(album.cover_key());
+        }
+
         Ok(())
     }
 }
@@ -349,7 +491,7 @@ impl Library {
         });
 
         // Phase 2: Processing song metadata
-        let removed_ids = existing_hashes.into_iter().collect::<Vec<u64>>();
+        let removed_ids = existing_hashes.iter().copied().collect::<Vec<u64>>();
         let total_new = new_files.len();
 
         if !new_files.is_empty() {
@@ -358,7 +500,13 @@ impl Library {
                 current: 0,
                 total: total_new,
             });
-            Self::insert_new_songs_with_progress(&mut self.db, new_files, tx)?;
+            let relinked = Self::insert_new_songs_with_progress(
+                &mut self.db,
+                new_files,
+                &existing_hashes,
+                tx,
+            )?;
+            self.relinked.extend(relinked);
         } else {
             let _ = tx.send(LibraryRefreshProgress::Processing {
                 progress: PROCESSING_FINISHED,
@@ -387,6 +535,8 @@ impl Library {
         });
 
         // Phase 3: Collecting songs from database
+        self.backfill_genres()?;
+        self.db.backfill_bitrates()?;
         self.collect_songs()?;
         let _ = tx.send(LibraryRefreshProgress::UpdatingDatabase { progress: 90 });
 
@@ -401,13 +551,14 @@ impl Library {
     fn insert_new_songs_with_progress(
         db: &mut Database,
         new_files: Vec<PathBuf>,
+        stale: &HashSet<u64>,
         tx: &Sender<LibraryRefreshProgress>,
-    ) -> Result<()> {
+    ) -> Result<HashMap<u64, u64>> {
         let total = new_files.len();
         let processed = AtomicUsize::new(0);
         let tx_clone = tx.clone();
 
-        let songs: Vec<LongSong> = new_files
+        let mut songs: Vec<LongSong> = new_files
             .into_par_iter()
             .filter_map(|path| {
                 // let result = LongSong::build_song_lofty(&path).ok();
@@ -446,8 +597,10 @@ impl Library {
 
         db.insert_artists(&artist_cache)?;
         db.insert_albums(&aa_binding)?;
+
+        let relinked = db.relink_songs(stale, &mut songs)?;
         db.insert_songs(&songs)?;
 
-        Ok(())
+        Ok(relinked)
     }
 }
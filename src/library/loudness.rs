@@ -0,0 +1,219 @@
+//! EBU R128 loudness measurement, used to fill in ReplayGain values for
+//! songs which were not tagged by another tool
+
+use anyhow::Result;
+use std::{f64::consts::PI, path::Path};
+
+use crate::{
+    Database,
+    library::{
+        GainState, LongSong, REFERENCE_LUFS, ReplayGain, biquad::Biquad, decode::PcmDecoder,
+    },
+};
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Blocks are 400ms long and overlap by 75%, so they are assembled from
+/// four consecutive 100ms steps
+const STEPS_PER_BLOCK: usize = 4;
+
+/// Resolve gain for songs which have not been looked at yet. Tags are read
+/// for songs scanned before gains were tracked, and when `analyze` is set,
+/// any song still lacking gain information is measured one album at a time.
+///
+/// Returns the number of songs which were updated
+pub fn resolve_replay_gain(analyze: bool) -> Result<usize> {
+    let mut db = Database::open()?;
+    let mut updated = 0;
+
+    for (id, path, _) in db.get_songs_by_gain_state(GainState::Unchecked)? {
+        let gain = LongSong::build_song_symphonia(path.into())
+            .map(|song| song.replay_gain)
+            .unwrap_or_default();
+
+        if !gain.is_empty() {
+            updated += 1;
+        }
+        db.set_replay_gain(id, &gain, gain.state())?;
+    }
+
+    if !analyze {
+        return Ok(updated);
+    }
+
+    let untagged = db.get_songs_by_gain_state(GainState::Untagged)?;
+
+    for album in untagged.chunk_by(|a, b| a.2 == b.2) {
+        let mut album_blocks = vec![];
+        let mut album_peak = 0f32;
+        let mut measured = vec![];
+
+        for (id, path, _) in album {
+            match measure(Path::new(path)) {
+                Ok(m) => {
+                    album_peak = album_peak.max(m.peak);
+                    album_blocks.extend_from_slice(&m.blocks);
+                    measured.push((*id, Some(m)));
+                }
+                Err(_) => measured.push((*id, None)),
+            }
+        }
+
+        let album_gain = integrated_loudness(&album_blocks).map(loudness_to_gain);
+
+        for (id, m) in measured {
+            // Undecodable songs are resolved without gain so they aren't retried
+            let gain = match m {
+                Some(m) => {
+                    updated += 1;
+                    ReplayGain {
+                        track_gain: integrated_loudness(&m.blocks).map(loudness_to_gain),
+                        track_peak: Some(m.peak),
+                        album_gain,
+                        album_peak: album_gain.map(|_| album_peak),
+                    }
+                }
+                None => ReplayGain::default(),
+            };
+            db.set_replay_gain(id, &gain, GainState::Resolved)?;
+        }
+    }
+
+    Ok(updated)
+}
+
+fn loudness_to_gain(lufs: f64) -> f32 {
+    (REFERENCE_LUFS as f64 - lufs) as f32
+}
+
+struct Measurement {
+    /// Mean square energy of every gating block
+    blocks: Vec<f64>,
+    /// Sample peak
+    peak: f32,
+}
+
+fn measure(path: &Path) -> Result<Measurement> {
+    let mut decoder = PcmDecoder::open(path)?;
+    let channels = decoder.channels.max(1);
+    let mut meter = LoudnessMeter::new(decoder.sample_rate, channels);
+
+    while let Some(samples) = decoder.next_chunk()? {
+        meter.process(samples);
+    }
+
+    Ok(meter.finish())
+}
+
+/// Integrated loudness in LUFS, `None` if every block falls below the
+/// absolute gate (silence)
+fn integrated_loudness(blocks: &[f64]) -> Option<f64> {
+    let relative = block_loudness(gated_mean(blocks, ABSOLUTE_GATE)?) + RELATIVE_GATE;
+    gated_mean(blocks, relative.max(ABSOLUTE_GATE)).map(block_loudness)
+}
+
+/// Mean energy of the blocks louder than `gate`
+fn gated_mean(blocks: &[f64], gate: f64) -> Option<f64> {
+    let (sum, n) = blocks
+        .iter()
+        .filter(|z| block_loudness(**z) > gate)
+        .fold((0.0, 0usize), |(sum, n), z| (sum + z, n + 1));
+
+    (n > 0).then(|| sum / n as f64)
+}
+
+fn block_loudness(z: f64) -> f64 {
+    -0.691 + 10.0 * z.log10()
+}
+
+/// K-weighting filter from ITU-R BS.1770, coefficients are derived for the
+/// actual sample rate rather than the tabled 48kHz values
+fn k_weighting(rate: u32) -> [Biquad; 2] {
+    let rate = rate as f64;
+
+    // High shelf modelling the acoustic effect of the head
+    let k = (PI * 1681.974450955533 / rate).tan();
+    let q = 0.7071752369554196;
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    // RLB high pass
+    let k = (PI * 38.13547087602444 / rate).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    step_len: usize,
+    step_pos: usize,
+    step_energy: f64,
+    steps: Vec<f64>,
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    fn new(rate: u32, channels: usize) -> Self {
+        Self {
+            channels,
+            filters: vec![k_weighting(rate); channels],
+            step_len: (rate as usize / 10).max(1),
+            step_pos: 0,
+            step_energy: 0.0,
+            steps: vec![],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (sample, filters) in frame.iter().zip(self.filters.iter_mut()) {
+                self.peak = self.peak.max(sample.abs());
+
+                let weighted = filters
+                    .iter_mut()
+                    .fold(*sample as f64, |x, filter| filter.process(x));
+                self.step_energy += weighted * weighted;
+            }
+
+            self.step_pos += 1;
+            if self.step_pos == self.step_len {
+                self.steps.push(self.step_energy / self.step_len as f64);
+                self.step_pos = 0;
+                self.step_energy = 0.0;
+            }
+        }
+    }
+
+    fn finish(self) -> Measurement {
+        let blocks = self
+            .steps
+            .windows(STEPS_PER_BLOCK)
+            .map(|w| w.iter().sum::<f64>() / STEPS_PER_BLOCK as f64)
+            .collect();
+
+        Measurement {
+            blocks,
+            peak: self.peak,
+        }
+    }
+}
@@ -1,8 +1,25 @@
+mod biquad;
+mod cover;
+mod decode;
 mod domain;
 mod library;
+mod loudness;
+mod tags;
+mod watcher;
 
+pub(crate) use biquad::Biquad;
+pub use cover::{cached_cover, cover_key, find_cover};
+pub(crate) use decode::PcmDecoder;
 pub use domain::LEGAL_EXTENSION;
 pub use domain::{
-    Album, FileType, LongSong, Playlist, PlaylistSong, SimpleSong, SongDatabase, SongInfo,
+    Album, FileType, GainState, LongSong, LyricLine, Lyrics, MAX_STARS, Playlist, PlaylistSong,
+    REFERENCE_LUFS, Rating, ReplayGain, SimpleSong, SmartRules, SongDatabase, SongInfo, SongStats,
+    db_to_linear,
+};
+pub(crate) use domain::{
+    SongField, average_bitrate, fold_text, parse_days, parse_duration, parse_flag, parse_format,
 };
 pub use library::Library;
+pub use loudness::resolve_replay_gain;
+pub use tags::{TagEdit, TagField, supports_rating, write_tags};
+pub use watcher::LibraryWatcher;
@@ -0,0 +1,155 @@
+use anyhow::{Result, bail};
+use std::io::{self, Read, Write};
+
+use super::{TagEdit, vorbis::VorbisComments};
+
+const STREAMINFO: u8 = 0;
+const VORBIS_COMMENT: u8 = 4;
+const MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+/// Metadata blocks are copied over with the comment block replaced, followed
+/// by the untouched audio frames
+pub(super) fn write(src: &mut impl Read, dst: &mut impl Write, edit: &TagEdit) -> Result<()> {
+    let mut magic = [0; 4];
+    src.read_exact(&mut magic)?;
+    if &magic != b"fLaC" {
+        bail!("Not a FLAC file");
+    }
+
+    let mut blocks: Vec<(u8, Vec<u8>)> = vec![];
+    loop {
+        let mut header = [0; 4];
+        src.read_exact(&mut header)?;
+
+        let last = header[0] & 0x80 != 0;
+        let kind = header[0] & 0x7f;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+
+        let mut data = vec![0; len];
+        src.read_exact(&mut data)?;
+        blocks.push((kind, data));
+
+        if last {
+            break;
+        }
+    }
+
+    if blocks.first().map(|(kind, _)| *kind) != Some(STREAMINFO) {
+        bail!("FLAC file is missing its STREAMINFO block");
+    }
+
+    let mut comments = match blocks.iter().find(|(kind, _)| *kind == VORBIS_COMMENT) {
+        Some((_, data)) => VorbisComments::parse(data)?.0,
+        None => VorbisComments::new(),
+    };
+    comments.apply(edit);
+
+    let comment_block = comments.to_bytes();
+    if comment_block.len() > MAX_BLOCK_LEN {
+        bail!("Vorbis comment block is too large");
+    }
+
+    blocks.retain(|(kind, _)| *kind != VORBIS_COMMENT);
+    blocks.insert(1, (VORBIS_COMMENT, comment_block));
+
+    dst.write_all(b"fLaC")?;
+    let count = blocks.len();
+    for (idx, (kind, data)) in blocks.iter().enumerate() {
+        let last = match idx + 1 == count {
+            true => 0x80,
+            false => 0,
+        };
+        let len = (data.len() as u32).to_be_bytes();
+
+        dst.write_all(&[kind | last, len[1], len[2], len[3]])?;
+        dst.write_all(data)?;
+    }
+
+    io::copy(src, dst)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::TagField;
+
+    const AUDIO: &[u8] = b"\xff\xf8frames";
+
+    fn block(kind: u8, last: bool, data: &[u8]) -> Vec<u8> {
+        let len = (data.len() as u32).to_be_bytes();
+        let flag = match last {
+            true => 0x80,
+            false => 0,
+        };
+        [&[kind | flag, len[1], len[2], len[3]], data].concat()
+    }
+
+    fn comments(title: &str) -> Vec<u8> {
+        let mut edit = TagEdit::default();
+        edit.set(TagField::Title, title);
+
+        let mut comments = VorbisComments::new();
+        comments.apply(&edit);
+        comments.to_bytes()
+    }
+
+    fn edit_title(file: &[u8], title: &str) -> Result<Vec<u8>> {
+        let mut edit = TagEdit::default();
+        edit.set(TagField::Title, title);
+
+        let mut out = vec![];
+        write(&mut &file[..], &mut out, &edit)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn write_replaces_comment_block() {
+        let streaminfo = [7; 34];
+        let file = [
+            b"fLaC".as_slice(),
+            &block(STREAMINFO, false, &streaminfo),
+            &block(VORBIS_COMMENT, false, &comments("Old")),
+            &block(1, true, &[0; 4]),
+            AUDIO,
+        ]
+        .concat();
+
+        let expected = [
+            b"fLaC".as_slice(),
+            &block(STREAMINFO, false, &streaminfo),
+            &block(VORBIS_COMMENT, false, &comments("New")),
+            &block(1, true, &[0; 4]),
+            AUDIO,
+        ]
+        .concat();
+        assert_eq!(edit_title(&file, "New").unwrap(), expected);
+    }
+
+    #[test]
+    fn write_adds_missing_comment_block() {
+        let file = [
+            b"fLaC".as_slice(),
+            &block(STREAMINFO, true, &[7; 34]),
+            AUDIO,
+        ]
+        .concat();
+
+        let expected = [
+            b"fLaC".as_slice(),
+            &block(STREAMINFO, false, &[7; 34]),
+            &block(VORBIS_COMMENT, true, &comments("New")),
+            AUDIO,
+        ]
+        .concat();
+        assert_eq!(edit_title(&file, "New").unwrap(), expected);
+    }
+
+    #[test]
+    fn write_rejects_invalid_files() {
+        assert!(edit_title(b"OggS", "New").is_err());
+
+        let no_streaminfo = [b"fLaC".as_slice(), &block(1, true, &[0; 4])].concat();
+        assert!(edit_title(&no_streaminfo, "New").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Writing edited metadata back to audio files. Only the fields exposed by
+//! the tag editor are touched, everything else in the file is preserved
+
+use anyhow::{Result, bail};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::library::{FileType, MAX_STARS};
+
+mod flac;
+mod mp3;
+mod mp4;
+mod ogg;
+mod vorbis;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Year,
+    Track,
+    Disc,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 7] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::AlbumArtist,
+        TagField::Year,
+        TagField::Track,
+        TagField::Disc,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TagField::Title => "Title",
+            TagField::Artist => "Artist",
+            TagField::Album => "Album",
+            TagField::AlbumArtist => "Album Artist",
+            TagField::Year => "Year",
+            TagField::Track => "Track",
+            TagField::Disc => "Disc",
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, TagField::Year | TagField::Track | TagField::Disc)
+    }
+}
+
+/// Fields to change in a file, an empty value removes the field
+#[derive(Clone, Debug, Default)]
+pub struct TagEdit {
+    fields: Vec<(TagField, String)>,
+    /// Stars to store in the file's rating tag, 0 removes it
+    rating: Option<u8>,
+}
+
+impl TagEdit {
+    pub fn set(&mut self, field: TagField, value: &str) {
+        let value = value.trim().to_string();
+        match self.fields.iter_mut().find(|(f, _)| *f == field) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((field, value)),
+        }
+    }
+
+    pub fn set_rating(&mut self, stars: u8) {
+        self.rating = Some(stars.min(MAX_STARS));
+    }
+
+    pub fn rating(&self) -> Option<u8> {
+        self.rating
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.rating.is_none()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TagField, &str)> {
+        self.fields.iter().map(|(f, v)| (*f, v.as_str()))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in self.iter() {
+            if field.is_numeric() && !value.is_empty() && value.parse::<u32>().is_err() {
+                bail!("{} must be a number, got \"{value}\"", field.label());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `edit` into the file at `path`. Files are rewritten through a
+/// temporary copy, except MP3s which the id3 crate updates in place
+pub fn write_tags(path: &Path, edit: &TagEdit) -> Result<()> {
+    edit.validate()?;
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match FileType::from(ext.as_str()) {
+        FileType::MP3 => mp3::write(path, edit),
+        FileType::FLAC => rewrite(path, |src, dst| flac::write(src, dst, edit)),
+        FileType::OGG | FileType::OPUS => rewrite(path, |src, dst| ogg::write(src, dst, edit)),
+        FileType::M4A => rewrite(path, |src, dst| mp4::write(src, dst, edit)),
+        filetype => bail!("Editing {} tags is not supported", filetype.to_str()),
+    }
+}
+
+/// Formats which rating tags are written to, MP4 and WAV files only keep
+/// their rating in the database
+pub fn supports_rating(filetype: FileType) -> bool {
+    matches!(
+        filetype,
+        FileType::MP3 | FileType::FLAC | FileType::OGG | FileType::OPUS
+    )
+}
+
+type Source = BufReader<File>;
+type Sink = BufWriter<File>;
+
+/// The edited copy is written next to the original and renamed over it, so a
+/// failure never leaves a truncated file behind
+fn rewrite(path: &Path, write: impl FnOnce(&mut Source, &mut Sink) -> Result<()>) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{name}.vox-tmp"));
+
+    let result = (|| {
+        let mut src = BufReader::new(File::open(path)?);
+        let mut dst = BufWriter::new(File::create(&tmp)?);
+
+        write(&mut src, &mut dst)?;
+        dst.flush()?;
+        dst.get_ref().sync_all()?;
+
+        fs::set_permissions(&tmp, fs::metadata(path)?.permissions())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
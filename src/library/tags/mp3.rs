@@ -0,0 +1,93 @@
+use anyhow::Result;
+use id3::{Tag, TagLike, Timestamp, Version, frame::Popularimeter, no_tag_ok};
+use std::{fs::OpenOptions, path::Path};
+
+use super::{TagEdit, TagField};
+use crate::library::domain::popm_byte;
+
+/// Owner of the POPM frame written, most taggers read ratings from any owner
+const POPM_USER: &str = "Windows Media Player 9 Series";
+
+pub(super) fn write(path: &Path, edit: &TagEdit) -> Result<()> {
+    let mut tag = no_tag_ok(Tag::read_from_path(path))?.unwrap_or_default();
+    let version = match tag.version() {
+        Version::Id3v22 => Version::Id3v23,
+        v => v,
+    };
+
+    for (field, value) in edit.iter() {
+        let number = value.parse::<u32>().ok();
+
+        match field {
+            TagField::Title => match value.is_empty() {
+                true => tag.remove_title(),
+                false => tag.set_title(value),
+            },
+            TagField::Artist => match value.is_empty() {
+                true => tag.remove_artist(),
+                false => tag.set_artist(value),
+            },
+            TagField::Album => match value.is_empty() {
+                true => tag.remove_album(),
+                false => tag.set_album(value),
+            },
+            TagField::AlbumArtist => match value.is_empty() {
+                true => tag.remove_album_artist(),
+                false => tag.set_album_artist(value),
+            },
+            TagField::Year => {
+                // A stale release date would take priority over the new year
+                tag.remove_year();
+                tag.remove_date_recorded();
+                tag.remove_date_released();
+
+                if let Some(year) = number {
+                    match version {
+                        Version::Id3v24 => tag.set_date_recorded(Timestamp {
+                            year: year as i32,
+                            month: None,
+                            day: None,
+                            hour: None,
+                            minute: None,
+                            second: None,
+                        }),
+                        _ => tag.set_year(year as i32),
+                    }
+                }
+            }
+            TagField::Track => match number {
+                Some(track) => tag.set_track(track),
+                None => tag.remove_track(),
+            },
+            TagField::Disc => match number {
+                Some(disc) => tag.set_disc(disc),
+                None => tag.remove_disc(),
+            },
+        }
+    }
+
+    if let Some(stars) = edit.rating() {
+        // Ratings from other players would be read back in place of ours
+        let removed = tag.remove("POPM");
+        let counter = removed
+            .iter()
+            .find_map(|f| f.content().popularimeter())
+            .map_or(0, |p| p.counter);
+
+        if stars > 0 {
+            tag.add_frame(Popularimeter {
+                user: POPM_USER.to_string(),
+                rating: popm_byte(stars),
+                counter,
+            });
+        }
+    }
+
+    tag.write_to_path(path, version)?;
+
+    // An ID3v1 tag would still carry the old values
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    id3::v1::Tag::remove_from_file(file)?;
+
+    Ok(())
+}
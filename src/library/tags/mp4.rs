@@ -0,0 +1,444 @@
+use anyhow::{Result, anyhow, bail};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use super::{TagEdit, TagField};
+
+/// iTunes data types
+const DATA_UTF8: u32 = 1;
+const DATA_IMPLICIT: u32 = 0;
+
+fn item_kind(field: TagField) -> [u8; 4] {
+    match field {
+        TagField::Title => *b"\xa9nam",
+        TagField::Artist => *b"\xa9ART",
+        TagField::Album => *b"\xa9alb",
+        TagField::AlbumArtist => *b"aART",
+        TagField::Year => *b"\xa9day",
+        TagField::Track => *b"trkn",
+        TagField::Disc => *b"disk",
+    }
+}
+
+struct Atom {
+    kind: [u8; 4],
+    payload: Vec<u8>,
+}
+
+impl Atom {
+    fn new(kind: [u8; 4], payload: Vec<u8>) -> Self {
+        Atom { kind, payload }
+    }
+}
+
+/// Position of a top level atom in the source file
+struct Entry {
+    kind: [u8; 4],
+    start: u64,
+    header_len: u64,
+    size: u64,
+}
+
+/// Only the `moov` atom is rebuilt, with its metadata list edited. When it
+/// precedes the media data, chunk offsets are moved by its change in size
+pub(super) fn write(
+    src: &mut (impl Read + Seek),
+    dst: &mut impl Write,
+    edit: &TagEdit,
+) -> Result<()> {
+    let entries = read_entries(src)?;
+
+    let moov_idx = entries
+        .iter()
+        .position(|e| &e.kind == b"moov")
+        .ok_or_else(|| anyhow!("MP4 file has no moov atom"))?;
+    let moov = &entries[moov_idx];
+
+    src.seek(SeekFrom::Start(moov.start + moov.header_len))?;
+    let mut payload = vec![0; (moov.size - moov.header_len) as usize];
+    src.read_exact(&mut payload)?;
+
+    let mut children = parse_atoms(&payload)?;
+    edit_udta(&mut children, edit)?;
+
+    let old_len = moov.size as i64;
+    let new_len = serialize(&Atom::new(*b"moov", serialize_all(&children))).len() as i64;
+    let delta = new_len - old_len;
+
+    let moves_media = entries[moov_idx + 1..].iter().any(|e| &e.kind == b"mdat");
+    if delta != 0 && moves_media {
+        if entries.iter().any(|e| &e.kind == b"moof") {
+            bail!("Resizing metadata in fragmented MP4 files is not supported");
+        }
+        shift_chunk_offsets(&mut children, delta)?;
+    }
+
+    let new_moov = serialize(&Atom::new(*b"moov", serialize_all(&children)));
+
+    for (idx, entry) in entries.iter().enumerate() {
+        match idx == moov_idx {
+            true => dst.write_all(&new_moov)?,
+            false => {
+                src.seek(SeekFrom::Start(entry.start))?;
+                let copied = io::copy(&mut src.take(entry.size), dst)?;
+                if copied != entry.size {
+                    bail!("Truncated MP4 atom");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_entries(src: &mut (impl Read + Seek)) -> Result<Vec<Entry>> {
+    let file_len = src.seek(SeekFrom::End(0))?;
+    let mut entries = vec![];
+    let mut pos = 0;
+
+    while pos + 8 <= file_len {
+        src.seek(SeekFrom::Start(pos))?;
+        let mut header = [0; 8];
+        src.read_exact(&mut header)?;
+
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (size, header_len) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (file_len - pos, 8),
+                1 => {
+                    let mut large = [0; 8];
+                    src.read_exact(&mut large)?;
+                    (u64::from_be_bytes(large), 16)
+                }
+                n => (n as u64, 8),
+            };
+
+        if size < header_len || pos + size > file_len {
+            bail!("Malformed MP4 atom");
+        }
+
+        entries.push(Entry {
+            kind,
+            start: pos,
+            header_len,
+            size,
+        });
+        pos += size;
+    }
+
+    Ok(entries)
+}
+
+fn parse_atoms(mut data: &[u8]) -> Result<Vec<Atom>> {
+    let mut atoms = vec![];
+
+    while data.len() >= 8 {
+        let kind = [data[4], data[5], data[6], data[7]];
+        let (size, header_len) = match u32::from_be_bytes([data[0], data[1], data[2], data[3]]) {
+            0 => (data.len(), 8),
+            1 => {
+                let large = data
+                    .get(8..16)
+                    .ok_or_else(|| anyhow!("Malformed MP4 atom"))?;
+                (u64::from_be_bytes(large.try_into()?) as usize, 16)
+            }
+            n => (n as usize, 8),
+        };
+
+        if size < header_len || size > data.len() {
+            bail!("Malformed MP4 atom");
+        }
+
+        atoms.push(Atom::new(kind, data[header_len..size].to_vec()));
+        data = &data[size..];
+    }
+
+    Ok(atoms)
+}
+
+fn serialize(atom: &Atom) -> Vec<u8> {
+    let len = atom.payload.len() + 8;
+    let mut out = Vec::with_capacity(len + 8);
+
+    match u32::try_from(len) {
+        Ok(len) => {
+            out.extend(len.to_be_bytes());
+            out.extend(atom.kind);
+        }
+        Err(_) => {
+            out.extend(1u32.to_be_bytes());
+            out.extend(atom.kind);
+            out.extend((len as u64 + 8).to_be_bytes());
+        }
+    }
+    out.extend(&atom.payload);
+    out
+}
+
+fn serialize_all(atoms: &[Atom]) -> Vec<u8> {
+    atoms.iter().flat_map(serialize).collect()
+}
+
+fn child<'a>(
+    atoms: &'a mut Vec<Atom>,
+    kind: &[u8; 4],
+    default: impl FnOnce() -> Vec<u8>,
+) -> &'a mut Atom {
+    let idx = match atoms.iter().position(|a| &a.kind == kind) {
+        Some(idx) => idx,
+        None => {
+            atoms.push(Atom::new(*kind, default()));
+            atoms.len() - 1
+        }
+    };
+    &mut atoms[idx]
+}
+
+/// Walk down `moov/udta/meta/ilst`, creating whatever is missing
+fn edit_udta(moov: &mut Vec<Atom>, edit: &TagEdit) -> Result<()> {
+    let udta = child(moov, b"udta", Vec::new);
+    let mut udta_children = parse_atoms(&udta.payload)?;
+
+    let meta = child(&mut udta_children, b"meta", new_meta);
+    // iTunes writes `meta` as a full box, QuickTime does not
+    let header_len = match meta.payload.get(4..8) == Some(b"hdlr") {
+        true => 0,
+        false => 4.min(meta.payload.len()),
+    };
+    let (header, body) = meta.payload.split_at(header_len);
+    let header = header.to_vec();
+    let mut meta_children = parse_atoms(body)?;
+
+    let ilst = child(&mut meta_children, b"ilst", Vec::new);
+    let mut items = parse_atoms(&ilst.payload)?;
+    edit_items(&mut items, edit)?;
+    ilst.payload = serialize_all(&items);
+
+    meta.payload = [header, serialize_all(&meta_children)].concat();
+    udta.payload = serialize_all(&udta_children);
+    Ok(())
+}
+
+fn new_meta() -> Vec<u8> {
+    let mut hdlr = vec![0; 8];
+    hdlr.extend(b"mdirappl");
+    hdlr.extend([0; 9]);
+
+    [vec![0; 4], serialize(&Atom::new(*b"hdlr", hdlr))].concat()
+}
+
+fn edit_items(items: &mut Vec<Atom>, edit: &TagEdit) -> Result<()> {
+    for (field, value) in edit.iter() {
+        let kind = item_kind(field);
+        let existing = items.iter().position(|a| a.kind == kind);
+
+        let data = match (field, value.parse::<u16>().ok()) {
+            _ if value.is_empty() => None,
+            (TagField::Track | TagField::Disc, None) => {
+                bail!("{} must be at most {}", field.label(), u16::MAX)
+            }
+            (TagField::Track | TagField::Disc, Some(number)) => {
+                // Keep the total count of the existing item
+                let total = existing
+                    .and_then(|idx| item_value(&items[idx]))
+                    .and_then(|v| v.get(4..6).map(|t| [t[0], t[1]]))
+                    .unwrap_or([0, 0]);
+
+                let mut value = vec![0, 0];
+                value.extend(number.to_be_bytes());
+                value.extend(total);
+                if field == TagField::Track {
+                    value.extend([0, 0]);
+                }
+                Some(data_atom(DATA_IMPLICIT, &value))
+            }
+            _ => Some(data_atom(DATA_UTF8, value.as_bytes())),
+        };
+
+        match (existing, data) {
+            (Some(idx), Some(data)) => items[idx].payload = data,
+            (None, Some(data)) => items.push(Atom::new(kind, data)),
+            (Some(idx), None) => {
+                items.remove(idx);
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+fn data_atom(data_type: u32, value: &[u8]) -> Vec<u8> {
+    let mut payload = data_type.to_be_bytes().to_vec();
+    payload.extend([0; 4]);
+    payload.extend(value);
+    serialize(&Atom::new(*b"data", payload))
+}
+
+fn item_value(item: &Atom) -> Option<Vec<u8>> {
+    parse_atoms(&item.payload)
+        .ok()?
+        .into_iter()
+        .find(|a| &a.kind == b"data")
+        .and_then(|a| a.payload.get(8..).map(|v| v.to_vec()))
+}
+
+/// Patch `stco`/`co64` tables inside `trak/mdia/minf/stbl`
+fn shift_chunk_offsets(atoms: &mut [Atom], delta: i64) -> Result<()> {
+    for atom in atoms {
+        match &atom.kind {
+            b"trak" | b"mdia" | b"minf" | b"stbl" => {
+                let mut children = parse_atoms(&atom.payload)?;
+                shift_chunk_offsets(&mut children, delta)?;
+                atom.payload = serialize_all(&children);
+            }
+            b"stco" => shift_table::<4>(&mut atom.payload, delta)?,
+            b"co64" => shift_table::<8>(&mut atom.payload, delta)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn shift_table<const N: usize>(payload: &mut [u8], delta: i64) -> Result<()> {
+    let entries = payload
+        .get_mut(8..)
+        .ok_or_else(|| anyhow!("Malformed chunk offset table"))?;
+
+    for chunk in entries.chunks_exact_mut(N) {
+        let mut bytes = [0; 8];
+        bytes[8 - N..].copy_from_slice(chunk);
+
+        let offset = u64::from_be_bytes(bytes)
+            .checked_add_signed(delta)
+            .ok_or_else(|| anyhow!("Chunk offset out of range"))?;
+
+        match N {
+            4 => chunk.copy_from_slice(
+                &u32::try_from(offset)
+                    .map_err(|_| anyhow!("Chunk offset out of range"))?
+                    .to_be_bytes(),
+            ),
+            _ => chunk.copy_from_slice(&offset.to_be_bytes()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn atom(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        serialize(&Atom::new(*kind, payload.to_vec()))
+    }
+
+    fn stco(offsets: &[u32]) -> Vec<u8> {
+        let mut payload = vec![0; 4];
+        payload.extend((offsets.len() as u32).to_be_bytes());
+        offsets.iter().for_each(|o| payload.extend(o.to_be_bytes()));
+        atom(b"stco", &payload)
+    }
+
+    #[test]
+    fn parse_atoms_round_trips() {
+        let data = [atom(b"free", b"abc"), atom(b"udta", &atom(b"meta", &[]))].concat();
+
+        let atoms = parse_atoms(&data).unwrap();
+        assert_eq!(atoms.len(), 2);
+        assert_eq!(&atoms[0].kind, b"free");
+        assert_eq!(atoms[0].payload, b"abc");
+        assert_eq!(serialize_all(&atoms), data);
+    }
+
+    #[test]
+    fn parse_atoms_reads_large_sizes() {
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend(b"free");
+        data.extend(20u64.to_be_bytes());
+        data.extend(b"abcd");
+
+        let atoms = parse_atoms(&data).unwrap();
+        assert_eq!(atoms[0].payload, b"abcd");
+    }
+
+    #[test]
+    fn parse_atoms_rejects_truncated_atoms() {
+        let mut data = atom(b"free", b"abcd");
+        data.truncate(10);
+
+        assert!(parse_atoms(&data).is_err());
+    }
+
+    #[test]
+    fn serialize_writes_header() {
+        assert_eq!(atom(b"free", b"ab"), b"\0\0\0\x0afreeab");
+    }
+
+    #[test]
+    fn shift_table_moves_offsets() {
+        let mut payload = stco(&[100, 200])[8..].to_vec();
+        shift_table::<4>(&mut payload, 16).unwrap();
+        assert_eq!(payload, stco(&[116, 216])[8..]);
+
+        shift_table::<4>(&mut payload, -16).unwrap();
+        assert_eq!(payload, stco(&[100, 200])[8..]);
+    }
+
+    #[test]
+    fn shift_table_rejects_out_of_range_offsets() {
+        let mut payload = stco(&[10])[8..].to_vec();
+        assert!(shift_table::<4>(&mut payload, -11).is_err());
+
+        let mut payload = stco(&[u32::MAX])[8..].to_vec();
+        assert!(shift_table::<4>(&mut payload, 1).is_err());
+
+        let mut co64 = vec![0; 4];
+        co64.extend(1u32.to_be_bytes());
+        co64.extend((u32::MAX as u64).to_be_bytes());
+        shift_table::<8>(&mut co64, 1).unwrap();
+        assert_eq!(co64[8..], (u32::MAX as u64 + 1).to_be_bytes());
+    }
+
+    #[test]
+    fn write_adds_metadata_and_shifts_chunk_offsets() {
+        let ftyp = atom(b"ftyp", b"M4A \0\0\0\0");
+        let moov_len = 8 + 8 * 4 + stco(&[0]).len();
+        let mdat_start = (ftyp.len() + moov_len) as u32;
+
+        let stbl = atom(b"stbl", &stco(&[mdat_start + 8]));
+        let trak = atom(b"trak", &atom(b"mdia", &atom(b"minf", &stbl)));
+        let file = [ftyp, atom(b"moov", &trak), atom(b"mdat", b"audio")].concat();
+
+        let mut edit = TagEdit::default();
+        edit.set(TagField::Title, "Title");
+        edit.set(TagField::Track, "3");
+
+        let mut out = vec![];
+        write(&mut Cursor::new(&file), &mut out, &edit).unwrap();
+
+        let top = parse_atoms(&out).unwrap();
+        let kinds = top.iter().map(|a| a.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [*b"ftyp", *b"moov", *b"mdat"]);
+        assert_eq!(top[2].payload, b"audio");
+
+        let moov = parse_atoms(&top[1].payload).unwrap();
+        let meta = find(&moov, &[b"udta", b"meta"]);
+        let ilst = find(&parse_atoms(&meta.payload[4..]).unwrap(), &[b"ilst"]);
+        let items = parse_atoms(&ilst.payload).unwrap();
+        assert_eq!(item_value(&items[0]).unwrap(), b"Title");
+        assert_eq!(item_value(&items[1]).unwrap(), [0, 0, 0, 3, 0, 0, 0, 0]);
+
+        // The chunk offset still points at the media data
+        let stco = find(&moov, &[b"trak", b"mdia", b"minf", b"stbl", b"stco"]);
+        let mdat_start = out.len() - atom(b"mdat", b"audio").len();
+        assert_eq!(stco.payload[8..], ((mdat_start + 8) as u32).to_be_bytes());
+    }
+
+    fn find(atoms: &[Atom], path: &[&[u8; 4]]) -> Atom {
+        let atom = atoms.iter().find(|a| &a.kind == path[0]).unwrap();
+        match path.len() {
+            1 => Atom::new(atom.kind, atom.payload.clone()),
+            _ => find(&parse_atoms(&atom.payload).unwrap(), &path[1..]),
+        }
+    }
+}
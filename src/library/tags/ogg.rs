@@ -0,0 +1,132 @@
+use anyhow::{Result, anyhow, bail};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+use std::io::{Read, Seek, Write};
+
+use super::{TagEdit, vorbis::VorbisComments};
+
+const VORBIS_COMMENT_HEADER: &[u8] = b"\x03vorbis";
+const OPUS_COMMENT_HEADER: &[u8] = b"OpusTags";
+
+/// Packets are copied one by one, replacing the comment header (the second
+/// packet of the first logical stream). Page boundaries and granule
+/// positions of the original are kept
+pub(super) fn write(
+    src: &mut (impl Read + Seek),
+    dst: &mut impl Write,
+    edit: &TagEdit,
+) -> Result<()> {
+    let mut reader = PacketReader::new(src);
+    let mut writer = PacketWriter::new(dst);
+
+    let mut serial = None;
+    let mut packet_idx = 0;
+    let mut replaced = false;
+
+    while let Some(packet) = reader.read_packet()? {
+        let stream = packet.stream_serial();
+        let is_first_stream = *serial.get_or_insert(stream) == stream;
+
+        let info = match (packet.last_in_stream(), packet.last_in_page()) {
+            (true, _) => PacketWriteEndInfo::EndStream,
+            (false, true) => PacketWriteEndInfo::EndPage,
+            (false, false) => PacketWriteEndInfo::NormalPacket,
+        };
+        let absgp = packet.absgp_page();
+
+        let data = match is_first_stream && packet_idx == 1 {
+            true => {
+                replaced = true;
+                edit_comment_packet(&packet.data, edit)?
+            }
+            false => packet.data,
+        };
+        if is_first_stream {
+            packet_idx += 1;
+        }
+
+        writer.write_packet(data.into_boxed_slice(), stream, info, absgp)?;
+    }
+
+    if !replaced {
+        bail!("Ogg file has no comment header");
+    }
+    Ok(())
+}
+
+fn edit_comment_packet(data: &[u8], edit: &TagEdit) -> Result<Vec<u8>> {
+    let prefix = [VORBIS_COMMENT_HEADER, OPUS_COMMENT_HEADER]
+        .into_iter()
+        .find(|prefix| data.starts_with(prefix))
+        .ok_or_else(|| anyhow!("Unrecognized Ogg comment header"))?;
+
+    let (mut comments, len) = VorbisComments::parse(&data[prefix.len()..])?;
+    comments.apply(edit);
+
+    // Vorbis keeps its framing bit here, Opus may keep padding
+    let trailing = &data[prefix.len() + len..];
+
+    Ok([prefix, &comments.to_bytes(), trailing].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::TagField;
+
+    fn comment_packet(prefix: &[u8], comments: &[&str], trailing: &[u8]) -> Vec<u8> {
+        let mut block = VorbisComments::new();
+        let mut edit = TagEdit::default();
+        for comment in comments {
+            let (key, value) = comment.split_once('=').unwrap();
+            let field = match key {
+                "TITLE" => TagField::Title,
+                _ => TagField::Artist,
+            };
+            edit.set(field, value);
+        }
+        block.apply(&edit);
+
+        [prefix, &block.to_bytes(), trailing].concat()
+    }
+
+    #[test]
+    fn edit_comment_packet_keeps_vorbis_framing_bit() {
+        let packet = comment_packet(
+            VORBIS_COMMENT_HEADER,
+            &["TITLE=Old", "ARTIST=Someone"],
+            &[1],
+        );
+
+        let mut edit = TagEdit::default();
+        edit.set(TagField::Title, "New");
+        let edited = edit_comment_packet(&packet, &edit).unwrap();
+
+        let expected = comment_packet(
+            VORBIS_COMMENT_HEADER,
+            &["ARTIST=Someone", "TITLE=New"],
+            &[1],
+        );
+        assert_eq!(edited, expected);
+    }
+
+    #[test]
+    fn edit_comment_packet_keeps_opus_padding() {
+        let packet = comment_packet(OPUS_COMMENT_HEADER, &["TITLE=Old"], &[0; 3]);
+
+        let mut edit = TagEdit::default();
+        edit.set(TagField::Title, "");
+        let edited = edit_comment_packet(&packet, &edit).unwrap();
+
+        assert_eq!(edited, comment_packet(OPUS_COMMENT_HEADER, &[], &[0; 3]));
+    }
+
+    #[test]
+    fn edit_comment_packet_rejects_other_packets() {
+        let packet = comment_packet(b"\x01vorbis", &[], &[]);
+        assert!(edit_comment_packet(&packet, &TagEdit::default()).is_err());
+
+        let mut truncated = comment_packet(OPUS_COMMENT_HEADER, &["TITLE=Old"], &[]);
+        truncated.pop();
+        assert!(edit_comment_packet(&truncated, &TagEdit::default()).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Vorbis comments, shared by FLAC, Ogg Vorbis and Opus
+
+use anyhow::{Result, anyhow};
+
+use super::{TagEdit, TagField};
+use crate::library::domain::fmps_value;
+
+const RATING_KEY: &str = "FMPS_RATING";
+
+/// The first key is the one written, the rest are aliases that get removed
+fn keys(field: TagField) -> &'static [&'static str] {
+    match field {
+        TagField::Title => &["TITLE"],
+        TagField::Artist => &["ARTIST"],
+        TagField::Album => &["ALBUM"],
+        TagField::AlbumArtist => &["ALBUMARTIST", "ALBUM ARTIST", "ALBUM_ARTIST"],
+        TagField::Year => &["DATE", "YEAR"],
+        TagField::Track => &["TRACKNUMBER"],
+        TagField::Disc => &["DISCNUMBER"],
+    }
+}
+
+pub(super) struct VorbisComments {
+    vendor: String,
+    comments: Vec<String>,
+}
+
+impl VorbisComments {
+    pub(super) fn new() -> Self {
+        VorbisComments {
+            vendor: String::from("noctavox"),
+            comments: vec![],
+        }
+    }
+
+    /// Parse a comment block, returning it along with the number of bytes
+    /// read so that any trailing data can be carried over
+    pub(super) fn parse(data: &[u8]) -> Result<(Self, usize)> {
+        let mut pos = 0;
+
+        let vendor_len = read_u32(data, &mut pos)?;
+        let vendor = String::from_utf8_lossy(read_bytes(data, &mut pos, vendor_len)?).to_string();
+
+        let count = read_u32(data, &mut pos)?;
+        let mut comments = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let len = read_u32(data, &mut pos)?;
+            comments.push(String::from_utf8_lossy(read_bytes(data, &mut pos, len)?).to_string());
+        }
+
+        Ok((VorbisComments { vendor, comments }, pos))
+    }
+
+    pub(super) fn apply(&mut self, edit: &TagEdit) {
+        for (field, value) in edit.iter() {
+            let keys = keys(field);
+            self.comments.retain(|comment| {
+                let key = comment.split('=').next().unwrap_or_default();
+                !keys.iter().any(|k| k.eq_ignore_ascii_case(key))
+            });
+
+            if !value.is_empty() {
+                self.comments.push(format!("{}={value}", keys[0]));
+            }
+        }
+
+        if let Some(stars) = edit.rating() {
+            self.comments.retain(|comment| {
+                let key = comment.split('=').next().unwrap_or_default();
+                !key.eq_ignore_ascii_case(RATING_KEY)
+            });
+
+            if stars > 0 {
+                self.comments
+                    .push(format!("{RATING_KEY}={}", fmps_value(stars)));
+            }
+        }
+    }
+
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+
+        push_string(&mut out, &self.vendor);
+        out.extend((self.comments.len() as u32).to_le_bytes());
+        for comment in &self.comments {
+            push_string(&mut out, comment);
+        }
+        out
+    }
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let bytes = data
+        .get(*pos..pos.saturating_add(len))
+        .ok_or_else(|| anyhow!("Truncated vorbis comment block"))?;
+    *pos += len;
+    Ok(bytes)
+}
+
+fn read_u32(data: &[u8], pos: &mut usize) -> Result<usize> {
+    let bytes = read_bytes(data, pos, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    out.extend((s.len() as u32).to_le_bytes());
+    out.extend(s.as_bytes());
+}
@@ -0,0 +1,137 @@
+//! Watches the library roots for changes so that songs can be added, removed
+//! or re-tagged without a full rescan.
+
+use anyhow::{Result, anyhow};
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::Library;
+
+/// Changes are applied once the roots have been quiet for this long
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+const POLL_TIMEOUT: Duration = Duration::from_millis(250);
+
+pub struct LibraryWatcher {
+    rx: Receiver<Result<Library>>,
+    stop: Arc<AtomicBool>,
+    roots: HashSet<PathBuf>,
+}
+
+impl LibraryWatcher {
+    pub fn spawn(roots: HashSet<PathBuf>) -> Self {
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_roots = roots.clone();
+        let thread_stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            if let Err(e) = watch(&thread_roots, &tx, &thread_stop) {
+                let _ = tx.send(Err(e));
+            }
+        });
+
+        LibraryWatcher { rx, stop, roots }
+    }
+
+    /// Yields a rebuilt library whenever the roots change. The channel is
+    /// disconnected once the watcher stops
+    pub fn updates(&self) -> &Receiver<Result<Library>> {
+        &self.rx
+    }
+
+    pub fn roots(&self) -> &HashSet<PathBuf> {
+        &self.roots
+    }
+}
+
+impl Drop for LibraryWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Apply a batch of changed paths and send the rebuilt library. A full
+/// rescan of the roots is done when events may have been missed
+fn flush(changed: HashSet<PathBuf>, rescan: bool, tx: &Sender<Result<Library>>) -> Result<()> {
+    let mut library = Library::init()?;
+
+    let updated = match rescan {
+        true => library
+            .update_db_by_root()
+            .map(|(new, removed)| new + removed > 0)?,
+        false => library.apply_changes(changed)?,
+    };
+
+    if updated {
+        library.collect_songs()?;
+        library.build_albums()?;
+        let _ = tx.send(Ok(library));
+    }
+
+    Ok(())
+}
+
+fn watch(roots: &HashSet<PathBuf>, tx: &Sender<Result<Library>>, stop: &AtomicBool) -> Result<()> {
+    let (event_tx, event_rx) = crossbeam::channel::unbounded();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = event_tx.send(event);
+    })
+    .map_err(|e| anyhow!("Failed to watch library: {e}"))?;
+
+    for root in roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|e| anyhow!("Failed to watch {}: {e}", root.display()))?;
+    }
+
+    let mut changed = HashSet::new();
+    let mut rescan = false;
+    let mut last_event = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        match event_rx.recv_timeout(POLL_TIMEOUT) {
+            Ok(Ok(event)) => {
+                rescan |= event.need_rescan();
+                changed.extend(event.paths.into_iter().filter(|p| !is_ignored(p, roots)));
+                last_event = Instant::now();
+                continue;
+            }
+            // Events may have been lost
+            Ok(Err(_)) => {
+                rescan = true;
+                last_event = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if (rescan || !changed.is_empty()) && last_event.elapsed() >= DEBOUNCE {
+            if let Err(e) = flush(std::mem::take(&mut changed), rescan, tx) {
+                let _ = tx.send(Err(e));
+            }
+            rescan = false;
+        }
+    }
+
+    Ok(())
+}
+
+/// Folders with a `.nomedia` file are skipped, same as during a scan
+fn is_ignored(path: &Path, roots: &HashSet<PathBuf>) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| !roots.contains(*dir))
+        .any(|dir| dir.join(".nomedia").exists())
+        || path.to_string_lossy().contains("$RECYCLE.BIN")
+}
@@ -3,7 +3,7 @@ use crossbeam::channel::{Receiver, bounded};
 use souvlaki::{
     MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
 };
-use std::time::Duration;
+use std::{fmt::Write, path::Path, time::Duration};
 
 pub struct MediaControlsHandle {
     controls: MediaControls,
@@ -47,13 +47,22 @@ impl MediaControlsHandle {
         &self.event_rx
     }
 
-    pub fn update_metadata(&mut self, title: &str, artist: &str, album: &str, duration: Duration) {
+    pub fn update_metadata(
+        &mut self,
+        title: &str,
+        artist: &str,
+        album: &str,
+        duration: Duration,
+        cover: Option<&Path>,
+    ) {
+        let cover_url = cover.map(file_uri);
+
         let _ = self.controls.set_metadata(MediaMetadata {
             title: Some(title),
             artist: Some(artist),
             album: Some(album),
             duration: Some(duration),
-            cover_url: None,
+            cover_url: cover_url.as_deref(),
         });
     }
 
@@ -72,6 +81,39 @@ impl MediaControlsHandle {
     pub fn set_stopped(&mut self) {
         let _ = self.controls.set_playback(MediaPlayback::Stopped);
     }
+
+    /// Only MPRIS exposes volume, `volume` ranges from 0.0 to 1.0
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    pub fn set_volume(&mut self, volume: f64) {
+        let _ = self.controls.set_volume(volume);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    pub fn set_volume(&mut self, _volume: f64) {}
+}
+
+/// A `file://` URI for the path, percent-encoding anything outside the
+/// unreserved characters so spaces, `#` and `%` survive
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let bytes = path.as_os_str().as_encoded_bytes();
+
+    // Windows paths start with a drive letter rather than a slash
+    if !bytes.starts_with(b"/") {
+        uri.push('/');
+    }
+
+    for &b in bytes {
+        match b {
+            b'\\' if cfg!(target_os = "windows") => uri.push('/'),
+            b if b.is_ascii_alphanumeric() || b"/-._~:".contains(&b) => uri.push(b as char),
+            b => {
+                let _ = write!(uri, "%{b:02X}");
+            }
+        }
+    }
+
+    uri
 }
 
 /// Create a zero-size, invisible top-level window owned by this process.
@@ -0,0 +1,98 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// Maps songs to the URIs clients know them by, their path relative to the
+/// library root. With several roots, each is prefixed with its root's name
+pub struct UriIndex {
+    uris: HashMap<u64, String>,
+    ids: HashMap<String, u64>,
+}
+
+impl UriIndex {
+    pub fn new(roots: &HashSet<PathBuf>, paths: HashMap<u64, String>) -> Self {
+        let prefixed = roots.len() > 1;
+
+        let uris = paths
+            .into_iter()
+            .filter_map(|(id, path)| {
+                let path = Path::new(&path);
+                let root = roots.iter().find(|r| path.starts_with(r))?;
+
+                let relative = match prefixed {
+                    true => path.strip_prefix(root.parent().unwrap_or(root)).ok()?,
+                    false => path.strip_prefix(root).ok()?,
+                };
+
+                let uri = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                Some((id, uri))
+            })
+            .collect::<HashMap<u64, String>>();
+
+        let ids = uris.iter().map(|(id, uri)| (uri.clone(), *id)).collect();
+
+        Self { uris, ids }
+    }
+
+    pub fn uri(&self, id: u64) -> Option<&str> {
+        self.uris.get(&id).map(String::as_str)
+    }
+
+    pub fn id(&self, uri: &str) -> Option<u64> {
+        self.ids.get(uri.trim_start_matches('/')).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.uris.iter().map(|(id, uri)| (*id, uri.as_str()))
+    }
+
+    /// Songs at or beneath `dir`, sorted by URI
+    pub fn songs_within(&self, dir: &str) -> Vec<(u64, &str)> {
+        let dir = dir.trim_matches('/');
+        let mut songs = self
+            .iter()
+            .filter(|(_, uri)| dir.is_empty() || *uri == dir || within(uri, dir))
+            .collect::<Vec<_>>();
+
+        songs.sort_unstable_by_key(|(_, uri)| *uri);
+        songs
+    }
+
+    /// The directories and songs immediately inside of `dir`
+    pub fn list_dir(&self, dir: &str) -> (BTreeSet<String>, Vec<(u64, &str)>) {
+        let dir = dir.trim_matches('/');
+        let mut dirs = BTreeSet::new();
+        let mut songs = vec![];
+
+        for (id, uri) in self.iter() {
+            let rest = match dir.is_empty() {
+                true => uri,
+                false if within(uri, dir) => &uri[dir.len() + 1..],
+                false => continue,
+            };
+
+            match rest.split_once('/') {
+                Some((sub, _)) => {
+                    dirs.insert(match dir.is_empty() {
+                        true => sub.to_string(),
+                        false => format!("{dir}/{sub}"),
+                    });
+                }
+                None => songs.push((id, uri)),
+            }
+        }
+
+        songs.sort_unstable_by_key(|(_, uri)| *uri);
+        (dirs, songs)
+    }
+}
+
+fn within(uri: &str, dir: &str) -> bool {
+    uri.len() > dir.len() && uri.starts_with(dir) && uri.as_bytes()[dir.len()] == b'/'
+}
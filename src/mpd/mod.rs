@@ -0,0 +1,173 @@
+mod index;
+mod protocol;
+mod server;
+
+pub use index::UriIndex;
+pub use protocol::{Ack, AckCode, Filter, MpdResult, Tag, field};
+
+use crate::playback::{PlayMode, next_entry_id};
+use crossbeam::channel::{Receiver, Sender, bounded};
+use std::sync::{Arc, Mutex};
+
+type Idlers = Arc<Mutex<Vec<Sender<Subsystem>>>>;
+
+/// Reported to clients waiting in `idle` when part of the player changes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Subsystem {
+    Database,
+    StoredPlaylist,
+    Playlist,
+    Player,
+    Mixer,
+    Options,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 6] = [
+        Subsystem::Database,
+        Subsystem::StoredPlaylist,
+        Subsystem::Playlist,
+        Subsystem::Player,
+        Subsystem::Mixer,
+        Subsystem::Options,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subsystem::Database => "database",
+            Subsystem::StoredPlaylist => "stored_playlist",
+            Subsystem::Playlist => "playlist",
+            Subsystem::Player => "player",
+            Subsystem::Mixer => "mixer",
+            Subsystem::Options => "options",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// A single command, or a command list, to be run on the main thread
+pub struct MpdRequest {
+    pub commands: Vec<Vec<String>>,
+    /// Set by `command_list_ok_begin`, each command is followed by `list_OK`
+    pub list_ok: bool,
+    reply: Sender<String>,
+}
+
+impl MpdRequest {
+    pub fn reply(&self, response: String) {
+        let _ = self.reply.send(response);
+    }
+}
+
+/// What was last reported to clients, changes are found by comparing against
+/// the current state of the player
+#[derive(Default, PartialEq)]
+pub struct MpdSnapshot {
+    /// Song ids of the playlist
+    pub playlist: Vec<u32>,
+    pub volume: u8,
+    pub muted: bool,
+    pub mode: PlayMode,
+}
+
+/// Song ids handed out for the playlist, as (library id, song id) pairs.
+/// Queued songs go by their queue entry, the playing song keeps the id it
+/// was queued under
+#[derive(Default)]
+struct SongIds {
+    current: Option<(u64, u32)>,
+    queued: Vec<(u64, u32)>,
+    /// Entries which left the queue since the playing song last changed, one
+    /// of them may be about to start
+    dequeued: Vec<(u64, u32)>,
+}
+
+pub struct MpdHandle {
+    request_rx: Receiver<MpdRequest>,
+    idlers: Idlers,
+    index: Option<Arc<UriIndex>>,
+    /// Bumped whenever the playlist changes, as reported by `status`
+    pub playlist_version: u32,
+    pub snapshot: MpdSnapshot,
+    ids: SongIds,
+}
+
+impl MpdHandle {
+    pub fn new(address: &str) -> anyhow::Result<Self> {
+        let (request_tx, request_rx) = bounded::<MpdRequest>(32);
+        let idlers = Idlers::default();
+
+        server::listen(address, request_tx, Arc::clone(&idlers))?;
+
+        Ok(Self {
+            request_rx,
+            idlers,
+            index: None,
+            playlist_version: 1,
+            snapshot: MpdSnapshot::default(),
+            ids: SongIds::default(),
+        })
+    }
+
+    pub fn request_rx(&self) -> &Receiver<MpdRequest> {
+        &self.request_rx
+    }
+
+    /// Wakes clients waiting on any of `changed`, dropping those that have
+    /// disconnected
+    pub fn notify(&self, changed: &[Subsystem]) {
+        if let Ok(mut idlers) = self.idlers.lock() {
+            idlers.retain(|tx| changed.iter().all(|s| tx.send(*s).is_ok()));
+        }
+    }
+
+    /// Songs are addressed by path, the index is rebuilt on first use after
+    /// the library changes
+    pub fn index(&mut self, build: impl FnOnce() -> UriIndex) -> Arc<UriIndex> {
+        Arc::clone(self.index.get_or_insert_with(|| Arc::new(build())))
+    }
+
+    pub fn invalidate_index(&mut self) {
+        self.index = None;
+    }
+
+    /// Song ids of the playlist, given the playing song and the queue's
+    /// entries. A song which left the queue to be played carries its id over
+    pub fn song_ids(&mut self, current: Option<u64>, queued: Vec<(u64, u32)>) -> Vec<u32> {
+        let mut dequeued = std::mem::take(&mut self.ids.dequeued);
+        dequeued.extend(
+            self.ids
+                .queued
+                .iter()
+                .filter(|(_, id)| !queued.iter().any(|(_, q)| q == id)),
+        );
+
+        let current = match (current, self.ids.current) {
+            (Some(song), Some((prev, id))) if prev == song => Some((song, id)),
+            (Some(song), _) => {
+                let id = dequeued
+                    .iter()
+                    .rfind(|(s, _)| *s == song)
+                    .map_or_else(next_entry_id, |(_, id)| *id);
+                dequeued.clear();
+                Some((song, id))
+            }
+            (None, _) => None,
+        };
+
+        self.ids = SongIds {
+            current,
+            queued,
+            dequeued,
+        };
+        self.ids
+            .current
+            .iter()
+            .chain(&self.ids.queued)
+            .map(|(_, id)| *id)
+            .collect()
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt::{self, Display, Write};
+
+/// Error codes from MPD's `ack.h`
+#[derive(Clone, Copy, Debug)]
+#[repr(u8)]
+pub enum AckCode {
+    Arg = 2,
+    Permission = 4,
+    Unknown = 5,
+    NoExist = 50,
+    System = 52,
+    Exist = 56,
+}
+
+#[derive(Debug)]
+pub struct Ack {
+    pub code: AckCode,
+    pub message: String,
+}
+
+impl Ack {
+    pub fn new(code: AckCode, message: impl Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn arg(message: impl Display) -> Self {
+        Self::new(AckCode::Arg, message)
+    }
+
+    pub fn no_exist(message: impl Display) -> Self {
+        Self::new(AckCode::NoExist, message)
+    }
+
+    /// `ACK [error@command_listNum] {current_command} message_text`
+    pub fn to_line(&self, index: usize, command: &str) -> String {
+        format!(
+            "ACK [{}@{index}] {{{command}}} {}\n",
+            self.code as u8, self.message
+        )
+    }
+}
+
+impl From<anyhow::Error> for Ack {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(AckCode::System, e)
+    }
+}
+
+pub type MpdResult<T = ()> = Result<T, Ack>;
+
+/// Append a `key: value` line to a response
+pub fn field(out: &mut String, key: &str, value: impl Display) {
+    let _ = writeln!(out, "{key}: {value}");
+}
+
+/// Split a command line into its arguments. Arguments are separated by
+/// whitespace, and may be double quoted with backslash escapes
+pub fn tokenize(line: &str) -> MpdResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut arg = String::new();
+        match c == '"' {
+            true => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => arg.extend(chars.next()),
+                        Some(c) => arg.push(c),
+                        None => return Err(Ack::arg("Missing closing '\"'")),
+                    }
+                }
+            }
+            false => {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    arg.push(c);
+                    chars.next();
+                }
+            }
+        }
+        args.push(arg);
+    }
+
+    Ok(args)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tag {
+    Artist,
+    AlbumArtist,
+    Album,
+    Title,
+    Track,
+    Disc,
+    Date,
+    Genre,
+    File,
+    Any,
+}
+
+impl Tag {
+    /// Tags reported by `tagtypes`
+    pub const SUPPORTED: [Tag; 8] = [
+        Tag::Artist,
+        Tag::AlbumArtist,
+        Tag::Album,
+        Tag::Title,
+        Tag::Track,
+        Tag::Disc,
+        Tag::Date,
+        Tag::Genre,
+    ];
+
+    pub fn parse(name: &str) -> MpdResult<Self> {
+        let tag = match name.to_lowercase().as_str() {
+            "artist" | "artistsort" => Tag::Artist,
+            "albumartist" | "albumartistsort" => Tag::AlbumArtist,
+            "album" | "albumsort" => Tag::Album,
+            "title" => Tag::Title,
+            "track" => Tag::Track,
+            "disc" => Tag::Disc,
+            "date" | "originaldate" => Tag::Date,
+            "genre" => Tag::Genre,
+            "file" | "filename" => Tag::File,
+            "any" => Tag::Any,
+            _ => return Err(Ack::arg(format!("Unknown tag type: {name}"))),
+        };
+        Ok(tag)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tag::Artist => "Artist",
+            Tag::AlbumArtist => "AlbumArtist",
+            Tag::Album => "Album",
+            Tag::Title => "Title",
+            Tag::Track => "Track",
+            Tag::Disc => "Disc",
+            Tag::Date => "Date",
+            Tag::Genre => "Genre",
+            Tag::File => "file",
+            Tag::Any => "any",
+        }
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Eq,
+    Ne,
+    Contains,
+    StartsWith,
+}
+
+/// Song filters, in both the legacy `TAG VALUE` form and the expression
+/// syntax of MPD 0.21, e.g. `((artist == 'x') AND (!(album contains 'y')))`
+#[derive(Debug)]
+pub enum Filter {
+    Tag {
+        tag: Tag,
+        op: Op,
+        value: String,
+    },
+    /// Songs within a directory
+    Base(String),
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+}
+
+impl Filter {
+    /// Parse the filter at the start of `args`, returning it along with the
+    /// arguments which follow (`sort`, `window`, `group`...). Legacy pairs
+    /// match exactly when `exact` is set, otherwise by substring
+    pub fn parse_args(args: &[String], exact: bool) -> MpdResult<(Option<Filter>, &[String])> {
+        let mut filters = Vec::new();
+        let mut rest = args;
+
+        while let Some(arg) = rest.first() {
+            if arg.starts_with('(') {
+                filters.push(Parser::new(arg).parse()?);
+                rest = &rest[1..];
+                continue;
+            }
+
+            if matches!(arg.to_lowercase().as_str(), "sort" | "window" | "group") {
+                break;
+            }
+
+            let value = rest
+                .get(1)
+                .ok_or_else(|| Ack::arg("Incorrect number of filter arguments"))?;
+
+            filters.push(match arg.to_lowercase().as_str() {
+                "base" => Filter::Base(value.clone()),
+                _ => Filter::Tag {
+                    tag: Tag::parse(arg)?,
+                    op: match exact {
+                        true => Op::Eq,
+                        false => Op::Contains,
+                    },
+                    value: value.clone(),
+                },
+            });
+            rest = &rest[2..];
+        }
+
+        let filter = match filters.len() {
+            0 => None,
+            1 => filters.pop(),
+            _ => Some(Filter::And(filters)),
+        };
+
+        Ok((filter, rest))
+    }
+
+    /// `get` returns a song's values for a tag, of which genres may have
+    /// several. Values are compared without regard to case when `fold` is set
+    pub fn matches(&self, get: &impl Fn(Tag) -> Vec<String>, fold: bool) -> bool {
+        match self {
+            Filter::Tag {
+                tag: Tag::Any,
+                op,
+                value,
+            } => {
+                let any = |op| {
+                    Tag::SUPPORTED
+                        .iter()
+                        .chain(&[Tag::File])
+                        .any(|t| any_value(&get(*t), op, value, fold))
+                };
+                match op {
+                    Op::Ne => !any(Op::Eq),
+                    op => any(*op),
+                }
+            }
+            Filter::Tag { tag, op, value } => match op {
+                Op::Ne => !any_value(&get(*tag), Op::Eq, value, fold),
+                op => any_value(&get(*tag), *op, value, fold),
+            },
+            Filter::Base(dir) => {
+                let dir = dir.trim_end_matches('/');
+                dir.is_empty()
+                    || get(Tag::File)
+                        .first()
+                        .is_some_and(|file| file.starts_with(&format!("{dir}/")))
+            }
+            Filter::Not(inner) => !inner.matches(get, fold),
+            Filter::And(filters) => filters.iter().all(|f| f.matches(get, fold)),
+        }
+    }
+}
+
+/// A missing tag compares as empty, so that `(genre == "")` finds songs
+/// without one
+fn any_value(values: &[String], op: Op, value: &str, fold: bool) -> bool {
+    match values.is_empty() {
+        true => compare("", op, value, fold),
+        false => values.iter().any(|v| compare(v, op, value, fold)),
+    }
+}
+
+fn compare(actual: &str, op: Op, value: &str, fold: bool) -> bool {
+    let (actual, value) = match fold {
+        true => (actual.to_lowercase(), value.to_lowercase()),
+        false => (actual.to_string(), value.to_string()),
+    };
+
+    match op {
+        Op::Eq => actual == value,
+        Op::Ne => actual != value,
+        Op::Contains => actual.contains(&value),
+        Op::StartsWith => actual.starts_with(&value),
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn parse(mut self) -> MpdResult<Filter> {
+        let filter = self.expression()?;
+        self.skip_whitespace();
+        match self.pos == self.input.len() {
+            true => Ok(filter),
+            false => Err(Ack::arg("Unparsed garbage after expression")),
+        }
+    }
+
+    fn expression(&mut self) -> MpdResult<Filter> {
+        self.skip_whitespace();
+        self.expect('(')?;
+        self.skip_whitespace();
+
+        if self.eat('!') {
+            let inner = self.expression()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(Filter::Not(Box::new(inner)));
+        }
+
+        if self.peek() == Some('(') {
+            let mut filters = vec![self.expression()?];
+            loop {
+                self.skip_whitespace();
+                if self.eat(')') {
+                    break;
+                }
+                match self.word().as_str() {
+                    "AND" => filters.push(self.expression()?),
+                    _ => return Err(Ack::arg("Expected 'AND' or ')'")),
+                }
+            }
+            return Ok(match filters.len() {
+                1 => filters.remove(0),
+                _ => Filter::And(filters),
+            });
+        }
+
+        let name = self.word();
+        self.skip_whitespace();
+
+        let filter = match name.to_lowercase().as_str() {
+            "base" => Filter::Base(self.string()?),
+            _ => {
+                let tag = Tag::parse(&name)?;
+                let op = match self.word().as_str() {
+                    "==" => Op::Eq,
+                    "!=" => Op::Ne,
+                    "contains" => Op::Contains,
+                    "starts_with" => Op::StartsWith,
+                    op => return Err(Ack::arg(format!("Unsupported operator: {op}"))),
+                };
+                self.skip_whitespace();
+                Filter::Tag {
+                    tag,
+                    op,
+                    value: self.string()?,
+                }
+            }
+        };
+
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(filter)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.peek() == Some(c) {
+            true => {
+                self.pos += c.len_utf8();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> MpdResult {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(Ack::arg(format!("Expected '{c}'"))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' || c == '\'' || c == '"' {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        self.input[start..self.pos].to_string()
+    }
+
+    fn string(&mut self) -> MpdResult<String> {
+        let quote = match self.peek() {
+            Some(q @ ('\'' | '"')) => q,
+            _ => return Err(Ack::arg("Expected a quoted value")),
+        };
+        self.pos += 1;
+
+        let mut value = String::new();
+        let mut chars = self.input[self.pos..].chars();
+        loop {
+            match chars.next() {
+                Some(c) if c == quote => break,
+                Some('\\') => value.extend(chars.next()),
+                Some(c) => value.push(c),
+                None => return Err(Ack::arg("Missing closing quote")),
+            }
+        }
+        self.pos = self.input.len() - chars.as_str().len();
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use crossbeam::channel::{Receiver, Sender, bounded, select, unbounded};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    mem,
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+};
+
+use crate::mpd::{Idlers, MpdRequest, Subsystem, protocol::tokenize};
+
+/// The protocol version we claim to speak, clients use it to pick features
+const GREETING: &str = "OK MPD 0.23.5\n";
+
+pub(super) fn listen(address: &str, request_tx: Sender<MpdRequest>, idlers: Idlers) -> Result<()> {
+    let listener = TcpListener::bind(address)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle_client(stream, request_tx.clone(), Arc::clone(&idlers));
+        }
+    });
+
+    Ok(())
+}
+
+/// Each client gets a reader thread feeding lines to a connection thread,
+/// which answers commands in order and waits on changes while idle.
+fn handle_client(stream: TcpStream, request_tx: Sender<MpdRequest>, idlers: Idlers) {
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+
+    let (line_tx, lines) = unbounded::<String>();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            if line_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (event_tx, events) = unbounded::<Subsystem>();
+    if let Ok(mut idlers) = idlers.lock() {
+        idlers.push(event_tx);
+    }
+
+    thread::spawn(move || {
+        let client = Client {
+            stream,
+            lines,
+            events,
+            request_tx,
+            pending: Vec::new(),
+        };
+        let _ = client.run();
+    });
+}
+
+struct Client {
+    stream: TcpStream,
+    lines: Receiver<String>,
+    events: Receiver<Subsystem>,
+    request_tx: Sender<MpdRequest>,
+    /// Changes not yet reported by `idle`
+    pending: Vec<Subsystem>,
+}
+
+impl Client {
+    fn run(mut self) -> io::Result<()> {
+        self.stream.write_all(GREETING.as_bytes())?;
+
+        // Commands collected between `command_list_begin` and `command_list_end`
+        let mut list: Option<(Vec<Vec<String>>, bool)> = None;
+
+        while let Ok(line) = self.lines.recv() {
+            let args = match tokenize(&line) {
+                Ok(args) => args,
+                Err(ack) => {
+                    list = None;
+                    self.stream.write_all(ack.to_line(0, "").as_bytes())?;
+                    continue;
+                }
+            };
+
+            let Some(command) = args.first().map(|c| c.to_lowercase()) else {
+                continue;
+            };
+
+            if let Some((commands, list_ok)) = list.as_mut() {
+                match command == "command_list_end" {
+                    true => {
+                        let (commands, list_ok) = (mem::take(commands), *list_ok);
+                        list = None;
+                        self.dispatch(commands, list_ok)?;
+                    }
+                    false => commands.push(args),
+                }
+                continue;
+            }
+
+            match command.as_str() {
+                "command_list_begin" => list = Some((Vec::new(), false)),
+                "command_list_ok_begin" => list = Some((Vec::new(), true)),
+                "close" => break,
+                "idle" => self.idle(&args[1..])?,
+                // Only meaningful while idle, there is nothing to cancel
+                "noidle" => (),
+                _ => self.dispatch(vec![args], false)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dispatch(&mut self, commands: Vec<Vec<String>>, list_ok: bool) -> io::Result<()> {
+        let (reply, reply_rx) = bounded(1);
+        let request = MpdRequest {
+            commands,
+            list_ok,
+            reply,
+        };
+
+        let response = self
+            .request_tx
+            .send(request)
+            .ok()
+            .and_then(|_| reply_rx.recv().ok())
+            .ok_or(io::ErrorKind::BrokenPipe)?;
+
+        self.stream.write_all(response.as_bytes())
+    }
+
+    /// Blocks until one of the requested subsystems changes, or the client
+    /// sends `noidle`. Changes since the previous `idle` are reported at once
+    fn idle(&mut self, names: &[String]) -> io::Result<()> {
+        let wanted = names
+            .iter()
+            .filter_map(|n| Subsystem::parse(&n.to_lowercase()))
+            .collect::<Vec<_>>();
+
+        loop {
+            for subsystem in self.events.try_iter() {
+                if !self.pending.contains(&subsystem) {
+                    self.pending.push(subsystem);
+                }
+            }
+
+            let (changed, rest) = self
+                .pending
+                .iter()
+                .partition::<Vec<Subsystem>, _>(|s| wanted.is_empty() || wanted.contains(s));
+
+            if !changed.is_empty() {
+                self.pending = rest;
+                let mut response = changed
+                    .iter()
+                    .map(|s| format!("changed: {}\n", s.name()))
+                    .collect::<String>();
+                response.push_str("OK\n");
+                return self.stream.write_all(response.as_bytes());
+            }
+
+            select! {
+                recv(self.events) -> event => match event {
+                    Ok(subsystem) if !self.pending.contains(&subsystem) => {
+                        self.pending.push(subsystem)
+                    }
+                    Ok(_) => (),
+                    Err(_) => return Err(io::ErrorKind::BrokenPipe.into()),
+                },
+                recv(self.lines) -> line => match line {
+                    Ok(line) if line.trim() == "noidle" => {
+                        return self.stream.write_all(b"OK\n");
+                    }
+                    // Anything else while idle is a protocol error
+                    _ => return Err(io::ErrorKind::InvalidInput.into()),
+                },
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
+mod play_mode;
+mod radio;
 mod session;
 mod validated_song;
 
+pub use play_mode::PlayMode;
 pub use session::PlaybackSession;
-pub use validated_song::ValidatedSong;
+pub use validated_song::{ValidatedSong, next_entry_id};
 
 use std::sync::Arc;
 
@@ -13,3 +16,14 @@ pub enum QueueDelta {
         curr: Option<Arc<ValidatedSong>>,
     },
 }
+
+/// A queued song as it's kept between sessions
+#[derive(Clone, PartialEq)]
+pub struct SavedEntry {
+    pub id: u64,
+    pub path: String,
+    /// Position in the queue before it was shuffled
+    pub unshuffled_pos: Option<usize>,
+    /// Picked by the radio once the queue ran dry
+    pub auto_added: bool,
+}
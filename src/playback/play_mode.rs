@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    #[default]
+    Off,
+    /// The current song is played on a loop
+    RepeatOne,
+    /// Finished songs are sent to the back of the queue
+    RepeatAll,
+    Shuffle,
+    /// Shuffles while keeping songs by the same artist apart
+    SmartShuffle,
+}
+
+impl PlayMode {
+    pub fn next(&self) -> PlayMode {
+        match self {
+            PlayMode::Off => PlayMode::RepeatOne,
+            PlayMode::RepeatOne => PlayMode::RepeatAll,
+            PlayMode::RepeatAll => PlayMode::Shuffle,
+            PlayMode::Shuffle => PlayMode::SmartShuffle,
+            PlayMode::SmartShuffle => PlayMode::Off,
+        }
+    }
+
+    pub fn is_repeat(&self) -> bool {
+        matches!(self, PlayMode::RepeatOne | PlayMode::RepeatAll)
+    }
+
+    pub fn is_shuffle(&self) -> bool {
+        matches!(self, PlayMode::Shuffle | PlayMode::SmartShuffle)
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            PlayMode::Off => "off",
+            PlayMode::RepeatOne => "repeat_one",
+            PlayMode::RepeatAll => "repeat_all",
+            PlayMode::Shuffle => "shuffle",
+            PlayMode::SmartShuffle => "smart_shuffle",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "repeat_one" => PlayMode::RepeatOne,
+            "repeat_all" => PlayMode::RepeatAll,
+            "shuffle" => PlayMode::Shuffle,
+            "smart_shuffle" => PlayMode::SmartShuffle,
+            _ => PlayMode::Off,
+        }
+    }
+}
+
+impl Display for PlayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PlayMode::Off => "Off",
+            PlayMode::RepeatOne => "Repeat One",
+            PlayMode::RepeatAll => "Repeat All",
+            PlayMode::Shuffle => "Shuffle",
+            PlayMode::SmartShuffle => "Smart Shuffle",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    SongMap,
+    config::RadioStrategy,
+    library::{SimpleSong, SongStats},
+};
+use rand::seq::{IndexedRandom, SliceRandom};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Number of songs added each time the radio tops up the queue
+const RADIO_BATCH: usize = 5;
+/// Years either side of the playing song which count as the same era
+const YEAR_SPAN: u32 = 3;
+
+/// Pick songs to follow `seed`, never those in `exclude`. Recently played
+/// songs are only picked once nothing else fits, and a strategy without any
+/// matches falls back to the whole library
+pub(super) fn pick_songs(
+    strategy: RadioStrategy,
+    seed: &SimpleSong,
+    songs: &SongMap,
+    stats: &HashMap<u64, SongStats>,
+    exclude: &HashSet<u64>,
+    recent: &HashSet<u64>,
+) -> Vec<Arc<SimpleSong>> {
+    let fits = |song: &SimpleSong| match strategy {
+        RadioStrategy::AlbumArtist => song.album_artist == seed.album_artist,
+        RadioStrategy::Year => match (song.year, seed.year) {
+            (Some(a), Some(b)) => a.abs_diff(b) <= YEAR_SPAN,
+            _ => false,
+        },
+        _ => true,
+    };
+
+    let available = songs
+        .values()
+        .filter(|s| !exclude.contains(&s.id))
+        .collect::<Vec<_>>();
+
+    let mut candidates = [(true, true), (true, false), (false, true), (false, false)]
+        .into_iter()
+        .map(|(matching, fresh)| {
+            available
+                .iter()
+                .copied()
+                .filter(|s| (!matching || fits(s)) && (!fresh || !recent.contains(&s.id)))
+                .collect::<Vec<_>>()
+        })
+        .find(|c| !c.is_empty())
+        .unwrap_or_default();
+
+    let mut rng = rand::rng();
+    match strategy {
+        RadioStrategy::LeastRecent => {
+            candidates.shuffle(&mut rng);
+            candidates.sort_by_key(|s| stats.get(&s.id).and_then(|st| st.last_played));
+            candidates.truncate(RADIO_BATCH);
+            candidates.into_iter().cloned().collect()
+        }
+        RadioStrategy::Weighted => {
+            let plays = |s: &&Arc<SimpleSong>| stats.get(&s.id).map_or(0, |st| st.plays) + 1;
+            match candidates.sample_weighted(&mut rng, RADIO_BATCH, plays) {
+                Ok(picked) => picked.map(|s| Arc::clone(s)).collect(),
+                Err(_) => candidates
+                    .sample(&mut rng, RADIO_BATCH)
+                    .map(|s| Arc::clone(s))
+                    .collect(),
+            }
+        }
+        _ => candidates
+            .sample(&mut rng, RADIO_BATCH)
+            .map(|s| Arc::clone(s))
+            .collect(),
+    }
+}
@@ -1,20 +1,29 @@
 use crate::{
     Database, SongMap,
-    library::SimpleSong,
-    playback::{QueueDelta, ValidatedSong},
+    config::RadioStrategy,
+    library::{SimpleSong, SongStats},
+    playback::{PlayMode, QueueDelta, SavedEntry, ValidatedSong, radio},
     user_config,
 };
 use anyhow::Result;
 use rand::seq::SliceRandom;
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
 };
 
+/// Number of preceding artists smart shuffle tries to keep apart
+const ARTIST_SPACING: usize = 4;
+
 pub struct PlaybackSession {
-    repeat: bool,
+    mode: PlayMode,
 
     queue: VecDeque<Arc<ValidatedSong>>,
+    /// Order of the queue before it was shuffled, restored once shuffle is off
+    unshuffled: Vec<Arc<ValidatedSong>>,
+    /// Number of songs at the back of the queue which were added by the
+    /// radio. Songs queued by hand are placed ahead of them
+    auto_len: usize,
     history: VecDeque<Arc<SimpleSong>>,
     queue_ids: HashSet<u64>,
 
@@ -24,9 +33,11 @@ pub struct PlaybackSession {
 impl PlaybackSession {
     pub fn init() -> Self {
         PlaybackSession {
-            repeat: false,
+            mode: PlayMode::Off,
 
             queue: VecDeque::new(),
+            unshuffled: Vec::new(),
+            auto_len: 0,
             history: VecDeque::with_capacity(user_config().history_capacity as usize),
             queue_ids: HashSet::new(),
             now_playing: None,
@@ -57,6 +68,53 @@ impl PlaybackSession {
             .collect()
     }
 
+    /// The queue in the order it's played, marked with its unshuffled order
+    pub fn queue_entries(&self) -> Vec<SavedEntry> {
+        let unshuffled = self
+            .unshuffled
+            .iter()
+            .enumerate()
+            .map(|(pos, s)| (Arc::as_ptr(s), pos))
+            .collect::<HashMap<_, _>>();
+
+        let user_len = self.user_len();
+        self.queue
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| SavedEntry {
+                id: s.id(),
+                path: s.path_str(),
+                unshuffled_pos: unshuffled.get(&Arc::as_ptr(s)).copied(),
+                auto_added: idx >= user_len,
+            })
+            .collect()
+    }
+
+    /// Replace the queue with songs from a previous session, given in the
+    /// order they're played along with their unshuffled positions. The last
+    /// `auto_len` songs are those the radio added
+    pub fn restore_queue(
+        &mut self,
+        songs: Vec<(Arc<ValidatedSong>, Option<usize>)>,
+        auto_len: usize,
+        mode: PlayMode,
+    ) {
+        let mut unshuffled = songs
+            .iter()
+            .filter_map(|(song, pos)| pos.map(|p| (p, Arc::clone(song))))
+            .collect::<Vec<_>>();
+        unshuffled.sort_by_key(|(pos, _)| *pos);
+
+        self.queue = songs.into_iter().map(|(song, _)| song).collect();
+        self.auto_len = auto_len.min(self.queue.len());
+        self.queue_ids = self.queue.iter().map(|s| s.id()).collect();
+        self.unshuffled = match mode.is_shuffle() {
+            true => unshuffled.into_iter().map(|(_, song)| song).collect(),
+            false => Vec::new(),
+        };
+        self.mode = mode;
+    }
+
     // =====================
     //    QUEUE METHODS
     // =====================
@@ -64,8 +122,7 @@ impl PlaybackSession {
         let validated = ValidatedSong::new(song)?;
         let prev = self.get_head();
 
-        self.queue_ids.insert(validated.id());
-        self.queue.push_back(validated);
+        self.insert_queued(validated);
 
         Ok(self.head_delta(prev))
     }
@@ -75,8 +132,7 @@ impl PlaybackSession {
 
         for song in songs {
             if let Ok(validated) = ValidatedSong::new(song) {
-                self.queue_ids.insert(validated.id());
-                self.queue.push_back(validated);
+                self.insert_queued(validated);
             }
         }
 
@@ -90,6 +146,9 @@ impl PlaybackSession {
 
         self.queue_ids.insert(validated.id());
         self.queue.push_front(Arc::clone(&validated));
+        if self.mode.is_shuffle() {
+            self.unshuffled.insert(0, Arc::clone(&validated));
+        }
 
         Ok(QueueDelta::HeadChanged {
             prev,
@@ -97,7 +156,16 @@ impl PlaybackSession {
         })
     }
 
-    /// Take now_playing, put to history.
+    /// Take now_playing and put it to history without moving on, as when
+    /// playback is stopped
+    pub fn end_current(&mut self) -> Option<Arc<SimpleSong>> {
+        let song = self.now_playing.take()?;
+        self.push_history(&song);
+        Some(song)
+    }
+
+    /// Take now_playing, put to history. When repeating the queue it's also
+    /// sent to the back of the queue
     pub fn advance(
         &mut self,
     ) -> (
@@ -112,6 +180,16 @@ impl PlaybackSession {
             c
         });
 
+        if self.mode == PlayMode::RepeatAll
+            && let Some(validated) = pushed.as_ref().and_then(|s| ValidatedSong::new(s).ok())
+        {
+            self.queue_ids.insert(validated.id());
+            self.queue.insert(self.user_len(), validated);
+        }
+
+        if self.user_len() == 0 {
+            self.auto_len = self.auto_len.saturating_sub(1);
+        }
         let next = self.queue.pop_front().map(|song| {
             self.remove_id_if_final(song.id());
             song
@@ -122,6 +200,9 @@ impl PlaybackSession {
 
     pub fn remove_from_queue(&mut self, idx: usize) -> (QueueDelta, Option<Arc<ValidatedSong>>) {
         let prev = self.get_head();
+        if idx >= self.user_len() && idx < self.queue.len() {
+            self.auto_len -= 1;
+        }
         let dropped = self.queue.remove(idx).map(|s| {
             self.remove_id_if_final(s.id());
             s
@@ -132,6 +213,8 @@ impl PlaybackSession {
 
     pub fn clear_queue(&mut self) {
         self.queue.clear();
+        self.unshuffled.clear();
+        self.auto_len = 0;
         self.queue_ids.clear();
     }
 
@@ -142,12 +225,25 @@ impl PlaybackSession {
 
         let prev = self.get_head();
         self.queue.swap(a, b);
+        self.keep_moved(a, b);
+        Some(self.head_delta(prev))
+    }
+
+    pub fn move_queued(&mut self, from: usize, to: usize) -> Option<QueueDelta> {
+        if from.max(to) >= self.queue.len() {
+            return None;
+        }
+
+        let prev = self.get_head();
+        let song = self.queue.remove(from)?;
+        self.queue.insert(to, song);
+        self.keep_moved(from, to);
         Some(self.head_delta(prev))
     }
 
     pub fn shuffle_queue(&mut self) -> QueueDelta {
         let prev = self.get_head();
-        self.queue.make_contiguous().shuffle(&mut rand::rng());
+        self.shuffle_order();
         self.head_delta(prev)
     }
 
@@ -155,6 +251,11 @@ impl PlaybackSession {
         self.queue_ids.contains(&id)
     }
 
+    /// The queue as (song id, entry id) pairs, in the order it's played
+    pub fn queued_entries(&self) -> Vec<(u64, u32)> {
+        self.queue.iter().map(|s| (s.id(), s.entry)).collect()
+    }
+
     pub fn queue_len(&self) -> usize {
         self.queue.len()
     }
@@ -163,6 +264,51 @@ impl PlaybackSession {
         self.queue.is_empty()
     }
 
+    /// Whether the song at `idx` in the queue was added by the radio
+    pub fn is_auto_added(&self, idx: usize) -> bool {
+        idx >= self.user_len() && idx < self.queue.len()
+    }
+
+    pub fn auto_added_len(&self) -> usize {
+        self.auto_len
+    }
+
+    // =====================
+    //    RADIO METHODS
+    // =====================
+
+    /// The radio steps in once the last queued song has started playing
+    pub fn wants_radio(&self) -> bool {
+        self.queue.is_empty() && self.now_playing.is_some() && !self.mode.is_repeat()
+    }
+
+    /// Append songs picked to follow the one playing, marked as auto-added
+    pub fn add_radio_songs(
+        &mut self,
+        strategy: RadioStrategy,
+        songs: &SongMap,
+        stats: &HashMap<u64, SongStats>,
+    ) -> QueueDelta {
+        let prev = self.get_head();
+        let Some(seed) = self.now_playing.clone() else {
+            return self.head_delta(prev);
+        };
+
+        let mut exclude = self.queue_ids.clone();
+        exclude.insert(seed.id);
+        let recent = self.history.iter().map(|s| s.id).collect::<HashSet<_>>();
+
+        for song in radio::pick_songs(strategy, &seed, songs, stats, &exclude, &recent) {
+            if let Ok(validated) = ValidatedSong::new(&song) {
+                self.queue_ids.insert(validated.id());
+                self.queue.push_back(validated);
+                self.auto_len += 1;
+            }
+        }
+
+        self.head_delta(prev)
+    }
+
     // ======================
     //    HISTORY METHODS
     // ======================
@@ -190,11 +336,23 @@ impl PlaybackSession {
 
         let prev_head = self.get_head();
 
+        // Undo the requeue of a song which finished while repeating the queue
+        if self.mode == PlayMode::RepeatAll
+            && let Some(idx) = self.user_len().checked_sub(1)
+            && self.queue[idx].id() == last_played.id
+            && let Some(requeued) = self.queue.remove(idx)
+        {
+            self.remove_id_if_final(requeued.id());
+        }
+
         // If something is playing, place it back in the queue
         if let Some(current) = self.now_playing.take() {
             let validated = ValidatedSong::new(&current)?;
             self.queue_ids.insert(validated.id());
-            self.queue.push_front(validated);
+            self.queue.push_front(Arc::clone(&validated));
+            if self.mode.is_shuffle() {
+                self.unshuffled.insert(0, validated);
+            }
         }
 
         // Validate what was popped, set as now playing
@@ -206,18 +364,165 @@ impl PlaybackSession {
         Ok(Some((delta, validated_popped)))
     }
 
-    pub fn repeat_is_enabled(&self) -> bool {
-        self.repeat
+    pub fn get_play_mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// Whether the player loops the current song
+    pub fn repeats_song(&self) -> bool {
+        self.mode == PlayMode::RepeatOne
+    }
+
+    /// Shuffles the queue when entering a shuffle mode, and puts it back in
+    /// its original order when leaving one
+    pub fn set_play_mode(&mut self, mode: PlayMode) {
+        match (self.mode.is_shuffle(), mode.is_shuffle()) {
+            (false, true) => {
+                self.unshuffled = self.queue.iter().take(self.user_len()).cloned().collect()
+            }
+            (true, false) => self.restore_order(),
+            _ => {}
+        }
+
+        let reshuffle = mode.is_shuffle() && mode != self.mode;
+        self.mode = mode;
+        if reshuffle {
+            self.shuffle_order();
+        }
     }
 
-    pub fn set_repeat(&mut self, status: bool) {
-        self.repeat = status
+    /// Point queued, playing and played songs at their rebuilt entries after
+    /// their ids changed, e.g. from editing tags or moving files
+    pub fn remap_songs(&mut self, renamed: &HashMap<u64, u64>, songs: &SongMap) -> QueueDelta {
+        let prev = self.get_head();
+        let lookup = |song: &SimpleSong| renamed.get(&song.id).and_then(|id| songs.get(id));
+
+        for entry in self.queue.iter_mut() {
+            if let Some(meta) = lookup(&entry.meta) {
+                // The entry keeps its id, so clients following it don't lose track
+                let path =
+                    ValidatedSong::new(meta).map_or_else(|_| entry.path.clone(), |s| s.path_str());
+                let remapped = Arc::new(ValidatedSong {
+                    meta: Arc::clone(meta),
+                    path,
+                    entry: entry.entry,
+                });
+
+                if let Some(original) = self.unshuffled.iter_mut().find(|s| Arc::ptr_eq(s, entry)) {
+                    *original = Arc::clone(&remapped);
+                }
+                *entry = remapped;
+            }
+        }
+        self.queue_ids = self.queue.iter().map(|s| s.id()).collect();
+
+        for song in self.history.iter_mut() {
+            if let Some(new) = lookup(song) {
+                *song = Arc::clone(new);
+            }
+        }
+
+        if let Some(new) = self.now_playing.as_deref().and_then(lookup) {
+            self.now_playing = Some(Arc::clone(new));
+        }
+
+        self.head_delta(prev)
     }
 
     // ======================
     //    INTERNAL METHODS
     // ======================
 
+    fn insert_queued(&mut self, song: Arc<ValidatedSong>) {
+        self.queue_ids.insert(song.id());
+
+        match self.mode.is_shuffle() {
+            true => {
+                self.unshuffled.push(Arc::clone(&song));
+                let idx = rand::random_range(0..=self.user_len());
+                self.queue.insert(idx, song);
+            }
+            false => self.queue.insert(self.user_len(), song),
+        }
+    }
+
+    /// Songs added by the radio stay at the back
+    fn shuffle_order(&mut self) {
+        let user_len = self.user_len();
+        self.queue.make_contiguous()[..user_len].shuffle(&mut rand::rng());
+        if self.mode == PlayMode::SmartShuffle {
+            self.spread_artists();
+        }
+    }
+
+    /// Reorder the shuffled queue so each song's artist differs from the few
+    /// played or queued right before it, wherever the queue allows
+    fn spread_artists(&mut self) {
+        let auto_added = self.queue.split_off(self.user_len());
+        let mut pending = self.queue.drain(..).collect::<Vec<_>>();
+
+        let artists = pending
+            .iter()
+            .map(|s| s.meta.artist.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+        let spacing = ARTIST_SPACING.min(artists.saturating_sub(1));
+
+        // Most recent first
+        let mut recent = self
+            .now_playing
+            .iter()
+            .chain(self.history.iter())
+            .take(spacing)
+            .map(|s| Arc::clone(&s.artist))
+            .collect::<VecDeque<_>>();
+
+        while !pending.is_empty() {
+            let idx = pending
+                .iter()
+                .position(|s| !recent.contains(&s.meta.artist))
+                .unwrap_or(0);
+            let song = pending.remove(idx);
+
+            recent.push_front(Arc::clone(&song.meta.artist));
+            recent.truncate(spacing);
+            self.queue.push_back(song);
+        }
+        self.queue.extend(auto_added);
+    }
+
+    /// Songs queued ahead of the shuffle, such as those pushed to the front,
+    /// stay where they are
+    fn restore_order(&mut self) {
+        let auto_added = self.queue.split_off(self.user_len());
+        let mut remaining = self.queue.drain(..).collect::<Vec<_>>();
+        let mut ordered = Vec::with_capacity(remaining.len());
+
+        for song in std::mem::take(&mut self.unshuffled) {
+            if let Some(idx) = remaining.iter().position(|s| Arc::ptr_eq(s, &song)) {
+                ordered.push(remaining.remove(idx));
+            }
+        }
+
+        self.queue.extend(remaining);
+        self.queue.extend(ordered);
+        self.queue.extend(auto_added);
+    }
+
+    /// Songs queued by hand, ahead of those added by the radio
+    fn user_len(&self) -> usize {
+        self.queue.len() - self.auto_len
+    }
+
+    /// A song moved across the start of the auto-added section is kept as if
+    /// it were queued by hand, as are any auto-added songs now ahead of it
+    fn keep_moved(&mut self, a: usize, b: usize) {
+        let user_len = self.user_len();
+        if a.min(b) < user_len && a.max(b) >= user_len {
+            self.auto_len = self.queue.len() - (a.max(b) + 1);
+        }
+    }
+
     fn get_head(&self) -> Option<Arc<ValidatedSong>> {
         self.queue.front().cloned()
     }
@@ -3,11 +3,27 @@ use crate::{
     library::{SimpleSong, SongDatabase, SongInfo},
 };
 use anyhow::Result;
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+static NEXT_ENTRY: AtomicU32 = AtomicU32::new(1);
+
+/// A fresh id, unique among everything queued or played in this session
+pub fn next_entry_id() -> u32 {
+    NEXT_ENTRY.fetch_add(1, Ordering::Relaxed)
+}
 
 pub struct ValidatedSong {
     pub meta: Arc<SimpleSong>,
     pub path: String,
+    /// Tells apart entries of the same song, kept as the entry is moved
+    pub entry: u32,
 }
 
 impl ValidatedSong {
@@ -19,6 +35,7 @@ impl ValidatedSong {
         Ok(Arc::new(Self {
             meta: Arc::clone(&song),
             path,
+            entry: next_entry_id(),
         }))
     }
 
@@ -1,8 +1,11 @@
 use anyhow::Result;
+
+use crate::player::EqGains;
 use std::{path::Path, time::Duration};
 
 pub(super) trait PlayerBackend: Send + 'static {
-    fn play(&mut self, path: &Path) -> Result<()>;
+    /// `gain` is the track's linear ReplayGain factor
+    fn play(&mut self, path: &Path, gain: f32) -> Result<()>;
     fn stop(&mut self);
     fn pause(&mut self);
     fn resume(&mut self);
@@ -17,17 +20,45 @@ pub(super) trait PlayerBackend: Send + 'static {
     fn is_stopped(&self) -> bool;
     fn track_ended(&self) -> bool;
 
+    /// Length of the current track, zero if unknown
+    fn duration(&self) -> Duration {
+        Duration::ZERO
+    }
+
     // Optional features - default no-ops
     fn supports_gapless(&self) -> bool {
         false
     }
 
-    fn set_next(&mut self, _path: &Path) -> Result<()> {
+    fn set_next(&mut self, _path: &Path, _gain: f32) -> Result<()> {
         Ok(()) // silently succeed if not supported
     }
 
     fn clear_next(&mut self) -> Result<()>;
 
+    fn supports_crossfade(&self) -> bool {
+        false
+    }
+
+    /// Start playing `path` on top of the current track. From here on
+    /// position and duration refer to the incoming track
+    fn start_crossfade(&mut self, _path: &Path, _gain: f32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fade curve factors of the outgoing and incoming tracks, applied on
+    /// top of their ReplayGain
+    fn set_crossfade_gains(&mut self, _outgoing: f32, _incoming: f32) {}
+
+    /// Drop the outgoing track once faded out
+    fn finish_crossfade(&mut self) {}
+
+    /// Linear output volume from 0.0 to 1.0
+    fn set_volume(&mut self, _volume: f32) {}
+
+    /// Equalizer applied to the decoded signal, a flat curve disables it
+    fn set_equalizer(&mut self, _gains: EqGains) {}
+
     fn drain_samples(&mut self) -> Vec<f32> {
         Vec::new()
     }
@@ -0,0 +1,389 @@
+use crate::{
+    TAP_BUFFER_CAPACITY,
+    library::PcmDecoder,
+    player::{
+        EqGains, PlayerBackend,
+        mixer::{self, MixerCommand, SeekTo},
+    },
+};
+use anyhow::{Result, anyhow};
+use cpal::{
+    Stream, StreamConfig,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use crossbeam::{channel::Sender, queue::ArrayQueue};
+use rtrb::Consumer;
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+/// Audio buffered between the mixer and the device
+const BUFFER_MS: usize = 150;
+/// Fade in after seeking to avoid a click
+const SEEK_FADE_MS: usize = 30;
+
+/// Shared between the engine, the mixer thread and the output callback
+pub(super) struct EngineState {
+    /// A track is loaded, playing or paused
+    active: AtomicBool,
+    paused: AtomicBool,
+    /// Set from the moment a track is started or seeked until the mixer has
+    /// caught up, the output plays silence in the meantime
+    seeking: AtomicBool,
+    track_ended: AtomicBool,
+    /// Interleaved samples of the current track sent to the device
+    samples_played: AtomicU64,
+    duration_micros: AtomicU64,
+    /// Bits of the linear output volume
+    volume: AtomicU32,
+    /// Samples per second across all channels
+    rate: f64,
+}
+
+impl EngineState {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        EngineState {
+            active: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            seeking: AtomicBool::new(false),
+            track_ended: AtomicBool::new(false),
+            samples_played: AtomicU64::new(0),
+            duration_micros: AtomicU64::new(0),
+            volume: AtomicU32::new(1f32.to_bits()),
+            rate: sample_rate as f64 * channels as f64,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn is_seeking(&self) -> bool {
+        self.seeking.load(Ordering::Acquire)
+    }
+
+    fn start_seek(&self) {
+        self.seeking.store(true, Ordering::Release);
+    }
+
+    pub fn finish_seek(&self) {
+        self.seeking.store(false, Ordering::Release);
+    }
+
+    pub fn signal_track_ended(&self) {
+        self.track_ended.store(true, Ordering::Release);
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_secs_f64(self.samples_played.load(Ordering::Acquire) as f64 / self.rate)
+    }
+
+    pub fn set_samples(&self, samples: u64) {
+        self.samples_played.store(samples, Ordering::Release);
+    }
+
+    pub fn reset_samples(&self) {
+        self.set_samples(0);
+    }
+
+    fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_duration(&self, secs: Option<f64>) {
+        let micros = (secs.unwrap_or(0.0) * 1_000_000.0) as u64;
+        self.duration_micros.store(micros, Ordering::Release);
+    }
+}
+
+/// Plays through the default output device. Tracks are decoded and mixed on
+/// a separate thread, see [`mixer`]
+pub struct CpalEngine {
+    state: Arc<EngineState>,
+    commands: Sender<MixerCommand>,
+    tap: Arc<ArrayQueue<f32>>,
+    sample_rate: u32,
+    channels: usize,
+    _stream: Stream,
+}
+
+impl CpalEngine {
+    pub fn new() -> Result<Self> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| anyhow!("No output device recognized!"))?;
+
+        let config = device.default_output_config()?;
+        let sample_rate = config.sample_rate();
+        let channels = config.channels() as usize;
+        let stream_config: StreamConfig = config.into();
+
+        let capacity = sample_rate as usize * channels * BUFFER_MS / 1000;
+        let (producer, consumer) = rtrb::RingBuffer::new(capacity);
+
+        let state = Arc::new(EngineState::new(sample_rate, channels));
+        let tap = Arc::new(ArrayQueue::new(TAP_BUFFER_CAPACITY));
+
+        let mut output = Output {
+            consumer,
+            state: Arc::clone(&state),
+            tap: Arc::clone(&tap),
+            was_seeking: false,
+            fade_len: sample_rate as usize * channels * SEEK_FADE_MS / 1000,
+            fade_left: 0,
+            volume: 1.0,
+        };
+
+        let stream = device.build_output_stream(
+            &stream_config,
+            move |data: &mut [f32], _| output.fill(data),
+            |_| {},
+            None,
+        )?;
+
+        let commands = mixer::spawn(producer, Arc::clone(&state), sample_rate, channels);
+        stream.play()?;
+
+        Ok(Self {
+            state,
+            commands,
+            tap,
+            sample_rate,
+            channels,
+            _stream: stream,
+        })
+    }
+
+    fn send(&self, cmd: MixerCommand) -> Result<()> {
+        self.commands
+            .send(cmd)
+            .map_err(|_| anyhow!("Audio thread has stopped"))
+    }
+
+    fn seek(&mut self, to: SeekTo) -> Result<()> {
+        if !self.state.is_active() {
+            return Ok(());
+        }
+
+        self.state.start_seek();
+        self.send(MixerCommand::Seek(to))
+    }
+}
+
+impl PlayerBackend for CpalEngine {
+    fn play(&mut self, path: &Path, gain: f32) -> Result<()> {
+        let decoder = PcmDecoder::open(path)?;
+
+        self.state.start_seek();
+        self.state.reset_samples();
+        self.state.set_duration(decoder.duration());
+        self.state.set_active(true);
+        self.state.paused.store(false, Ordering::Relaxed);
+
+        self.send(MixerCommand::Play(decoder, gain))
+    }
+
+    fn pause(&mut self) {
+        if self.state.is_active() {
+            self.state.paused.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn resume(&mut self) {
+        self.state.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn stop(&mut self) {
+        self.state.set_active(false);
+        let _ = self.send(MixerCommand::Stop);
+    }
+
+    fn seek_to(&mut self, secs: f32) -> Result<()> {
+        self.seek(SeekTo::Absolute(secs as f64))
+    }
+
+    fn seek_forward(&mut self, secs: u64) -> Result<()> {
+        self.seek(SeekTo::Relative(secs as f64))
+    }
+
+    fn seek_back(&mut self, secs: u64) -> Result<()> {
+        self.seek(SeekTo::Relative(-(secs as f64)))
+    }
+
+    fn position(&self) -> Duration {
+        self.state.position()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.is_paused()
+    }
+
+    fn is_stopped(&self) -> bool {
+        !self.state.is_active()
+    }
+
+    fn track_ended(&self) -> bool {
+        self.state.track_ended.swap(false, Ordering::Acquire)
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_micros(self.state.duration_micros.load(Ordering::Acquire))
+    }
+
+    fn supports_gapless(&self) -> bool {
+        true
+    }
+
+    fn set_next(&mut self, path: &Path, gain: f32) -> Result<()> {
+        let decoder = PcmDecoder::open(path)?;
+        self.send(MixerCommand::QueueNext(decoder, gain))
+    }
+
+    fn clear_next(&mut self) -> Result<()> {
+        self.send(MixerCommand::ClearNext)
+    }
+
+    fn supports_crossfade(&self) -> bool {
+        true
+    }
+
+    fn start_crossfade(&mut self, path: &Path, gain: f32) -> Result<()> {
+        let decoder = PcmDecoder::open(path)?;
+
+        self.state.reset_samples();
+        self.state.set_duration(decoder.duration());
+        self.send(MixerCommand::Crossfade(decoder, gain))
+    }
+
+    fn set_crossfade_gains(&mut self, outgoing: f32, incoming: f32) {
+        let _ = self.send(MixerCommand::FadeGains(outgoing, incoming));
+    }
+
+    fn finish_crossfade(&mut self) {
+        let _ = self.send(MixerCommand::FinishFade);
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        self.state.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_equalizer(&mut self, gains: EqGains) {
+        let _ = self.send(MixerCommand::SetEqualizer(gains));
+    }
+
+    fn drain_samples(&mut self) -> Vec<f32> {
+        std::iter::from_fn(|| self.tap.pop()).collect()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+}
+
+/// Runs on the device's audio callback, so it must never block
+struct Output {
+    consumer: Consumer<f32>,
+    state: Arc<EngineState>,
+    tap: Arc<ArrayQueue<f32>>,
+    was_seeking: bool,
+    fade_len: usize,
+    fade_left: usize,
+    /// Volume at the end of the last buffer
+    volume: f32,
+}
+
+impl Output {
+    fn fill(&mut self, data: &mut [f32]) {
+        let seeking = self.state.is_seeking();
+
+        // Stale audio is thrown away, while a paused track keeps its place
+        if seeking || !self.state.is_active() {
+            let stale = self.consumer.slots();
+            if let Ok(chunk) = self.consumer.read_chunk(stale) {
+                chunk.commit_all();
+            }
+            self.was_seeking = seeking;
+            self.silence(data);
+            return;
+        }
+
+        if self.state.is_paused() {
+            self.silence(data);
+            return;
+        }
+
+        if self.was_seeking {
+            self.was_seeking = false;
+            self.fade_left = self.fade_len;
+        }
+
+        let len = self.consumer.slots().min(data.len());
+        if let Ok(chunk) = self.consumer.read_chunk(len) {
+            let (first, second) = chunk.as_slices();
+            for (out, sample) in data.iter_mut().zip(first.iter().chain(second)) {
+                *out = *sample;
+            }
+            chunk.commit_all();
+        }
+        data[len..].fill(0.0);
+
+        for sample in data[..len].iter_mut().take(self.fade_left) {
+            *sample *= 1.0 - self.fade_left as f32 / self.fade_len as f32;
+            self.fade_left -= 1;
+        }
+
+        self.state
+            .samples_played
+            .fetch_add(len as u64, Ordering::AcqRel);
+
+        for sample in data.iter() {
+            self.tap.force_push(*sample);
+        }
+
+        self.apply_volume(data);
+    }
+
+    /// Nothing is heard while silent, so volume changes take effect at once
+    fn silence(&mut self, data: &mut [f32]) {
+        data.fill(0.0);
+        self.volume = self.state.volume();
+    }
+
+    /// Ramp towards the target volume over the length of the buffer, so that
+    /// changes don't click
+    fn apply_volume(&mut self, data: &mut [f32]) {
+        let target = self.state.volume();
+        let start = self.volume;
+        self.volume = target;
+
+        if start == target {
+            if target != 1.0 {
+                data.iter_mut().for_each(|s| *s *= target);
+            }
+            return;
+        }
+
+        let step = (target - start) / data.len().max(1) as f32;
+        for (i, sample) in data.iter_mut().enumerate() {
+            *sample *= start + step * i as f32;
+        }
+    }
+}
@@ -1,16 +1,25 @@
 use crate::{
-    config::timing,
+    config::{CrossfadeCurve, timing},
     player::{
         PlaybackMetrics, PlaybackState, PlayerBackend, PlayerCommand, PlayerEvent,
         track::VoxioTrack,
     },
+    user_config,
 };
 use crossbeam::channel::{Receiver, Sender, TryRecvError};
 use std::{
+    f32::consts::FRAC_PI_2,
     sync::Arc,
     thread::{self, JoinHandle},
+    time::Duration,
 };
 
+/// A crossfade in progress, `current` already holds the incoming track
+struct Fade {
+    length: Duration,
+    curve: CrossfadeCurve,
+}
+
 pub struct PlayerCore {
     backend: Box<dyn PlayerBackend>,
     commands: Receiver<PlayerCommand>,
@@ -19,6 +28,10 @@ pub struct PlayerCore {
 
     current: Option<VoxioTrack>,
     next: Option<VoxioTrack>,
+    fade: Option<Fade>,
+
+    volume: u8,
+    muted: bool,
 }
 
 impl PlayerCore {
@@ -37,6 +50,10 @@ impl PlayerCore {
 
                 current: None,
                 next: None,
+                fade: None,
+
+                volume: 100,
+                muted: false,
             };
 
             core.run();
@@ -50,6 +67,7 @@ impl PlayerCore {
                 break;
             }
             self.process_commands();
+            self.check_crossfade();
             self.check_track_end();
             self.update_metrics();
             thread::sleep(timing().refresh_rate);
@@ -69,6 +87,9 @@ impl PlayerCore {
                     PlayerCommand::SeekTo(x) => self.seek_to(x),
                     PlayerCommand::SeekForward(x) => self.seek_forward(x),
                     PlayerCommand::SeekBack(x) => self.seek_back(x),
+                    PlayerCommand::SetVolume(v) => self.set_volume(v),
+                    PlayerCommand::SetMuted(m) => self.set_muted(m),
+                    PlayerCommand::SetEqualizer(g) => self.backend.set_equalizer(g),
                 },
                 Err(TryRecvError::Empty) => return true,
                 Err(TryRecvError::Disconnected) => return false,
@@ -79,7 +100,8 @@ impl PlayerCore {
     fn check_track_end(&mut self) {
         // Checking status of `current` ensures the stop event is only sent once
         if self.backend.track_ended() && self.current.is_some() {
-            match self.next.take() {
+            // Without gapless the next song is only held on to for crossfades
+            match self.next.take().filter(|_| self.gapless()) {
                 // GAPLESS BRANCH
                 Some(next) => {
                     self.current = Some(next.clone());
@@ -95,6 +117,75 @@ impl PlayerCore {
         }
     }
 
+    /// Starts fading into the next track once the current one is within the
+    /// crossfade length of its end, and advances a fade in progress
+    fn check_crossfade(&mut self) {
+        if let Some(fade) = &self.fade {
+            let progress = match fade.length.is_zero() {
+                true => 1.0,
+                false => {
+                    (self.backend.position().as_secs_f32() / fade.length.as_secs_f32()).min(1.0)
+                }
+            };
+
+            let (outgoing, incoming) = fade_gains(fade.curve, progress);
+            self.backend.set_crossfade_gains(outgoing, incoming);
+
+            if progress >= 1.0 {
+                self.backend.finish_crossfade();
+                self.fade = None;
+            }
+            return;
+        }
+
+        let config = user_config();
+        let length = Duration::from_secs_f32(config.crossfade);
+
+        if length.is_zero() || !self.backend.supports_crossfade() || self.backend.is_paused() {
+            return;
+        }
+
+        let (Some(current), Some(next)) = (&self.current, &self.next) else {
+            return;
+        };
+
+        // Albums and repeats are left gapless, as are songs too short to fade
+        let duration = self.backend.duration();
+        if current == next || current.same_album(next) || duration < length * 2 {
+            return;
+        }
+
+        let remaining = duration.saturating_sub(self.backend.position());
+        if remaining > length {
+            return;
+        }
+
+        let next = next.clone();
+
+        if self.gapless() {
+            let _ = self.backend.clear_next();
+        }
+
+        // The next song is dropped either way, on failure playback stops at the
+        // end of the current song and moves on from there
+        self.next = None;
+        if let Err(e) = self.backend.start_crossfade(next.path(), next.gain()) {
+            self.emit(PlayerEvent::Error(e.to_string()));
+            return;
+        }
+
+        self.fade = Some(Fade {
+            length: remaining,
+            curve: config.crossfade_curve,
+        });
+        self.current = Some(next.clone());
+        self.emit(PlayerEvent::TrackStarted((next, true)));
+    }
+
+    fn gapless(&self) -> bool {
+        self.backend.supports_gapless() && user_config().gapless
+    }
+
     fn update_metrics(&mut self) {
         if self.current.is_some() {
             self.metrics.set_elapsed(self.backend.position())
@@ -110,7 +201,8 @@ impl PlayerCore {
     }
 
     fn play_song(&mut self, song: VoxioTrack) {
-        if let Err(e) = self.backend.play(&song.path()) {
+        self.fade = None;
+        if let Err(e) = self.backend.play(song.path(), song.gain()) {
             self.emit(PlayerEvent::Error(e.to_string()));
             return;
         }
@@ -122,17 +214,21 @@ impl PlayerCore {
     }
 
     fn set_next(&mut self, next: Option<VoxioTrack>) {
-        if self.backend.supports_gapless() {
+        if !self.gapless() && !self.backend.supports_crossfade() {
+            return;
+        }
+
+        if self.gapless() {
             if let Some(song) = &next {
-                if let Err(e) = self.backend.set_next(&song.path()) {
+                if let Err(e) = self.backend.set_next(song.path(), song.gain()) {
                     self.emit(PlayerEvent::Error(e.to_string()));
                     return;
                 }
             } else {
                 let _ = self.backend.clear_next();
             }
-            self.next = next;
         }
+        self.next = next;
     }
 
     fn toggle_playback(&mut self) {
@@ -160,6 +256,7 @@ impl PlayerCore {
 
     fn stop(&mut self) {
         self.backend.stop();
+        self.fade = None;
         self.current = None;
         self.metrics.reset();
         self.emit(PlayerEvent::PlaybackStopped);
@@ -185,7 +282,34 @@ impl PlayerCore {
         }
     }
 
+    fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+        self.apply_volume();
+    }
+
+    fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_volume();
+    }
+
+    fn apply_volume(&mut self) {
+        let volume = match self.muted {
+            true => 0.0,
+            false => self.volume as f32 / 100.0,
+        };
+        self.backend.set_volume(volume);
+    }
+
     fn emit(&self, event: PlayerEvent) {
         let _ = self.events.send(event);
     }
 }
+
+/// Gains of the outgoing and incoming tracks at `progress` (0.0 to 1.0).
+/// Equal power keeps the perceived loudness steady through the fade
+fn fade_gains(curve: CrossfadeCurve, progress: f32) -> (f32, f32) {
+    match curve {
+        CrossfadeCurve::Linear => (1.0 - progress, progress),
+        CrossfadeCurve::EqualPower => ((progress * FRAC_PI_2).cos(), (progress * FRAC_PI_2).sin()),
+    }
+}
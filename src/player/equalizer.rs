@@ -0,0 +1,80 @@
+//! Ten band graphic equalizer made of peaking biquads, following the RBJ
+//! audio EQ cookbook.
+
+use crate::library::Biquad;
+use std::f64::consts::SQRT_2;
+
+pub const EQ_BANDS: usize = 10;
+pub const EQ_FREQUENCIES: [f32; EQ_BANDS] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// Gains are limited to this many dB either way
+pub const EQ_MAX_GAIN: f32 = 12.0;
+
+/// One octave between bands
+const EQ_Q: f64 = SQRT_2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EqGains {
+    /// Gain in dB applied before the bands
+    pub preamp: f32,
+    pub bands: [f32; EQ_BANDS],
+}
+
+impl EqGains {
+    pub fn is_flat(&self) -> bool {
+        self.preamp == 0.0 && self.bands.iter().all(|g| *g == 0.0)
+    }
+
+    /// Combined response of the preamp and every band at `freq`, in dB
+    pub fn response_db(&self, freq: f32, sample_rate: u32) -> f32 {
+        self.preamp
+            + filters(self, sample_rate)
+                .map(|f| 20.0 * f.magnitude(freq as f64, sample_rate).log10() as f32)
+                .sum::<f32>()
+    }
+}
+
+/// Filters for the bands that actually change the signal. Bands at or above
+/// the Nyquist frequency are left out
+fn filters(gains: &EqGains, sample_rate: u32) -> impl Iterator<Item = Biquad> + '_ {
+    let nyquist = sample_rate as f32 / 2.0;
+
+    EQ_FREQUENCIES
+        .iter()
+        .zip(gains.bands)
+        .filter(move |(freq, gain)| *gain != 0.0 && **freq < nyquist)
+        .map(move |(freq, gain)| Biquad::peaking(*freq as f64, gain as f64, EQ_Q, sample_rate))
+}
+
+/// Filters interleaved samples in place
+pub struct Equalizer {
+    preamp: f32,
+    /// The chain of band filters for every channel
+    filters: Vec<Vec<Biquad>>,
+}
+
+impl Equalizer {
+    pub fn new(gains: &EqGains, sample_rate: u32, channels: usize) -> Self {
+        let chain = filters(gains, sample_rate).collect::<Vec<_>>();
+
+        Equalizer {
+            preamp: 10f32.powf(gains.preamp / 20.0),
+            filters: vec![chain; channels.max(1)],
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(self.filters.len()) {
+            for (sample, chain) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                let x = (*sample * self.preamp) as f64;
+                *sample = chain.iter_mut().fold(x, |x, f| f.process(x)) as f32;
+            }
+        }
+    }
+
+    /// Forget the filter history, e.g. after seeking
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().flatten().for_each(Biquad::reset);
+    }
+}
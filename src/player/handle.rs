@@ -3,7 +3,7 @@ use crossbeam::channel::{Receiver, Sender};
 use std::{sync::Arc, time::Duration};
 
 use crate::player::{
-    PlaybackState, PlayerCommand, PlayerEvent, VoxioTrack, backend_voxio::VoxEngine,
+    EqGains, PlaybackState, PlayerCommand, PlayerEvent, VoxioTrack, backend_cpal::CpalEngine,
     core::PlayerCore, metrics::PlaybackMetrics,
 };
 
@@ -15,8 +15,8 @@ pub struct PlayerHandle {
 
 impl PlayerHandle {
     pub fn spawn() -> Result<Self> {
-        let backend = VoxEngine::new()
-            .map_err(|e| anyhow!("\nFailed to initialize backend.\nVoxio {e}\n\nPossible fix: Install pipewire-alsa or an equivalent ALSA bridge for your audio server."))?;
+        let backend = CpalEngine::new()
+            .map_err(|e| anyhow!("\nFailed to initialize backend.\n{e}\n\nPossible fix: Install pipewire-alsa or an equivalent ALSA bridge for your audio server."))?;
         let (cmd_tx, cmd_rx) = crossbeam::channel::bounded(32);
         let (event_tx, event_rx) = crossbeam::channel::bounded(32);
         let metrics = PlaybackMetrics::new();
@@ -83,6 +83,35 @@ impl PlayerHandle {
         self.commands.send(PlayerCommand::SeekBack(dur))?;
         Ok(())
     }
+
+    /// Metrics are updated immediately so that repeated adjustments made
+    /// before the player thread catches up are not lost
+    pub fn set_volume(&self, volume: u8) -> Result<()> {
+        let volume = volume.min(100);
+        self.metrics.set_volume(volume);
+        self.commands.send(PlayerCommand::SetVolume(volume))?;
+        Ok(())
+    }
+
+    pub fn adjust_volume(&self, delta: i16) -> Result<()> {
+        let volume = (self.volume() as i16 + delta).clamp(0, 100);
+        self.set_volume(volume as u8)
+    }
+
+    pub fn set_muted(&self, muted: bool) -> Result<()> {
+        self.metrics.set_muted(muted);
+        self.commands.send(PlayerCommand::SetMuted(muted))?;
+        Ok(())
+    }
+
+    pub fn toggle_mute(&self) -> Result<()> {
+        self.set_muted(!self.is_muted())
+    }
+
+    pub fn set_equalizer(&self, gains: EqGains) -> Result<()> {
+        self.commands.send(PlayerCommand::SetEqualizer(gains))?;
+        Ok(())
+    }
 }
 
 // ===============
@@ -106,6 +135,14 @@ impl PlayerHandle {
         self.get_playback_state() == PlaybackState::Stopped
     }
 
+    pub fn volume(&self) -> u8 {
+        self.metrics.volume()
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.metrics.is_muted()
+    }
+
     pub fn events(&self) -> &Receiver<PlayerEvent> {
         &self.events
     }
@@ -1,7 +1,7 @@
 use crate::TAP_BUFFER_CAPACITY;
 use crate::player::PlaybackState;
 use crossbeam::queue::ArrayQueue;
-use std::sync::atomic::AtomicU32;
+use std::sync::atomic::{AtomicBool, AtomicU32};
 use std::time::Duration;
 use std::{
     collections::VecDeque,
@@ -16,6 +16,8 @@ pub struct PlaybackMetrics {
     channels: AtomicU8,
     sample_rate: AtomicU32,
     elapsed_ms: AtomicU64,
+    volume: AtomicU8,
+    muted: AtomicBool,
     pub(crate) audio_tap: ArrayQueue<f32>,
 }
 
@@ -26,6 +28,8 @@ impl PlaybackMetrics {
             channels: AtomicU8::new(0),
             sample_rate: AtomicU32::new(0),
             elapsed_ms: AtomicU64::new(0),
+            volume: AtomicU8::new(100),
+            muted: AtomicBool::new(false),
             audio_tap: ArrayQueue::new(TAP_BUFFER_CAPACITY),
         })
     }
@@ -74,6 +78,23 @@ impl PlaybackMetrics {
         self.channels.load(Ordering::Relaxed)
     }
 
+    pub fn set_volume(&self, volume: u8) {
+        self.volume.store(volume, Ordering::Relaxed);
+    }
+
+    /// Volume as a percentage, regardless of mute
+    pub fn volume(&self) -> u8 {
+        self.volume.load(Ordering::Relaxed)
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
     pub fn reset(&self) {
         self.set_elapsed(Duration::ZERO);
         self.set_playback_state(PlaybackState::Stopped);
@@ -0,0 +1,492 @@
+use crate::{
+    library::PcmDecoder,
+    player::{EqGains, Equalizer, backend_cpal::EngineState, resampler::Resampler},
+};
+use anyhow::Result;
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use rtrb::Producer;
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Frames mixed at a time
+const CHUNK_FRAMES: usize = 1024;
+/// Longest wait for the output to throw away stale audio after a seek
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(50);
+
+pub(super) enum MixerCommand {
+    Play(PcmDecoder, f32),
+    QueueNext(PcmDecoder, f32),
+    ClearNext,
+    Crossfade(PcmDecoder, f32),
+    /// Fade curve factors of the outgoing and incoming tracks
+    FadeGains(f32, f32),
+    FinishFade,
+    Seek(SeekTo),
+    SetEqualizer(EqGains),
+    Stop,
+}
+
+pub(super) enum SeekTo {
+    Absolute(f64),
+    Relative(f64),
+}
+
+/// Spawns the thread which decodes tracks, applies their gain and the
+/// equalizer, and feeds the result to the output
+pub(super) fn spawn(
+    producer: Producer<f32>,
+    state: Arc<EngineState>,
+    sample_rate: u32,
+    channels: usize,
+) -> Sender<MixerCommand> {
+    let (tx, rx) = crossbeam::channel::bounded(32);
+
+    thread::spawn(move || {
+        let mut mixer = Mixer {
+            commands: rx,
+            capacity: producer.buffer().capacity(),
+            producer,
+            state,
+            sample_rate,
+            channels,
+
+            current: None,
+            next: None,
+            fade: None,
+            equalizer: None,
+            backlog: Vec::new(),
+            draining: false,
+        };
+        mixer.run();
+    });
+
+    tx
+}
+
+/// A track converted to the output sample rate and channel count
+struct Source {
+    decoder: PcmDecoder,
+    /// Linear ReplayGain factor
+    gain: f32,
+    channels: usize,
+    resampler: Option<Resampler>,
+    /// Samples at the track's own rate waiting on the resampler
+    pending: Vec<f32>,
+    ready: VecDeque<f32>,
+    /// The decoder has run out
+    exhausted: bool,
+    /// Nothing else will be added to `ready`
+    finished: bool,
+}
+
+impl Source {
+    fn new(decoder: PcmDecoder, gain: f32, sample_rate: u32, channels: usize) -> Result<Self> {
+        Ok(Source {
+            resampler: Resampler::new(decoder.sample_rate, sample_rate, channels)?,
+            decoder,
+            gain,
+            channels,
+            pending: Vec::new(),
+            ready: VecDeque::new(),
+            exhausted: false,
+            finished: false,
+        })
+    }
+
+    /// Decode until `len` samples are ready or the track runs out. Packets
+    /// which fail to decode end the track early
+    fn fill(&mut self, len: usize) -> Result<()> {
+        while self.ready.len() < len && !self.exhausted {
+            let input_channels = self.decoder.channels;
+
+            let Ok(Some(samples)) = self.decoder.next_chunk() else {
+                self.exhausted = true;
+                break;
+            };
+
+            match &mut self.resampler {
+                Some(r) => {
+                    map_channels(
+                        samples,
+                        input_channels,
+                        self.channels,
+                        self.gain,
+                        &mut self.pending,
+                    );
+                    r.process(&mut self.pending, |s| self.ready.extend(s))?;
+                }
+                None => map_channels(
+                    samples,
+                    input_channels,
+                    self.channels,
+                    self.gain,
+                    &mut self.ready,
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Carry on with the next track through the same resampler, so that no
+    /// gap or padding ends up between the two
+    fn continue_with(&mut self, decoder: PcmDecoder, gain: f32) {
+        self.decoder = decoder;
+        self.gain = gain;
+        self.exhausted = false;
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(r) = &mut self.resampler {
+            r.flush(&mut self.pending, |s| self.ready.extend(s))?;
+        }
+        self.finished = true;
+        Ok(())
+    }
+
+    fn seek(&mut self, secs: f64) -> Result<f64> {
+        let actual = self.decoder.seek(secs)?;
+
+        self.pending.clear();
+        self.ready.clear();
+        if let Some(r) = &mut self.resampler {
+            r.reset();
+        }
+        self.exhausted = false;
+        self.finished = false;
+
+        Ok(actual)
+    }
+}
+
+/// The track being faded out from underneath the current one
+struct Fade {
+    outgoing: Source,
+    /// Factors of the outgoing and incoming tracks to reach by the end of the
+    /// next chunk
+    gains: (f32, f32),
+    /// Factors at the end of the last chunk
+    applied: (f32, f32),
+}
+
+impl Fade {
+    /// Scale the incoming samples and mix the outgoing track on top, ramping
+    /// between the factors across the chunk
+    fn mix(&mut self, samples: &mut [f32]) -> Result<()> {
+        let outgoing = &mut self.outgoing;
+        outgoing.fill(samples.len())?;
+        if outgoing.exhausted && !outgoing.finished {
+            outgoing.finish()?;
+        }
+
+        let channels = outgoing.channels;
+        let frames = (samples.len() / channels).max(1) as f32;
+        let (from, to) = (self.applied, self.gains);
+
+        for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+            let t = i as f32 / frames;
+            let out_gain = from.0 + (to.0 - from.0) * t;
+            let in_gain = from.1 + (to.1 - from.1) * t;
+
+            for sample in frame {
+                let old = outgoing.ready.pop_front().unwrap_or(0.0);
+                *sample = *sample * in_gain + old * out_gain;
+            }
+        }
+
+        self.applied = to;
+        Ok(())
+    }
+}
+
+struct Mixer {
+    commands: Receiver<MixerCommand>,
+    producer: Producer<f32>,
+    capacity: usize,
+    state: Arc<EngineState>,
+    sample_rate: u32,
+    channels: usize,
+
+    current: Option<Source>,
+    next: Option<(PcmDecoder, f32)>,
+    fade: Option<Fade>,
+    /// `None` while the curve is flat
+    equalizer: Option<Equalizer>,
+    /// Mixed samples which didn't fit in the output buffer yet
+    backlog: Vec<f32>,
+    /// Waiting on the output to play out the end of the last track
+    draining: bool,
+}
+
+impl Mixer {
+    fn run(&mut self) {
+        while self.poll_commands() {
+            if self.step().is_err() {
+                self.stop();
+                self.state.signal_track_ended();
+            }
+        }
+    }
+
+    /// Blocks while there is nothing to play. Returns false once the engine
+    /// has been dropped
+    fn poll_commands(&mut self) -> bool {
+        let idle = self.current.is_none() && self.backlog.is_empty() && !self.draining;
+        if idle {
+            match self.commands.recv() {
+                Ok(cmd) => self.handle_command(cmd),
+                Err(_) => return false,
+            }
+        }
+
+        loop {
+            match self.commands.try_recv() {
+                Ok(cmd) => self.handle_command(cmd),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn handle_command(&mut self, cmd: MixerCommand) {
+        match cmd {
+            MixerCommand::Play(decoder, gain) => self.play(decoder, gain),
+            MixerCommand::QueueNext(decoder, gain) => self.next = Some((decoder, gain)),
+            MixerCommand::ClearNext => self.next = None,
+            MixerCommand::Crossfade(decoder, gain) => self.crossfade(decoder, gain),
+            MixerCommand::FadeGains(outgoing, incoming) => {
+                if let Some(fade) = &mut self.fade {
+                    fade.gains = (outgoing, incoming);
+                }
+            }
+            MixerCommand::FinishFade => self.fade = None,
+            MixerCommand::Seek(to) => self.seek(to),
+            MixerCommand::SetEqualizer(gains) => self.set_equalizer(gains),
+            MixerCommand::Stop => self.stop(),
+        }
+    }
+
+    fn step(&mut self) -> Result<()> {
+        if self.state.is_paused() {
+            thread::sleep(Duration::from_millis(5));
+            return Ok(());
+        }
+
+        if !self.flush_backlog() {
+            thread::sleep(Duration::from_millis(2));
+            return Ok(());
+        }
+
+        if self.draining {
+            match self.producer.slots() == self.capacity {
+                true => {
+                    self.draining = false;
+                    self.state.set_active(false);
+                }
+                false => thread::sleep(Duration::from_millis(2)),
+            }
+            return Ok(());
+        }
+
+        self.mix_chunk()
+    }
+
+    fn mix_chunk(&mut self) -> Result<()> {
+        let len = CHUNK_FRAMES * self.channels;
+        let Some(current) = self.current.as_mut() else {
+            return Ok(());
+        };
+
+        current.fill(len)?;
+        if current.exhausted && !current.finished {
+            return self.end_of_track();
+        }
+
+        let len = len.min(current.ready.len());
+        if len == 0 {
+            self.current = None;
+            self.draining = true;
+            return Ok(());
+        }
+
+        let start = self.backlog.len();
+        self.backlog.extend(current.ready.drain(..len));
+
+        if let Some(fade) = &mut self.fade {
+            fade.mix(&mut self.backlog[start..])?;
+        }
+
+        if let Some(eq) = &mut self.equalizer {
+            eq.process(&mut self.backlog[start..]);
+        }
+        Ok(())
+    }
+
+    fn end_of_track(&mut self) -> Result<()> {
+        self.state.signal_track_ended();
+        self.state.reset_samples();
+
+        let Some(current) = self.current.as_mut() else {
+            return Ok(());
+        };
+
+        match self.next.take() {
+            Some((decoder, gain)) if decoder.sample_rate == current.decoder.sample_rate => {
+                self.state.set_duration(decoder.duration());
+                current.continue_with(decoder, gain);
+            }
+            Some((decoder, gain)) => {
+                self.state.set_duration(decoder.duration());
+                current.finish()?;
+
+                let mut next = Source::new(decoder, gain, self.sample_rate, self.channels)?;
+                next.ready = mem::take(&mut current.ready);
+                *current = next;
+            }
+            None => current.finish()?,
+        }
+
+        Ok(())
+    }
+
+    /// Returns true once everything has been handed to the output
+    fn flush_backlog(&mut self) -> bool {
+        let len = self.producer.slots().min(self.backlog.len());
+        if len > 0
+            && let Ok(chunk) = self.producer.write_chunk_uninit(len)
+        {
+            chunk.fill_from_iter(self.backlog.drain(..len));
+        }
+
+        self.backlog.is_empty()
+    }
+
+    fn play(&mut self, decoder: PcmDecoder, gain: f32) {
+        self.backlog.clear();
+        self.draining = false;
+        self.next = None;
+        self.fade = None;
+
+        self.current = Source::new(decoder, gain, self.sample_rate, self.channels).ok();
+        if let Some(eq) = &mut self.equalizer {
+            eq.reset();
+        }
+        if self.current.is_none() {
+            self.state.set_active(false);
+        }
+
+        self.wait_for_drain();
+        self.state.finish_seek();
+    }
+
+    /// Start `decoder` on top of the current track, which keeps playing at
+    /// full volume until the first fade gains arrive
+    fn crossfade(&mut self, decoder: PcmDecoder, gain: f32) {
+        let Ok(incoming) = Source::new(decoder, gain, self.sample_rate, self.channels) else {
+            return;
+        };
+
+        self.next = None;
+        self.draining = false;
+        self.fade = self.current.replace(incoming).map(|outgoing| Fade {
+            outgoing,
+            gains: (1.0, 0.0),
+            applied: (1.0, 0.0),
+        });
+        self.state.set_active(true);
+    }
+
+    fn seek(&mut self, to: SeekTo) {
+        let Some(current) = self.current.as_mut() else {
+            self.state.finish_seek();
+            return;
+        };
+
+        let target = match to {
+            SeekTo::Absolute(secs) => secs,
+            SeekTo::Relative(delta) => self.state.position().as_secs_f64() + delta,
+        };
+
+        let duration = current.decoder.duration().unwrap_or(f64::MAX);
+        let landed = match target < duration {
+            true => current.seek(target.max(0.0)).ok(),
+            false => None,
+        };
+
+        self.backlog.clear();
+        self.fade = None;
+        if let Some(eq) = &mut self.equalizer {
+            eq.reset();
+        }
+
+        match landed {
+            Some(secs) => {
+                let samples = secs * self.sample_rate as f64 * self.channels as f64;
+                self.state.set_samples(samples as u64);
+            }
+            // Seeking past the end moves on to the next track
+            None => {
+                current.exhausted = true;
+                current.pending.clear();
+                current.ready.clear();
+            }
+        }
+
+        self.wait_for_drain();
+        self.state.finish_seek();
+    }
+
+    fn set_equalizer(&mut self, gains: EqGains) {
+        self.equalizer = match gains.is_flat() {
+            true => None,
+            false => Some(Equalizer::new(&gains, self.sample_rate, self.channels)),
+        };
+    }
+
+    fn stop(&mut self) {
+        self.current = None;
+        self.next = None;
+        self.fade = None;
+        self.backlog.clear();
+        self.draining = false;
+        self.state.set_active(false);
+        self.state.reset_samples();
+    }
+
+    /// The output discards its buffer while a seek is pending, anything left
+    /// in it belongs to the old position
+    fn wait_for_drain(&self) {
+        let start = Instant::now();
+        while self.producer.slots() < self.capacity && start.elapsed() < DRAIN_TIMEOUT {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+/// Convert interleaved samples between channel counts, duplicating mono
+/// and leaving missing channels silent, applying `gain` along the way
+fn map_channels(
+    samples: &[f32],
+    input: usize,
+    output: usize,
+    gain: f32,
+    out: &mut impl Extend<f32>,
+) {
+    let input = input.max(1);
+
+    out.extend(samples.chunks_exact(input).flat_map(|frame| {
+        (0..output).map(move |ch| {
+            let sample = match (frame.get(ch), input) {
+                (Some(s), _) => *s,
+                (None, 1) => frame[0],
+                (None, _) => 0.0,
+            };
+            sample * gain
+        })
+    }));
+}
@@ -1,12 +1,16 @@
 mod backend;
-mod backend_voxio;
+mod backend_cpal;
 mod core;
+mod equalizer;
 mod handle;
 mod metrics;
+mod mixer;
+mod resampler;
 mod track;
 
 pub use crate::player::track::VoxioTrack;
 use backend::PlayerBackend;
+pub use equalizer::{EQ_BANDS, EQ_FREQUENCIES, EQ_MAX_GAIN, EqGains, Equalizer};
 pub use handle::PlayerHandle;
 pub use metrics::PlaybackMetrics;
 
@@ -27,6 +31,9 @@ pub enum PlayerCommand {
     SeekTo(f32),
     SeekForward(u64),
     SeekBack(u64),
+    SetVolume(u8),
+    SetMuted(bool),
+    SetEqualizer(EqGains),
 }
 
 #[derive(PartialEq, Eq)]
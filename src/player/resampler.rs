@@ -0,0 +1,82 @@
+use anyhow::{Result, anyhow};
+use rubato::{Fft, FixedSync, Resampler as _, audioadapter_buffers::direct::InterleavedSlice};
+
+const CHUNK_FRAMES: usize = 1024;
+const SUB_CHUNKS: usize = 2;
+
+/// Converts interleaved samples to the output sample rate in fixed size chunks
+pub(super) struct Resampler {
+    resampler: Fft<f32>,
+    output: Vec<f32>,
+    channels: usize,
+}
+
+impl Resampler {
+    /// `None` when the rates already match
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Result<Option<Self>> {
+        if input_rate == output_rate {
+            return Ok(None);
+        }
+
+        let resampler = Fft::<f32>::new(
+            input_rate as usize,
+            output_rate as usize,
+            CHUNK_FRAMES,
+            SUB_CHUNKS,
+            channels,
+            FixedSync::Input,
+        )
+        .map_err(|e| anyhow!("Resampler error: {e}"))?;
+
+        Ok(Some(Self {
+            output: vec![0.0; resampler.output_frames_max() * channels],
+            resampler,
+            channels,
+        }))
+    }
+
+    /// Resample every complete chunk of `pending`, leaving the remainder
+    /// behind for the next call
+    pub fn process(
+        &mut self,
+        pending: &mut Vec<f32>,
+        mut output: impl FnMut(&[f32]),
+    ) -> Result<()> {
+        let frames = self.resampler.input_frames_next();
+        let samples = frames * self.channels;
+
+        while pending.len() >= samples {
+            let input = InterleavedSlice::new(&pending[..samples], self.channels, frames)
+                .map_err(|e| anyhow!("Resampler error: {e}"))?;
+
+            let max_frames = self.resampler.output_frames_max();
+            let mut out = InterleavedSlice::new_mut(&mut self.output, self.channels, max_frames)
+                .map_err(|e| anyhow!("Resampler error: {e}"))?;
+
+            let (_, written) = self
+                .resampler
+                .process_into_buffer(&input, &mut out, None)
+                .map_err(|e| anyhow!("Resampler error: {e}"))?;
+
+            pending.drain(..samples);
+            output(&self.output[..written * self.channels]);
+        }
+
+        Ok(())
+    }
+
+    /// Pad out and resample whatever is left of `pending`
+    pub fn flush(&mut self, pending: &mut Vec<f32>, output: impl FnMut(&[f32])) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let samples = self.resampler.input_frames_next() * self.channels;
+        pending.resize(samples, 0.0);
+        self.process(pending, output)
+    }
+
+    pub fn reset(&mut self) {
+        self.resampler.reset();
+    }
+}
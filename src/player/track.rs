@@ -9,6 +9,10 @@ use crate::{
 pub struct VoxioTrack {
     id: u64,
     path: PathBuf,
+    /// Linear ReplayGain factor
+    gain: f32,
+    /// Album id, `None` for songs without an album tag
+    album: Option<i64>,
 }
 
 impl PartialEq for VoxioTrack {
@@ -24,6 +28,8 @@ impl TryFrom<&SimpleSong> for VoxioTrack {
         Ok(Self {
             id: song.id,
             path: PathBuf::from(song.get_path()?),
+            gain: song.gain.factor(),
+            album: album_key(song),
         })
     }
 }
@@ -33,6 +39,8 @@ impl From<&ValidatedSong> for VoxioTrack {
         VoxioTrack {
             id: song.id(),
             path: song.path(),
+            gain: song.meta.gain.factor(),
+            album: album_key(&song.meta),
         }
     }
 }
@@ -40,7 +48,12 @@ impl From<&ValidatedSong> for VoxioTrack {
 impl VoxioTrack {
     pub fn new<P: AsRef<Path>>(id: u64, p: P) -> Self {
         let path = PathBuf::from(p.as_ref());
-        VoxioTrack { id, path }
+        VoxioTrack {
+            id,
+            path,
+            gain: 1.0,
+            album: None,
+        }
     }
 
     pub fn id(&self) -> u64 {
@@ -50,4 +63,16 @@ impl VoxioTrack {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn same_album(&self, other: &VoxioTrack) -> bool {
+        self.album.is_some() && self.album == other.album
+    }
+}
+
+fn album_key(song: &SimpleSong) -> Option<i64> {
+    (!song.album.is_empty()).then_some(song.album_id)
 }
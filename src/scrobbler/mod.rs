@@ -0,0 +1,198 @@
+mod service;
+
+use crate::{Database, config::ScrobbleConfig, library::SimpleSong};
+use anyhow::Result;
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender, unbounded};
+use service::{Outcome, Service};
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Songs shorter than this are never scrobbled
+const MIN_DURATION: Duration = Duration::from_secs(30);
+/// A song is scrobbled once half of it has been played, or this much of it
+const MAX_THRESHOLD: Duration = Duration::from_secs(240);
+/// Larger jumps in the playback position are seeks, and don't count as played
+const MAX_STEP: Duration = Duration::from_secs(2);
+
+const BATCH_SIZE: usize = 50;
+const MIN_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug)]
+pub struct Scrobble {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: String,
+    pub track_no: Option<u32>,
+    pub duration: Duration,
+    /// Unix timestamp of when the song started playing
+    pub timestamp: u64,
+}
+
+impl Scrobble {
+    fn new(song: &SimpleSong) -> Self {
+        Scrobble {
+            title: song.title.clone(),
+            artist: song.artist.to_string(),
+            album: song.album.to_string(),
+            album_artist: song.album_artist.to_string(),
+            track_no: song.track_no,
+            duration: song.duration,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+}
+
+enum ScrobbleMessage {
+    NowPlaying(Scrobble),
+    Scrobble(Scrobble),
+}
+
+/// The song currently playing, and how much of it has actually been heard
+struct Listen {
+    scrobble: Scrobble,
+    played: Duration,
+    position: Duration,
+    submitted: bool,
+}
+
+/// Decides when songs count as played. Scrobbles are queued in the database
+/// and submitted from a background thread, which retries with a growing
+/// delay while the service can't be reached
+pub struct ScrobblerHandle {
+    tx: Sender<ScrobbleMessage>,
+    current: Option<Listen>,
+}
+
+impl ScrobblerHandle {
+    pub fn spawn(config: ScrobbleConfig) -> Result<Self> {
+        let service = Service::new(config)?;
+        let (tx, rx) = unbounded();
+
+        thread::spawn(move || submit_loop(service, rx));
+
+        Ok(Self { tx, current: None })
+    }
+
+    pub fn track_started(&mut self, song: &SimpleSong) {
+        let scrobble = Scrobble::new(song);
+        let _ = self.tx.send(ScrobbleMessage::NowPlaying(scrobble.clone()));
+
+        self.current = Some(Listen {
+            scrobble,
+            played: Duration::ZERO,
+            position: Duration::ZERO,
+            submitted: false,
+        });
+    }
+
+    pub fn stopped(&mut self) {
+        self.current = None;
+    }
+
+    /// Called periodically with the playback position while a song is playing
+    pub fn update(&mut self, elapsed: Duration) {
+        let Some(listen) = self.current.as_mut() else {
+            return;
+        };
+
+        if let Some(step) = elapsed.checked_sub(listen.position)
+            && step <= MAX_STEP
+        {
+            listen.played += step;
+        }
+        listen.position = elapsed;
+
+        let duration = listen.scrobble.duration;
+        if !listen.submitted
+            && duration >= MIN_DURATION
+            && listen.played >= (duration / 2).min(MAX_THRESHOLD)
+        {
+            listen.submitted = true;
+            let _ = self
+                .tx
+                .send(ScrobbleMessage::Scrobble(listen.scrobble.clone()));
+        }
+    }
+}
+
+fn submit_loop(service: Service, rx: Receiver<ScrobbleMessage>) {
+    let Ok(mut db) = Database::open() else {
+        return;
+    };
+
+    // Anything left over from a previous session goes out first
+    let mut retry_at = Some(Instant::now());
+    let mut backoff = None;
+
+    loop {
+        let timeout = retry_at.map_or(Duration::MAX, |at| {
+            at.saturating_duration_since(Instant::now())
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(ScrobbleMessage::NowPlaying(scrobble)) => {
+                let _ = service.now_playing(&scrobble);
+            }
+            Ok(ScrobbleMessage::Scrobble(scrobble)) => {
+                if db.queue_scrobble(&scrobble).is_ok() && backoff.is_none() {
+                    retry_at = Some(Instant::now());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if retry_at.is_some_and(|at| at <= Instant::now()) {
+            retry_at = None;
+
+            match flush(&service, &mut db) {
+                Ok(()) => backoff = None,
+                Err(_) => {
+                    let delay = backoff.map_or(MIN_BACKOFF, |b: Duration| (b * 2).min(MAX_BACKOFF));
+                    backoff = Some(delay);
+                    retry_at = Some(Instant::now() + delay);
+                }
+            }
+        }
+    }
+}
+
+/// Submit queued scrobbles, oldest first, until none are left. Scrobbles the
+/// service refuses are dropped, as they would never be accepted
+fn flush(service: &Service, db: &mut Database) -> Result<()> {
+    loop {
+        let pending = db.get_pending_scrobbles(BATCH_SIZE)?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let (ids, scrobbles): (Vec<i64>, Vec<Scrobble>) = pending.into_iter().unzip();
+
+        match service.submit(&scrobbles) {
+            Outcome::Accepted => db.delete_scrobbles(&ids)?,
+            // A single bad scrobble fails the whole batch, so each is sent on
+            // its own to find the ones refused
+            Outcome::Rejected if ids.len() > 1 => {
+                for (id, scrobble) in ids.iter().zip(&scrobbles) {
+                    submit_one(service, db, *id, scrobble)?;
+                }
+            }
+            Outcome::Rejected => db.delete_scrobbles(&ids)?,
+            Outcome::Retry => anyhow::bail!("Scrobbling service unavailable"),
+        }
+    }
+}
+
+fn submit_one(service: &Service, db: &mut Database, id: i64, scrobble: &Scrobble) -> Result<()> {
+    match service.submit(std::slice::from_ref(scrobble)) {
+        Outcome::Accepted | Outcome::Rejected => db.delete_scrobbles(&[id]),
+        Outcome::Retry => anyhow::bail!("Scrobbling service unavailable"),
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    config::{ScrobbleConfig, ScrobbleService},
+    scrobbler::Scrobble,
+};
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+use std::{slice, time::Duration};
+use ureq::{Agent, Body, http::Response};
+
+const LISTENBRAINZ_URL: &str = "https://api.listenbrainz.org";
+const LASTFM_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+pub(super) enum Outcome {
+    Accepted,
+    /// The request itself was invalid, retrying it won't help
+    Rejected,
+    Retry,
+}
+
+enum Api {
+    ListenBrainz {
+        url: String,
+        token: String,
+    },
+    LastFm {
+        url: String,
+        session_key: String,
+        api_key: String,
+        api_secret: String,
+    },
+}
+
+pub(super) struct Service {
+    agent: Agent,
+    api: Api,
+}
+
+impl Service {
+    pub(super) fn new(config: ScrobbleConfig) -> Result<Self> {
+        let ScrobbleConfig {
+            service,
+            url,
+            token,
+            api_key,
+            api_secret,
+        } = config;
+
+        let api = match service {
+            ScrobbleService::ListenBrainz => Api::ListenBrainz {
+                url: url.unwrap_or_else(|| LISTENBRAINZ_URL.to_string()),
+                token,
+            },
+            ScrobbleService::LastFm => {
+                let (Some(api_key), Some(api_secret)) = (api_key, api_secret) else {
+                    bail!("Last.fm scrobbling requires an `api_key` and `api_secret`");
+                };
+
+                Api::LastFm {
+                    url: url.unwrap_or_else(|| LASTFM_URL.to_string()),
+                    session_key: token,
+                    api_key,
+                    api_secret,
+                }
+            }
+        };
+
+        let agent = Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .http_status_as_error(false)
+            .build()
+            .into();
+
+        Ok(Self { agent, api })
+    }
+
+    pub(super) fn now_playing(&self, scrobble: &Scrobble) -> Outcome {
+        match &self.api {
+            Api::ListenBrainz { .. } => self.listenbrainz("playing_now", slice::from_ref(scrobble)),
+            Api::LastFm { .. } => self.lastfm("track.updateNowPlaying", slice::from_ref(scrobble)),
+        }
+    }
+
+    pub(super) fn submit(&self, scrobbles: &[Scrobble]) -> Outcome {
+        match (&self.api, scrobbles.len()) {
+            (Api::ListenBrainz { .. }, 1) => self.listenbrainz("single", scrobbles),
+            (Api::ListenBrainz { .. }, _) => self.listenbrainz("import", scrobbles),
+            (Api::LastFm { .. }, _) => self.lastfm("track.scrobble", scrobbles),
+        }
+    }
+
+    fn listenbrainz(&self, listen_type: &str, scrobbles: &[Scrobble]) -> Outcome {
+        let Api::ListenBrainz { url, token } = &self.api else {
+            return Outcome::Rejected;
+        };
+
+        let payload = scrobbles
+            .iter()
+            .map(|s| {
+                let mut info = json!({
+                    "duration_ms": s.duration.as_millis() as u64,
+                    "media_player": "NoctaVox",
+                    "submission_client": "NoctaVox",
+                    "submission_client_version": env!("CARGO_PKG_VERSION"),
+                });
+                if let Some(track_no) = s.track_no {
+                    info["tracknumber"] = json!(track_no);
+                }
+
+                let mut listen = json!({
+                    "track_metadata": {
+                        "artist_name": s.artist,
+                        "track_name": s.title,
+                        "additional_info": info,
+                    }
+                });
+                if !s.album.is_empty() {
+                    listen["track_metadata"]["release_name"] = json!(s.album);
+                }
+                if listen_type != "playing_now" {
+                    listen["listened_at"] = json!(s.timestamp);
+                }
+
+                listen
+            })
+            .collect::<Vec<Value>>();
+
+        let body = json!({ "listen_type": listen_type, "payload": payload });
+
+        let response = self
+            .agent
+            .post(format!("{}/1/submit-listens", url.trim_end_matches('/')))
+            .header("Authorization", format!("Token {token}"))
+            .header("Content-Type", "application/json")
+            .send(body.to_string());
+
+        outcome(response)
+    }
+
+    fn lastfm(&self, method: &str, scrobbles: &[Scrobble]) -> Outcome {
+        let Api::LastFm {
+            url,
+            session_key,
+            api_key,
+            api_secret,
+        } = &self.api
+        else {
+            return Outcome::Rejected;
+        };
+
+        let batch = method == "track.scrobble";
+        let mut params = vec![
+            ("method".to_string(), method.to_string()),
+            ("api_key".to_string(), api_key.clone()),
+            ("sk".to_string(), session_key.clone()),
+        ];
+
+        for (i, s) in scrobbles.iter().enumerate() {
+            let mut push = |name: &str, value: String| {
+                let key = match batch {
+                    true => format!("{name}[{i}]"),
+                    false => name.to_string(),
+                };
+                params.push((key, value));
+            };
+
+            push("artist", s.artist.clone());
+            push("track", s.title.clone());
+            push("duration", s.duration.as_secs().to_string());
+            if !s.album.is_empty() {
+                push("album", s.album.clone());
+            }
+            if !s.album_artist.is_empty() {
+                push("albumArtist", s.album_artist.clone());
+            }
+            if let Some(track_no) = s.track_no {
+                push("trackNumber", track_no.to_string());
+            }
+            if batch {
+                push("timestamp", s.timestamp.to_string());
+            }
+        }
+
+        // Signed with every parameter, sorted by name, followed by the secret
+        params.sort();
+        let mut signature = params
+            .iter()
+            .map(|(k, v)| format!("{k}{v}"))
+            .collect::<String>();
+        signature.push_str(api_secret);
+
+        params.push((
+            "api_sig".to_string(),
+            format!("{:x}", md5::compute(signature)),
+        ));
+        params.push(("format".to_string(), "json".to_string()));
+
+        let response = self
+            .agent
+            .post(url)
+            .send_form(params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+        outcome(response)
+    }
+}
+
+fn outcome(response: Result<Response<Body>, ureq::Error>) -> Outcome {
+    match response {
+        Ok(r) if r.status().is_success() => Outcome::Accepted,
+        Ok(r) if r.status() == 400 => Outcome::Rejected,
+        _ => Outcome::Retry,
+    }
+}
@@ -0,0 +1,515 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlaylistFormat {
+    M3u,
+    Pls,
+    Xspf,
+    Csv,
+    Json,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match ext.as_str() {
+            "m3u" | "m3u8" => Ok(Self::M3u),
+            "pls" => Ok(Self::Pls),
+            "xspf" => Ok(Self::Xspf),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => bail!(
+                "Unsupported playlist format `{ext}`\nSupported formats: m3u, m3u8, pls, xspf, csv, json"
+            ),
+        }
+    }
+}
+
+/// A single track as described by a playlist file. Any field may be missing
+/// depending on the format and the program that wrote it.
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaylistEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
+}
+
+impl PlaylistEntry {
+    /// Human readable description used in the import report
+    pub fn describe(&self) -> String {
+        match (&self.artist, &self.title, &self.path) {
+            (Some(artist), Some(title), _) => format!("{artist} - {title}"),
+            (None, Some(title), _) => title.clone(),
+            (_, None, Some(path)) => path.clone(),
+            _ => "<empty entry>".to_string(),
+        }
+    }
+
+    /// Fills artist/title from the `Artist - Title` convention used by m3u and pls
+    fn set_display_title(&mut self, display: &str) {
+        let display = display.trim();
+        if display.is_empty() {
+            return;
+        }
+
+        match display.split_once(" - ") {
+            Some((artist, title)) => {
+                self.artist = Some(artist.trim().to_string());
+                self.title = Some(title.trim().to_string());
+            }
+            None => self.title = Some(display.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonPlaylist {
+    #[serde(default)]
+    name: Option<String>,
+    tracks: Vec<PlaylistEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonInput {
+    Playlist(JsonPlaylist),
+    Tracks(Vec<PlaylistEntry>),
+}
+
+/// Returns the playlist name (if the format stores one) and its entries
+pub fn parse(
+    format: PlaylistFormat,
+    contents: &str,
+) -> Result<(Option<String>, Vec<PlaylistEntry>)> {
+    let contents = contents.trim_start_matches('\u{feff}');
+
+    match format {
+        PlaylistFormat::M3u => Ok(parse_m3u(contents)),
+        PlaylistFormat::Pls => Ok((None, parse_pls(contents))),
+        PlaylistFormat::Xspf => Ok(parse_xspf(contents)),
+        PlaylistFormat::Csv => Ok((None, parse_csv(contents)?)),
+        PlaylistFormat::Json => match serde_json::from_str::<JsonInput>(contents)? {
+            JsonInput::Playlist(p) => Ok((p.name, p.tracks)),
+            JsonInput::Tracks(tracks) => Ok((None, tracks)),
+        },
+    }
+}
+
+pub fn write(format: PlaylistFormat, name: &str, entries: Vec<PlaylistEntry>) -> Result<String> {
+    let output = match format {
+        PlaylistFormat::M3u => write_m3u(&entries),
+        PlaylistFormat::Pls => write_pls(&entries),
+        PlaylistFormat::Xspf => write_xspf(name, &entries),
+        PlaylistFormat::Csv => write_csv(&entries),
+        PlaylistFormat::Json => serde_json::to_string_pretty(&JsonPlaylist {
+            name: Some(name.to_string()),
+            tracks: entries,
+        })?,
+    };
+
+    Ok(output)
+}
+
+// =========
+//    M3U
+// =========
+
+fn parse_m3u(contents: &str) -> (Option<String>, Vec<PlaylistEntry>) {
+    let mut name = None;
+    let mut entries = vec![];
+    let mut pending = PlaylistEntry::default();
+
+    for line in contents.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<duration> [attributes],<display title>
+            let (meta, display) = info.split_once(',').unwrap_or((info, ""));
+            pending.duration = meta
+                .split_whitespace()
+                .next()
+                .and_then(|d| d.parse::<f32>().ok())
+                .filter(|d| *d > 0.0);
+            pending.set_display_title(display);
+        } else if let Some(album) = line.strip_prefix("#EXTALB:") {
+            pending.album = Some(album.trim().to_string());
+        } else if let Some(title) = line.strip_prefix("#PLAYLIST:") {
+            name = Some(title.trim().to_string());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            pending.path = Some(line.to_string());
+            entries.push(std::mem::take(&mut pending));
+        }
+    }
+
+    (name, entries)
+}
+
+fn write_m3u(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+
+    for entry in entries {
+        let duration = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+        let artist = entry.artist.as_deref().unwrap_or_default();
+        let title = entry.title.as_deref().unwrap_or_default();
+
+        out.push_str(&format!("#EXTINF:{duration},{artist} - {title}\n"));
+        if let Some(album) = &entry.album {
+            out.push_str(&format!("#EXTALB:{album}\n"));
+        }
+        out.push_str(entry.path.as_deref().unwrap_or_default());
+        out.push('\n');
+    }
+
+    out
+}
+
+// =========
+//    PLS
+// =========
+
+fn parse_pls(contents: &str) -> Vec<PlaylistEntry> {
+    let mut entries: Vec<(usize, PlaylistEntry)> = vec![];
+
+    for line in contents.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim().to_lowercase();
+        let (field, idx) = match ["file", "title", "length"]
+            .iter()
+            .find_map(|f| key.strip_prefix(f).map(|n| (*f, n)))
+        {
+            Some((field, n)) => match n.parse::<usize>() {
+                Ok(idx) => (field, idx),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+
+        let entry = match entries.iter().position(|(i, _)| *i == idx) {
+            Some(pos) => &mut entries[pos].1,
+            None => {
+                entries.push((idx, PlaylistEntry::default()));
+                &mut entries.last_mut().expect("just pushed").1
+            }
+        };
+
+        match field {
+            "file" => entry.path = Some(value.trim().to_string()),
+            "title" => entry.set_display_title(value),
+            _ => entry.duration = value.trim().parse::<f32>().ok().filter(|d| *d > 0.0),
+        }
+    }
+
+    entries.sort_by_key(|(idx, _)| *idx);
+    entries
+        .into_iter()
+        .map(|(_, e)| e)
+        .filter(|e| e.path.is_some())
+        .collect()
+}
+
+fn write_pls(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let n = i + 1;
+        let artist = entry.artist.as_deref().unwrap_or_default();
+        let title = entry.title.as_deref().unwrap_or_default();
+        let length = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+
+        out.push_str(&format!(
+            "File{n}={}\nTitle{n}={artist} - {title}\nLength{n}={length}\n",
+            entry.path.as_deref().unwrap_or_default()
+        ));
+    }
+
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+// ==========
+//    XSPF
+// ==========
+
+fn parse_xspf(contents: &str) -> (Option<String>, Vec<PlaylistEntry>) {
+    let (head, tracklist) = contents.split_once("<trackList>").unwrap_or((contents, ""));
+
+    let name = xml_tag(head, "title");
+
+    let entries = tracklist
+        .split("<track>")
+        .skip(1)
+        .map(|track| {
+            let track = track.split("</track>").next().unwrap_or(track);
+            PlaylistEntry {
+                path: xml_tag(track, "location"),
+                title: xml_tag(track, "title"),
+                artist: xml_tag(track, "creator"),
+                album: xml_tag(track, "album"),
+                duration: xml_tag(track, "duration")
+                    .and_then(|ms| ms.parse::<f32>().ok())
+                    .map(|ms| ms / 1000.0),
+            }
+        })
+        .collect();
+
+    (name, entries)
+}
+
+fn write_xspf(name: &str, entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    out.push_str(&format!(
+        "  <title>{}</title>\n  <trackList>\n",
+        xml_escape(name)
+    ));
+
+    for entry in entries {
+        out.push_str("    <track>\n");
+        if let Some(path) = &entry.path {
+            out.push_str(&format!(
+                "      <location>{}</location>\n",
+                xml_escape(&path_to_uri(path))
+            ));
+        }
+        for (tag, value) in [
+            ("title", &entry.title),
+            ("creator", &entry.artist),
+            ("album", &entry.album),
+        ] {
+            if let Some(value) = value {
+                out.push_str(&format!("      <{tag}>{}</{tag}>\n", xml_escape(value)));
+            }
+        }
+        if let Some(duration) = entry.duration {
+            out.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                (duration * 1000.0).round() as u64
+            ));
+        }
+        out.push_str("    </track>\n");
+    }
+
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// Returns the unescaped text of the first `<tag>` element in `s`
+fn xml_tag(s: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+
+    let start = s.find(&open)? + open.len();
+    let len = s[start..].find(&close)?;
+    let text = xml_unescape(s[start..start + len].trim());
+
+    (!text.is_empty()).then_some(text)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn xml_unescape(s: &str) -> String {
+    if let Some(cdata) = s
+        .strip_prefix("<![CDATA[")
+        .and_then(|s| s.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// =========
+//    CSV
+// =========
+
+const CSV_HEADER: [&str; 5] = ["title", "artist", "album", "duration", "path"];
+
+fn parse_csv(contents: &str) -> Result<Vec<PlaylistEntry>> {
+    let mut rows = contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(csv_split);
+
+    let Some(header) = rows.next() else {
+        return Ok(vec![]);
+    };
+
+    let header = header
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let path_col = column(&["path", "location", "file", "filename"]);
+    let title_col = column(&["title", "track", "track name", "name"]);
+    let artist_col = column(&["artist", "artist name", "artist name(s)", "creator"]);
+    let album_col = column(&["album", "album name"]);
+    let duration_col = column(&["duration", "length"]);
+
+    if path_col.is_none() && title_col.is_none() {
+        bail!("CSV header must contain a `path` or `title` column");
+    }
+
+    let entries = rows
+        .map(|row| {
+            let get = |col: Option<usize>| {
+                col.and_then(|c| row.get(c))
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+
+            PlaylistEntry {
+                path: get(path_col),
+                title: get(title_col),
+                artist: get(artist_col),
+                album: get(album_col),
+                duration: get(duration_col).and_then(|d| d.parse().ok()),
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+fn write_csv(entries: &[PlaylistEntry]) -> String {
+    let mut out = CSV_HEADER.join(",");
+    out.push('\n');
+
+    for entry in entries {
+        let duration = entry.duration.map(|d| format!("{d:.2}"));
+        let row = [
+            entry.title.as_deref(),
+            entry.artist.as_deref(),
+            entry.album.as_deref(),
+            duration.as_deref(),
+            entry.path.as_deref(),
+        ]
+        .map(|field| csv_escape(field.unwrap_or_default()));
+
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn csv_split(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    fields.push(field);
+    fields
+}
+
+fn csv_escape(s: &str) -> String {
+    match s.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+// ===========
+//    PATHS
+// ===========
+
+/// Converts `file://` URIs into plain paths, leaving anything else untouched
+pub fn uri_to_path(location: &str) -> String {
+    match location.strip_prefix("file://") {
+        Some(rest) => {
+            // file://localhost/path and file:///C:/path
+            let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+            let decoded = percent_decode(rest);
+            match decoded.as_bytes() {
+                [b'/', _, b':', ..] => decoded[1..].to_string(),
+                _ => decoded,
+            }
+        }
+        None => location.to_string(),
+    }
+}
+
+fn path_to_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(b as char)
+            }
+            _ => uri.push_str(&format!("%{b:02X}")),
+        }
+    }
+
+    uri
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
@@ -0,0 +1,320 @@
+//! Native playlist import/export for m3u/m3u8, pls, xspf, csv and json files
+
+mod formats;
+
+pub use formats::{PlaylistEntry, PlaylistFormat};
+
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    Database, SimpleSong, SongMap, expand_tilde,
+    library::{SmartRules, SongInfo},
+    normalize_metadata_str, strip_diacritics,
+    transpose::formats::uri_to_path,
+};
+
+pub struct ImportReport {
+    pub name: String,
+    pub total: usize,
+    pub unmatched: Vec<String>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let matched = self.total - self.unmatched.len();
+        write!(
+            f,
+            "Imported {matched}/{} songs into \"{}\"",
+            self.total, self.name
+        )?;
+
+        if !self.unmatched.is_empty() {
+            write!(f, "\n\nUnmatched entries:")?;
+            for entry in &self.unmatched {
+                write!(f, "\n  {entry}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Import a playlist file into the database. Paths are resolved relative to
+/// the playlist file, and entries that cannot be found on disk are matched
+/// against the library by their tags.
+pub fn import_playlist(file: &Path, name: Option<String>) -> Result<ImportReport> {
+    let format = PlaylistFormat::from_path(file)?;
+    let file = expand_tilde(file)?.canonicalize()?;
+    let bytes = fs::read(&file)?;
+    let (file_name, entries) = formats::parse(format, &String::from_utf8_lossy(&bytes))?;
+
+    let name = name
+        .or(file_name)
+        .or_else(|| file.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .ok_or_else(|| anyhow!("Could not determine a name for the playlist"))?;
+
+    let mut db = Database::open()?;
+    if find_playlist(&mut db, &name)?.is_some() {
+        bail!(
+            "A playlist named \"{name}\" already exists\nUse --name to import under a different name"
+        );
+    }
+
+    let matcher = SongMatcher::new(&mut db)?;
+    let base_dir = file.parent().unwrap_or(Path::new("."));
+
+    let mut song_ids = vec![];
+    let mut unmatched = vec![];
+
+    for entry in &entries {
+        match matcher.resolve(entry, base_dir) {
+            Some(id) => song_ids.push(id),
+            None => unmatched.push(entry.describe()),
+        }
+    }
+
+    if song_ids.is_empty() {
+        bail!(
+            "None of the {} entries in {} matched a song in the library",
+            entries.len(),
+            file.display()
+        );
+    }
+
+    db.create_playlist(&name)?;
+    let (playlist_id, _) =
+        find_playlist(&mut db, &name)?.ok_or_else(|| anyhow!("Failed to create playlist"))?;
+    db.add_to_playlist_multi(song_ids, playlist_id)?;
+
+    Ok(ImportReport {
+        name,
+        total: entries.len(),
+        unmatched,
+    })
+}
+
+/// Export a playlist from the database, the format is chosen based on the
+/// extension of `output` (defaults to `./<name>.m3u8`)
+pub fn export_playlist(name: &str, output: Option<PathBuf>) -> Result<PathBuf> {
+    let output = match output {
+        Some(path) => expand_tilde(path)?,
+        None => PathBuf::from(format!("{}.m3u8", sanitize_file_name(name))),
+    };
+    let format = PlaylistFormat::from_path(&output)?;
+
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+
+    let mut db = Database::open()?;
+    let (_, tracklist) =
+        find_playlist(&mut db, name)?.ok_or_else(|| anyhow!("No playlist named \"{name}\""))?;
+
+    let songs = db.get_all_songs()?;
+    let paths = db.get_song_paths()?;
+
+    let entries = tracklist
+        .iter()
+        .filter_map(|id| songs.get(id).map(|song| (id, song)))
+        .map(|(id, song)| PlaylistEntry {
+            path: paths.get(id).cloned(),
+            title: Some(song.get_title().to_string()),
+            artist: Some(song.get_artist().to_string()),
+            album: Some(song.get_album().to_string()),
+            duration: Some(song.get_duration_f32()),
+        })
+        .collect();
+
+    fs::write(&output, formats::write(format, name, entries)?)?;
+    Ok(output)
+}
+
+/// Playlist names and their track counts, most recently updated first
+pub fn list_playlists() -> Result<Vec<(String, usize)>> {
+    let mut db = Database::open()?;
+    let playlists = db.build_playlists()?;
+
+    playlists
+        .into_iter()
+        .map(|((_, name, rules), songs)| {
+            let count = playlist_songs(&mut db, rules.as_deref(), &songs)?.len();
+            Ok((name, count))
+        })
+        .collect()
+}
+
+/// Exact name match first, then case-insensitive
+fn find_playlist(db: &mut Database, name: &str) -> Result<Option<(i64, Vec<u64>)>> {
+    let playlists = db.build_playlists()?;
+
+    let found = playlists
+        .iter()
+        .find(|((_, n, _), _)| n == name)
+        .or_else(|| {
+            playlists
+                .iter()
+                .find(|((_, n, _), _)| n.to_lowercase() == name.to_lowercase())
+        });
+
+    match found {
+        Some(((id, _, rules), songs)) => {
+            Ok(Some((*id, playlist_songs(db, rules.as_deref(), songs)?)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Song ids of a playlist, smart playlists are evaluated against the library
+fn playlist_songs(
+    db: &mut Database,
+    rules: Option<&str>,
+    songs: &[(i64, u64)],
+) -> Result<Vec<u64>> {
+    let Some(rules) = rules else {
+        return Ok(songs.iter().map(|(_, song_id)| *song_id).collect());
+    };
+
+    let rules = SmartRules::parse(rules)?;
+    let library = db.get_all_songs()?;
+    let stats = db.get_song_stats()?;
+
+    Ok(rules
+        .evaluate(&library, &stats)
+        .iter()
+        .map(|song| song.get_id())
+        .collect())
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+fn normalize_tag(s: &str) -> String {
+    strip_diacritics(&normalize_metadata_str(s))
+}
+
+/// Artists match when every word of one appears in the other, so that
+/// `Beatles` finds `The Beatles`. Names without any words never match
+fn same_artist(a: &str, b: &str) -> bool {
+    fn words(s: &str) -> HashSet<&str> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect()
+    }
+
+    let (a, b) = (words(a), words(b));
+    !a.is_empty() && !b.is_empty() && (a.is_subset(&b) || b.is_subset(&a))
+}
+
+struct SongMatcher {
+    songs: SongMap,
+    by_path: HashMap<String, u64>,
+    by_title: HashMap<String, Vec<u64>>,
+}
+
+impl SongMatcher {
+    fn new(db: &mut Database) -> Result<Self> {
+        let songs = db.get_all_songs()?;
+        let by_path = db
+            .get_song_paths()?
+            .into_iter()
+            .map(|(id, path)| (path, id))
+            .collect();
+
+        let mut by_title: HashMap<String, Vec<u64>> = HashMap::new();
+        for song in songs.values() {
+            by_title
+                .entry(normalize_tag(song.get_title()))
+                .or_default()
+                .push(song.get_id());
+        }
+
+        Ok(Self {
+            songs,
+            by_path,
+            by_title,
+        })
+    }
+
+    fn resolve(&self, entry: &PlaylistEntry, base_dir: &Path) -> Option<u64> {
+        entry
+            .path
+            .as_deref()
+            .and_then(|p| self.match_path(p, base_dir))
+            .or_else(|| self.match_tags(entry))
+    }
+
+    fn match_path(&self, location: &str, base_dir: &Path) -> Option<u64> {
+        let location = uri_to_path(location);
+
+        // Playlists written on Windows use backslash separators
+        [location.clone(), location.replace('\\', "/")]
+            .into_iter()
+            .filter_map(|loc| expand_tilde(&loc).ok())
+            .map(|path| match path.is_relative() {
+                true => base_dir.join(path),
+                false => path,
+            })
+            .find_map(|path| {
+                let canon = path.canonicalize().ok();
+                [Some(path), canon]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|p| self.by_path.get(p.to_string_lossy().as_ref()).copied())
+            })
+    }
+
+    fn match_tags(&self, entry: &PlaylistEntry) -> Option<u64> {
+        let title = normalize_tag(entry.title.as_deref()?);
+        let mut candidates = self
+            .by_title
+            .get(&title)?
+            .iter()
+            .filter_map(|id| self.songs.get(id))
+            .collect::<Vec<&Arc<SimpleSong>>>();
+
+        let artist = entry.artist.as_deref().map(normalize_tag);
+        if let Some(artist) = artist.filter(|a| !a.is_empty()) {
+            candidates.retain(|song| {
+                [song.get_artist(), song.album_artist.as_str()]
+                    .iter()
+                    .any(|a| same_artist(&normalize_tag(a), &artist))
+            });
+        }
+
+        // Album only narrows the search, a compilation may hold the same track
+        if let Some(album) = entry.album.as_deref().map(normalize_tag) {
+            let on_album = candidates
+                .iter()
+                .filter(|song| normalize_tag(song.get_album()) == album)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if !on_album.is_empty() {
+                candidates = on_album;
+            }
+        }
+
+        let closest = match entry.duration {
+            Some(duration) => candidates.into_iter().min_by(|a, b| {
+                let diff_a = (a.get_duration_f32() - duration).abs();
+                let diff_b = (b.get_duration_f32() - duration).abs();
+                diff_a.total_cmp(&diff_b)
+            }),
+            None => candidates.into_iter().next(),
+        };
+
+        closest.map(|song| song.get_id())
+    }
+}
@@ -0,0 +1,220 @@
+//! Terminal graphics protocols. Cover art placed while rendering a frame is
+//! written straight to the terminal once ratatui has drawn the frame, the
+//! cells beneath it are skipped by ratatui so the image isn't drawn over.
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops::FilterType};
+use ratatui::{
+    DefaultTerminal,
+    crossterm::{cursor::MoveTo, queue},
+    layout::Rect,
+};
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    io::{Cursor, Write},
+};
+
+use crate::ui_state::{ArtProtocol, UiState};
+
+const KITTY_IMAGE_ID: u32 = 7707;
+const KITTY_CHUNK: usize = 4096;
+
+/// Levels per channel of the fixed sixel palette (6 * 7 * 6 = 252 colors)
+const SIXEL_LEVELS: [u32; 3] = [6, 7, 6];
+
+pub fn draw_graphics(t: &mut DefaultTerminal, state: &mut UiState) -> Result<()> {
+    let art = &mut state.cover_art;
+    let Some(protocol) = art.protocol() else {
+        return Ok(());
+    };
+
+    let placement = art.placement.take();
+
+    // Resizing clears the screen
+    let size = t.size()?;
+    if art.terminal_size.replace(size) != Some(size) {
+        art.refresh_cell_size();
+        if art.shown.take().is_some() && protocol == ArtProtocol::Kitty {
+            write!(t.backend_mut(), "{}", kitty_delete())?;
+        }
+    }
+
+    if placement == art.shown {
+        return Ok(());
+    }
+
+    if let Some(shown) = art.shown.take() {
+        match protocol {
+            ArtProtocol::Kitty => write!(t.backend_mut(), "{}", kitty_delete())?,
+            // Sixel and iTerm images only disappear once the cells beneath are
+            // redrawn, a new image covering the same cells paints over it
+            _ if placement.is_none_or(|p| p.area != shown.area) => {
+                t.clear()?;
+                return Ok(());
+            }
+            _ => (),
+        }
+    }
+
+    let Some(placement) = placement else {
+        t.backend_mut().flush()?;
+        return Ok(());
+    };
+
+    let Some(image) = art.get_image(placement.key) else {
+        return Ok(());
+    };
+
+    let area = placement.area;
+    let (cell_w, cell_h) = art.cell_size;
+    let (width, height) = (
+        area.width as u32 * cell_w as u32,
+        area.height as u32 * cell_h as u32,
+    );
+
+    let payload = match protocol {
+        ArtProtocol::Kitty => kitty(image, area, width, height),
+        ArtProtocol::Iterm => iterm(image, area, width, height)?,
+        ArtProtocol::Sixel => {
+            sixel(&image.resize_exact(width, (height - height % 6).max(6), FilterType::Triangle))
+        }
+        ArtProtocol::HalfBlocks => return Ok(()),
+    };
+
+    let out = t.backend_mut();
+    queue!(out, MoveTo(area.x, area.y))?;
+    out.write_all(payload.as_bytes())?;
+    out.flush()?;
+
+    art.shown = Some(placement);
+
+    Ok(())
+}
+
+fn kitty_delete() -> String {
+    format!("\x1b_Ga=d,d=I,i={KITTY_IMAGE_ID},q=2\x1b\\")
+}
+
+/// Raw RGB data, scaled by the terminal to fill the cells
+fn kitty(image: &DynamicImage, area: Rect, width: u32, height: u32) -> String {
+    let rgb = image
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_rgb8();
+    let data = STANDARD.encode(rgb.as_raw());
+
+    let mut out = String::with_capacity(data.len() + data.len() / KITTY_CHUNK * 16);
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK);
+    let last = chunks.len().saturating_sub(1);
+
+    for (idx, chunk) in chunks.enumerate() {
+        let more = (idx != last) as u8;
+        let chunk = std::str::from_utf8(chunk).expect("base64 is ascii");
+
+        match idx {
+            0 => write!(
+                out,
+                "\x1b_Ga=T,f=24,s={width},v={height},c={},r={},i={KITTY_IMAGE_ID},C=1,q=2,m={more};{chunk}\x1b\\",
+                area.width, area.height
+            ),
+            _ => write!(out, "\x1b_Gm={more};{chunk}\x1b\\"),
+        }
+        .expect("writing to a String");
+    }
+
+    out
+}
+
+fn iterm(image: &DynamicImage, area: Rect, width: u32, height: u32) -> Result<String> {
+    let mut png = Cursor::new(vec![]);
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .write_to(&mut png, ImageFormat::Png)?;
+    let png = png.into_inner();
+
+    Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        png.len(),
+        area.width,
+        area.height,
+        STANDARD.encode(&png)
+    ))
+}
+
+fn sixel(image: &DynamicImage) -> String {
+    let rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    let indices = quantize(&rgb);
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+
+    let [r_levels, g_levels, b_levels] = SIXEL_LEVELS;
+    let used = indices.iter().copied().collect::<BTreeSet<_>>();
+    for idx in &used {
+        let (r, g, b) = (
+            idx / (g_levels * b_levels),
+            idx / b_levels % g_levels,
+            idx % b_levels,
+        );
+        let _ = write!(
+            out,
+            "#{idx};2;{};{};{}",
+            r * 100 / (r_levels - 1),
+            g * 100 / (g_levels - 1),
+            b * 100 / (b_levels - 1)
+        );
+    }
+
+    let mut row = vec![0u8; width as usize];
+    for band in (0..height).step_by(6) {
+        let band_rows = (height - band).min(6);
+        let band_colors = (band..band + band_rows)
+            .flat_map(|y| &indices[(y * width) as usize..((y + 1) * width) as usize])
+            .copied()
+            .collect::<BTreeSet<_>>();
+
+        for color in band_colors {
+            for (x, bits) in row.iter_mut().enumerate() {
+                *bits = (0..band_rows)
+                    .filter(|dy| indices[((band + dy) * width) as usize + x] == color)
+                    .fold(0, |acc, dy| acc | 1 << dy);
+            }
+
+            let _ = write!(out, "#{color}");
+            push_sixel_row(&mut out, &row);
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Run length encode a row of sixels
+fn push_sixel_row(out: &mut String, row: &[u8]) {
+    for run in row.chunk_by(|a, b| a == b) {
+        let ch = (63 + run[0]) as char;
+        match run.len() {
+            1..=3 => run.iter().for_each(|_| out.push(ch)),
+            n => {
+                let _ = write!(out, "!{n}{ch}");
+            }
+        }
+    }
+}
+
+fn quantize(rgb: &RgbImage) -> Vec<u32> {
+    let level = |v: u8, levels: u32| (v as u32 * (levels - 1) + 127) / 255;
+    let [_, g_levels, b_levels] = SIXEL_LEVELS;
+
+    rgb.pixels()
+        .map(|p| {
+            let [r, g, b] = p.0;
+            level(r, SIXEL_LEVELS[0]) * g_levels * b_levels
+                + level(g, g_levels) * b_levels
+                + level(b, b_levels)
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use crate::ui_state::{LibraryView, Mode, Pane, ProgressDisplay, UiState};
+use crate::ui_state::{Mode, Pane, ProgressDisplay, UiState};
 use ratatui::layout::{Constraint, Layout, Rect};
 
 pub struct LayoutMinimal {
@@ -37,10 +37,7 @@ impl LayoutMinimal {
         };
 
         let item_count = match state.get_pane() {
-            Pane::SideBar => match state.get_sidebar_view() {
-                LibraryView::Albums => state.albums.len(),
-                LibraryView::Playlists => state.playlists.len(),
-            },
+            Pane::SideBar => state.get_sidebar_details().1,
             _ => state.get_legal_songs().len(),
         };
 
@@ -1,3 +1,4 @@
+mod graphics;
 mod layout_minimal;
 mod layout_traditional;
 mod renderer;
@@ -9,6 +10,7 @@ use ratatui::{
     widgets::{Block, Widget},
 };
 
+pub use graphics::draw_graphics;
 pub use layout_traditional::LayoutTraditional;
 pub use renderer::render;
 pub use widgets::{ErrorMsg, Progress, SearchBar, SideBarHandler as SideBar, SongTable};
@@ -4,18 +4,25 @@ use crate::{
     tui::{
         layout_minimal::LayoutMinimal,
         render_bg,
-        widgets::{BreadCrumbs, BufferLine, PopupManager},
+        widgets::{BreadCrumbs, BufferLine, CoverArt, PopupManager, has_cover},
     },
     ui_state::{LayoutStyle, Mode, Pane},
 };
-use ratatui::{Frame, layout::Rect, widgets::StatefulWidget};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Margin, Rect},
+    widgets::StatefulWidget,
+};
+
+const FULLSCREEN_COVER_MIN_WIDTH: u16 = 16;
 
 pub fn render(f: &mut Frame, state: &mut UiState) {
     let area = f.area();
 
     if matches!(state.get_mode(), Mode::Fullscreen) {
         let bf_area = get_bufferline_area(area);
-        Progress.render(area, f.buffer_mut(), state);
+        let progress_area = render_fullscreen_cover(area, f, state);
+        Progress.render(progress_area, f.buffer_mut(), state);
         BufferLine.render(bf_area, f.buffer_mut(), state);
 
         return;
@@ -62,6 +69,34 @@ fn render_traditional(area: Rect, f: &mut Frame, state: &mut UiState) {
     BufferLine.render(bf_area, f.buffer_mut(), state);
 }
 
+/// Draws the cover of the song now playing on the left, returning the area
+/// left over for the progress display
+fn render_fullscreen_cover(area: Rect, f: &mut Frame, state: &mut UiState) -> Rect {
+    let Some(song) = state.playback.get_now_playing().cloned() else {
+        return area;
+    };
+
+    if !has_cover(&song, state) {
+        return area;
+    }
+
+    let (cell_w, cell_h) = state.cover_art.cell_size;
+    let height = area.height.saturating_sub(4);
+    let width = (height as u32 * cell_h as u32 / cell_w.max(1) as u32) as u16;
+    let width = width.min(area.width / 3);
+
+    if width < FULLSCREEN_COVER_MIN_WIDTH {
+        return area;
+    }
+
+    let [cover_area, progress_area] =
+        Layout::horizontal([Constraint::Length(width + 4), Constraint::Min(0)]).areas(area);
+
+    CoverArt { song: &song }.render(cover_area.inner(Margin::new(2, 2)), f.buffer_mut(), state);
+
+    progress_area
+}
+
 fn get_breadcrumbs_area(area: Rect) -> Rect {
     Rect {
         x: area.x,
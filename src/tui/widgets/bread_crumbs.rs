@@ -31,6 +31,10 @@ impl StatefulWidget for BreadCrumbs {
         let right_label = match top_level {
             LibraryView::Albums => state.get_album_sort_string(),
             LibraryView::Playlists => format!("{} 󰲸", state.playlists.len()),
+            _ => match state.get_selected_category() {
+                Some(category) if state.category_is_open() => category.name.clone(),
+                _ => state.categories.len().to_string(),
+            },
         };
 
         let spans = match state.get_pane() {
@@ -68,6 +72,19 @@ impl StatefulWidget for BreadCrumbs {
                         Span::from(format!("{}", playlist.name)).fg(bc_highlight),
                     ])
                 }
+                Mode::Library(_) => {
+                    let Some(category) = state.get_selected_category() else {
+                        return;
+                    };
+                    let mut spans = Vec::from([
+                        Span::from(format!("{top_level}  ")).fg(theme.text_muted),
+                        Span::from(category.name.clone()).fg(bc_highlight),
+                    ]);
+                    if let Some(album) = state.get_category_album() {
+                        spans.push(Span::from(format!("  {}", album.title)).fg(theme.text_muted));
+                    }
+                    spans
+                }
                 Mode::Queue => {
                     let queue_len = state.playback.queue_len();
                     Vec::from([
@@ -1,7 +1,10 @@
 use crate::{
     library::SongInfo,
+    playback::PlayMode,
     truncate_at_last_space,
-    tui::widgets::{PAUSE_ICON, QUEUE_ICON, REPEAT_ICON},
+    tui::widgets::{
+        MUTE_ICON, PAUSE_ICON, QUEUE_ICON, REPEAT_ALL_ICON, REPEAT_ICON, SHUFFLE_ICON, VOLUME_ICON,
+    },
     ui_state::{DisplayTheme, UiState},
 };
 use ratatui::{
@@ -51,7 +54,9 @@ impl StatefulWidget for BufferLine {
 
         let buffer = state.get_buffer_count();
 
-        get_buffer_count(buffer, &theme).render(left, buf);
+        get_buffer_count(buffer, theme)
+            .or_else(|| volume_display(state, theme))
+            .render(left, buf);
         playing_title(state, &theme, center.width as usize).render(center, buf);
         queue_display(state, &theme, right.width as usize).render(right, buf);
     }
@@ -63,9 +68,11 @@ const MIN_ARTIST_LEN: usize = 15;
 
 fn playing_title(state: &UiState, theme: &DisplayTheme, width: usize) -> Option<Line<'static>> {
     let song = state.get_now_playing()?;
-    let decorator = match state.playback.repeat_is_enabled() {
-        true => REPEAT_ICON,
-        false => &state.get_decorator(),
+    let decorator = match state.playback.get_play_mode() {
+        PlayMode::RepeatOne => REPEAT_ICON,
+        PlayMode::RepeatAll => REPEAT_ALL_ICON,
+        PlayMode::Shuffle | PlayMode::SmartShuffle => SHUFFLE_ICON,
+        PlayMode::Off => &state.get_decorator(),
     };
 
     let separator = match state.is_paused() {
@@ -147,6 +154,23 @@ fn get_buffer_count(size: Option<usize>, theme: &DisplayTheme) -> Option<Line<'s
     None
 }
 
+/// Hidden at full volume to keep the line uncluttered
+fn volume_display(state: &UiState, theme: &DisplayTheme) -> Option<Line<'static>> {
+    let volume = state.get_volume();
+
+    let icon = match state.is_muted() {
+        true => MUTE_ICON,
+        false if volume < 100 => VOLUME_ICON,
+        false => return None,
+    };
+
+    Some(
+        format!(" {icon} {volume}%")
+            .fg(theme.text_muted)
+            .into_left_aligned_line(),
+    )
+}
+
 const BAD_WIDTH: usize = 22;
 fn queue_display(state: &UiState, theme: &DisplayTheme, width: usize) -> Option<Line<'static>> {
     let up_next_str = state.peek_queue()?.get_title();
@@ -0,0 +1,101 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Rect, Size},
+    style::Color,
+    widgets::StatefulWidget,
+};
+use std::sync::Arc;
+
+use crate::{
+    library::SimpleSong,
+    ui_state::{ArtProtocol, Placement, UiState},
+};
+
+/// Album art of the album `song` belongs to, centered in the given area.
+/// Nothing is drawn until the cover has been loaded
+pub struct CoverArt<'a> {
+    pub song: &'a Arc<SimpleSong>,
+}
+
+impl StatefulWidget for CoverArt<'_> {
+    type State = UiState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let Some(protocol) = state.cover_art.protocol() else {
+            return;
+        };
+
+        let key = self.song.cover_key();
+        let Some(cover) = state.get_cover(self.song, false) else {
+            return;
+        };
+        let Some(image) = &cover.image else {
+            return;
+        };
+
+        let area = fit_area(
+            area,
+            (image.width(), image.height()),
+            state.cover_art.cell_size,
+        );
+
+        // Graphics are drawn above every cell, popups fall back to half blocks
+        match protocol {
+            ArtProtocol::HalfBlocks => render_halfblocks(area, buf, state, key, image),
+            _ if state.popup.is_open() => render_halfblocks(area, buf, state, key, image),
+            _ => {
+                for pos in area.positions() {
+                    buf[pos].set_skip(true);
+                }
+                state.cover_art.placement = Some(Placement { key, area });
+            }
+        }
+    }
+}
+
+/// Whether a cover would be drawn for `song`, requests it otherwise
+pub fn has_cover(song: &Arc<SimpleSong>, state: &mut UiState) -> bool {
+    state.cover_art.protocol().is_some()
+        && state
+            .get_cover(song, false)
+            .is_some_and(|cover| cover.image.is_some())
+}
+
+/// Largest area with the aspect ratio of the image, centered in `area`
+fn fit_area(area: Rect, (img_w, img_h): (u32, u32), (cell_w, cell_h): (u16, u16)) -> Rect {
+    let (cell_w, cell_h) = (cell_w as f32, cell_h as f32);
+    let scale =
+        (area.width as f32 * cell_w / img_w as f32).min(area.height as f32 * cell_h / img_h as f32);
+
+    let width = ((img_w as f32 * scale / cell_w).round() as u16).clamp(1, area.width.max(1));
+    let height = ((img_h as f32 * scale / cell_h).round() as u16).clamp(1, area.height.max(1));
+
+    Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    }
+    .intersection(area)
+}
+
+fn render_halfblocks(
+    area: Rect,
+    buf: &mut Buffer,
+    state: &mut UiState,
+    key: u64,
+    image: &image::DynamicImage,
+) {
+    let size = Size::new(area.width, area.height);
+    let pixels = state.cover_art.halfblocks(key, image, size);
+
+    for (y, x) in (0..area.height).flat_map(|y| (0..area.width).map(move |x| (y, x))) {
+        let [tr, tg, tb] = pixels.get_pixel(x as u32, y as u32 * 2).0;
+        let [br, bg, bb] = pixels.get_pixel(x as u32, y as u32 * 2 + 1).0;
+
+        buf[(area.x + x, area.y + y)]
+            .set_char('▀')
+            .set_fg(Color::Rgb(tr, tg, tb))
+            .set_bg(Color::Rgb(br, bg, bb));
+    }
+}
@@ -1,5 +1,6 @@
 mod bread_crumbs;
 mod buffer_line;
+mod cover_art;
 mod popup;
 mod popups;
 mod progress;
@@ -10,8 +11,11 @@ mod tracklist;
 
 pub use bread_crumbs::BreadCrumbs;
 pub use buffer_line::BufferLine;
+pub use cover_art::{CoverArt, has_cover};
 pub use popup::PopupManager;
-pub use popups::{ErrorMsg, PlaylistPopup, RootManager, ThemeManager, UserStats};
+pub use popups::{
+    EqualizerPopup, ErrorMsg, PlaylistPopup, RootManager, TagEditorPopup, ThemeManager, UserStats,
+};
 pub use progress::Progress;
 pub use search::SearchBar;
 pub use sidebar::SideBarHandler;
@@ -21,9 +25,17 @@ const PAUSE_ICON: &str = "󰏤";
 const SELECTOR: &str = "⮞  ";
 const QUEUE_ICON: &str = "󰐑";
 const REPEAT_ICON: &str = "";
+const REPEAT_ALL_ICON: &str = "󰑖";
+const SHUFFLE_ICON: &str = "󰒟";
+const VOLUME_ICON: &str = "󰕾";
+const MUTE_ICON: &str = "󰝟";
+const SMART_PLAYLIST_ICON: &str = "󰑓 ";
 const MUSIC_NOTE: &str = "♫";
 const QUEUED: &str = "";
+const RADIO_ICON: &str = "󰐹";
 const SELECTED: &str = "󱕣";
+const STAR: &str = "★";
+const FAVORITE: &str = "♥";
 const WAVEFORM_WIDGET_HEIGHT: f64 = 50.0;
 
 static POPUP_PADDING: ratatui::widgets::Padding = ratatui::widgets::Padding {
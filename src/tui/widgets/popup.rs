@@ -6,7 +6,9 @@ use ratatui::{
 use crate::{
     tui::{
         ErrorMsg,
-        widgets::{PlaylistPopup, RootManager, ThemeManager, UserStats},
+        widgets::{
+            EqualizerPopup, PlaylistPopup, RootManager, TagEditorPopup, ThemeManager, UserStats,
+        },
     },
     ui_state::{PopupType, UiState},
 };
@@ -22,10 +24,12 @@ impl StatefulWidget for PopupManager {
         state: &mut Self::State,
     ) {
         let popup_rect = match &state.popup.current {
-            PopupType::Stats => centered_rect(60, 80, area),
+            PopupType::Stats => centered_rect(80, 90, area),
             PopupType::Playlist(_) => centered_rect(35, 40, area),
             PopupType::Settings(_) => centered_rect(40, 40, area),
             PopupType::ThemeManager => centered_rect(40, 40, area),
+            PopupType::Equalizer => centered_rect(60, 50, area),
+            PopupType::TagEditor => centered_rect(50, 50, area),
             PopupType::Error(_) => centered_rect(40, 35, area),
             _ => return,
         };
@@ -36,6 +40,8 @@ impl StatefulWidget for PopupManager {
             PopupType::Playlist(_) => PlaylistPopup.render(popup_rect, buf, state),
             PopupType::Settings(_) => RootManager.render(popup_rect, buf, state),
             PopupType::ThemeManager => ThemeManager.render(popup_rect, buf, state),
+            PopupType::Equalizer => EqualizerPopup.render(popup_rect, buf, state),
+            PopupType::TagEditor => TagEditorPopup.render(popup_rect, buf, state),
             PopupType::Error(_) => ErrorMsg.render(popup_rect, buf, state),
             _ => unreachable!(),
         }
@@ -0,0 +1,102 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::{
+    player::{EQ_BANDS, EQ_FREQUENCIES, EQ_MAX_GAIN},
+    tui::widgets::POPUP_PADDING,
+    ui_state::UiState,
+};
+
+pub struct EqualizerPopup;
+impl StatefulWidget for EqualizerPopup {
+    type State = UiState;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) {
+        let theme = state.theme_manager.get_display_theme(true);
+        let gains = state.get_eq_settings();
+        let selected = state.get_eq_selection();
+
+        let title = match (state.get_eq_preset(), state.eq_enabled()) {
+            (_, false) => " Equalizer (off) ".to_string(),
+            (Some(preset), true) => format!(" Equalizer: {preset} "),
+            (None, true) => " Equalizer: custom ".to_string(),
+        };
+
+        let block = Block::bordered()
+            .border_type(theme.border_type)
+            .border_style(theme.border)
+            .title(title)
+            .title_bottom(" [h/l] band [j/k] gain [p] preset [e] on/off [o] overlay ")
+            .title_alignment(Alignment::Center)
+            .padding(POPUP_PADDING)
+            .bg(theme.bg);
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Gain label above and frequency label below each slider
+        let slider_height = inner.height.saturating_sub(2) as usize;
+        if slider_height == 0 {
+            return;
+        }
+
+        let columns = Layout::horizontal([Constraint::Fill(1); EQ_BANDS + 1]).split(inner);
+        let values = std::iter::once(gains.preamp).chain(gains.bands);
+
+        for (idx, (gain, column)) in values.zip(columns.iter()).enumerate() {
+            let label = match idx {
+                0 => "pre".to_string(),
+                n => format_frequency(EQ_FREQUENCIES[n - 1]),
+            };
+
+            let (text_style, fill) = match idx == selected {
+                true => (
+                    Style::new()
+                        .fg(theme.text_primary)
+                        .add_modifier(Modifier::BOLD),
+                    theme.text_secondary,
+                ),
+                false => (Style::new().fg(theme.text_muted), theme.text_muted),
+            };
+
+            let mut lines = vec![Line::styled(format!("{gain:+.0}"), text_style)];
+            lines.extend((0..slider_height).map(|row| {
+                // dB covered by this row, top to bottom
+                let step = 2.0 * EQ_MAX_GAIN / slider_height as f32;
+                let top = EQ_MAX_GAIN - row as f32 * step;
+                let bottom = top - step;
+
+                let filled = match gain >= 0.0 {
+                    true => bottom < gain && top > 0.0,
+                    false => top > gain && bottom < 0.0,
+                };
+
+                match filled {
+                    true => Line::from("██").fg(fill),
+                    false => Line::from("│").fg(theme.text_muted),
+                }
+            }));
+            lines.push(Line::styled(label, text_style));
+
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .render(*column, buf);
+        }
+    }
+}
+
+fn format_frequency(freq: f32) -> String {
+    match freq >= 1000.0 {
+        true => format!("{}k", (freq / 1000.0) as u32),
+        false => format!("{}", freq as u32),
+    }
+}
@@ -1,11 +1,15 @@
+mod equalizer;
 mod error;
 mod playlist_popup;
 mod root_manager;
 mod stats;
+mod tag_editor;
 mod theme_popup;
 
+pub use equalizer::EqualizerPopup;
 pub use error::ErrorMsg;
 pub use playlist_popup::PlaylistPopup;
 pub use root_manager::RootManager;
 pub use stats::UserStats;
+pub use tag_editor::TagEditorPopup;
 pub use theme_popup::ThemeManager;
@@ -1,5 +1,5 @@
 use crate::{
-    tui::widgets::POPUP_PADDING,
+    tui::widgets::{POPUP_PADDING, SMART_PLAYLIST_ICON},
     ui_state::{Pane, PlaylistAction, PopupType, UiState, fade_color},
 };
 use ratatui::{
@@ -21,9 +21,34 @@ impl StatefulWidget for PlaylistPopup {
     ) {
         if let PopupType::Playlist(action) = &state.popup.current {
             match action {
-                PlaylistAction::Create | PlaylistAction::CreateWithSongs => {
-                    render_create_popup(area, buf, state)
-                }
+                PlaylistAction::Create | PlaylistAction::CreateWithSongs => render_create_popup(
+                    area,
+                    buf,
+                    state,
+                    " Create New Playlist ",
+                    "Enter playlist title: ",
+                ),
+                PlaylistAction::CreateSmart => render_create_popup(
+                    area,
+                    buf,
+                    state,
+                    " Create Smart Playlist ",
+                    "Enter playlist title: ",
+                ),
+                PlaylistAction::CreateSmartRules(_) => render_create_popup(
+                    area,
+                    buf,
+                    state,
+                    " Create Smart Playlist ",
+                    "Enter playlist rules: ",
+                ),
+                PlaylistAction::EditRules => render_create_popup(
+                    area,
+                    buf,
+                    state,
+                    " Edit Smart Playlist Rules ",
+                    "Enter playlist rules: ",
+                ),
                 PlaylistAction::AddSong => render_add_song_popup(area, buf, state),
                 PlaylistAction::Delete => render_delete_popup(area, buf, state),
                 PlaylistAction::Rename => render_rename_popup(area, buf, state),
@@ -36,6 +61,8 @@ fn render_create_popup(
     area: ratatui::prelude::Rect,
     buf: &mut ratatui::prelude::Buffer,
     state: &mut UiState,
+    title: &str,
+    prompt: &str,
 ) {
     let focus = matches!(state.get_pane(), Pane::Popup);
     let theme = state.theme_manager.get_display_theme(focus);
@@ -45,7 +72,7 @@ fn render_create_popup(
     let block = Block::bordered()
         .border_type(theme.border_type)
         .border_style(theme.border)
-        .title(" Create New Playlist ")
+        .title(title)
         .title_bottom(" [Enter] confirm / [Esc] cancel ")
         .title_alignment(ratatui::layout::Alignment::Center)
         .padding(Padding {
@@ -62,9 +89,7 @@ fn render_create_popup(
 
     let chunks = Layout::vertical([Constraint::Max(2), Constraint::Length(3)]).split(inner);
 
-    Paragraph::new("Enter playlist title: ")
-        .centered()
-        .render(chunks[0], buf);
+    Paragraph::new(prompt).centered().render(chunks[0], buf);
 
     state.popup.input.set_block(
         Block::bordered()
@@ -89,7 +114,10 @@ fn render_add_song_popup(
         .playlists
         .iter()
         .map(|p| {
-            let playlist_name = p.name.to_string();
+            let playlist_name = match p.is_smart() {
+                true => format!("{SMART_PLAYLIST_ICON}{}", p.name),
+                false => p.name.to_string(),
+            };
             Line::from(playlist_name)
                 .fg(fade_color(theme.dark, theme.text_muted, 0.85))
                 .centered()
@@ -1,17 +1,25 @@
-use std::{sync::Arc, u32};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+    u32,
+};
 
 use ratatui::{
+    buffer::Buffer,
     layout::{Constraint, HorizontalAlignment, Layout, Rect},
     style::Stylize,
     text::{Line, Span},
-    widgets::{Block, Padding, Paragraph, StatefulWidget, Widget},
+    widgets::{Block, Padding, Paragraph, Sparkline, StatefulWidget, Widget},
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
     SimpleSong,
     library::SongInfo,
-    ui_state::{DisplayTheme, LayoutStyle, LibraryStats, UiState, fade_color},
+    ui_state::{
+        DisplayTheme, LayoutStyle, LibraryStats, ListeningStats, StatsPage, UiState, fade_color,
+    },
 };
 
 pub struct UserStats;
@@ -24,17 +32,21 @@ impl StatefulWidget for UserStats {
         state: &mut Self::State,
     ) {
         let theme = state.theme_manager.get_display_theme(true);
-        let stats = state.get_lib_stats();
-        let most_played = state.get_most_played().to_vec();
+        let page = state.get_stats_page();
 
         let bg = match state.get_layout() {
             LayoutStyle::Traditional => theme.bg,
             LayoutStyle::Minimal => theme.bg_global,
         };
 
+        let footer = match state.get_stats_export() {
+            Some(path) => format!(" Exported to {} ", path.display()),
+            None => " [h/l] page [p] period [x] export [esc] close ".to_string(),
+        };
+
         let block = Block::bordered()
             .title(" Library Stats ")
-            .title_bottom(" Press anything to close ")
+            .title_bottom(footer)
             .title_alignment(HorizontalAlignment::Center)
             .border_style(theme.border)
             .bg(bg);
@@ -42,62 +54,381 @@ impl StatefulWidget for UserStats {
         let inner = block.inner(area);
         block.render(area, buf);
 
-        let [_upper_buf, lib_stats, central_buf] = Layout::vertical([
-            Constraint::Percentage(7),
-            Constraint::Length(7),
-            Constraint::Fill(1),
-        ])
-        .areas(inner);
-
-        let [duration_buf, _, top_play_title, top_played_buf] = Layout::vertical([
+        let [tabs, _, body] = Layout::vertical([
             Constraint::Length(1),
-            Constraint::Length(2),
             Constraint::Length(1),
             Constraint::Fill(1),
         ])
-        .areas(central_buf);
+        .areas(inner);
 
-        let [left, _buffer, right] = Layout::horizontal([
-            Constraint::Percentage(50),
-            Constraint::Length(2),
-            Constraint::Percentage(50),
-        ])
-        .areas(lib_stats);
+        let mut titles = vec![];
+        for (idx, p) in StatsPage::ALL.iter().enumerate() {
+            if idx > 0 {
+                titles.push(Span::from(" │ ").fg(theme.text_muted));
+            }
+            titles.push(match *p == page {
+                true => Span::from(p.title()).fg(theme.accent).bold(),
+                false => Span::from(p.title()).fg(theme.text_muted),
+            });
+        }
+        Line::from(titles).centered().render(tabs, buf);
+
+        match page {
+            StatsPage::Overview => render_overview(body, buf, state, theme),
+            StatsPage::TopPlayed => render_top_played(body, buf, state, theme),
+            StatsPage::Listening => render_listening(body, buf, state.get_listening_stats(), theme),
+            StatsPage::Forgotten => render_forgotten(body, buf, state.get_listening_stats(), theme),
+            StatsPage::Formats => render_formats(body, buf, state.get_listening_stats(), theme),
+        }
+    }
+}
+
+fn render_overview(area: Rect, buf: &mut Buffer, state: &UiState, theme: &DisplayTheme) {
+    let stats = state.get_lib_stats();
+    let most_played = state.get_most_played();
+
+    let [lib_stats, central_buf] =
+        Layout::vertical([Constraint::Length(7), Constraint::Fill(1)]).areas(area);
+
+    let [duration_buf, _, top_play_title, top_played_buf] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(central_buf);
+
+    let [left, _buffer, right] = Layout::horizontal([
+        Constraint::Percentage(50),
+        Constraint::Length(2),
+        Constraint::Percentage(50),
+    ])
+    .areas(lib_stats);
+
+    let library_stats = library_column(stats, theme);
+    let playback_stats = listening_column(stats, theme);
 
-        let library_stats = library_column(stats, theme);
-        let playback_stats = listening_column(stats, theme);
+    let horiz_padding = area.width / 10;
 
-        let horiz_padding = area.width / 10;
+    Paragraph::new(library_stats)
+        .right_aligned()
+        .render(left, buf);
+    Paragraph::new(playback_stats).render(right, buf);
 
-        Paragraph::new(library_stats)
-            .right_aligned()
-            .render(left, buf);
-        Paragraph::new(playback_stats).render(right, buf);
+    Line::from_iter([
+        "Total duration of library: ".fg(theme.text_muted),
+        format_duration(stats.total_duration).fg(theme.accent),
+    ])
+    .centered()
+    .render(duration_buf, buf);
 
+    Line::from("Top Played Songs")
+        .fg(theme.text_secondary)
+        .centered()
+        .render(top_play_title, buf);
+
+    if !most_played.is_empty() {
+        let most_played_vec = get_most_played(most_played, theme, &area);
+        Paragraph::new(most_played_vec)
+            .block(Block::default().padding(Padding {
+                left: horiz_padding,
+                right: horiz_padding,
+                top: 1,
+                bottom: 3,
+            }))
+            .centered()
+            .render(top_played_buf, buf);
+    }
+}
+
+fn render_top_played(area: Rect, buf: &mut Buffer, state: &UiState, theme: &DisplayTheme) {
+    let period = state.get_stats_period();
+    let Some(top) = state
+        .get_listening_stats()
+        .top
+        .iter()
+        .find(|t| t.period == period)
+    else {
+        return;
+    };
+
+    let [period_buf, _, columns] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    Line::from_iter([
+        Span::from(period.label()).fg(theme.accent),
+        Span::from("  [p] change period").fg(theme.text_muted),
+    ])
+    .centered()
+    .render(period_buf, buf);
+
+    if top.songs.is_empty() {
+        return no_history(columns, buf, theme);
+    }
+
+    let columns = Layout::horizontal([Constraint::Fill(1); 3])
+        .horizontal_margin(1)
+        .spacing(2)
+        .split(columns);
+
+    for ((title, entries), column) in [
+        ("Artists", &top.artists),
+        ("Albums", &top.albums),
+        ("Songs", &top.songs),
+    ]
+    .into_iter()
+    .zip(columns.iter())
+    {
+        let width = (column.width as usize).saturating_sub(6);
+        let mut lines = vec![
+            Line::from(title).fg(theme.text_secondary),
+            Line::from("-".repeat(column.width as usize)).fg(theme.text_muted),
+        ];
+
+        lines.extend(entries.iter().flat_map(|e| {
+            let mut rows = vec![Line::from_iter([
+                Span::from(format!("{:>4}  ", e.plays)).fg(theme.text_secondary),
+                Span::from(truncate_display(&e.name, width)).fg(theme.text_primary),
+            ])];
+            if let Some(artist) = &e.artist {
+                rows.push(Line::from_iter([
+                    Span::raw("      "),
+                    Span::from(truncate_display(artist, width)).fg(theme.text_muted),
+                ]));
+            }
+            rows
+        }));
+
+        Paragraph::new(lines).render(*column, buf);
+    }
+}
+
+fn render_listening(area: Rect, buf: &mut Buffer, stats: &ListeningStats, theme: &DisplayTheme) {
+    if stats.days.is_empty() {
+        return no_history(area, buf, theme);
+    }
+
+    let [streak_buf, _, chart_buf, _, summary_buf] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+        Constraint::Length(2),
+    ])
+    .areas(area);
+
+    let streaks = &stats.streaks;
+    let longest_ended = match &streaks.longest_ended {
+        Some(date) => format!(" (ended {date})"),
+        None => String::new(),
+    };
+
+    Paragraph::new(vec![
         Line::from_iter([
-            "Total duration of library: ".fg(theme.text_muted),
-            format_duration(stats.total_duration).fg(theme.accent),
-        ])
+            "Current streak: ".fg(theme.text_muted),
+            plural(streaks.current, "day").fg(theme.accent),
+            "    Longest streak: ".fg(theme.text_muted),
+            plural(streaks.longest, "day").fg(theme.text_primary),
+            longest_ended.fg(theme.text_muted),
+        ]),
+        Line::from_iter([
+            "Days with listening: ".fg(theme.text_muted),
+            print_commas(streaks.active_days).fg(theme.text_primary),
+        ]),
+    ])
+    .centered()
+    .render(streak_buf, buf);
+
+    let block = Block::bordered()
+        .border_type(theme.border_type)
+        .border_style(theme.text_muted);
+    let chart = block.inner(chart_buf);
+
+    // One bar per day, ending today
+    let span = chart.width as i64;
+    let first = stats.today - span + 1;
+    let seconds = stats
+        .days
+        .iter()
+        .filter(|d| d.day >= first)
+        .map(|d| (d.day, d.seconds))
+        .collect::<HashMap<_, _>>();
+
+    let daily = (first..=stats.today)
+        .map(|day| seconds.get(&day).copied().unwrap_or(0.0) as u64)
+        .collect::<Vec<_>>();
+
+    let total = seconds.values().sum::<f64>();
+    let best = stats
+        .days
+        .iter()
+        .filter(|d| d.day >= first)
+        .max_by(|a, b| a.seconds.total_cmp(&b.seconds));
+
+    block
+        .title(format!(" Listening time per day, last {span} days "))
+        .title_alignment(HorizontalAlignment::Center)
+        .render(chart_buf, buf);
+
+    Sparkline::default()
+        .data(&daily)
+        .style(theme.accent)
+        .render(chart, buf);
+
+    let mut summary = vec![
+        "Total: ".fg(theme.text_muted),
+        format_duration(total as f32).fg(theme.text_primary),
+        "    Daily average: ".fg(theme.text_muted),
+        format_duration((total / span as f64) as f32).fg(theme.text_primary),
+    ];
+    if let Some(best) = best {
+        summary.extend([
+            "    Best day: ".fg(theme.text_muted),
+            best.date.clone().fg(theme.text_primary),
+            format!(" ({})", format_duration(best.seconds as f32)).fg(theme.text_muted),
+        ]);
+    }
+
+    Paragraph::new(vec![
+        Line::from(summary),
+        Line::from("Listening time counts the full length of every song in history")
+            .fg(fade_color(theme.dark, theme.text_muted, 1.4)),
+    ])
+    .centered()
+    .render(summary_buf, buf);
+}
+
+fn render_forgotten(area: Rect, buf: &mut Buffer, stats: &ListeningStats, theme: &DisplayTheme) {
+    let [intro_buf, _, list_buf] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    Line::from("Often played songs which haven't come up in months")
+        .fg(theme.text_secondary)
         .centered()
-        .render(duration_buf, buf);
+        .render(intro_buf, buf);
 
-        Line::from("Top Played Songs")
-            .fg(theme.text_secondary)
+    if stats.forgotten.is_empty() {
+        return Line::from("Nothing forgotten yet")
+            .fg(theme.text_muted)
             .centered()
-            .render(top_play_title, buf);
-
-        if !most_played.is_empty() {
-            let most_played_vec = get_most_played(&most_played, theme, &area);
-            Paragraph::new(most_played_vec)
-                .block(Block::default().padding(Padding {
-                    left: horiz_padding,
-                    right: horiz_padding,
-                    top: 1,
-                    bottom: 3,
-                }))
-                .centered()
-                .render(top_played_buf, buf);
-        }
+            .render(list_buf, buf);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    let row_width = (list_buf.width as usize).saturating_sub(4).min(100);
+    let content = row_width.saturating_sub(5 + 3 + 1 + 16);
+    let title_width = content * 6 / 10;
+    let artist_width = content - title_width;
+
+    let header = Line::from_iter([
+        Span::from(format!("{:>5}", "#")),
+        Span::from(format!("   {:<title_width$} ", "Title")),
+        Span::from(format!("{:<artist_width$}", "Artist")),
+        Span::from(format!("{:>16}", "Last played")),
+    ])
+    .fg(fade_color(theme.dark, theme.text_muted, 1.4));
+
+    let mut lines = vec![header, Line::from("-".repeat(row_width))];
+    lines.extend(stats.forgotten.iter().map(|f| {
+        let last = match f.last_played {
+            Some(t) => format!("{} days ago", (now - t) / 86400),
+            None => "long ago".to_string(),
+        };
+
+        Line::from_iter([
+            Span::from(format!("{:>5}", f.plays)).fg(theme.text_secondary),
+            Span::raw("   "),
+            Span::from(pad_to_width(
+                &truncate_display(&f.title, title_width),
+                title_width,
+            ))
+            .fg(theme.text_primary),
+            Span::raw(" "),
+            Span::from(pad_to_width(
+                &truncate_display(&f.artist, artist_width),
+                artist_width,
+            ))
+            .fg(theme.text_muted),
+            Span::from(format!("{last:>16}")).fg(theme.text_muted),
+        ])
+    }));
+
+    Paragraph::new(lines).centered().render(list_buf, buf);
+}
+
+fn render_formats(area: Rect, buf: &mut Buffer, stats: &ListeningStats, theme: &DisplayTheme) {
+    let total = stats.formats.iter().map(|f| f.songs).sum::<u32>().max(1) as f64;
+    let header_style = fade_color(theme.dark, theme.text_muted, 1.4);
+
+    let [formats_buf, _, bitrate_buf] = Layout::vertical([
+        Constraint::Length(stats.formats.len() as u16 + 3),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    let mut lines = vec![
+        Line::from("By Format").fg(theme.text_secondary),
+        Line::from(format!(
+            "{:<8}{:>9}{:>9}{:>15}{:>11}",
+            "Format", "Songs", "Share", "Duration", "Avg kbps"
+        ))
+        .fg(header_style),
+        Line::from("-".repeat(52)),
+    ];
+    lines.extend(stats.formats.iter().map(|f| {
+        let kbps = f.bitrate.map_or("-".to_string(), |b| b.to_string());
+        Line::from_iter([
+            Span::from(format!("{:<8}", f.format)).fg(theme.text_primary),
+            Span::from(format!("{:>9}", print_commas(f.songs))).fg(theme.text_secondary),
+            Span::from(format!("{:>8.1}%", f.songs as f64 * 100.0 / total)).fg(theme.text_muted),
+            Span::from(format!("{:>15}", format_duration(f.seconds as f32))).fg(theme.text_muted),
+            Span::from(format!("{kbps:>11}")).fg(theme.text_muted),
+        ])
+    }));
+    Paragraph::new(lines).centered().render(formats_buf, buf);
+
+    let bar_width = 24;
+    let mut lines = vec![
+        Line::from("By Bitrate").fg(theme.text_secondary),
+        Line::from("-".repeat(52)),
+    ];
+    lines.extend(stats.bitrates.iter().map(|b| {
+        let share = b.songs as f64 / total;
+        let filled = (share * bar_width as f64).round() as usize;
+        Line::from_iter([
+            Span::from(format!("{:<16}", b.range)).fg(theme.text_primary),
+            Span::from("█".repeat(filled)).fg(theme.accent),
+            Span::from(" ".repeat(bar_width - filled.min(bar_width))),
+            Span::from(format!("{:>7}", print_commas(b.songs))).fg(theme.text_secondary),
+            Span::from(format!("{:>5.0}%", share * 100.0)).fg(theme.text_muted),
+        ])
+    }));
+    Paragraph::new(lines).centered().render(bitrate_buf, buf);
+}
+
+fn no_history(area: Rect, buf: &mut Buffer, theme: &DisplayTheme) {
+    Line::from("No listening history yet")
+        .fg(theme.text_muted)
+        .centered()
+        .render(area, buf);
+}
+
+fn plural(n: u32, unit: &str) -> String {
+    match n == 1 {
+        true => format!("{n} {unit}"),
+        false => format!("{n} {unit}s"),
     }
 }
 
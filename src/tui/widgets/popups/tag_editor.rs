@@ -0,0 +1,69 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::{library::SongInfo, tui::widgets::POPUP_PADDING, ui_state::UiState};
+
+const LABEL_WIDTH: u16 = 14;
+
+pub struct TagEditorPopup;
+impl StatefulWidget for TagEditorPopup {
+    type State = UiState;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) {
+        let theme = state.theme_manager.get_display_theme(true);
+        let editor = &mut state.tag_editor;
+
+        let title = match (editor.song_count(), editor.first_song()) {
+            (1, Some(song)) => format!(" Edit Tags: {} ", song.get_title()),
+            (n, _) => format!(" Edit Tags: {n} songs "),
+        };
+
+        let block = Block::bordered()
+            .border_type(theme.border_type)
+            .border_style(theme.border)
+            .title(title)
+            .title_bottom(" [Tab/↑↓] field [Enter] save [Esc] cancel ")
+            .title_alignment(Alignment::Center)
+            .padding(POPUP_PADDING)
+            .bg(theme.bg);
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let rows = Layout::vertical(vec![Constraint::Length(1); editor.fields.len()])
+            .spacing(1)
+            .split(inner);
+
+        let selected = editor.selected;
+        for (idx, (entry, row)) in editor.fields.iter_mut().zip(rows.iter()).enumerate() {
+            let [label_area, input_area] =
+                Layout::horizontal([Constraint::Length(LABEL_WIDTH), Constraint::Fill(1)])
+                    .areas(*row);
+
+            let (label_style, cursor) = match idx == selected {
+                true => (
+                    Style::new()
+                        .fg(theme.text_primary)
+                        .add_modifier(Modifier::BOLD),
+                    Style::new().add_modifier(Modifier::REVERSED),
+                ),
+                false => (Style::new().fg(theme.text_muted), Style::new()),
+            };
+
+            Line::styled(entry.field.label(), label_style).render(label_area, buf);
+
+            entry.input.set_style(Style::new().fg(theme.text_primary));
+            entry.input.set_cursor_style(cursor);
+            entry.input.render(input_area, buf);
+        }
+    }
+}
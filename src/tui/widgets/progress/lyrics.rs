@@ -0,0 +1,126 @@
+use crate::{
+    library::SongInfo,
+    ui_state::{LayoutStyle, LyricsState, UiState},
+};
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Padding, Paragraph, StatefulWidget, Widget},
+};
+
+pub struct LyricsView;
+
+impl StatefulWidget for LyricsView {
+    type State = UiState;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) {
+        let theme = state.theme_manager.get_display_theme(true);
+
+        // Leave room for the timer on either side
+        let w_pad = match state.get_layout() {
+            LayoutStyle::Traditional => 10,
+            LayoutStyle::Minimal => 1,
+        };
+
+        let block = Block::new()
+            .bg(theme.bg_global)
+            .padding(Padding::horizontal(w_pad));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height == 0 || inner.width == 0 {
+            return;
+        }
+
+        let lyrics = match state.get_lyrics_state() {
+            LyricsState::Ready(lyrics) => lyrics,
+            LyricsState::Loading => return render_message("Loading lyrics...", inner, buf, state),
+            _ => return render_message("No lyrics found", inner, buf, state),
+        };
+
+        let lines = lyrics.lines();
+        let height = inner.height as usize;
+        let elapsed = state.get_playback_elapsed();
+
+        let current = lyrics.current_line(elapsed, state.get_lyrics_offset());
+
+        // Synced lyrics keep the current line centered, plain lyrics scroll
+        // along with the progress of the song
+        let first = match lyrics.is_synced() {
+            true => current.unwrap_or(0).saturating_sub(height / 2),
+            false => {
+                let duration = state
+                    .get_now_playing()
+                    .map(|np| np.get_duration().as_secs_f32())
+                    .unwrap_or_default();
+                let ratio = match duration > 0.0 {
+                    true => (elapsed.as_secs_f32() / duration).min(1.0),
+                    false => 0.0,
+                };
+                (lines.len().saturating_sub(height) as f32 * ratio) as usize
+            }
+        };
+
+        // Pad the top so the first lines also sit in the middle
+        let top_pad = match (lyrics.is_synced(), current) {
+            (true, Some(idx)) => (height / 2).saturating_sub(idx),
+            (true, None) => height / 2 + 1,
+            (false, _) => 0,
+        };
+
+        let active = Style::new()
+            .fg(theme.text_primary)
+            .add_modifier(Modifier::BOLD);
+        let inactive = Style::new().fg(theme.text_muted);
+
+        let text = std::iter::repeat_n(Line::default(), top_pad)
+            .chain(lines.iter().enumerate().skip(first).map(|(idx, line)| {
+                match Some(idx) == current {
+                    true => Line::styled(line.text.as_str(), active),
+                    false => Line::styled(line.text.as_str(), inactive),
+                }
+            }))
+            .take(height)
+            .collect::<Vec<_>>();
+
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .render(inner, buf);
+
+        let offset = state.get_lyrics_offset();
+        if offset != 0 {
+            let label = format!("{:+.1}s", offset as f32 / 1000.0);
+            let width = (label.len() as u16).min(inner.width);
+            let label_area = Rect {
+                x: inner.right() - width,
+                y: inner.y,
+                width,
+                height: 1,
+            };
+
+            Line::from(label)
+                .fg(theme.text_muted)
+                .render(label_area, buf);
+        }
+    }
+}
+
+fn render_message(msg: &str, area: Rect, buf: &mut ratatui::prelude::Buffer, state: &UiState) {
+    let theme = state.theme_manager.get_display_theme(true);
+    let y = area.y + area.height.saturating_sub(1) / 2;
+
+    Line::from(msg).fg(theme.text_muted).centered().render(
+        Rect {
+            y,
+            height: 1,
+            ..area
+        },
+        buf,
+    );
+}
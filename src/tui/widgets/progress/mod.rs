@@ -1,9 +1,11 @@
+mod lyrics;
 mod oscilloscope;
 mod progress_bar;
 mod spectrum;
 mod timer;
 mod waveform;
 
+pub use lyrics::LyricsView;
 pub use oscilloscope::Oscilloscope;
 pub use progress_bar::ProgressBar;
 pub use spectrum::SpectrumAnalyzer;
@@ -35,6 +37,7 @@ impl StatefulWidget for Progress {
                 },
                 ProgressDisplay::Oscilloscope => Oscilloscope.render(area, buf, state),
                 ProgressDisplay::Spectrum => SpectrumAnalyzer.render(area, buf, state),
+                ProgressDisplay::Lyrics => LyricsView.render(area, buf, state),
             }
             if state.get_layout() == &LayoutStyle::Traditional {
                 Timer.render(area, buf, state);
@@ -1,4 +1,7 @@
-use crate::ui_state::{UiState, fade_color};
+use crate::{
+    player::EQ_MAX_GAIN,
+    ui_state::{UiState, fade_color},
+};
 use ratatui::{
     style::{Color, Stylize},
     widgets::{
@@ -45,6 +48,11 @@ impl StatefulWidget for SpectrumAnalyzer {
             false => 0.05,
         };
 
+        let overlay = match state.eq_overlay_visible() {
+            true => eq_curve(state, canvas_width, is_mirrored),
+            false => vec![],
+        };
+
         Canvas::default()
             .x_bounds([0.00, pixel_width as f64])
             .y_bounds([y_min, 1.05]) // The 0.05 prevents overflow
@@ -63,6 +71,16 @@ impl StatefulWidget for SpectrumAnalyzer {
                         ctx.draw(&spectrum_line(x as f64, mag as f64, is_mirrored, color))
                     }
                 }
+
+                for pair in overlay.windows(2) {
+                    ctx.draw(&Line {
+                        x1: pair[0].0,
+                        y1: pair[0].1,
+                        x2: pair[1].0,
+                        y2: pair[1].1,
+                        color: theme.text_primary,
+                    });
+                }
             })
             .background_color(theme.bg_global)
             .block(Block::new().bg(theme.bg_global))
@@ -82,3 +100,25 @@ fn spectrum_line(x: f64, mag: f64, mirrored: bool, color: Color) -> Line {
         color,
     }
 }
+
+/// Response of the equalizer for every column, drawn across the bars. 0 dB
+/// sits in the middle, or on the axis when mirrored
+fn eq_curve(state: &UiState, width: usize, mirrored: bool) -> Vec<(f64, f64)> {
+    let gains = state.get_eq_gains();
+    let sample_rate = state.spectrum.sample_rate();
+
+    (0..width)
+        .filter_map(|i| {
+            let t = i as f32 / (width - 1).max(1) as f32;
+            let freq = state.spectrum.frequency_at(t)?;
+            let level = (gains.response_db(freq, sample_rate) / EQ_MAX_GAIN).clamp(-1.0, 1.0);
+
+            let y = match mirrored {
+                true => level,
+                false => 0.5 + level / 2.0,
+            };
+
+            Some((i as f64 * 2.0 + 1.0, y as f64))
+        })
+        .collect()
+}
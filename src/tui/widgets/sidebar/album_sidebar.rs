@@ -1,12 +1,21 @@
 use crate::{
-    tui::widgets::sidebar::{KILL_WIDTH_ALBUM, PADDING_L, PADDING_R, create_standard_list},
+    library::SimpleSong,
+    tui::widgets::{
+        CoverArt, has_cover,
+        sidebar::{
+            KILL_HEIGHT_COVER, KILL_WIDTH_ALBUM, PADDING_L, PADDING_R, create_standard_list,
+        },
+    },
     ui_state::{AlbumSort, LayoutStyle, Pane, UiState, fade_color},
 };
 use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
     style::{Style, Stylize},
     text::{Line, Span},
-    widgets::{Borders, ListItem, ListState, StatefulWidget},
+    widgets::{Block, Borders, ListItem, ListState, Padding, StatefulWidget},
 };
+use std::sync::Arc;
 use unicode_width::UnicodeWidthStr;
 
 pub struct SideBarAlbum;
@@ -19,6 +28,14 @@ impl StatefulWidget for SideBarAlbum {
         buf: &mut ratatui::prelude::Buffer,
         state: &mut Self::State,
     ) {
+        let selected_song = state
+            .get_selected_album()
+            .and_then(|a| a.tracklist.first().cloned());
+        let area = match selected_song {
+            Some(song) => render_cover(area, buf, state, &song),
+            None => area,
+        };
+
         let focus = matches!(&state.get_pane(), Pane::SideBar);
         let theme = &state.theme_manager.get_display_theme(focus);
 
@@ -144,3 +161,46 @@ impl StatefulWidget for SideBarAlbum {
         *state.display_state.album_pos.offset_mut() = render_state.offset();
     }
 }
+
+/// Draws the cover of the selected album beneath the list, returning the area
+/// left over for the list
+pub(super) fn render_cover(
+    area: Rect,
+    buf: &mut Buffer,
+    state: &mut UiState,
+    song: &Arc<SimpleSong>,
+) -> Rect {
+    if area.height < KILL_HEIGHT_COVER || !has_cover(song, state) {
+        return area;
+    }
+
+    let focus = matches!(&state.get_pane(), Pane::SideBar);
+    let theme = state.theme_manager.get_display_theme(focus);
+
+    let block = Block::bordered()
+        .borders(theme.border_display)
+        .border_type(theme.border_type)
+        .border_style(theme.border)
+        .bg(match state.get_layout() {
+            LayoutStyle::Traditional => theme.bg,
+            LayoutStyle::Minimal => theme.bg_global,
+        })
+        .padding(Padding::horizontal(1));
+
+    // Covers are square, cells are roughly twice as tall as they are wide
+    let (cell_w, cell_h) = state.cover_art.cell_size;
+    let width = block.inner(area).width as u32;
+    let height = (width * cell_w as u32 / cell_h.max(1) as u32) as u16 + 2;
+
+    let [list_area, cover_area] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(height.min(area.height * 2 / 5)),
+    ])
+    .areas(area);
+
+    let inner = block.inner(cover_area);
+    ratatui::widgets::Widget::render(block, cover_area, buf);
+    CoverArt { song }.render(inner, buf, state);
+
+    list_area
+}
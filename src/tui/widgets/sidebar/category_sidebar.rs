@@ -0,0 +1,126 @@
+use crate::{
+    truncate_at_last_space,
+    tui::widgets::sidebar::{
+        KILL_WIDTH_ALBUM, PADDING_L, PADDING_R, album_sidebar::render_cover, create_standard_list,
+    },
+    ui_state::{LayoutStyle, LibraryView, Pane, UiState},
+};
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Borders, ListItem, StatefulWidget},
+};
+use unicode_width::UnicodeWidthStr;
+
+pub struct SideBarCategory;
+impl StatefulWidget for SideBarCategory {
+    type State = UiState;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) {
+        let selected_song = state
+            .get_category_album()
+            .and_then(|a| a.tracklist.first().cloned());
+        let area = match selected_song {
+            Some(song) => render_cover(area, buf, state, &song),
+            None => area,
+        };
+
+        let focus = matches!(&state.get_pane(), Pane::SideBar);
+        let theme = state.theme_manager.get_display_theme(focus);
+        let decorator = state.get_decorator();
+
+        let padding = PADDING_L
+            + PADDING_R
+            + match theme.border_display {
+                Borders::NONE => 0,
+                _ => 2,
+            };
+        let width = area.width.saturating_sub(padding) as usize;
+
+        let list_items = match state.get_selected_category() {
+            Some(category) if state.category_is_open() => {
+                let show_artist = state.get_sidebar_view() != &LibraryView::Artists;
+
+                category
+                    .albums
+                    .iter()
+                    .map(|album| {
+                        let mut year = album.year.map_or("????".to_string(), |y| format!("{y}"));
+                        if area.width < KILL_WIDTH_ALBUM {
+                            year.clear();
+                        }
+
+                        let title = match album.title.is_empty() {
+                            true => "[Unknown Album]".to_string(),
+                            false => album.title.to_string(),
+                        };
+
+                        let artist = match show_artist {
+                            true => format!(" [{}]", album.artist),
+                            false => String::new(),
+                        };
+
+                        match state.layout {
+                            LayoutStyle::Minimal => {
+                                let n = width.saturating_sub(title.width() + year.width());
+                                ListItem::new(Line::from_iter([
+                                    Span::from(title).fg(theme.text_primary),
+                                    Span::from(" ".repeat(n)),
+                                    Span::from(year).fg(theme.text_muted),
+                                ]))
+                            }
+                            LayoutStyle::Traditional => ListItem::new(Line::from_iter([
+                                Span::from(format!("{year: >4} ")).fg(theme.text_muted),
+                                Span::from(format!("{decorator} ")).fg(theme.text_muted),
+                                Span::from(title).fg(theme.text_primary),
+                                Span::from(artist).fg(theme.text_muted),
+                            ])),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            }
+            _ => state
+                .categories
+                .iter()
+                .map(|category| {
+                    let count = category.albums.len();
+                    let count_str = match state.layout {
+                        LayoutStyle::Traditional => format!("({count})"),
+                        LayoutStyle::Minimal => format!("{count}"),
+                    };
+
+                    let max_name_width = width.saturating_sub(count_str.width() + 1);
+                    let name = match category.name.width() > max_name_width {
+                        true => truncate_at_last_space(&category.name, max_name_width),
+                        false => category.name.clone(),
+                    };
+
+                    let n = width.saturating_sub(name.width() + count_str.width());
+
+                    ListItem::new(Line::from_iter([
+                        Span::from(name).fg(theme.text_secondary),
+                        Span::from(" ".repeat(n)),
+                        Span::from(count_str).fg(theme.text_muted),
+                    ]))
+                })
+                .collect::<Vec<_>>(),
+        };
+
+        let mut render_state = match state.category_is_open() {
+            true => state.display_state.category_album_pos,
+            false => state.display_state.category_pos,
+        };
+
+        create_standard_list(list_items, None, state, area).render(area, buf, &mut render_state);
+
+        match state.category_is_open() {
+            true => state.display_state.category_album_pos = render_state,
+            false => state.display_state.category_pos = render_state,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{SideBarAlbum, SideBarPlaylist};
+use super::{SideBarAlbum, SideBarCategory, SideBarPlaylist};
 use crate::ui_state::{LibraryView, UiState};
 use ratatui::widgets::StatefulWidget;
 
@@ -15,6 +15,7 @@ impl StatefulWidget for SideBarHandler {
         match state.get_sidebar_view() {
             LibraryView::Albums => SideBarAlbum.render(area, buf, state),
             LibraryView::Playlists => SideBarPlaylist.render(area, buf, state),
+            _ => SideBarCategory.render(area, buf, state),
         }
     }
 }
@@ -1,8 +1,10 @@
 mod album_sidebar;
+mod category_sidebar;
 mod handler;
 mod playlist_sidebar;
 
 pub use album_sidebar::SideBarAlbum;
+pub use category_sidebar::SideBarCategory;
 pub use handler::SideBarHandler;
 pub use playlist_sidebar::SideBarPlaylist;
 use ratatui::{
@@ -14,6 +16,7 @@ use ratatui::{
 
 const KILL_WIDTH_ALBUM: u16 = 40;
 const KILL_WIDTH_PLAYLIST: u16 = 25;
+const KILL_HEIGHT_COVER: u16 = 24;
 const PADDING_L: u16 = 3;
 const PADDING_R: u16 = 2;
 
@@ -30,9 +33,13 @@ pub fn create_standard_list<'a>(
 
     let (sidebar_type, count) = state.get_sidebar_details();
 
-    let title = Line::from(format!(" ⟪ {} {} ⟫ ", count, sidebar_type))
-        .left_aligned()
-        .fg(theme.accent);
+    let title = match state.get_selected_category() {
+        Some(category) if sidebar_type.is_category() && state.category_is_open() => {
+            format!(" ⟪ {} ⫽ {} Albums ⟫ ", category.name, count)
+        }
+        _ => format!(" ⟪ {} {} ⟫ ", count, sidebar_type),
+    };
+    let title = Line::from(title).left_aligned().fg(theme.accent);
 
     let keymaps = if state.get_pane() == Pane::SideBar {
         match state.display_state.sidebar_view {
@@ -46,6 +53,16 @@ pub fn create_standard_list<'a>(
                     false => Line::from(playlist_keymaps).centered().fg(theme.text_muted),
                 }
             }
+            _ => {
+                let category_keymaps = match state.category_is_open() {
+                    true => " [h] Back | [q]ueue Album ",
+                    false => " [l] Albums | [q]ueue All ",
+                };
+                match area.width as usize + 2 < category_keymaps.len() {
+                    true => Line::default(),
+                    false => Line::from(category_keymaps).centered().fg(theme.text_muted),
+                }
+            }
         }
     } else {
         Line::default()
@@ -8,7 +8,10 @@ use unicode_width::UnicodeWidthStr;
 
 use crate::{
     truncate_at_last_space,
-    tui::widgets::sidebar::{KILL_WIDTH_PLAYLIST, PADDING_L, PADDING_R, create_standard_list},
+    tui::widgets::{
+        SMART_PLAYLIST_ICON,
+        sidebar::{KILL_WIDTH_PLAYLIST, PADDING_L, PADDING_R, create_standard_list},
+    },
     ui_state::{LayoutStyle, Pane, UiState},
 };
 
@@ -70,7 +73,16 @@ impl StatefulWidget for SideBarPlaylist {
 
                 let count_width = count_str.len() as u16;
 
-                let max_name_width = area.width.saturating_sub(count_width + padding + 1) as usize;
+                let icon = match p.is_smart() {
+                    true => SMART_PLAYLIST_ICON,
+                    false => "",
+                };
+                let icon_width = icon.width() as u16;
+
+                let max_name_width = area
+                    .width
+                    .saturating_sub(count_width + icon_width + padding + 1)
+                    as usize;
 
                 let name = match p.name.width() > max_name_width {
                     true => truncate_at_last_space(&p.name, max_name_width),
@@ -81,9 +93,11 @@ impl StatefulWidget for SideBarPlaylist {
                     .width
                     .saturating_sub(padding)
                     .saturating_sub(name.width() as u16)
+                    .saturating_sub(icon_width)
                     .saturating_sub(count_width) as usize;
 
                 ListItem::new(Line::from_iter([
+                    Span::from(icon).fg(theme.text_muted),
                     Span::from(name).fg(theme.text_secondary),
                     Span::from(" ".repeat(n)),
                     Span::from(count_str).fg(theme.text_muted),
@@ -17,9 +17,7 @@ impl StatefulWidget for SongTable {
     ) {
         match state.get_mode() {
             &Mode::Library(LibraryView::Albums) => AlbumView.render(area, buf, state),
-            &Mode::Library(LibraryView::Playlists) | &Mode::Queue => {
-                GenericView.render(area, buf, state)
-            }
+            &Mode::Library(_) | &Mode::Queue => GenericView.render(area, buf, state),
             _ => StandardTable.render(area, buf, state),
         }
     }
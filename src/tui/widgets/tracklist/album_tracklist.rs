@@ -46,6 +46,7 @@ impl StatefulWidget for AlbumView {
                 let icon = CellFactory::status_cell(song, state, is_m_selected);
                 let title = CellFactory::title_cell(theme, song.get_title(), is_m_selected);
                 let artist = CellFactory::artist_cell(theme, song, is_m_selected);
+                let rating = CellFactory::rating_cell(state, song, is_m_selected);
                 let format = CellFactory::filetype_cell(theme, song, is_m_selected);
                 let duration =
                     CellFactory::duration_cell(theme, song, DurationStyle::Clean, is_m_selected);
@@ -53,11 +54,11 @@ impl StatefulWidget for AlbumView {
                 match state.get_layout() {
                     LayoutStyle::Traditional => match is_m_selected {
                         true => {
-                            Row::new([track_disc_no, icon, title.into(), artist, format, duration])
+                            Row::new([track_disc_no, icon, title, artist, rating, format, duration])
                                 .bg(state.theme_manager.active.accent_inactive)
                         }
                         false => {
-                            Row::new([track_disc_no, icon, title.into(), artist, format, duration])
+                            Row::new([track_disc_no, icon, title, artist, rating, format, duration])
                         }
                     },
                     LayoutStyle::Minimal => match is_m_selected {
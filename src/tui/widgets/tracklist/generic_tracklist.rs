@@ -1,12 +1,15 @@
 use crate::{
     DurationStyle,
     library::SongInfo,
-    tui::widgets::tracklist::{CellFactory, create_standard_table, get_title},
-    ui_state::{LayoutStyle, Pane, UiState},
+    tui::widgets::{
+        RADIO_ICON,
+        tracklist::{CellFactory, create_standard_table, get_title},
+    },
+    ui_state::{LayoutStyle, Mode, Pane, UiState},
 };
 use ratatui::{
     style::Stylize,
-    widgets::{Row, StatefulWidget},
+    widgets::{Cell, Row, StatefulWidget},
 };
 
 pub struct GenericView;
@@ -30,19 +33,32 @@ impl StatefulWidget for GenericView {
 
                 let index =
                     CellFactory::index_cell(&theme, &state.get_layout(), idx, is_m_selected);
-                let icon = CellFactory::status_cell(song, state, is_m_selected);
-                let title = CellFactory::title_cell(&theme, song.get_title(), is_m_selected);
+                let auto_added =
+                    matches!(state.get_mode(), Mode::Queue) && state.playback.is_auto_added(idx);
+
+                // Songs added by the radio are dimmed and marked in place of the status
+                let (icon, title) = match auto_added && !is_m_selected {
+                    true => (
+                        Cell::from(RADIO_ICON.fg(theme.text_muted)),
+                        Cell::from(song.get_title().to_owned()).fg(theme.text_muted),
+                    ),
+                    false => (
+                        CellFactory::status_cell(song, state, is_m_selected),
+                        CellFactory::title_cell(theme, song.get_title(), is_m_selected),
+                    ),
+                };
                 let artist = CellFactory::artist_cell(&theme, song, is_m_selected);
+                let rating = CellFactory::rating_cell(state, song, is_m_selected);
                 let filetype = CellFactory::filetype_cell(&theme, song, is_m_selected);
                 let duration =
                     CellFactory::duration_cell(theme, song, DurationStyle::Clean, is_m_selected);
 
                 match state.get_layout() {
                     LayoutStyle::Traditional => match is_m_selected {
-                        true => Row::new([index, icon, title, artist, filetype, duration])
+                        true => Row::new([index, icon, title, artist, rating, filetype, duration])
                             .fg(theme.text_selected)
                             .bg(state.theme_manager.active.accent_inactive),
-                        false => Row::new([index, icon, title, artist, filetype, duration]),
+                        false => Row::new([index, icon, title, artist, rating, filetype, duration]),
                     },
                     LayoutStyle::Minimal => match is_m_selected {
                         true => Row::new([index, icon, title, duration])
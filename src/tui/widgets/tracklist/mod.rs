@@ -14,8 +14,9 @@ pub use search_results::StandardTable;
 use crate::{
     DurationStyle, get_readable_duration,
     library::{SimpleSong, SongInfo},
+    playback::PlayMode,
     truncate_at_last_space,
-    tui::widgets::{MUSIC_NOTE, QUEUED, SELECTED},
+    tui::widgets::{FAVORITE, MUSIC_NOTE, QUEUED, SELECTED, STAR},
     ui_state::{DisplayTheme, LayoutStyle, LibraryView, Mode, Pane, UiState, fade_color},
 };
 use ratatui::{
@@ -26,6 +27,8 @@ use ratatui::{
 };
 
 const COLUMN_SPACING: u16 = 2;
+/// A favorite marker followed by up to five stars
+const RATING_WIDTH: u16 = 6;
 
 pub(super) fn get_widths(state: &UiState) -> Vec<Constraint> {
     let layout = state.get_layout();
@@ -45,6 +48,7 @@ pub(super) fn get_widths(state: &UiState) -> Vec<Constraint> {
                 Constraint::Ratio(3, 9),
                 Constraint::Ratio(2, 9),
                 Constraint::Ratio(2, 9),
+                Constraint::Length(RATING_WIDTH),
                 Constraint::Length(8),
             ],
             LayoutStyle::Minimal => {
@@ -61,6 +65,7 @@ pub(super) fn get_widths(state: &UiState) -> Vec<Constraint> {
                 Constraint::Length(1),
                 Constraint::Min(25),
                 Constraint::Max(20),
+                Constraint::Length(RATING_WIDTH),
                 Constraint::Length(4),
                 Constraint::Length(max_dur_len),
             ],
@@ -183,6 +188,28 @@ impl CellFactory {
         Cell::from(Line::from(song.get_artist().to_string())).fg(set_color_selection(ms, theme))
     }
 
+    pub fn rating_cell(state: &UiState, song: &Arc<SimpleSong>, ms: bool) -> Cell<'static> {
+        let focus = matches!(state.get_pane(), Pane::TrackList | Pane::Search);
+        let theme = state.theme_manager.get_display_theme(focus);
+        let rating = state.get_rating(song.id);
+
+        let favorite = match rating.favorite {
+            true => FAVORITE,
+            false => " ",
+        };
+
+        Cell::from(Line::from_iter([
+            favorite.fg(match ms {
+                true => theme.text_selected,
+                false => theme.accent,
+            }),
+            STAR.repeat(rating.stars as usize).fg(match ms {
+                true => theme.text_selected,
+                false => theme.text_secondary,
+            }),
+        ]))
+    }
+
     pub fn filetype_cell(theme: &DisplayTheme, song: &Arc<SimpleSong>, ms: bool) -> Cell<'static> {
         Cell::from(Line::from(format!("{}", song.filetype)).centered()).fg(match ms {
             true => theme.text_selected,
@@ -296,10 +323,18 @@ fn get_title(state: &UiState, area: Rect) -> Line<'static> {
     match state.get_mode() {
         &Mode::Queue => {
             let q = state.playback.queue_len();
+            let mode = match state.playback.get_play_mode() {
+                PlayMode::Off => String::new(),
+                mode => format!(" ⫽ {mode}"),
+            };
+            let auto_added = match state.playback.auto_added_len() {
+                0 => String::new(),
+                n => format!(" ⫽ {n} Auto-added"),
+            };
             let queue_len = match q {
-                0 => format!("[0 Songs] "),
-                1 => format!("[1 Song] "),
-                _ => format!("[{q} Songs] "),
+                0 => format!("[0 Songs{mode}] "),
+                1 => format!("[1 Song{mode}{auto_added}] "),
+                _ => format!("[{q} Songs{mode}{auto_added}] "),
             };
 
             Line::from_iter([
@@ -340,6 +375,28 @@ fn get_title(state: &UiState, area: Rect) -> Line<'static> {
                 info.fg(theme.text_muted),
             ])
         }
+        &Mode::Library(_) => {
+            let name = match (state.get_category_album(), state.get_selected_category()) {
+                (Some(album), _) => album.title.to_string(),
+                (None, Some(category)) => category.name.clone(),
+                _ => return "".into(),
+            };
+
+            let songs = state.get_legal_songs();
+            let song_count = match songs.len() {
+                1 => "1 Song".to_string(),
+                n => format!("{n} Songs"),
+            };
+            let total_length = songs.iter().map(|s| s.duration).sum();
+            let readable = get_readable_duration(total_length, DurationStyle::Clean);
+
+            let truncated_title = truncate_at_last_space(&name, (area.width / 3) as usize);
+
+            Line::from_iter([
+                Span::from(format!(" {truncated_title} ")).fg(theme.text_secondary),
+                format!("[{song_count} ⫽ {readable}] ").fg(theme.text_muted),
+            ])
+        }
         _ => Line::default(),
     }
 }
@@ -44,6 +44,7 @@ impl StatefulWidget for StandardTable {
                 let mut title_col = Cell::from(song.get_title()).fg(inactive);
                 let mut artist_col = Cell::from(song.get_artist()).fg(inactive);
                 let mut album_col = Cell::from(song.get_album()).fg(inactive);
+                let rating_col = CellFactory::rating_cell(state, song, false);
                 let dur_col = Cell::from(
                     Line::from(song.get_duration_str(DurationStyle::Compact)).right_aligned(),
                 )
@@ -58,9 +59,9 @@ impl StatefulWidget for StandardTable {
                 }
 
                 match state.get_layout() {
-                    LayoutStyle::Traditional => {
-                        Row::new([idx, symbol, title_col, artist_col, album_col, dur_col])
-                    }
+                    LayoutStyle::Traditional => Row::new([
+                        idx, symbol, title_col, artist_col, album_col, rating_col, dur_col,
+                    ]),
                     LayoutStyle::Minimal => Row::new([title_col, artist_col, album_col]),
                 }
             })
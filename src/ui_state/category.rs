@@ -0,0 +1,180 @@
+use super::{LibraryView, UiState};
+use crate::library::{Album, SimpleSong};
+use indexmap::IndexMap;
+use std::sync::Arc;
+
+const UNKNOWN_GENRE: &str = "[Unknown Genre]";
+const UNKNOWN_YEAR: &str = "[Unknown Year]";
+
+/// An artist, genre or year along with the albums filed under it
+pub struct Category {
+    pub name: String,
+    pub albums: Vec<Album>,
+}
+
+impl Category {
+    pub fn get_tracklist(&self) -> Vec<Arc<SimpleSong>> {
+        self.albums
+            .iter()
+            .flat_map(|a| a.tracklist.iter().cloned())
+            .collect()
+    }
+}
+
+impl UiState {
+    /// Group the library's albums for the current sidebar view. Albums are
+    /// split across genres, keeping only the songs tagged with each
+    pub(super) fn build_categories(&mut self) {
+        let view = self.display_state.sidebar_view;
+        if !view.is_category() {
+            return;
+        }
+
+        let mut groups: IndexMap<String, Category> = IndexMap::new();
+
+        let mut file = |name: &str, album: Album| {
+            groups
+                .entry(name.to_lowercase())
+                .or_insert_with(|| Category {
+                    name: name.to_string(),
+                    albums: Vec::new(),
+                })
+                .albums
+                .push(album)
+        };
+
+        for album in self.library.albums.values() {
+            match view {
+                LibraryView::Artists => file(&album.artist, album.clone()),
+                LibraryView::Years => match album.year {
+                    Some(year) => file(&year.to_string(), album.clone()),
+                    None => file(UNKNOWN_YEAR, album.clone()),
+                },
+                LibraryView::Genres => {
+                    let mut by_genre: IndexMap<String, (&str, Vec<Arc<SimpleSong>>)> =
+                        IndexMap::new();
+                    for song in album.tracklist.iter() {
+                        let names = match self.library.genres.get(&song.id) {
+                            Some(genres) if !genres.is_empty() => {
+                                genres.iter().map(String::as_str).collect()
+                            }
+                            _ => vec![UNKNOWN_GENRE],
+                        };
+
+                        for name in names {
+                            by_genre
+                                .entry(name.to_lowercase())
+                                .or_insert_with(|| (name, Vec::new()))
+                                .1
+                                .push(Arc::clone(song));
+                        }
+                    }
+
+                    for (name, songs) in by_genre.into_values() {
+                        let album = Album {
+                            tracklist: songs.into(),
+                            ..album.clone()
+                        };
+                        file(name, album);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut categories = groups.into_values().collect::<Vec<Category>>();
+        for category in &mut categories {
+            category.albums.sort_by(|a, b| {
+                a.year
+                    .cmp(&b.year)
+                    .then(a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+            });
+        }
+
+        // Unknown genres and years are listed last
+        match view {
+            LibraryView::Years => categories.sort_by_key(|c| c.albums[0].year.unwrap_or(u32::MAX)),
+            _ => categories.sort_by(|a, b| {
+                (a.name == UNKNOWN_GENRE)
+                    .cmp(&(b.name == UNKNOWN_GENRE))
+                    .then(a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            }),
+        }
+
+        self.categories = categories;
+        self.clamp_category_selection();
+    }
+
+    pub(super) fn clamp_category_selection(&mut self) {
+        let len = self.categories.len();
+        let pos = &mut self.display_state.category_pos;
+        match len {
+            0 => pos.select(None),
+            _ => pos.select(Some(pos.selected().unwrap_or(0).min(len - 1))),
+        }
+
+        let album_len = self.get_selected_category().map_or(0, |c| c.albums.len());
+        let album_pos = &mut self.display_state.category_album_pos;
+        match album_len {
+            0 => {
+                album_pos.select(None);
+                self.display_state.category_open = false;
+            }
+            _ => album_pos.select(Some(album_pos.selected().unwrap_or(0).min(album_len - 1))),
+        }
+    }
+
+    pub fn get_selected_category(&self) -> Option<&Category> {
+        self.display_state
+            .category_pos
+            .selected()
+            .and_then(|idx| self.categories.get(idx))
+    }
+
+    /// The album picked within an open category
+    pub fn get_category_album(&self) -> Option<&Album> {
+        if !self.display_state.category_open {
+            return None;
+        }
+
+        self.display_state
+            .category_album_pos
+            .selected()
+            .and_then(|idx| self.get_selected_category()?.albums.get(idx))
+    }
+
+    pub fn category_is_open(&self) -> bool {
+        self.display_state.category_open
+    }
+
+    pub(crate) fn open_category(&mut self) {
+        if self.get_selected_category().is_some() {
+            self.display_state.category_open = true;
+            self.display_state.category_album_pos.select(Some(0));
+            *self.display_state.category_album_pos.offset_mut() = 0;
+            *self.display_state.table_pos.offset_mut() = 0;
+            self.set_legal_songs();
+        }
+    }
+
+    pub(crate) fn close_category(&mut self) {
+        if self.display_state.category_open {
+            self.display_state.category_open = false;
+            *self.display_state.table_pos.offset_mut() = 0;
+            self.set_legal_songs();
+        }
+    }
+
+    pub(super) fn category_songs(&self) -> Vec<Arc<SimpleSong>> {
+        match self.display_state.category_open {
+            true => self
+                .get_category_album()
+                .map(|a| a.get_tracklist())
+                .unwrap_or_default(),
+            false => self
+                .get_selected_category()
+                .map(|c| c.get_tracklist())
+                .unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crossbeam::channel::{Receiver, Sender, unbounded};
+use image::{DynamicImage, ImageReader, RgbImage, imageops::FilterType};
+use indexmap::IndexMap;
+use ratatui::{
+    crossterm::terminal::window_size,
+    layout::{Rect, Size},
+};
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    config::AlbumArtMode,
+    library::{SimpleSong, SongDatabase, find_cover},
+    ui_state::UiState,
+    user_config,
+};
+
+/// Decoded covers are kept for the most recently viewed albums
+const CACHE_CAPACITY: usize = 32;
+/// Covers are downscaled once loaded, large enough for the fullscreen view
+const MAX_DIMENSION: u32 = 512;
+/// Assumed font size when the terminal does not report its pixel size
+const FALLBACK_CELL_SIZE: (u16, u16) = (8, 16);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ArtProtocol {
+    Kitty,
+    Sixel,
+    Iterm,
+    HalfBlocks,
+}
+
+impl ArtProtocol {
+    /// Resolve the configured mode, `None` when album art is disabled
+    fn from_config(mode: AlbumArtMode) -> Option<Self> {
+        match mode {
+            AlbumArtMode::Auto => Some(Self::detect()),
+            AlbumArtMode::Kitty => Some(Self::Kitty),
+            AlbumArtMode::Sixel => Some(Self::Sixel),
+            AlbumArtMode::Iterm => Some(Self::Iterm),
+            AlbumArtMode::Halfblocks => Some(Self::HalfBlocks),
+            AlbumArtMode::Off => None,
+        }
+    }
+
+    /// Best guess based on the environment, terminals rarely agree on a
+    /// reliable way to query support
+    fn detect() -> Self {
+        let var = |key: &str| env::var(key).unwrap_or_default();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        // Multiplexers swallow graphics sequences
+        if env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            return Self::HalfBlocks;
+        }
+
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "ghostty"
+        {
+            Self::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" || var("LC_TERMINAL") == "iTerm2" {
+            Self::Iterm
+        } else if ["foot", "mlterm", "contour", "yaft"]
+            .iter()
+            .any(|t| term.contains(t))
+            || env::var_os("WT_SESSION").is_some()
+        {
+            Self::Sixel
+        } else {
+            Self::HalfBlocks
+        }
+    }
+}
+
+pub struct Cover {
+    /// Cached image file, handed to the OS media controls
+    pub path: PathBuf,
+    /// `None` when album art is disabled or the image could not be decoded
+    pub image: Option<DynamicImage>,
+}
+
+pub enum CoverLoad {
+    Loaded(Cover),
+    Missing,
+    /// Superseded by a newer request before it was looked at
+    Skipped,
+}
+
+/// Where a cover was drawn with a graphics protocol
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub key: u64,
+    pub area: Rect,
+}
+
+struct CoverRequest {
+    key: u64,
+    song: Arc<SimpleSong>,
+    pinned: bool,
+}
+
+pub struct CoverArtManager {
+    protocol: Option<ArtProtocol>,
+    covers: IndexMap<u64, Option<Arc<Cover>>>,
+    pending: HashSet<u64>,
+    requests: Sender<CoverRequest>,
+    results: Receiver<(u64, CoverLoad)>,
+
+    pub(crate) cell_size: (u16, u16),
+    pub(crate) terminal_size: Option<Size>,
+    /// Placement requested while rendering the current frame
+    pub(crate) placement: Option<Placement>,
+    /// Placement currently on screen
+    pub(crate) shown: Option<Placement>,
+    halfblocks: Option<(u64, Size, RgbImage)>,
+}
+
+impl CoverArtManager {
+    pub fn new() -> Self {
+        let protocol = ArtProtocol::from_config(user_config().album_art);
+        let (requests, results) = spawn_loader(protocol.is_some());
+
+        CoverArtManager {
+            protocol,
+            covers: IndexMap::new(),
+            pending: HashSet::new(),
+            requests,
+            results,
+            cell_size: cell_size(),
+            terminal_size: None,
+            placement: None,
+            shown: None,
+            halfblocks: None,
+        }
+    }
+
+    pub fn protocol(&self) -> Option<ArtProtocol> {
+        self.protocol
+    }
+
+    pub fn refresh_cell_size(&mut self) {
+        self.cell_size = cell_size();
+    }
+
+    /// Returns the cover once loaded, `pinned` requests are never skipped in
+    /// favour of newer ones
+    fn get(&mut self, song: &Arc<SimpleSong>, pinned: bool) -> Option<Arc<Cover>> {
+        let key = song.cover_key();
+
+        if let Some(cover) = self.covers.get(&key) {
+            return cover.clone();
+        }
+
+        if self.pending.insert(key) {
+            let _ = self.requests.send(CoverRequest {
+                key,
+                song: Arc::clone(song),
+                pinned,
+            });
+        }
+
+        None
+    }
+
+    fn complete(&mut self, key: u64, load: CoverLoad) {
+        self.pending.remove(&key);
+
+        let cover = match load {
+            CoverLoad::Loaded(cover) => Some(Arc::new(cover)),
+            CoverLoad::Missing => None,
+            CoverLoad::Skipped => return,
+        };
+
+        self.covers.insert(key, cover);
+        if self.covers.len() > CACHE_CAPACITY {
+            self.covers.shift_remove_index(0);
+        }
+    }
+
+    pub fn get_image(&self, key: u64) -> Option<&DynamicImage> {
+        self.covers.get(&key)?.as_ref()?.image.as_ref()
+    }
+
+    /// The cover scaled to one pixel per half cell, reused while the size
+    /// stays the same
+    pub fn halfblocks(&mut self, key: u64, image: &DynamicImage, size: Size) -> &RgbImage {
+        let stale = self
+            .halfblocks
+            .as_ref()
+            .is_none_or(|(k, s, _)| *k != key || *s != size);
+
+        if stale {
+            let scaled = image
+                .resize_exact(
+                    size.width as u32,
+                    size.height as u32 * 2,
+                    FilterType::Triangle,
+                )
+                .to_rgb8();
+            self.halfblocks = Some((key, size, scaled));
+        }
+
+        &self.halfblocks.as_ref().expect("set above").2
+    }
+}
+
+impl Default for CoverArtManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn cell_size() -> (u16, u16) {
+    match window_size() {
+        Ok(ws) if ws.width > 0 && ws.height > 0 && ws.columns > 0 && ws.rows > 0 => {
+            (ws.width / ws.columns, ws.height / ws.rows)
+        }
+        _ => FALLBACK_CELL_SIZE,
+    }
+}
+
+/// Covers are extracted and decoded on a single worker. When requests pile up
+/// (e.g. scrolling through albums) only the latest and pinned ones are loaded
+fn spawn_loader(decode: bool) -> (Sender<CoverRequest>, Receiver<(u64, CoverLoad)>) {
+    let (request_tx, request_rx) = unbounded::<CoverRequest>();
+    let (result_tx, result_rx) = unbounded();
+
+    thread::spawn(move || {
+        while let Ok(first) = request_rx.recv() {
+            let batch = std::iter::once(first)
+                .chain(request_rx.try_iter())
+                .collect::<Vec<_>>();
+            let last = batch.len() - 1;
+
+            for (idx, request) in batch.into_iter().enumerate() {
+                let load = match idx == last || request.pinned {
+                    true => load_cover(&request, decode),
+                    false => CoverLoad::Skipped,
+                };
+
+                if result_tx.send((request.key, load)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    (request_tx, result_rx)
+}
+
+fn load_cover(request: &CoverRequest, decode: bool) -> CoverLoad {
+    let path = request
+        .song
+        .get_path()
+        .ok()
+        .and_then(|path| find_cover(request.key, Path::new(&path)).ok().flatten());
+
+    let Some(path) = path else {
+        return CoverLoad::Missing;
+    };
+
+    let image = match decode {
+        true => ImageReader::open(&path)
+            .and_then(|reader| reader.with_guessed_format())
+            .ok()
+            .and_then(|reader| reader.decode().ok())
+            .map(|image| image.thumbnail(MAX_DIMENSION, MAX_DIMENSION)),
+        false => None,
+    };
+
+    CoverLoad::Loaded(Cover { path, image })
+}
+
+impl UiState {
+    /// The cover of the album a song belongs to. Covers are loaded in the
+    /// background, `None` is returned until then or if the album has none
+    pub fn get_cover(&mut self, song: &Arc<SimpleSong>, pinned: bool) -> Option<Arc<Cover>> {
+        self.cover_art.get(song, pinned)
+    }
+
+    pub fn cover_reciever(&self) -> &Receiver<(u64, CoverLoad)> {
+        &self.cover_art.results
+    }
+
+    pub fn handle_cover_result(&mut self, key: u64, load: CoverLoad) {
+        self.cover_art.complete(key, load);
+    }
+}
@@ -21,6 +21,10 @@ pub struct DisplayState {
     pub sidebar_view: LibraryView,
     pub album_pos: ListState,
     pub playlist_pos: ListState,
+    pub category_pos: ListState,
+    pub category_album_pos: ListState,
+    /// Whether the sidebar lists the albums of the selected category
+    pub category_open: bool,
 
     pub table_pos: TableState,
     table_pos_cached: usize,
@@ -43,6 +47,9 @@ impl DisplayState {
             sidebar_view: LibraryView::Albums,
             album_pos: ListState::default().with_selected(Some(0)),
             playlist_pos: ListState::default().with_selected(Some(0)),
+            category_pos: ListState::default().with_selected(Some(0)),
+            category_album_pos: ListState::default(),
+            category_open: false,
 
             table_pos: TableState::default().with_selected(0),
             table_pos_cached: 0,
@@ -75,6 +82,10 @@ impl UiState {
         let count = match sidebar_type {
             LibraryView::Albums => self.albums.len(),
             LibraryView::Playlists => self.playlists.len(),
+            _ => match self.display_state.category_open {
+                true => self.get_selected_category().map_or(0, |c| c.albums.len()),
+                false => self.categories.len(),
+            },
         };
 
         (*sidebar_type, count)
@@ -105,6 +116,11 @@ impl UiState {
             }
 
             Mode::Library(view) => {
+                if view.is_category() && view != self.display_state.sidebar_view {
+                    self.display_state.category_pos.select(Some(0));
+                    *self.display_state.category_pos.offset_mut() = 0;
+                    self.display_state.category_open = false;
+                }
                 self.display_state.sidebar_view = view;
                 self.display_state.mode = Mode::Library(view);
                 self.display_state.pane = Pane::SideBar;
@@ -125,6 +141,7 @@ impl UiState {
                             self.display_state.playlist_pos.select(Some(0));
                         }
                     }
+                    _ => self.build_categories(),
                 }
 
                 *self.display_state.table_pos.offset_mut() = 0;
@@ -149,6 +166,7 @@ impl UiState {
             Mode::Search => {
                 self.display_state.table_sort = TableSort::Title;
                 self.search.input.clear();
+                self.search.stats = None;
                 self.display_state.mode = Mode::Search;
                 self.display_state.pane = Pane::Search;
             }
@@ -274,6 +292,18 @@ impl UiState {
                     .partial_cmp(&b.duration)
                     .expect("Error sorting by duration.")
             }),
+            // Highest rated first, favorites ahead of others with as many stars
+            TableSort::Rating => {
+                let ratings = &self.ratings;
+                self.legal_songs.sort_by(|a, b| {
+                    let rating_a = ratings.get(&a.id).copied().unwrap_or_default();
+                    let rating_b = ratings.get(&b.id).copied().unwrap_or_default();
+
+                    (rating_b.stars, rating_b.favorite)
+                        .cmp(&(rating_a.stars, rating_a.favorite))
+                        .then_with(|| a.title.cmp(&b.title))
+                })
+            }
         };
     }
 
@@ -373,6 +403,7 @@ impl UiState {
                         self.legal_songs.clear()
                     }
                 }
+                _ => self.legal_songs = self.category_songs(),
             },
             Mode::Queue => self.legal_songs = self.playback.get_queue(),
 
@@ -432,6 +463,13 @@ impl UiState {
         let (items_len, state) = match self.display_state.sidebar_view {
             LibraryView::Albums => (self.albums.len(), &mut self.display_state.album_pos),
             LibraryView::Playlists => (self.playlists.len(), &mut self.display_state.playlist_pos),
+            _ => match self.display_state.category_open {
+                true => (
+                    self.get_selected_category().map_or(0, |c| c.albums.len()),
+                    &mut self.display_state.category_album_pos,
+                ),
+                false => (self.categories.len(), &mut self.display_state.category_pos),
+            },
         };
 
         if items_len == 0 {
@@ -5,6 +5,9 @@ pub enum LibraryView {
     #[default]
     Albums,
     Playlists,
+    Artists,
+    Genres,
+    Years,
 }
 
 impl Display for LibraryView {
@@ -12,6 +15,35 @@ impl Display for LibraryView {
         match self {
             LibraryView::Albums => write!(f, "Albums"),
             LibraryView::Playlists => write!(f, "Playlists"),
+            LibraryView::Artists => write!(f, "Artists"),
+            LibraryView::Genres => write!(f, "Genres"),
+            LibraryView::Years => write!(f, "Years"),
+        }
+    }
+}
+
+impl LibraryView {
+    /// Views which group albums under an artist, genre or year
+    pub fn is_category(&self) -> bool {
+        matches!(
+            self,
+            LibraryView::Artists | LibraryView::Genres | LibraryView::Years
+        )
+    }
+
+    pub fn next_category(&self) -> LibraryView {
+        match self {
+            LibraryView::Artists => LibraryView::Genres,
+            LibraryView::Genres => LibraryView::Years,
+            _ => LibraryView::Artists,
+        }
+    }
+
+    pub fn prev_category(&self) -> LibraryView {
+        match self {
+            LibraryView::Years => LibraryView::Genres,
+            LibraryView::Artists => LibraryView::Years,
+            _ => LibraryView::Artists,
         }
     }
 }
@@ -44,6 +76,9 @@ impl Display for Mode {
             Mode::Power => write!(f, "power"),
             Mode::Library(LibraryView::Albums) => write!(f, "library_album"),
             Mode::Library(LibraryView::Playlists) => write!(f, "library_playlist"),
+            Mode::Library(LibraryView::Artists) => write!(f, "library_artist"),
+            Mode::Library(LibraryView::Genres) => write!(f, "library_genre"),
+            Mode::Library(LibraryView::Years) => write!(f, "library_year"),
             Mode::Fullscreen => write!(f, "fullscreen"),
             Mode::Queue => write!(f, "queue"),
             Mode::Search => write!(f, "search"),
@@ -58,6 +93,9 @@ impl Mode {
             "power" => Mode::Power,
             "library_album" => Mode::Library(LibraryView::Albums),
             "library_playlist" => Mode::Library(LibraryView::Playlists),
+            "library_artist" => Mode::Library(LibraryView::Artists),
+            "library_genre" => Mode::Library(LibraryView::Genres),
+            "library_year" => Mode::Library(LibraryView::Years),
             "queue" => Mode::Queue,
             "search" => Mode::Search,
             "quit" => Mode::QUIT,
@@ -4,6 +4,7 @@ pub enum TableSort {
     Artist,
     Album,
     Duration,
+    Rating,
 }
 
 impl ToString for TableSort {
@@ -13,6 +14,7 @@ impl ToString for TableSort {
             TableSort::Artist => "Artist".into(),
             TableSort::Album => "Album".into(),
             TableSort::Duration => "Duration".into(),
+            TableSort::Rating => "Rating".into(),
         }
     }
 }
@@ -23,15 +25,17 @@ impl TableSort {
             TableSort::Title => TableSort::Artist,
             TableSort::Artist => TableSort::Album,
             TableSort::Album => TableSort::Duration,
-            TableSort::Duration => TableSort::Title,
+            TableSort::Duration => TableSort::Rating,
+            TableSort::Rating => TableSort::Title,
         }
     }
     pub fn prev(&self) -> Self {
         match self {
-            TableSort::Title => TableSort::Duration,
+            TableSort::Title => TableSort::Rating,
             TableSort::Artist => TableSort::Title,
             TableSort::Album => TableSort::Artist,
             TableSort::Duration => TableSort::Album,
+            TableSort::Rating => TableSort::Duration,
         }
     }
 }
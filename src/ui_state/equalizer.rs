@@ -0,0 +1,270 @@
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{
+    EQ_DIR,
+    key_handler::Incrementor,
+    player::{EQ_BANDS, EQ_MAX_GAIN, EqGains},
+    ui_state::{PopupType, UiState},
+};
+
+/// Shipped presets, files in `EQ_DIR` with the same name take priority
+const BUILTIN_PRESETS: [(&str, f32, [f32; EQ_BANDS]); 5] = [
+    ("flat", 0.0, [0.0; EQ_BANDS]),
+    (
+        "bass",
+        -4.0,
+        [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    ),
+    (
+        "treble",
+        -4.0,
+        [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 4.0, 5.0, 6.0],
+    ),
+    (
+        "vocal",
+        -2.0,
+        [-2.0, -2.0, -1.0, 1.0, 3.0, 3.0, 2.0, 1.0, 0.0, -1.0],
+    ),
+    (
+        "loudness",
+        -4.0,
+        [5.0, 4.0, 2.0, 0.0, -1.0, -1.0, 0.0, 2.0, 4.0, 5.0],
+    ),
+];
+
+#[derive(Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    preamp: f32,
+    bands: Vec<f32>,
+}
+
+#[derive(Clone)]
+pub struct EqPreset {
+    pub name: String,
+    pub gains: EqGains,
+}
+
+impl EqPreset {
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file_str = std::fs::read_to_string(path.as_ref())?;
+        let file = toml::from_str::<PresetFile>(&file_str)?;
+
+        let Ok(bands) = <[f32; EQ_BANDS]>::try_from(file.bands) else {
+            bail!("Equalizer presets need exactly {EQ_BANDS} bands");
+        };
+
+        let name = path
+            .as_ref()
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or(anyhow!("Could not identify preset name"))?
+            .to_string();
+
+        Ok(EqPreset {
+            name,
+            gains: EqGains {
+                preamp: clamp_gain(file.preamp),
+                bands: bands.map(clamp_gain),
+            },
+        })
+    }
+}
+
+pub struct EqualizerState {
+    pub(crate) presets: Vec<EqPreset>,
+    /// Preset the gains were taken from, `None` once a band is adjusted
+    preset: Option<String>,
+    gains: EqGains,
+    enabled: bool,
+    overlay: bool,
+    /// Column selected in the popup, 0 is the preamp
+    selected: usize,
+}
+
+impl EqualizerState {
+    pub fn new() -> Self {
+        EqualizerState {
+            presets: Self::collect_presets(),
+            preset: Some(BUILTIN_PRESETS[0].0.to_string()),
+            gains: EqGains::default(),
+            enabled: false,
+            overlay: false,
+            selected: 0,
+        }
+    }
+
+    fn collect_presets() -> Vec<EqPreset> {
+        let mut presets = vec![];
+
+        if let Ok(entries) = EQ_DIR.read_dir() {
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                if path.extension().and_then(|s| s.to_str()) == Some("toml")
+                    && let Ok(preset) = EqPreset::load_from_file(&path)
+                {
+                    presets.push(preset);
+                }
+            }
+        }
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let builtin = BUILTIN_PRESETS
+            .iter()
+            .filter(|(name, ..)| !presets.iter().any(|p| p.name == *name))
+            .map(|(name, preamp, bands)| EqPreset {
+                name: name.to_string(),
+                gains: EqGains {
+                    preamp: *preamp,
+                    bands: *bands,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        builtin.into_iter().chain(presets).collect()
+    }
+
+    fn apply_preset(&mut self, preset: &EqPreset) {
+        self.gains = preset.gains;
+        self.preset = Some(preset.name.clone());
+        self.enabled = true;
+    }
+
+    fn selected_gain(&mut self) -> &mut f32 {
+        match self.selected {
+            0 => &mut self.gains.preamp,
+            n => &mut self.gains.bands[n - 1],
+        }
+    }
+
+    fn mark_edited(&mut self) {
+        self.preset = None;
+        self.enabled = true;
+    }
+}
+
+impl Default for EqualizerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn clamp_gain(gain: f32) -> f32 {
+    match gain.is_finite() {
+        true => gain.clamp(-EQ_MAX_GAIN, EQ_MAX_GAIN),
+        false => 0.0,
+    }
+}
+
+impl UiState {
+    pub fn open_equalizer(&mut self) {
+        self.equalizer.presets = EqualizerState::collect_presets();
+        self.show_popup(PopupType::Equalizer);
+    }
+
+    /// Gains handed to the player, flat while the equalizer is disabled
+    pub fn get_eq_gains(&self) -> EqGains {
+        match self.equalizer.enabled {
+            true => self.equalizer.gains,
+            false => EqGains::default(),
+        }
+    }
+
+    /// Gains shown in the popup, regardless of whether they're applied
+    pub fn get_eq_settings(&self) -> &EqGains {
+        &self.equalizer.gains
+    }
+
+    pub fn get_eq_preset(&self) -> Option<&str> {
+        self.equalizer.preset.as_deref()
+    }
+
+    pub fn get_eq_selection(&self) -> usize {
+        self.equalizer.selected
+    }
+
+    pub fn eq_enabled(&self) -> bool {
+        self.equalizer.enabled
+    }
+
+    pub fn eq_overlay(&self) -> bool {
+        self.equalizer.overlay
+    }
+
+    /// The overlay is only drawn while the equalizer is enabled
+    pub fn eq_overlay_visible(&self) -> bool {
+        self.equalizer.enabled && self.equalizer.overlay
+    }
+
+    pub fn eq_select_band(&mut self, dir: Incrementor) {
+        let len = EQ_BANDS + 1;
+        let idx = self.equalizer.selected;
+
+        self.equalizer.selected = match dir {
+            Incrementor::Up => (idx + len - 1) % len,
+            Incrementor::Down => (idx + 1) % len,
+        };
+    }
+
+    pub fn eq_adjust_gain(&mut self, delta: i8) {
+        let gain = self.equalizer.selected_gain();
+        *gain = clamp_gain(*gain + delta as f32);
+        self.equalizer.mark_edited();
+    }
+
+    pub fn eq_reset_band(&mut self) {
+        *self.equalizer.selected_gain() = 0.0;
+        self.equalizer.mark_edited();
+    }
+
+    pub fn eq_cycle_preset(&mut self, dir: Incrementor) {
+        let len = self.equalizer.presets.len();
+        if len == 0 {
+            return;
+        }
+
+        let new_idx = match self.find_eq_preset_index() {
+            Some(idx) => match dir {
+                Incrementor::Up => (idx + len - 1) % len,
+                Incrementor::Down => (idx + 1) % len,
+            },
+            None => 0,
+        };
+
+        let preset = self.equalizer.presets[new_idx].clone();
+        self.equalizer.apply_preset(&preset);
+    }
+
+    fn find_eq_preset_index(&self) -> Option<usize> {
+        let name = self.equalizer.preset.as_deref()?;
+        self.equalizer.presets.iter().position(|p| p.name == name)
+    }
+
+    pub fn toggle_equalizer(&mut self) {
+        self.equalizer.enabled = !self.equalizer.enabled;
+    }
+
+    pub fn toggle_eq_overlay(&mut self) {
+        self.equalizer.overlay = !self.equalizer.overlay;
+    }
+
+    pub(crate) fn restore_equalizer(
+        &mut self,
+        enabled: bool,
+        overlay: bool,
+        preset: &str,
+        gains: EqGains,
+    ) {
+        let eq = &mut self.equalizer;
+        eq.gains = EqGains {
+            preamp: clamp_gain(gains.preamp),
+            bands: gains.bands.map(clamp_gain),
+        };
+        eq.preset = (!preset.is_empty()).then(|| preset.to_string());
+        eq.enabled = enabled;
+        eq.overlay = overlay;
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use crossbeam::channel::Receiver;
+use std::{path::Path, thread};
+
+use crate::{
+    library::{Lyrics, SimpleSong, SongDatabase},
+    ui_state::UiState,
+};
+
+/// Offset adjustments are clamped to a minute either way
+const MAX_OFFSET_MS: i64 = 60_000;
+
+pub enum LyricsState {
+    None,
+    Loading,
+    Ready(Lyrics),
+    Missing,
+}
+
+pub struct LyricsManager {
+    state: LyricsState,
+    /// Adjustment in milliseconds on top of the file's own offset, reset
+    /// whenever a new song starts
+    offset: i64,
+    reciever: Option<Receiver<Result<Option<Lyrics>>>>,
+}
+
+impl LyricsManager {
+    pub fn new() -> Self {
+        LyricsManager {
+            state: LyricsState::None,
+            offset: 0,
+            reciever: None,
+        }
+    }
+
+    pub fn request(&mut self, song: &SimpleSong) {
+        self.offset = 0;
+
+        match song.get_path() {
+            Ok(path) => {
+                let (tx, rx) = crossbeam::channel::bounded(1);
+                self.state = LyricsState::Loading;
+
+                thread::spawn(move || {
+                    let _ = tx.send(Lyrics::load(Path::new(&path)));
+                });
+
+                self.reciever = Some(rx)
+            }
+            Err(_) => self.state = LyricsState::Missing,
+        }
+    }
+
+    pub fn complete(&mut self, result: Result<Option<Lyrics>>) {
+        self.state = match result {
+            Ok(Some(lyrics)) => LyricsState::Ready(lyrics),
+            _ => LyricsState::Missing,
+        };
+        self.reciever = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.reciever = None;
+        self.offset = 0;
+        self.state = LyricsState::None;
+    }
+}
+
+impl Default for LyricsManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UiState {
+    pub fn request_lyrics(&mut self, song: &SimpleSong) {
+        self.lyrics.request(song);
+    }
+
+    pub fn handle_lyrics_result(&mut self, result: Result<Option<Lyrics>>) {
+        self.lyrics.complete(result);
+    }
+
+    pub fn lyrics_reciever(&self) -> Option<&Receiver<Result<Option<Lyrics>>>> {
+        self.lyrics.reciever.as_ref()
+    }
+
+    pub fn clear_lyrics(&mut self) {
+        self.lyrics.clear();
+    }
+
+    pub fn get_lyrics_state(&self) -> &LyricsState {
+        &self.lyrics.state
+    }
+
+    pub fn get_lyrics_offset(&self) -> i64 {
+        self.lyrics.offset
+    }
+
+    /// Positive values show lines sooner
+    pub fn shift_lyrics_offset(&mut self, ms: i64) {
+        self.lyrics.offset = (self.lyrics.offset + ms).clamp(-MAX_OFFSET_MS, MAX_OFFSET_MS);
+    }
+}
@@ -1,36 +1,51 @@
+mod category;
+mod cover_art;
 mod display_state;
 mod domain;
+mod equalizer;
+mod lyrics;
 mod multi_select;
 mod playlist;
 mod popup;
 mod progress_display;
+mod ratings;
+mod search_query;
 mod search_state;
 mod settings;
 mod spectrum;
 mod stats;
+mod tag_editor;
 mod theme;
 mod ui_snapshot;
 mod ui_state;
 mod waveform;
 
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
+pub use category::Category;
+pub use cover_art::{ArtProtocol, Cover, CoverArtManager, CoverLoad, Placement};
 pub use display_state::DisplayState;
 pub use domain::{AlbumSort, LibraryView, Mode, Pane, TableSort};
+pub use equalizer::{EqPreset, EqualizerState};
+pub use lyrics::{LyricsManager, LyricsState};
 pub use playlist::PlaylistAction;
 pub use popup::PopupType;
 pub use progress_display::ProgressDisplay;
 pub use search_state::MatchField;
 pub use settings::SettingsMode;
-pub use stats::LibraryStats;
+pub use stats::{LibraryStats, ListeningDay, ListeningStats, StatsPage, StatsPeriod};
+pub use tag_editor::{TagEditor, TagEditorField};
 pub use theme::DisplayTheme;
 pub use ui_snapshot::UiSnapshot;
-pub use waveform::WaveformManager;
+pub use waveform::{WaveformManager, precompute_waveforms};
 
 use crate::{
     Library, PlaybackSession,
     database::DbWorker,
-    library::{Album, Playlist, SimpleSong},
+    library::{Album, Playlist, Rating, SimpleSong},
     player::PlaybackMetrics,
     ui_state::{
         popup::PopupState, search_state::SearchState, spectrum::SpectrumState, stats::VoxStats,
@@ -78,12 +93,18 @@ pub struct UiState {
 
     pub(crate) layout: LayoutStyle,
     waveform: WaveformManager,
+    pub(crate) cover_art: CoverArtManager,
+    lyrics: LyricsManager,
+    equalizer: EqualizerState,
+    pub(crate) tag_editor: TagEditor,
     progress_display: ProgressDisplay,
     stats: VoxStats,
 
     legal_songs: Vec<Arc<SimpleSong>>,
+    ratings: HashMap<u64, Rating>,
     pub(crate) albums: Vec<Album>,
     pub(crate) playlists: Vec<Playlist>,
+    pub(crate) categories: Vec<Category>,
 
     pub library_refresh_progress: Option<u8>,
     pub library_refresh_detail: Option<String>,
@@ -1,9 +1,9 @@
 use crate::{
     key_handler::{Director, Incrementor, SelectionType},
-    library::SimpleSong,
+    library::{Playlist, SimpleSong},
     ui_state::{Mode, UiState},
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use indexmap::IndexSet;
 use std::sync::Arc;
 
@@ -91,13 +91,23 @@ impl UiState {
         self.display_state.multi_select.clear();
     }
 
+    /// The selected playlist, as long as its songs can be changed by hand
+    fn get_editable_playlist(&self) -> Result<&Playlist> {
+        let playlist = self
+            .get_selected_playlist()
+            .ok_or_else(|| anyhow!("No playlist selected"))?;
+
+        if playlist.is_smart() {
+            bail!("Smart playlists are edited through their rules!");
+        }
+
+        Ok(playlist)
+    }
+
     pub fn remove_from_playlist(&mut self) -> Result<()> {
         let song_idx = self.get_selected_idx()?;
 
-        let playlist_id = self
-            .get_selected_playlist()
-            .ok_or_else(|| anyhow!("No playlist selected"))?
-            .id;
+        let playlist_id = self.get_editable_playlist()?.id;
 
         let playlist = self
             .playlists
@@ -121,10 +131,7 @@ impl UiState {
 
     pub fn remove_from_playlist_multi(&mut self) -> Result<()> {
         // Obtain selected playlist id
-        let playlist_id = self
-            .get_selected_playlist()
-            .ok_or_else(|| anyhow!("No song selected"))?
-            .id;
+        let playlist_id = self.get_editable_playlist()?.id;
 
         let mut indicies = self.get_multi_select_indices().clone();
         indicies.sort_unstable();
@@ -169,6 +176,8 @@ impl UiState {
     }
 
     pub fn shift_playlist_position(&mut self, dir: Incrementor) -> Result<()> {
+        self.get_editable_playlist()?;
+
         match self.multi_select_empty() {
             true => self.shift_playlist_position_single(dir)?,
             false => self.shift_playlist_position_multi(dir)?,
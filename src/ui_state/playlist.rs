@@ -1,5 +1,5 @@
 use crate::{
-    library::{Playlist, PlaylistSong},
+    library::{Playlist, PlaylistSong, SmartRules},
     ui_state::{LibraryView, PopupType, UiState},
 };
 use anyhow::{Result, anyhow, bail};
@@ -11,6 +11,9 @@ pub enum PlaylistAction {
     Delete,
     Rename,
     CreateWithSongs,
+    CreateSmart,
+    CreateSmartRules(String),
+    EditRules,
 }
 
 impl UiState {
@@ -18,9 +21,26 @@ impl UiState {
         let playlist_db = self.db_worker.build_playlists()?;
         let songs_map = self.library.get_songs_map();
 
+        let stats = match playlist_db.keys().any(|(_, _, rules)| rules.is_some()) {
+            true => self.db_worker.get_song_stats()?,
+            false => Default::default(),
+        };
+
         self.playlists = playlist_db
             .iter()
-            .map(|((id, name), track_ids)| {
+            .map(|((id, name, rules), track_ids)| {
+                if let Some(rules) = rules {
+                    // Rules are validated before saving, an unparsable rule
+                    // set (e.g. from a newer version) yields an empty list
+                    return match SmartRules::parse(rules) {
+                        Ok(rules) => {
+                            let songs = rules.evaluate(songs_map, &stats);
+                            Playlist::smart(*id, name.to_string(), rules, songs)
+                        }
+                        Err(_) => Playlist::new(*id, name.to_string(), vec![]),
+                    };
+                }
+
                 let tracklist = track_ids
                     .iter()
                     .filter_map(|&s_id| {
@@ -75,6 +95,78 @@ impl UiState {
         Ok(())
     }
 
+    pub fn create_smart_playlist_popup(&mut self) {
+        if self.get_sidebar_view() == &LibraryView::Playlists {
+            self.show_popup(PopupType::Playlist(PlaylistAction::CreateSmart));
+        }
+    }
+
+    /// Smart playlists are named first, their rules are asked for after
+    pub fn create_smart_playlist(&mut self) -> Result<()> {
+        let PopupType::Playlist(PlaylistAction::CreateSmartRules(name)) = &self.popup.current
+        else {
+            let name = self.get_popup_string();
+
+            if name.is_empty() {
+                bail!("Playlist name cannot be empty!");
+            }
+
+            if self
+                .playlists
+                .iter()
+                .any(|p| p.name.to_lowercase() == name.to_lowercase())
+            {
+                bail!("Playlist name already exists!");
+            }
+
+            self.show_popup(PopupType::Playlist(PlaylistAction::CreateSmartRules(name)));
+            return Ok(());
+        };
+
+        let name = name.clone();
+        let rules = SmartRules::parse(&self.get_popup_string())?;
+        self.db_worker
+            .create_smart_playlist(name, rules.as_str().to_string())?;
+
+        self.get_playlists()?;
+
+        if !self.playlists.is_empty() {
+            self.display_state.playlist_pos.select_first();
+        }
+
+        self.set_legal_songs();
+        self.close_popup();
+        Ok(())
+    }
+
+    pub fn edit_playlist_rules_popup(&mut self) {
+        let Some(rules) = self
+            .get_selected_playlist()
+            .and_then(|p| p.rules.as_ref())
+            .map(|r| r.as_str().to_string())
+        else {
+            return;
+        };
+
+        self.show_popup(PopupType::Playlist(PlaylistAction::EditRules));
+        self.popup.input.insert_str(rules);
+    }
+
+    pub fn edit_playlist_rules(&mut self) -> Result<()> {
+        let playlist = self
+            .get_selected_playlist()
+            .ok_or_else(|| anyhow!("No playlist selected!"))?;
+
+        let rules = SmartRules::parse(&self.get_popup_string())?;
+        self.db_worker
+            .set_playlist_rules(playlist.id, rules.as_str().to_string())?;
+
+        self.get_playlists()?;
+        self.set_legal_songs();
+        self.close_popup();
+        Ok(())
+    }
+
     pub fn rename_playlist_popup(&mut self) {
         if self.get_selected_playlist().is_some() {
             self.show_popup(PopupType::Playlist(PlaylistAction::Rename));
@@ -152,7 +244,11 @@ impl UiState {
     pub fn add_to_playlist(&mut self) -> Result<()> {
         match self.popup.selection.selected() {
             Some(playlist_idx) => {
-                let playlist_id = self.playlists.get(playlist_idx).unwrap().id;
+                let playlist = self.playlists.get(playlist_idx).unwrap();
+                if playlist.is_smart() {
+                    bail!("Songs cannot be added to a smart playlist!");
+                }
+                let playlist_id = playlist.id;
                 match self.multi_select_empty() {
                     true => {
                         let song_id = self.get_selected_song()?.id;
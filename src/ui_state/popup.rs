@@ -14,6 +14,8 @@ pub enum PopupType {
     Settings(SettingsMode),
     Playlist(PlaylistAction),
     ThemeManager,
+    Equalizer,
+    TagEditor,
 }
 
 pub struct PopupState {
@@ -37,11 +39,18 @@ impl PopupState {
         match &popup {
             PopupType::Playlist(PlaylistAction::Rename)
             | PopupType::Playlist(PlaylistAction::Create)
-            | PopupType::Playlist(PlaylistAction::CreateWithSongs) => {
+            | PopupType::Playlist(PlaylistAction::CreateWithSongs)
+            | PopupType::Playlist(PlaylistAction::CreateSmart) => {
                 let placeholder = get_random_playlist_idea();
                 self.input.set_placeholder_text(format!(" {placeholder} "));
                 self.input.clear();
             }
+            PopupType::Playlist(PlaylistAction::CreateSmartRules(_))
+            | PopupType::Playlist(PlaylistAction::EditRules) => {
+                self.input
+                    .set_placeholder_text(" artist = Radiohead and year >= 2000 ");
+                self.input.clear();
+            }
             PopupType::Settings(SettingsMode::ViewRoots) => {
                 self.input.clear();
             }
@@ -93,6 +102,11 @@ impl UiState {
     }
 
     pub fn process_popup_input(&mut self, key: &KeyEvent) {
-        self.popup.input.input(*key);
+        match self.popup.current {
+            PopupType::TagEditor => self.process_tag_editor_input(key),
+            _ => {
+                self.popup.input.input(*key);
+            }
+        }
     }
 }
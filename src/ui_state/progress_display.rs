@@ -1,10 +1,11 @@
-use crate::{FFMPEG_AVAILABLE, TAP_BUFFER_CAPACITY, player::PlaybackState, ui_state::UiState};
+use crate::{TAP_BUFFER_CAPACITY, player::PlaybackState, ui_state::UiState};
 
 #[derive(Clone, Default, PartialEq, Eq)]
 pub enum ProgressDisplay {
     Waveform,
     Oscilloscope,
     Spectrum,
+    Lyrics,
     #[default]
     ProgressBar,
 }
@@ -15,7 +16,8 @@ impl ProgressDisplay {
             Self::ProgressBar => Self::Waveform,
             Self::Waveform => Self::Oscilloscope,
             Self::Oscilloscope => Self::Spectrum,
-            Self::Spectrum => Self::ProgressBar,
+            Self::Spectrum => Self::Lyrics,
+            Self::Lyrics => Self::ProgressBar,
         }
     }
 
@@ -24,6 +26,7 @@ impl ProgressDisplay {
             "spectrum" => Self::Spectrum,
             "waveform" => Self::Waveform,
             "oscilloscope" => Self::Oscilloscope,
+            "lyrics" => Self::Lyrics,
             _ => Self::ProgressBar,
         }
     }
@@ -36,6 +39,7 @@ impl std::fmt::Display for ProgressDisplay {
             ProgressDisplay::Spectrum => write!(f, "spectrum"),
             ProgressDisplay::ProgressBar => write!(f, "progress_bar"),
             ProgressDisplay::Oscilloscope => write!(f, "oscilloscope"),
+            ProgressDisplay::Lyrics => write!(f, "lyrics"),
         }
     }
 }
@@ -54,13 +58,7 @@ impl UiState {
     }
 
     pub fn set_progress_display(&mut self, display: ProgressDisplay) {
-        self.progress_display = match display {
-            ProgressDisplay::Waveform => match *FFMPEG_AVAILABLE {
-                true => display,
-                false => ProgressDisplay::default(),
-            },
-            _ => display,
-        }
+        self.progress_display = display
     }
 
     pub fn fill_tap(&mut self) {
@@ -0,0 +1,74 @@
+use crate::{
+    library::{MAX_STARS, Rating, SimpleSong},
+    ui_state::UiState,
+};
+use anyhow::{Result, bail};
+use std::sync::Arc;
+
+impl UiState {
+    pub(crate) fn load_ratings(&mut self) -> Result<()> {
+        self.ratings = self.db_worker.get_ratings()?;
+        Ok(())
+    }
+
+    pub fn get_rating(&self, id: u64) -> Rating {
+        self.ratings.get(&id).copied().unwrap_or_default()
+    }
+
+    fn rating_targets(&mut self) -> Result<Vec<Arc<SimpleSong>>> {
+        let songs = match self.multi_select_empty() {
+            true => vec![self.get_selected_song()?],
+            false => self.get_multi_select_songs(),
+        };
+
+        if songs.is_empty() {
+            bail!("No songs to rate!");
+        }
+        Ok(songs)
+    }
+
+    /// Rate the multi-selection or the selected song. Without `stars` the
+    /// first song's rating goes up by one, wrapping around to unrated.
+    /// Returns the rated songs along with the stars they were given
+    pub(crate) fn rate_songs(&mut self, stars: Option<u8>) -> Result<(Vec<Arc<SimpleSong>>, u8)> {
+        let songs = self.rating_targets()?;
+        let stars = match stars {
+            Some(stars) => stars.min(MAX_STARS),
+            None => (self.get_rating(songs[0].id).stars + 1) % (MAX_STARS + 1),
+        };
+
+        let ids = songs.iter().map(|s| s.id).collect::<Vec<_>>();
+        self.db_worker.set_stars(ids, stars)?;
+
+        for song in &songs {
+            self.update_rating(song.id, |r| r.stars = stars);
+        }
+
+        Ok((songs, stars))
+    }
+
+    /// Favorite the multi-selection or the selected song, or unfavorite
+    /// them all if the first one already is
+    pub(crate) fn toggle_favorite(&mut self) -> Result<()> {
+        let songs = self.rating_targets()?;
+        let favorite = !self.get_rating(songs[0].id).favorite;
+
+        let ids = songs.iter().map(|s| s.id).collect::<Vec<_>>();
+        self.db_worker.set_favorite(ids, favorite)?;
+
+        for song in &songs {
+            self.update_rating(song.id, |r| r.favorite = favorite);
+        }
+
+        Ok(())
+    }
+
+    fn update_rating(&mut self, id: u64, update: impl FnOnce(&mut Rating)) {
+        let rating = self.ratings.entry(id).or_default();
+        update(rating);
+
+        if rating.is_empty() {
+            self.ratings.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use super::MatchField;
+use crate::library::{
+    FileType, Rating, SimpleSong, SongField as Field, SongStats, fold_text, parse_days,
+    parse_duration, parse_flag, parse_format,
+};
+use std::{
+    ops::{Bound, RangeBounds},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+enum Test {
+    /// Ignores case and diacritics
+    Contains(String),
+    Number(Bound<f64>, Bound<f64>),
+    Format(FileType),
+}
+
+struct Term {
+    field: Field,
+    negated: bool,
+    test: Test,
+}
+
+/// A search split into field filters and free text, e.g.
+/// `artist:"boards of canada" year:1995..2000 dur:>5m rating:>=4 -title:remix`.
+///
+/// Words which don't form a valid filter, such as `field:` while it's still
+/// being typed, are searched for as free text instead
+pub(super) struct SearchQuery {
+    pub text: String,
+    terms: Vec<Term>,
+    now: i64,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut words = Vec::new();
+        let mut terms = Vec::new();
+
+        for (token, quoted) in tokenize(query) {
+            match quoted {
+                true => words.push(token),
+                false => match parse_term(&token) {
+                    Some(term) => terms.push(term),
+                    None => words.push(token),
+                },
+            }
+        }
+
+        SearchQuery {
+            text: words.join(" "),
+            terms,
+            now: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+        }
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.terms.is_empty()
+    }
+
+    pub fn uses_stats(&self) -> bool {
+        self.terms.iter().any(|t| t.field.uses_stats())
+    }
+
+    pub fn matches(&self, song: &SimpleSong, stats: Option<&SongStats>, rating: Rating) -> bool {
+        let stats = SongStats {
+            rating,
+            ..stats.copied().unwrap_or_default()
+        };
+
+        self.terms
+            .iter()
+            .all(|term| term.matches(song, &stats, self.now) != term.negated)
+    }
+
+    /// The column to highlight when there's no free text to match against
+    pub fn match_field(&self) -> Option<MatchField> {
+        self.terms
+            .iter()
+            .filter(|t| !t.negated)
+            .find_map(|t| match t.field {
+                Field::Title => Some(MatchField::Title),
+                Field::Artist => Some(MatchField::Artist),
+                Field::Album => Some(MatchField::Album),
+                _ => None,
+            })
+    }
+}
+
+impl Term {
+    fn matches(&self, song: &SimpleSong, stats: &SongStats, now: i64) -> bool {
+        match &self.test {
+            Test::Contains(value) => self
+                .field
+                .text(song)
+                .is_some_and(|text| fold_text(text).contains(value.as_str())),
+            Test::Format(format) => song.filetype == *format,
+            Test::Number(min, max) => self
+                .field
+                .number(song, stats, now)
+                .is_some_and(|actual| (*min, *max).contains(&actual)),
+        }
+    }
+}
+
+/// Splits on whitespace outside of double quotes, flagging words which
+/// started with a quote so `"a:b"` can be searched for literally
+fn tokenize(query: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted |= token.is_empty();
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push((std::mem::take(&mut token), quoted));
+                }
+                quoted = false;
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push((token, quoted));
+    }
+
+    tokens
+}
+
+fn parse_term(token: &str) -> Option<Term> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+
+    let (name, value) = token.split_once(':')?;
+    if value.is_empty() {
+        return None;
+    }
+
+    let field = Field::parse(name)?;
+
+    let test = match field {
+        Field::Title | Field::Artist | Field::Album | Field::AlbumArtist => {
+            Test::Contains(fold_text(value))
+        }
+        Field::Format => Test::Format(parse_format(value)?),
+        Field::Favorite => {
+            let flag = parse_flag(value)? as u8 as f64;
+            Test::Number(Bound::Included(flag), Bound::Included(flag))
+        }
+        Field::Duration => parse_range(value, parse_duration, 1.0)?,
+        Field::Added | Field::Played => parse_range(value, parse_days, 1.0)?,
+        _ => parse_range(value, |v| v.parse().ok(), 0.0)?,
+    };
+
+    Some(Term {
+        field,
+        negated,
+        test,
+    })
+}
+
+/// Accepts `N`, `>N`, `>=N`, `<N`, `<=N`, `A..B`, `A..` and `..B`. A lone
+/// value matches anything up to `width` above it, as durations and days have
+/// fractions
+fn parse_range(value: &str, parse: impl Fn(&str) -> Option<f64>, width: f64) -> Option<Test> {
+    use Bound::*;
+
+    let range = if let Some(v) = value.strip_prefix(">=") {
+        (Included(parse(v)?), Unbounded)
+    } else if let Some(v) = value.strip_prefix("<=") {
+        (Unbounded, Included(parse(v)?))
+    } else if let Some(v) = value.strip_prefix('>') {
+        (Excluded(parse(v)?), Unbounded)
+    } else if let Some(v) = value.strip_prefix('<') {
+        (Unbounded, Excluded(parse(v)?))
+    } else if let Some((min, max)) = value.split_once("..") {
+        let bound = |v: &str| match v.is_empty() {
+            true => Some(Unbounded),
+            false => parse(v).map(Included),
+        };
+        (bound(min)?, bound(max)?)
+    } else {
+        let v = parse(value)?;
+        match width > 0.0 {
+            true => (Included(v), Excluded(v + width)),
+            false => (Included(v), Included(v)),
+        }
+    };
+
+    Some(Test::Number(range.0, range.1))
+}
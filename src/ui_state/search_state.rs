@@ -1,6 +1,6 @@
-use super::{Pane, UiState, new_textarea};
+use super::{Pane, UiState, new_textarea, search_query::SearchQuery};
 use crate::{
-    library::{SimpleSong, SongInfo},
+    library::{SimpleSong, SongInfo, SongStats},
     strip_diacritics,
 };
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
@@ -22,6 +22,8 @@ pub struct SearchState {
     pub input: TextArea<'static>,
     matcher: SkimMatcherV2,
     pub(super) match_fields: HashMap<u64, MatchField>,
+    /// Play counts for `plays:` filters, loaded once per search
+    pub(super) stats: Option<HashMap<u64, SongStats>>,
 }
 
 impl SearchState {
@@ -30,6 +32,7 @@ impl SearchState {
             input: new_textarea("Enter search query"),
             matcher: SkimMatcherV2::default(),
             match_fields: HashMap::new(),
+            stats: None,
         }
     }
 }
@@ -40,15 +43,37 @@ impl UiState {
     // weight to the title field and returns the highest score.
     // Assuming the score is higher than the threshold, the
     // result is valid. Results are ordered by score.
+    //
+    // Field filters (`artist:x`, `year:1990..2000`...) narrow the
+    // songs down first, without free text they're kept in library order.
     pub(crate) fn filter_songs_by_search(&mut self) {
-        let raw_search_str = self.read_search();
-        let query = strip_diacritics(raw_search_str);
+        let search = SearchQuery::parse(self.read_search());
+        let query = strip_diacritics(&search.text);
+
+        if search.uses_stats() && self.search.stats.is_none() {
+            self.search.stats = self.db_worker.get_song_stats().ok();
+        }
+
+        self.search.match_fields.clear();
+        let highlight = search.match_field();
 
         let mut scored_songs: Vec<(Arc<SimpleSong>, i64)> = self
             .library
             .get_all_songs()
             .iter()
+            .filter(|song| {
+                let stats = self.search.stats.as_ref().and_then(|s| s.get(&song.id));
+                let rating = self.ratings.get(&song.id).copied().unwrap_or_default();
+                search.matches(song, stats, rating)
+            })
             .filter_map(|song| {
+                if query.is_empty() {
+                    if let Some(field) = highlight {
+                        self.search.match_fields.insert(song.get_id(), field);
+                    }
+                    return search.has_filters().then(|| (Arc::clone(song), 0));
+                }
+
                 let title_score = self
                     .search
                     .matcher
@@ -126,6 +126,30 @@ impl SpectrumState {
         self.last_display_width = width;
         self.bins_dirty = false;
     }
+
+    /// Frequency displayed at `t` (0.0 to 1.0) across the width, following
+    /// the same interpolation as `remap_display`
+    pub fn frequency_at(&self, t: f32) -> Option<f32> {
+        let num_bands = self.bands.len();
+        if num_bands == 0 {
+            return None;
+        }
+
+        let center = |idx: usize| {
+            let (lo, hi) = self.bands[idx];
+            (lo * hi).sqrt()
+        };
+
+        let src = t.clamp(0.0, 1.0) * (num_bands - 1) as f32;
+        let lo = src.floor() as usize;
+        let hi = (lo + 1).min(num_bands - 1);
+        let frac = src - lo as f32;
+        Some(center(lo) * (1.0 - frac) + center(hi) * frac)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
 }
 
 impl Default for SpectrumState {
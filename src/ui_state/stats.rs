@@ -1,14 +1,41 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{SimpleSong, ui_state::UiState};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    STATS_DIR, SimpleSong,
+    key_handler::Incrementor,
+    library::{FileType, SongInfo},
+    ui_state::UiState,
+};
+
+const TOP_LIMIT: usize = 10;
+const FORGOTTEN_LIMIT: usize = 25;
+/// Plays a song needs before it can be counted as a favorite
+const FORGOTTEN_MIN_PLAYS: u32 = 5;
+/// Days without a play before a favorite counts as forgotten
+const FORGOTTEN_AFTER_DAYS: i64 = 90;
+const DAY: i64 = 86400;
 
 #[derive(Default)]
 pub struct VoxStats {
     pub lib_stats: LibraryStats,
     pub top_played: Vec<(Arc<SimpleSong>, u16)>,
+    pub listening: ListeningStats,
+    pub page: StatsPage,
+    pub period: StatsPeriod,
+    pub exported: Option<PathBuf>,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize)]
 pub struct LibraryStats {
     pub total_tracks: u32,
     pub total_albums: u32,
@@ -22,9 +49,164 @@ pub struct LibraryStats {
     pub play_percentage: f32,
 }
 
+#[derive(Default)]
+pub struct ListeningStats {
+    /// One entry for each of `StatsPeriod::ALL`
+    pub top: Vec<TopPlayed>,
+    /// Local day index of today, counted from the unix epoch
+    pub today: i64,
+    pub days: Vec<ListeningDay>,
+    pub streaks: Streaks,
+    pub forgotten: Vec<ForgottenFavorite>,
+    pub formats: Vec<FormatShare>,
+    pub bitrates: Vec<BitrateShare>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum StatsPage {
+    #[default]
+    Overview,
+    TopPlayed,
+    Listening,
+    Forgotten,
+    Formats,
+}
+
+impl StatsPage {
+    pub const ALL: [StatsPage; 5] = [
+        StatsPage::Overview,
+        StatsPage::TopPlayed,
+        StatsPage::Listening,
+        StatsPage::Forgotten,
+        StatsPage::Formats,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            StatsPage::Overview => "Overview",
+            StatsPage::TopPlayed => "Top Played",
+            StatsPage::Listening => "Listening Time",
+            StatsPage::Forgotten => "Forgotten Favorites",
+            StatsPage::Formats => "Formats",
+        }
+    }
+
+    fn slug(&self) -> &'static str {
+        match self {
+            StatsPage::Overview => "overview",
+            StatsPage::TopPlayed => "top-played",
+            StatsPage::Listening => "listening-time",
+            StatsPage::Forgotten => "forgotten-favorites",
+            StatsPage::Formats => "formats",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|p| p == self).unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsPeriod {
+    #[default]
+    Week,
+    Month,
+    Year,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub const ALL: [StatsPeriod; 4] = [
+        StatsPeriod::Week,
+        StatsPeriod::Month,
+        StatsPeriod::Year,
+        StatsPeriod::AllTime,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "Past Week",
+            StatsPeriod::Month => "Past Month",
+            StatsPeriod::Year => "Past Year",
+            StatsPeriod::AllTime => "All Time",
+        }
+    }
+
+    fn days(&self) -> Option<i64> {
+        match self {
+            StatsPeriod::Week => Some(7),
+            StatsPeriod::Month => Some(30),
+            StatsPeriod::Year => Some(365),
+            StatsPeriod::AllTime => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TopPlayed {
+    pub period: StatsPeriod,
+    pub artists: Vec<TopEntry>,
+    pub albums: Vec<TopEntry>,
+    pub songs: Vec<TopEntry>,
+}
+
+#[derive(Serialize)]
+pub struct TopEntry {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    pub plays: u32,
+}
+
+#[derive(Serialize)]
+pub struct ListeningDay {
+    #[serde(skip)]
+    pub day: i64,
+    pub date: String,
+    pub plays: u32,
+    pub seconds: f64,
+}
+
+#[derive(Default, Serialize)]
+pub struct Streaks {
+    /// Consecutive days listened up to today, or yesterday if nothing has
+    /// been played yet today
+    pub current: u32,
+    pub longest: u32,
+    pub longest_ended: Option<String>,
+    pub active_days: u32,
+}
+
+#[derive(Serialize)]
+pub struct ForgottenFavorite {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub plays: u32,
+    /// Unix timestamp, `None` when the last play predates the history log
+    pub last_played: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct FormatShare {
+    pub format: String,
+    pub songs: u32,
+    pub seconds: f64,
+    /// Average bitrate in kbps
+    pub bitrate: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct BitrateShare {
+    pub range: &'static str,
+    pub songs: u32,
+}
+
 impl UiState {
     pub fn show_stats_popup(&mut self) -> anyhow::Result<()> {
         self.update_stats()?;
+        self.stats.exported = None;
         self.show_popup(super::PopupType::Stats);
 
         Ok(())
@@ -39,9 +221,96 @@ impl UiState {
             .filter_map(|(id, plays)| self.library.get_song_by_id(id).cloned().map(|s| (s, plays)))
             .collect::<Vec<_>>();
 
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+        self.stats.listening.top = StatsPeriod::ALL
+            .into_iter()
+            .map(|period| {
+                let since = period.days().map_or(0, |d| now - d * DAY);
+                let plays = self.db_worker.get_plays_since(since)?;
+                Ok(self.rank_top_played(period, &plays))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (today, days) = self.db_worker.get_daily_listening()?;
+        self.stats.listening.streaks = count_streaks(today, &days);
+        self.stats.listening.today = today;
+        self.stats.listening.days = days;
+
+        self.stats.listening.forgotten = self.find_forgotten(now)?;
+
+        let formats = self.db_worker.get_formats()?;
+        self.stats.listening.bitrates = bitrate_shares(&formats);
+        self.stats.listening.formats = format_shares(formats);
+
         Ok(())
     }
 
+    fn rank_top_played(&self, period: StatsPeriod, plays: &[(u64, u32)]) -> TopPlayed {
+        let mut artists: HashMap<&str, u32> = HashMap::new();
+        let mut albums: HashMap<(&str, &str), u32> = HashMap::new();
+        let mut songs = Vec::new();
+
+        for (id, count) in plays {
+            let Some(song) = self.library.get_song_by_id(*id) else {
+                continue;
+            };
+
+            *artists.entry(song.get_artist()).or_default() += count;
+            *albums
+                .entry((song.get_album(), song.album_artist.as_str()))
+                .or_default() += count;
+            songs.push(((song.get_title(), song.get_artist()), *count));
+        }
+
+        let entry = |(name, artist): (&str, Option<&str>), plays| TopEntry {
+            name: name.to_string(),
+            artist: artist.map(str::to_string),
+            plays,
+        };
+
+        TopPlayed {
+            period,
+            artists: rank(artists.into_iter().map(|(a, n)| entry((a, None), n))),
+            albums: rank(
+                albums
+                    .into_iter()
+                    .map(|((a, aa), n)| entry((a, Some(aa)), n)),
+            ),
+            songs: rank(songs.into_iter().map(|((t, a), n)| entry((t, Some(a)), n))),
+        }
+    }
+
+    fn find_forgotten(&self, now: i64) -> Result<Vec<ForgottenFavorite>> {
+        let cutoff = now - FORGOTTEN_AFTER_DAYS * DAY;
+
+        let mut forgotten = self
+            .get_song_stats()?
+            .into_iter()
+            .filter(|(_, st)| st.plays >= FORGOTTEN_MIN_PLAYS)
+            .filter(|(_, st)| st.last_played.is_none_or(|t| t < cutoff))
+            .filter_map(|(id, st)| Some((self.library.get_song_by_id(id)?, st)))
+            .collect::<Vec<_>>();
+
+        forgotten.sort_by(|(_, a), (_, b)| {
+            b.plays
+                .cmp(&a.plays)
+                .then(a.last_played.cmp(&b.last_played))
+        });
+        forgotten.truncate(FORGOTTEN_LIMIT);
+
+        Ok(forgotten
+            .into_iter()
+            .map(|(song, st)| ForgottenFavorite {
+                title: song.get_title().to_string(),
+                artist: song.get_artist().to_string(),
+                album: song.get_album().to_string(),
+                plays: st.plays,
+                last_played: st.last_played,
+            })
+            .collect())
+    }
+
     pub fn get_lib_stats(&self) -> &LibraryStats {
         &self.stats.lib_stats
     }
@@ -49,4 +318,176 @@ impl UiState {
     pub fn get_most_played(&self) -> &[(Arc<SimpleSong>, u16)] {
         &self.stats.top_played
     }
+
+    pub fn get_listening_stats(&self) -> &ListeningStats {
+        &self.stats.listening
+    }
+
+    pub fn get_stats_page(&self) -> StatsPage {
+        self.stats.page
+    }
+
+    pub fn get_stats_period(&self) -> StatsPeriod {
+        self.stats.period
+    }
+
+    pub fn get_stats_export(&self) -> Option<&PathBuf> {
+        self.stats.exported.as_ref()
+    }
+
+    pub fn shift_stats_page(&mut self, dir: Incrementor) {
+        let len = StatsPage::ALL.len();
+        let idx = self.stats.page.index();
+        let idx = match dir {
+            Incrementor::Up => (idx + len - 1) % len,
+            Incrementor::Down => (idx + 1) % len,
+        };
+
+        self.stats.page = StatsPage::ALL[idx];
+        self.stats.exported = None;
+    }
+
+    pub fn cycle_stats_period(&mut self) {
+        let idx = StatsPeriod::ALL
+            .iter()
+            .position(|p| *p == self.stats.period)
+            .unwrap_or(0);
+
+        self.stats.period = StatsPeriod::ALL[(idx + 1) % StatsPeriod::ALL.len()];
+        self.stats.exported = None;
+    }
+
+    /// Write the page currently on display to a JSON file in `STATS_DIR`
+    pub fn export_stats_page(&mut self) -> Result<()> {
+        let stats = &self.stats;
+        let value = match stats.page {
+            StatsPage::Overview => json!({
+                "library": stats.lib_stats,
+                "most_played": stats
+                    .top_played
+                    .iter()
+                    .map(|(s, plays)| json!({
+                        "title": s.get_title(),
+                        "artist": s.get_artist(),
+                        "album": s.get_album(),
+                        "plays": plays,
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+            StatsPage::TopPlayed => json!({ "top_played": stats.listening.top }),
+            StatsPage::Listening => json!({
+                "streaks": stats.listening.streaks,
+                "days": stats.listening.days,
+            }),
+            StatsPage::Forgotten => json!({ "forgotten_favorites": stats.listening.forgotten }),
+            StatsPage::Formats => json!({
+                "formats": stats.listening.formats,
+                "bitrates": stats.listening.bitrates,
+            }),
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = STATS_DIR.join(format!("{}-{now}.json", stats.page.slug()));
+
+        fs::create_dir_all(&*STATS_DIR)?;
+        fs::write(&path, serde_json::to_string_pretty(&value)?)?;
+        self.stats.exported = Some(path);
+
+        Ok(())
+    }
+}
+
+fn rank(entries: impl Iterator<Item = TopEntry>) -> Vec<TopEntry> {
+    let mut entries = entries.collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.name.cmp(&b.name)));
+    entries.truncate(TOP_LIMIT);
+    entries
+}
+
+fn count_streaks(today: i64, days: &[ListeningDay]) -> Streaks {
+    let mut streaks = Streaks {
+        active_days: days.len() as u32,
+        ..Default::default()
+    };
+
+    let mut run = 0;
+    let mut prev = None;
+    for d in days {
+        run = match prev {
+            Some(p) if d.day == p + 1 => run + 1,
+            _ => 1,
+        };
+        prev = Some(d.day);
+
+        if run > streaks.longest {
+            streaks.longest = run;
+            streaks.longest_ended = Some(d.date.clone());
+        }
+    }
+
+    if prev.is_some_and(|p| p >= today - 1) {
+        streaks.current = run;
+    }
+
+    streaks
+}
+
+fn format_shares(formats: Vec<(FileType, Option<u32>, f64)>) -> Vec<FormatShare> {
+    // songs, seconds, bitrate total, songs with a bitrate
+    let mut totals: HashMap<FileType, (u32, f64, u64, u32)> = HashMap::new();
+    for (filetype, bitrate, seconds) in formats {
+        let entry = totals.entry(filetype).or_default();
+        entry.0 += 1;
+        entry.1 += seconds;
+        if let Some(kbps) = bitrate {
+            entry.2 += kbps as u64;
+            entry.3 += 1;
+        }
+    }
+
+    let mut shares = totals
+        .into_iter()
+        .map(|(filetype, (songs, seconds, kbps, rated))| FormatShare {
+            format: filetype.to_str().to_uppercase(),
+            songs,
+            seconds,
+            bitrate: (rated > 0).then(|| (kbps / rated as u64) as u32),
+        })
+        .collect::<Vec<_>>();
+
+    shares.sort_by(|a, b| b.songs.cmp(&a.songs).then_with(|| a.format.cmp(&b.format)));
+    shares
+}
+
+fn bitrate_shares(formats: &[(FileType, Option<u32>, f64)]) -> Vec<BitrateShare> {
+    const RANGES: [&str; 7] = [
+        "Lossless",
+        "320+ kbps",
+        "256 - 319 kbps",
+        "192 - 255 kbps",
+        "128 - 191 kbps",
+        "< 128 kbps",
+        "Unknown",
+    ];
+
+    let mut counts = [0; RANGES.len()];
+    for (filetype, bitrate, _) in formats {
+        let idx = match (filetype, bitrate) {
+            (FileType::FLAC | FileType::WAV, _) => 0,
+            (_, Some(320..)) => 1,
+            (_, Some(256..)) => 2,
+            (_, Some(192..)) => 3,
+            (_, Some(128..)) => 4,
+            (_, Some(_)) => 5,
+            (_, None) => 6,
+        };
+        counts[idx] += 1;
+    }
+
+    RANGES
+        .into_iter()
+        .zip(counts)
+        .filter(|(_, songs)| *songs > 0)
+        .map(|(range, songs)| BitrateShare { range, songs })
+        .collect()
 }
@@ -0,0 +1,152 @@
+use anyhow::{Result, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui_textarea::TextArea;
+use std::sync::Arc;
+
+use crate::{
+    key_handler::Incrementor,
+    library::{SimpleSong, SongInfo, TagEdit, TagField},
+    ui_state::{Pane, PopupType, UiState, new_textarea},
+};
+
+pub struct TagEditorField {
+    pub field: TagField,
+    /// Shared value of every song being edited, empty when they differ
+    original: String,
+    pub input: TextArea<'static>,
+}
+
+#[derive(Default)]
+pub struct TagEditor {
+    songs: Vec<Arc<SimpleSong>>,
+    pub fields: Vec<TagEditorField>,
+    pub selected: usize,
+}
+
+impl TagEditor {
+    fn new(songs: Vec<Arc<SimpleSong>>) -> Self {
+        let fields = TagField::ALL
+            .iter()
+            .map(|&field| {
+                let mut values = songs.iter().map(|s| field_value(s, field));
+                let first = values.next().unwrap_or_default();
+                let shared = values.all(|v| v == first);
+
+                let mut input = new_textarea("");
+                match shared {
+                    true => {
+                        input.insert_str(&first);
+                    }
+                    false => input.set_placeholder_text(" (multiple values) "),
+                }
+
+                TagEditorField {
+                    field,
+                    original: match shared {
+                        true => first,
+                        false => String::new(),
+                    },
+                    input,
+                }
+            })
+            .collect();
+
+        TagEditor {
+            songs,
+            fields,
+            selected: 0,
+        }
+    }
+
+    pub fn song_count(&self) -> usize {
+        self.songs.len()
+    }
+
+    pub fn first_song(&self) -> Option<&Arc<SimpleSong>> {
+        self.songs.first()
+    }
+}
+
+fn field_value(song: &SimpleSong, field: TagField) -> String {
+    let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+
+    match field {
+        TagField::Title => song.get_title().to_string(),
+        TagField::Artist => song.get_artist().to_string(),
+        TagField::Album => song.get_album().to_string(),
+        TagField::AlbumArtist => song.album_artist.to_string(),
+        TagField::Year => number(song.year),
+        TagField::Track => number(song.track_no),
+        TagField::Disc => number(song.disc_no),
+    }
+}
+
+impl UiState {
+    /// Edit the multi-selection or the selected song from the tracklist, or
+    /// the whole album (or playlist) from the sidebar
+    pub fn edit_tags_popup(&mut self) -> Result<()> {
+        let songs = match self.get_pane() {
+            Pane::SideBar => self.get_legal_songs().to_vec(),
+            _ => match self.multi_select_empty() {
+                true => vec![self.get_selected_song()?],
+                false => self.get_multi_select_songs(),
+            },
+        };
+
+        if songs.is_empty() {
+            bail!("No songs to edit!");
+        }
+
+        self.tag_editor = TagEditor::new(songs);
+        self.show_popup(PopupType::TagEditor);
+        Ok(())
+    }
+
+    pub fn tag_editor_select_field(&mut self, dir: Incrementor) {
+        let len = self.tag_editor.fields.len();
+        let idx = self.tag_editor.selected;
+
+        self.tag_editor.selected = match dir {
+            Incrementor::Up => (idx + len - 1) % len,
+            Incrementor::Down => (idx + 1) % len,
+        };
+    }
+
+    pub(crate) fn process_tag_editor_input(&mut self, key: &KeyEvent) {
+        let editor = &mut self.tag_editor;
+        let Some(entry) = editor.fields.get_mut(editor.selected) else {
+            return;
+        };
+
+        // Single line fields
+        if key.code == KeyCode::Enter {
+            return;
+        }
+
+        let numeric = entry.field.is_numeric();
+        if let KeyCode::Char(c) = key.code
+            && numeric
+            && !c.is_ascii_digit()
+        {
+            return;
+        }
+
+        entry.input.input(*key);
+    }
+
+    /// Ids of the songs being edited along with the fields that changed
+    pub(crate) fn get_tag_edit(&self) -> (Vec<u64>, TagEdit) {
+        let editor = &self.tag_editor;
+        let mut edit = TagEdit::default();
+
+        for entry in &editor.fields {
+            let value = entry.input.lines().join(" ");
+            if value.trim() != entry.original {
+                edit.set(entry.field, &value);
+            }
+        }
+
+        let ids = editor.songs.iter().map(|s| s.get_id()).collect();
+        (ids, edit)
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
 
-use crate::ui_state::{LayoutStyle, ProgressDisplay};
+use crate::{
+    player::{EQ_BANDS, EqGains},
+    ui_state::{LayoutStyle, ProgressDisplay},
+};
 
 use super::{AlbumSort, Mode, Pane, UiState};
 
@@ -17,13 +20,25 @@ pub struct UiSnapshot {
     pub song_selection: Option<usize>,
     pub album_selection: Option<usize>,
     pub playlist_selection: Option<usize>,
+    pub category_selection: Option<usize>,
+    pub category_album_selection: Option<usize>,
+    pub category_open: bool,
 
     pub song_sel_offset: usize,
     pub album_sel_offset: usize,
     pub playlist_sel_offset: usize,
+    pub category_sel_offset: usize,
 
     pub progress_display: String,
     pub smoothing_factor: f32,
+
+    pub volume: Option<u8>,
+    pub muted: bool,
+
+    pub eq_enabled: bool,
+    pub eq_overlay: bool,
+    pub eq_preset: String,
+    pub eq_gains: Option<EqGains>,
 }
 
 impl UiSnapshot {
@@ -37,8 +52,24 @@ impl UiSnapshot {
             ("ui_smooth", format!("{:.1}", self.smoothing_factor)),
             ("ui_sidebar_percent", self.sidebar_percentage.to_string()),
             ("ui_progress_display", self.progress_display.to_string()),
+            ("ui_muted", self.muted.to_string()),
+            ("ui_eq_enabled", self.eq_enabled.to_string()),
+            ("ui_eq_overlay", self.eq_overlay.to_string()),
+            ("ui_eq_preset", self.eq_preset.clone()),
         ];
 
+        if let Some(gains) = self.eq_gains {
+            let values = std::iter::once(gains.preamp)
+                .chain(gains.bands)
+                .map(|g| format!("{g:.1}"))
+                .collect::<Vec<_>>();
+            pairs.push(("ui_eq_gains", values.join(",")));
+        }
+
+        if let Some(volume) = self.volume {
+            pairs.push(("ui_volume", volume.to_string()));
+        }
+
         if let Some(pos) = self.album_selection {
             pairs.push(("ui_album_pos", pos.to_string()));
             pairs.push(("ui_album_offset", self.album_sel_offset.to_string()))
@@ -49,6 +80,16 @@ impl UiSnapshot {
             pairs.push(("ui_playlist_offset", self.playlist_sel_offset.to_string()))
         }
 
+        if let Some(pos) = self.category_selection {
+            pairs.push(("ui_category_pos", pos.to_string()));
+            pairs.push(("ui_category_offset", self.category_sel_offset.to_string()));
+            pairs.push(("ui_category_open", self.category_open.to_string()))
+        }
+
+        if let Some(pos) = self.category_album_selection {
+            pairs.push(("ui_category_album_pos", pos.to_string()))
+        }
+
         if let Some(pos) = self.song_selection {
             pairs.push(("ui_song_pos", pos.to_string()));
             pairs.push(("ui_song_offset", self.song_sel_offset.to_string()))
@@ -72,9 +113,19 @@ impl UiSnapshot {
                 "ui_playlist_pos" => snapshot.playlist_selection = value.parse().ok(),
                 "ui_album_offset" => snapshot.album_sel_offset = value.parse().unwrap_or(0),
                 "ui_playlist_offset" => snapshot.playlist_sel_offset = value.parse().unwrap_or(0),
+                "ui_category_pos" => snapshot.category_selection = value.parse().ok(),
+                "ui_category_offset" => snapshot.category_sel_offset = value.parse().unwrap_or(0),
+                "ui_category_open" => snapshot.category_open = value.parse().unwrap_or(false),
+                "ui_category_album_pos" => snapshot.category_album_selection = value.parse().ok(),
                 "ui_song_pos" => snapshot.song_selection = value.parse().ok(),
                 "ui_song_offset" => snapshot.song_sel_offset = value.parse::<usize>().unwrap_or(0),
                 "ui_smooth" => snapshot.smoothing_factor = value.parse::<f32>().unwrap_or(1.0),
+                "ui_volume" => snapshot.volume = value.parse().ok(),
+                "ui_muted" => snapshot.muted = value.parse().unwrap_or(false),
+                "ui_eq_enabled" => snapshot.eq_enabled = value.parse().unwrap_or(false),
+                "ui_eq_overlay" => snapshot.eq_overlay = value.parse().unwrap_or(false),
+                "ui_eq_preset" => snapshot.eq_preset = value,
+                "ui_eq_gains" => snapshot.eq_gains = parse_eq_gains(&value),
                 "ui_sidebar_percent" => {
                     snapshot.sidebar_percentage = value.parse::<u16>().unwrap_or(30)
                 }
@@ -86,6 +137,20 @@ impl UiSnapshot {
     }
 }
 
+/// Preamp followed by every band, comma separated
+fn parse_eq_gains(value: &str) -> Option<EqGains> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let (&preamp, bands) = values.split_first()?;
+    Some(EqGains {
+        preamp,
+        bands: <[f32; EQ_BANDS]>::try_from(bands).ok()?,
+    })
+}
+
 impl UiState {
     pub fn create_snapshot(&self) -> UiSnapshot {
         let orig_pane = self.get_pane();
@@ -106,13 +171,25 @@ impl UiState {
             song_selection: self.display_state.table_pos.selected(),
             album_selection: self.display_state.album_pos.selected(),
             playlist_selection: self.display_state.playlist_pos.selected(),
+            category_selection: self.display_state.category_pos.selected(),
+            category_album_selection: self.display_state.category_album_pos.selected(),
+            category_open: self.display_state.category_open,
 
             song_sel_offset: self.display_state.table_pos.offset(),
             album_sel_offset: self.display_state.album_pos.offset(),
             playlist_sel_offset: self.display_state.playlist_pos.offset(),
+            category_sel_offset: self.display_state.category_pos.offset(),
 
             progress_display: self.get_progress_display().to_string(),
             smoothing_factor: self.get_smoothing_factor(),
+
+            volume: Some(self.metrics.volume()),
+            muted: self.metrics.is_muted(),
+
+            eq_enabled: self.eq_enabled(),
+            eq_overlay: self.eq_overlay(),
+            eq_preset: self.get_eq_preset().unwrap_or_default().to_string(),
+            eq_gains: Some(*self.get_eq_settings()),
         }
     }
 
@@ -158,6 +235,20 @@ impl UiState {
             };
 
             self.set_mode(Mode::from_str(mode_to_restore));
+
+            if let Some(pos) = snapshot.category_selection
+                && pos < self.categories.len()
+            {
+                self.display_state.category_pos.select(Some(pos));
+                *self.display_state.category_pos.offset_mut() = snapshot.category_sel_offset;
+                self.display_state.category_open = snapshot.category_open;
+                self.display_state
+                    .category_album_pos
+                    .select(snapshot.category_album_selection);
+                self.clamp_category_selection();
+                self.set_legal_songs();
+            }
+
             self.set_pane(Pane::from_str(pane_to_restore));
 
             self.set_smoothing_factor(snapshot.smoothing_factor);
@@ -166,6 +257,21 @@ impl UiState {
 
             self.display_state.sidebar_percent = snapshot.sidebar_percentage;
 
+            // Applied to the player once the UI has been restored
+            if let Some(volume) = snapshot.volume {
+                self.metrics.set_volume(volume.min(100));
+            }
+            self.metrics.set_muted(snapshot.muted);
+
+            if let Some(gains) = snapshot.eq_gains {
+                self.restore_equalizer(
+                    snapshot.eq_enabled,
+                    snapshot.eq_overlay,
+                    &snapshot.eq_preset,
+                    gains,
+                );
+            }
+
             if let Some(pos) = snapshot.song_selection {
                 if pos < self.legal_songs.len() {
                     self.display_state.table_pos.select(Some(pos));
@@ -4,18 +4,24 @@ use crate::{
     Library, PlaybackSession, TAP_BUFFER_CAPACITY,
     database::DbWorker,
     key_handler::InputContext,
-    library::{SimpleSong, SongInfo},
+    library::{SimpleSong, SongInfo, SongStats},
+    playback::{PlayMode, SavedEntry},
     player::{PlaybackMetrics, PlaybackState},
     ui_state::{
-        LayoutStyle, LibraryView, Mode, Pane, PlaylistAction, ProgressDisplay, SettingsMode,
-        ThemeManager, UiState, WaveformManager,
+        CoverArtManager, EqualizerState, LayoutStyle, LibraryView, LyricsManager, Mode, Pane,
+        PlaylistAction, ProgressDisplay, SettingsMode, TagEditor, ThemeManager, UiState,
+        WaveformManager,
         popup::{PopupState, PopupType},
         spectrum::SpectrumState,
         stats::VoxStats,
     },
 };
 use anyhow::{Error, Result};
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 impl UiState {
     pub fn new(library: Arc<Library>, metrics: Arc<PlaybackMetrics>) -> Self {
@@ -29,6 +35,10 @@ impl UiState {
             playback: PlaybackSession::init(),
 
             waveform: WaveformManager::new(),
+            cover_art: CoverArtManager::new(),
+            lyrics: LyricsManager::new(),
+            equalizer: EqualizerState::new(),
+            tag_editor: TagEditor::default(),
             spectrum: SpectrumState::default(),
             sample_tap: VecDeque::with_capacity(TAP_BUFFER_CAPACITY),
             progress_display: ProgressDisplay::Oscilloscope,
@@ -40,7 +50,9 @@ impl UiState {
             theme_manager: ThemeManager::new(),
             albums: Vec::new(),
             legal_songs: Vec::new(),
+            ratings: HashMap::new(),
             playlists: Vec::new(),
+            categories: Vec::new(),
 
             library_refresh_progress: None,
             library_refresh_detail: None,
@@ -67,7 +79,9 @@ impl UiState {
             }
         }
 
+        self.load_ratings()?;
         self.get_playlists()?;
+        self.build_categories();
         self.set_legal_songs();
 
         Ok(())
@@ -107,6 +121,7 @@ impl UiState {
             (Mode::Fullscreen, _) => InputContext::Fullscreen,
             (Mode::Library(LibraryView::Albums), Pane::SideBar) => InputContext::AlbumView,
             (Mode::Library(LibraryView::Playlists), Pane::SideBar) => InputContext::PlaylistView,
+            (Mode::Library(_), Pane::SideBar) => InputContext::CategoryView,
             (Mode::Search, Pane::Search) => InputContext::Search,
             (mode, Pane::TrackList) => InputContext::TrackList(mode.clone()),
             (Mode::QUIT, _) => unreachable!(),
@@ -125,6 +140,15 @@ impl UiState {
                     PopupType::Playlist(PlaylistAction::CreateWithSongs)
                 )
                 | (Pane::Popup, PopupType::Playlist(PlaylistAction::Rename))
+                | (
+                    Pane::Popup,
+                    PopupType::Playlist(PlaylistAction::CreateSmart)
+                )
+                | (
+                    Pane::Popup,
+                    PopupType::Playlist(PlaylistAction::CreateSmartRules(_))
+                )
+                | (Pane::Popup, PopupType::Playlist(PlaylistAction::EditRules))
         )
     }
 }
@@ -164,6 +188,14 @@ impl UiState {
         self.metrics.is_paused()
     }
 
+    pub(crate) fn get_volume(&self) -> u8 {
+        self.metrics.volume()
+    }
+
+    pub(crate) fn is_muted(&self) -> bool {
+        self.metrics.is_muted()
+    }
+
     pub fn set_now_playing(&mut self, song: Option<Arc<SimpleSong>>) {
         match &song {
             Some(s) => self.db_worker.set_now_playing_db(s.get_id()),
@@ -215,6 +247,18 @@ impl UiState {
         self.db_worker.get_last_played()
     }
 
+    pub fn save_queue(&self, entries: Vec<SavedEntry>, mode: PlayMode) {
+        self.db_worker.save_queue(entries, mode);
+    }
+
+    pub fn load_queue(&self) -> Result<(Vec<SavedEntry>, PlayMode)> {
+        self.db_worker.load_queue()
+    }
+
+    pub fn get_song_stats(&self) -> Result<HashMap<u64, SongStats>> {
+        self.db_worker.get_song_stats()
+    }
+
     pub fn update_now_playing_elapsed(&self) {
         let elapsed = self.metrics.get_elapsed().as_secs_f32();
         self.db_worker.update_now_playing(elapsed);
@@ -1,17 +1,26 @@
 use anyhow::{Context, Result, anyhow, bail};
-use crossbeam::channel::Receiver;
+use crossbeam::channel::{Receiver, Sender};
+use rayon::{ThreadPoolBuilder, prelude::*};
 use std::{
-    io::{Cursor, Read},
+    fmt,
+    fs::File,
+    io::{self, Read},
     path::Path,
     process::Command,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     thread,
     time::Duration,
 };
+use symphonia::core::errors::Error as SymphoniaError;
 
 use crate::{
+    Database, FFMPEG_AVAILABLE,
+    app_core::WaveformScanProgress,
     key_handler::Incrementor,
-    library::{SimpleSong, SongDatabase},
+    library::{PcmDecoder, SimpleSong, SongDatabase},
     ui_state::UiState,
 };
 
@@ -19,6 +28,8 @@ const WF_LEN: usize = 500;
 static WAVEFORM_STEP: f32 = 0.5;
 const MIN_SAMPLES_PER_POINT: usize = 200; // Minimum for short files
 const MAX_SAMPLES_PER_POINT: usize = 4000; // Maximum for very long files
+const TARGET_RATE: u32 = 22050;
+const HIGH_PASS_HZ: f32 = 350.0;
 
 #[derive(PartialEq)]
 pub enum WaveformState {
@@ -46,10 +57,18 @@ impl WaveformManager {
     }
 
     pub fn request(&mut self, song: &SimpleSong) {
-        if let Ok(cached) = song.get_waveform() {
-            self.state = WaveformState::Ready(cached);
-            self.apply_smoothing();
-            return;
+        match song.get_waveform() {
+            // This file couldn't be decoded before
+            Ok(cached) if cached.is_empty() => {
+                self.state = WaveformState::Failed;
+                return;
+            }
+            Ok(cached) => {
+                self.state = WaveformState::Ready(cached);
+                self.apply_smoothing();
+                return;
+            }
+            Err(_) => {}
         }
 
         if let Ok(path) = song.get_path() {
@@ -78,7 +97,12 @@ impl WaveformManager {
                 self.state = WaveformState::Ready(waveform);
                 self.apply_smoothing();
             }
-            Err(_) => self.state = WaveformState::Failed,
+            Err(e) => {
+                if let Some(s) = song.filter(|_| e.is::<Undecodable>()) {
+                    let _ = s.set_waveform_db(&[]);
+                }
+                self.state = WaveformState::Failed;
+            }
         }
         self.reciever = None;
     }
@@ -157,10 +181,179 @@ impl UiState {
     }
 }
 
-/// Generate a waveform using ffmpeg by piping output directly to memory
+/// Generate waveforms for every song missing one, on a bounded pool so
+/// playback and the UI are left some headroom
+pub fn precompute_waveforms(
+    tx: &Sender<WaveformScanProgress>,
+    cancel: Arc<AtomicBool>,
+) -> Result<()> {
+    let mut db = Database::open()?;
+    let missing = db.get_songs_without_waveform()?;
+    let total = missing.len();
+
+    if total == 0 {
+        return Ok(());
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| (n.get() / 2).max(1));
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let (wf_tx, wf_rx) = crossbeam::channel::unbounded();
+
+    let worker_cancel = Arc::clone(&cancel);
+    thread::spawn(move || {
+        pool.install(|| {
+            missing
+                .into_par_iter()
+                .for_each_with(wf_tx, |wf_tx, (id, path)| {
+                    if !worker_cancel.load(Ordering::Relaxed) {
+                        let _ = wf_tx.send((id, generate_waveform(path)));
+                    }
+                })
+        })
+    });
+
+    for (i, (id, waveform)) in wf_rx.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // An empty waveform marks the song as undecodable, so that it is
+        // skipped until its file changes or the library is refreshed. Songs
+        // which couldn't be read are left for the next scan
+        match waveform {
+            Ok(waveform) => db.set_waveform(id, &waveform)?,
+            Err(e) if e.is::<Undecodable>() => db.set_waveform(id, &[])?,
+            Err(_) => {}
+        }
+
+        let current = i + 1;
+        if current % 10 == 0 || current == total {
+            let progress = (2 + current * 97 / total) as u8;
+            let _ = tx.send(WaveformScanProgress::Processing {
+                progress,
+                current,
+                total,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returned by `generate_waveform` when the file was read but holds no audio
+/// which could be decoded, as opposed to a file which couldn't be read at all
+#[derive(Debug)]
+pub struct Undecodable;
+
+impl fmt::Display for Undecodable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("No audio could be decoded")
+    }
+}
+
+impl std::error::Error for Undecodable {}
+
+/// Generate a waveform by decoding the file in-process. ffmpeg is only used
+/// as a fallback for codecs symphonia does not support
 pub fn generate_waveform<P: AsRef<Path>>(audio_path: P) -> Result<Vec<f32>> {
     let path = audio_path.as_ref();
-    extract_waveform_data(path)
+
+    // Symphonia's probe reports a file it couldn't read as an unsupported
+    // format, so make sure the file is readable before blaming its contents
+    File::open(path).and_then(|mut file| file.read(&mut [0; 1]))?;
+
+    match decode_mono(path) {
+        Ok((pcm, duration)) => build_waveform(&pcm, duration),
+        Err(e) if is_read_error(&e) => Err(e),
+        Err(e) => match *FFMPEG_AVAILABLE {
+            true => extract_waveform_data(path),
+            false => Err(e.context(Undecodable)),
+        },
+    }
+}
+
+/// Whether reading the file failed, rather than decoding it. Running into
+/// the end of the file counts as the latter
+fn is_read_error(e: &anyhow::Error) -> bool {
+    let is_read = |e: &io::Error| e.kind() != io::ErrorKind::UnexpectedEof;
+
+    e.chain().any(|cause| {
+        cause.downcast_ref::<io::Error>().is_some_and(is_read)
+            || matches!(cause.downcast_ref(), Some(SymphoniaError::IoError(e)) if is_read(e))
+    })
+}
+
+/// Decode to mono at roughly `TARGET_RATE`, returning the samples alongside
+/// the duration of the song
+fn decode_mono(path: &Path) -> Result<(Vec<f32>, Duration)> {
+    let mut decoder = PcmDecoder::open(path)?;
+    let channels = decoder.channels.max(1);
+    let sample_rate = decoder.sample_rate.max(1);
+
+    // Frames are averaged in groups, which also acts as a crude low pass
+    let group = (sample_rate / TARGET_RATE).max(1) as usize;
+    let mut filter = HighPass::new(HIGH_PASS_HZ, sample_rate as f32 / group as f32);
+
+    let mut pcm = vec![];
+    let mut frames = 0usize;
+    let mut acc = 0.0;
+    let mut acc_len = 0;
+
+    while let Some(samples) = decoder.next_chunk()? {
+        for frame in samples.chunks_exact(channels) {
+            acc += frame.iter().sum::<f32>() / channels as f32;
+            acc_len += 1;
+            frames += 1;
+
+            if acc_len == group {
+                pcm.push(filter.process(acc / group as f32));
+                acc = 0.0;
+                acc_len = 0;
+            }
+        }
+    }
+
+    if pcm.is_empty() {
+        bail!(Undecodable);
+    }
+
+    let duration = Duration::from_secs_f64(frames as f64 / sample_rate as f64);
+    Ok((pcm, duration))
+}
+
+/// First order high pass, keeps bass heavy songs from flattening the
+/// waveform
+struct HighPass {
+    alpha: f32,
+    prev_in: f32,
+    prev_out: f32,
+}
+
+impl HighPass {
+    fn new(cutoff: f32, rate: f32) -> Self {
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff);
+        HighPass {
+            alpha: rc / (rc + 1.0 / rate),
+            prev_in: 0.0,
+            prev_out: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        self.prev_out = self.alpha * (self.prev_out + x - self.prev_in);
+        self.prev_in = x;
+        self.prev_out
+    }
+}
+
+fn build_waveform(pcm: &[f32], duration: Duration) -> Result<Vec<f32>> {
+    // Calculate adaptive samples per point based on duration
+    let samples_per_point = calculate_adaptive_samples(duration);
+
+    let mut waveform = process_pcm_to_waveform(pcm, samples_per_point)?;
+    normalize_waveform(&mut waveform);
+
+    Ok(waveform)
 }
 
 /// Extract duration from audio file using ffmpeg
@@ -185,10 +378,11 @@ fn get_audio_duration<P: AsRef<Path>>(audio_path: P) -> Result<Duration> {
         .context("Failed to execute ffprobe")?;
 
     if !output.status.success() {
-        bail!(
+        return Err(anyhow!(
             "ffprobe failed: {}",
             String::from_utf8_lossy(&output.stderr)
-        );
+        )
+        .context(Undecodable));
     }
 
     let duration_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -199,18 +393,10 @@ fn get_audio_duration<P: AsRef<Path>>(audio_path: P) -> Result<Duration> {
     Ok(Duration::from_secs_f64(duration_secs))
 }
 
-/// Extract waveform data from audio file
+/// Extract waveform data from audio file using ffmpeg
 fn extract_waveform_data<P: AsRef<Path>>(audio_path: P) -> Result<Vec<f32>> {
     // Get audio duration to calculate optimal sampling
-    let duration = match get_audio_duration(&audio_path) {
-        Ok(d) => d,
-        Err(_) => {
-            bail!("Could not determine audio length");
-        }
-    };
-
-    // Calculate adaptive samples per point based on duration
-    let samples_per_point = calculate_adaptive_samples(duration);
+    let duration = get_audio_duration(&audio_path).context("Could not determine audio length")?;
 
     // Get the path as string, with better error handling
     let audio_path_str = audio_path
@@ -241,18 +427,20 @@ fn extract_waveform_data<P: AsRef<Path>>(audio_path: P) -> Result<Vec<f32>> {
 
     // Check for errors
     if !output.status.success() {
-        bail!(
+        return Err(anyhow!(
             "FFmpeg conversion failed: {}",
             String::from_utf8_lossy(&output.stderr)
-        );
+        )
+        .context(Undecodable));
     }
 
-    let pcm_data = output.stdout;
-    let mut waveform = process_pcm_to_waveform(&pcm_data, samples_per_point)?;
-
-    normalize_waveform(&mut waveform);
+    let pcm = output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect::<Vec<f32>>();
 
-    Ok(waveform)
+    build_waveform(&pcm, duration)
 }
 
 /// Calculate adaptive samples per point based on duration
@@ -266,75 +454,43 @@ fn calculate_adaptive_samples(duration: Duration) -> usize {
     ideal_samples.clamp(MIN_SAMPLES_PER_POINT, MAX_SAMPLES_PER_POINT)
 }
 
-/// Process raw PCM float data into a vector of f32 values
-fn process_pcm_to_waveform(pcm_data: &[u8], samples_per_point: usize) -> Result<Vec<f32>> {
-    let mut cursor = Cursor::new(pcm_data);
+/// RMS of a run of samples, clamped to 1.0
+fn rms(samples: &[f32]) -> f32 {
+    match samples.is_empty() {
+        true => 0.0,
+        false => {
+            let sum_squares = samples.iter().map(|s| s * s).sum::<f32>();
+            (sum_squares / samples.len() as f32).sqrt().min(1.0)
+        }
+    }
+}
 
-    let total_samples = pcm_data.len() / 4;
+/// Process mono PCM samples into a vector of f32 values
+fn process_pcm_to_waveform(pcm: &[f32], samples_per_point: usize) -> Result<Vec<f32>> {
+    let total_samples = pcm.len();
 
     // If the file is very short, adapt the approach
     if total_samples < WF_LEN * samples_per_point {
-        return process_short_pcm(pcm_data);
+        return process_short_pcm(pcm);
     }
 
     let sample_step = total_samples / WF_LEN;
-    let mut waveform = Vec::with_capacity(WF_LEN);
-
-    for i in 0..WF_LEN {
-        let position = i * sample_step * 4;
-        if position >= pcm_data.len() {
-            break;
-        }
-
-        cursor.set_position(position as u64);
-        let mut sum_squares = 0.0;
-        let mut samples_read = 0;
-        let mut max_value = 0.0f32;
+    let max_samples = samples_per_point.min(sample_step);
 
-        let max_samples = samples_per_point.min(sample_step);
-        for _ in 0..max_samples {
-            if cursor.position() >= pcm_data.len() as u64 {
-                break;
-            }
+    let mut waveform = (0..WF_LEN)
+        .map(|i| i * sample_step)
+        .take_while(|position| *position < total_samples)
+        .map(|position| rms(&pcm[position..(position + max_samples).min(total_samples)]))
+        .collect::<Vec<f32>>();
 
-            let mut bytes = [0u8; 4];
-            match cursor.read_exact(&mut bytes) {
-                Ok(_) => {
-                    let sample = f32::from_le_bytes(bytes);
-                    let abs_sample = sample.abs();
-                    if abs_sample > max_value {
-                        max_value = abs_sample;
-                    }
-
-                    // Sum squares for RMS calculation
-                    sum_squares += sample * sample;
-                    samples_read += 1;
-                }
-                Err(_) => break,
-            }
-        }
-
-        match samples_read > 0 {
-            true => {
-                let rms = (sum_squares / samples_read as f32).sqrt();
-                let value = rms.min(1.0);
-                waveform.push(value);
-            }
-            false => waveform.push(0.0),
-        }
-    }
-
-    while waveform.len() < WF_LEN {
-        waveform.push(0.0);
-    }
+    waveform.resize(WF_LEN, 0.0);
 
     Ok(waveform)
 }
 
 /// Process very short PCM files
-fn process_short_pcm(pcm_data: &[u8]) -> Result<Vec<f32>> {
-    let mut cursor = Cursor::new(pcm_data);
-    let total_samples = pcm_data.len() / 4;
+fn process_short_pcm(pcm: &[f32]) -> Result<Vec<f32>> {
+    let total_samples = pcm.len();
 
     // For very short files, we'll divide the available samples evenly
     let samples_per_section = total_samples / WF_LEN.max(1);
@@ -344,57 +500,14 @@ fn process_short_pcm(pcm_data: &[u8]) -> Result<Vec<f32>> {
     let mut position = 0;
 
     for i in 0..WF_LEN {
-        let samples_this_section = if i < extra_samples {
-            samples_per_section + 1
-        } else {
-            samples_per_section
+        let samples_this_section = match i < extra_samples {
+            true => samples_per_section + 1,
+            false => samples_per_section,
         };
 
-        if samples_this_section == 0 {
-            waveform.push(0.0);
-            continue;
-        }
-
-        cursor.set_position((position * 4) as u64);
-
-        let mut sum_squares = 0.0;
-        let mut max_value = 0.0f32;
-        let mut samples_read = 0;
-
-        for _ in 0..samples_this_section {
-            if cursor.position() >= pcm_data.len() as u64 {
-                break;
-            }
-
-            let mut bytes = [0u8; 4];
-            match cursor.read_exact(&mut bytes) {
-                Ok(_) => {
-                    let sample = f32::from_le_bytes(bytes);
-                    let abs_sample = sample.abs();
-                    if abs_sample > max_value {
-                        max_value = abs_sample;
-                    }
-                    sum_squares += sample * sample;
-                    samples_read += 1;
-                }
-                Err(_) => break,
-            }
-        }
-
-        position += samples_this_section;
-
-        match samples_read > 0 {
-            true => {
-                let rms = (sum_squares / samples_read as f32).sqrt();
-                let value = rms.min(1.0);
-                waveform.push(value);
-            }
-            false => waveform.push(0.0),
-        }
-    }
-
-    while waveform.len() < WF_LEN {
-        waveform.push(0.0);
+        let end = (position + samples_this_section).min(total_samples);
+        waveform.push(rms(&pcm[position..end]));
+        position = end;
     }
 
     Ok(waveform)